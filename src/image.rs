@@ -1,2 +1,9 @@
 pub mod color;
 pub mod film;
+pub mod spectrum;
+pub mod hdr;
+pub mod cie;
+pub mod blackbody;
+pub mod uplift;
+pub mod sampled;
+pub mod xyz;
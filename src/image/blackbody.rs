@@ -0,0 +1,68 @@
+use super::{
+    spectrum::Spectrum,
+    cie::{x_bar,y_bar,z_bar,xyz_to_rgb,LAMBDA_MIN,LAMBDA_MAX}
+};
+
+/// Planck's law, spectral radiance of a blackbody at wavelength
+/// lambda (nm) and temperature (K) in W/(m^2 sr nm)
+pub fn planck(lambda: f64,temperature: f64) -> f64 {
+    if temperature <= 0.0 {
+        return 0.0
+    }
+    const C: f64 = 299792458.0;
+    const H: f64 = 6.62606957e-34;
+    const KB: f64 = 1.3806488e-23;
+    let l = lambda * 1e-9;
+    // per metre of wavelength, scaled to per nm
+    2.0*H*C*C / (l.powi(5) * f64::exp_m1(H*C / (l*KB*temperature))) * 1e-9
+}
+
+/// Linear RGB radiance of a blackbody at temperature (K), Planck's law
+/// integrated against the color matching functions so luminance is
+/// in W/(m^2 sr) weighted by luminous efficiency, hotter is brighter
+pub fn blackbody(temperature: f64) -> Spectrum {
+    const STEP: f64 = 5.0;
+    let (mut x,mut y,mut z) = (0.0,0.0,0.0);
+    let mut lambda = LAMBDA_MIN;
+    while lambda <= LAMBDA_MAX {
+        let b = planck(lambda,temperature) * STEP;
+        x += b * x_bar(lambda);
+        y += b * y_bar(lambda);
+        z += b * z_bar(lambda);
+        lambda += STEP;
+    }
+    xyz_to_rgb(x,y,z)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // the peak should follow Wien's displacement law
+    fn test_planck() {
+        let temperature = 5000.;
+        let peak = (300..1000)
+            .map(|l| l as f64)
+            .max_by(|a,b| planck(*a,temperature).total_cmp(&planck(*b,temperature)))
+            .unwrap();
+        assert!((peak - 2.8977719e6 / temperature).abs() < 1.);
+        assert_eq!(planck(500.,0.),0.);
+    }
+
+    #[test]
+    // cool bodies glow red, hot ones blue, hotter is brighter
+    fn test_blackbody() {
+        let fire = blackbody(1500.);
+        assert!(fire.r > fire.g && fire.g > fire.b.max(0.));
+        let hot = blackbody(12000.);
+        assert!(hot.b > hot.r);
+        let white = blackbody(6500.);
+        assert!((white.r / white.b - 1.).abs() < 0.15);
+        assert!(blackbody(2000.).luminance() > fire.luminance());
+    }
+}
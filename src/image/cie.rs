@@ -0,0 +1,100 @@
+use super::spectrum::Spectrum;
+
+/// Visible wavelength range in nm
+pub const LAMBDA_MIN: f64 = 360.0;
+pub const LAMBDA_MAX: f64 = 830.0;
+
+/// Integral of y_bar over the visible range, luminance of a constant unit spectrum
+pub const CIE_Y_INTEGRAL: f64 = 106.922;
+
+/// CIE 1931 x color matching function at wavelength lambda (nm),
+/// multi-lobe fit of Wyman, Sloan and Shirley
+pub fn x_bar(lambda: f64) -> f64 {
+    1.056 * lobe(lambda,599.8,37.9,31.0)
+        + 0.362 * lobe(lambda,442.0,16.0,26.7)
+        - 0.065 * lobe(lambda,501.1,20.4,26.2)
+}
+
+/// CIE 1931 y color matching function (luminous efficiency)
+pub fn y_bar(lambda: f64) -> f64 {
+    0.821 * lobe(lambda,568.8,46.9,40.5)
+        + 0.286 * lobe(lambda,530.9,16.3,31.1)
+}
+
+/// CIE 1931 z color matching function
+pub fn z_bar(lambda: f64) -> f64 {
+    1.217 * lobe(lambda,437.0,11.8,36.0)
+        + 0.681 * lobe(lambda,459.0,26.0,13.8)
+}
+
+/// Convert CIE XYZ to linear sRGB (D65 white point)
+pub fn xyz_to_rgb(x: f64,y: f64,z: f64) -> Spectrum {
+    Spectrum::new(
+        3.2404542*x - 1.5371385*y - 0.4985314*z,
+        -0.9692660*x + 1.8760108*y + 0.0415560*z,
+        0.0556434*x - 0.2040259*y + 1.0572252*z
+    )
+}
+
+/// Convert CIE XYZ to linear sRGB treating the equal energy spectrum as
+/// white, chromatic adaptation to D65 (Bradford) is folded into the matrix.
+/// Spectral rendering uses this so a constant spectrum shows as grey
+pub fn xyz_to_rgb_balanced(x: f64,y: f64,z: f64) -> Spectrum {
+    Spectrum::new(
+        3.1478102*x - 1.6628457*y - 0.4805745*z,
+        -0.9947473*x + 1.9535705*y + 0.0397403*z,
+        0.0635154*x - 0.2145107*y + 1.1515927*z
+    )
+}
+
+/// Gaussian with different widths left and right of its mean
+fn lobe(lambda: f64,mean: f64,sigma_left: f64,sigma_right: f64) -> f64 {
+    let sigma = if lambda < mean { sigma_left } else { sigma_right };
+    let t = (lambda - mean) / sigma;
+    f64::exp(-0.5 * t*t)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // curves should peak near the tabulated maxima
+    fn test_color_matching() {
+        assert!((y_bar(555.) - 1.).abs() < 0.02);
+        assert!((x_bar(600.) - 1.06).abs() < 0.02);
+        assert!((z_bar(445.) - 1.78).abs() < 0.05);
+        assert!(y_bar(LAMBDA_MIN) < 1e-3 && y_bar(LAMBDA_MAX) < 1e-3);
+    }
+
+    #[test]
+    // the D65 white point should map to white
+    fn test_xyz_to_rgb() {
+        let white = xyz_to_rgb(0.95047,1.,1.08883);
+        assert!((white.r - 1.).abs() < 1e-3);
+        assert!((white.g - 1.).abs() < 1e-3);
+        assert!((white.b - 1.).abs() < 1e-3);
+    }
+
+    #[test]
+    // a constant spectrum should have unit luminance and balance to white
+    fn test_equal_energy_white() {
+        let (mut x,mut y,mut z) = (0.,0.,0.);
+        let mut lambda = LAMBDA_MIN;
+        while lambda <= LAMBDA_MAX {
+            x += x_bar(lambda);
+            y += y_bar(lambda);
+            z += z_bar(lambda);
+            lambda += 1.;
+        }
+        assert!((y - CIE_Y_INTEGRAL).abs() < 1e-3);
+        let white = xyz_to_rgb_balanced(x / CIE_Y_INTEGRAL,y / CIE_Y_INTEGRAL,z / CIE_Y_INTEGRAL);
+        assert!((white.r - 1.).abs() < 1e-4);
+        assert!((white.g - 1.).abs() < 1e-4);
+        assert!((white.b - 1.).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,318 @@
+use super::spectrum::Spectrum;
+
+/// # HdrImage
+/// High dynamic range image stored as linear RGB spectra
+/// Pixels are stored row by row starting at the top left
+///
+/// # Parameters
+/// * width
+/// * height
+/// * pixels
+pub struct HdrImage {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Spectrum>
+}
+
+impl HdrImage {
+    /// Construct image filled with the given value
+    pub fn new(width: usize,height: usize,fill: Spectrum) -> HdrImage {
+        HdrImage {
+            width,
+            height,
+            pixels: vec![fill; width*height]
+        }
+    }
+
+    /// Get pixel at (x,y)
+    pub fn at(&self,x: usize,y: usize) -> Spectrum {
+        self.pixels[y*self.width + x]
+    }
+
+    /// Load Radiance RGBE (.hdr) image from disk
+    pub fn load(path: &str) -> Result<HdrImage,String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("{}: {}",path,e))?;
+        HdrImage::from_rgbe(&bytes)
+    }
+
+    /// Save as Radiance RGBE (.hdr) image
+    pub fn save(&self,path: &str) -> Result<(),String> {
+        std::fs::write(path,self.to_rgbe()).map_err(|e| format!("{}: {}",path,e))
+    }
+
+    /// Encode as Radiance RGBE image with flat scanlines
+    pub fn to_rgbe(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = format!(
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            self.height,self.width
+        ).into_bytes();
+        for pixel in self.pixels.iter() {
+            bytes.extend_from_slice(&spectrum_to_rgbe(pixel));
+        }
+        bytes
+    }
+
+    /// Decode Radiance RGBE image held in memory
+    ///
+    /// Supports flat and run length encoded scanlines with
+    /// the standard -Y height +X width orientation
+    pub fn from_rgbe(bytes: &[u8]) -> Result<HdrImage,String> {
+        let mut pos: usize = 0;
+
+        // header is a list of text lines terminated by an empty line
+        let first = read_line(bytes,&mut pos)?;
+        if !first.starts_with("#?") {
+            return Err("not a radiance file".to_string())
+        }
+        loop {
+            let line = read_line(bytes,&mut pos)?;
+            if line.is_empty() {
+                break;
+            }
+            if let Some(format) = line.strip_prefix("FORMAT=") {
+                if format != "32-bit_rle_rgbe" {
+                    return Err(format!("unsupported format {}",format))
+                }
+            }
+        }
+
+        // resolution string
+        let resolution = read_line(bytes,&mut pos)?;
+        let tokens: Vec<&str> = resolution.split_whitespace().collect();
+        if tokens.len() != 4 || tokens[0] != "-Y" || tokens[2] != "+X" {
+            return Err(format!("unsupported resolution string {}",resolution))
+        }
+        let height: usize = tokens[1].parse().map_err(|_| format!("invalid height {}",tokens[1]))?;
+        let width: usize = tokens[3].parse().map_err(|_| format!("invalid width {}",tokens[3]))?;
+        if width == 0 || height == 0 {
+            return Err(format!("empty image {}x{}",width,height))
+        }
+
+        // a scanline takes at least two bytes per channel for every run of
+        // 127 pixels, check the data can hold the image before allocating it
+        let min_scanline = if (8..32768).contains(&width) {
+            4 + 8*width.div_ceil(127)
+        } else {
+            width.saturating_mul(4)
+        };
+        if height.saturating_mul(min_scanline) > bytes.len() - pos {
+            return Err(format!("{}x{} image does not fit in {} bytes of pixel data",width,height,bytes.len() - pos))
+        }
+
+        let mut image = HdrImage::new(width,height,super::spectrum::BLACK);
+        let mut scanline: Vec<[u8; 4]> = vec![[0; 4]; width];
+
+        for y in 0..height {
+            read_scanline(bytes,&mut pos,&mut scanline)?;
+            for (x,rgbe) in scanline.iter().enumerate() {
+                image.pixels[y*width + x] = rgbe_to_spectrum(*rgbe);
+            }
+        }
+
+        Ok(image)
+    }
+}
+
+/// Convert shared exponent RGBE pixel to linear RGB
+fn rgbe_to_spectrum(rgbe: [u8; 4]) -> Spectrum {
+    if rgbe[3] == 0 {
+        return super::spectrum::BLACK
+    }
+    let f: f64 = f64::powi(2.0,rgbe[3] as i32 - (128 + 8));
+    Spectrum::new(
+        (rgbe[0] as f64 + 0.5) * f,
+        (rgbe[1] as f64 + 0.5) * f,
+        (rgbe[2] as f64 + 0.5) * f
+    )
+}
+
+/// Convert linear RGB to shared exponent RGBE pixel
+fn spectrum_to_rgbe(s: &Spectrum) -> [u8; 4] {
+    let max = s.max_component();
+    if max < 1e-32 {
+        return [0; 4]
+    }
+    // max = mantissa * 2^exponent with mantissa in [0.5,1)
+    let exponent = max.log2().floor() as i32 + 1;
+    let scale = f64::powi(2.0,8 - exponent);
+    [
+        (f64::max(0.0,s.r) * scale) as u8,
+        (f64::max(0.0,s.g) * scale) as u8,
+        (f64::max(0.0,s.b) * scale) as u8,
+        (exponent + 128) as u8
+    ]
+}
+
+/// Read a newline terminated header line
+fn read_line(bytes: &[u8],pos: &mut usize) -> Result<String,String> {
+    let start = *pos;
+    while *pos < bytes.len() && bytes[*pos] != b'\n' {
+        *pos += 1;
+    }
+    if *pos >= bytes.len() {
+        return Err("unexpected end of header".to_string())
+    }
+    let line = String::from_utf8_lossy(&bytes[start..*pos]).trim().to_string();
+    *pos += 1; // skip newline
+    Ok(line)
+}
+
+/// Read next byte, failing at end of data
+fn next_byte(bytes: &[u8],pos: &mut usize) -> Result<u8,String> {
+    match bytes.get(*pos) {
+        Some(b) => {
+            *pos += 1;
+            Ok(*b)
+        },
+        None => Err("unexpected end of pixel data".to_string())
+    }
+}
+
+/// Read one scanline in either flat or new style run length encoding
+fn read_scanline(bytes: &[u8],pos: &mut usize,scanline: &mut [[u8; 4]]) -> Result<(),String> {
+    let width = scanline.len();
+
+    // new style RLE scanlines start with 2,2 followed by the width
+    let is_rle = (8..32768).contains(&width) &&
+        bytes.len() >= *pos + 4 &&
+        bytes[*pos] == 2 && bytes[*pos+1] == 2 && bytes[*pos+2] & 0x80 == 0;
+
+    if !is_rle {
+        for pixel in scanline.iter_mut() {
+            for channel in pixel.iter_mut() {
+                *channel = next_byte(bytes,pos)?;
+            }
+        }
+        return Ok(())
+    }
+
+    let encoded_width = ((bytes[*pos+2] as usize) << 8) | bytes[*pos+3] as usize;
+    if encoded_width != width {
+        return Err(format!("scanline width {} does not match image width {}",encoded_width,width))
+    }
+    *pos += 4;
+
+    // each channel is encoded separately
+    #[allow(clippy::needless_range_loop)]
+    for c in 0..4 {
+        let mut x: usize = 0;
+        while x < width {
+            let count = next_byte(bytes,pos)? as usize;
+            if count > 128 {
+                // run of the same value
+                let run = count - 128;
+                if x + run > width {
+                    return Err("bad scanline run".to_string())
+                }
+                let value = next_byte(bytes,pos)?;
+                for _ in 0..run {
+                    scanline[x][c] = value;
+                    x += 1;
+                }
+            } else {
+                // literal values
+                if count == 0 || x + count > width {
+                    return Err("bad scanline data".to_string())
+                }
+                for _ in 0..count {
+                    scanline[x][c] = next_byte(bytes,pos)?;
+                    x += 1;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // should decode a flat (unencoded) image
+    fn test_from_rgbe_flat() {
+        let mut bytes: Vec<u8> = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 2\n".to_vec();
+        // 1.0 is mantissa 128 with exponent 129
+        bytes.extend_from_slice(&[128,64,0,129]);
+        bytes.extend_from_slice(&[0,0,0,0]);
+        let image = HdrImage::from_rgbe(&bytes).unwrap();
+        assert_eq!(image.width,2);
+        assert_eq!(image.height,1);
+        let p = image.at(0,0);
+        assert!((p.r - 128.5/128.).abs() < 1e-12);
+        assert!((p.g - 64.5/128.).abs() < 1e-12);
+        assert!((p.b - 0.5/128.).abs() < 1e-12);
+        assert!(image.at(1,0).is_black());
+    }
+
+    #[test]
+    // should decode a run length encoded scanline
+    fn test_from_rgbe_rle() {
+        let mut bytes: Vec<u8> = b"#?RGBE\n\n-Y 1 +X 8\n".to_vec();
+        bytes.extend_from_slice(&[2,2,0,8]);
+        // red: run of 8 values
+        bytes.extend_from_slice(&[128 + 8,200]);
+        // green: 8 literal values
+        bytes.extend_from_slice(&[8,0,1,2,3,4,5,6,7]);
+        // blue: run of 8 zeros
+        bytes.extend_from_slice(&[128 + 8,0]);
+        // exponent: run of 8
+        bytes.extend_from_slice(&[128 + 8,136]);
+        let image = HdrImage::from_rgbe(&bytes).unwrap();
+        assert_eq!(image.width,8);
+        for x in 0..8 {
+            let p = image.at(x,0);
+            assert_eq!(p.r,200.5);
+            assert_eq!(p.g,x as f64 + 0.5);
+            assert_eq!(p.b,0.5);
+        }
+    }
+
+    #[test]
+    // encoding then decoding should preserve values to RGBE precision
+    fn test_to_rgbe_round_trip() {
+        let mut image = HdrImage::new(3,2,Spectrum::new(0.25,1.,7.5));
+        image.pixels[4] = Spectrum::new(1000.,0.001,0.);
+        image.pixels[5] = super::super::spectrum::BLACK;
+        let decoded = HdrImage::from_rgbe(&image.to_rgbe()).unwrap();
+        assert_eq!(decoded.width,3);
+        assert_eq!(decoded.height,2);
+        for (a,b) in image.pixels.iter().zip(decoded.pixels.iter()) {
+            let tolerance = a.max_component() / 128.;
+            assert!((a.r - b.r).abs() <= tolerance);
+            assert!((a.g - b.g).abs() <= tolerance);
+            assert!((a.b - b.b).abs() <= tolerance);
+        }
+        assert!(decoded.pixels[5].is_black());
+    }
+
+    #[test]
+    // should reject files without radiance header
+    fn test_from_rgbe_bad_header() {
+        let bytes: Vec<u8> = b"P6\n1 1\n255\n".to_vec();
+        assert!(HdrImage::from_rgbe(&bytes).is_err());
+    }
+
+    #[test]
+    // should reject truncated pixel data
+    fn test_from_rgbe_truncated() {
+        let mut bytes: Vec<u8> = b"#?RADIANCE\n\n-Y 2 +X 2\n".to_vec();
+        bytes.extend_from_slice(&[1,2,3,4]);
+        assert!(HdrImage::from_rgbe(&bytes).is_err());
+    }
+
+    #[test]
+    // should reject empty images and sizes the data cannot hold
+    fn test_from_rgbe_bad_size() {
+        for resolution in ["-Y 0 +X 0","-Y 4 +X 0","-Y 0 +X 4","-Y 4000000000 +X 4000000000","-Y 1 +X 18446744073709551615"] {
+            let mut bytes: Vec<u8> = format!("#?RADIANCE\n\n{}\n",resolution).into_bytes();
+            bytes.extend_from_slice(&[1,2,3,4]);
+            assert!(HdrImage::from_rgbe(&bytes).is_err());
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use super::{
+    spectrum::Spectrum,
+    cie::{x_bar,y_bar,z_bar,CIE_Y_INTEGRAL,LAMBDA_MIN,LAMBDA_MAX},
+    uplift::rgb_to_spectrum
+};
+
+/// Number of wavelengths carried by each path
+pub const SPECTRUM_SAMPLES: usize = 4;
+
+/// # SampledWavelengths
+/// Wavelengths carried by a path, a uniformly sampled hero wavelength
+/// and the others evenly spaced from it, wrapping around the visible range
+///
+/// # Parameters
+/// * lambda (wavelengths in nm, the hero first)
+/// * pdf (density of each wavelength, zero once it is terminated)
+#[derive(Clone,Copy)]
+pub struct SampledWavelengths {
+    pub lambda: [f64; SPECTRUM_SAMPLES],
+    pub pdf: [f64; SPECTRUM_SAMPLES]
+}
+
+impl SampledWavelengths {
+    /// Hero wavelength sampling from u in [0,1)
+    pub fn sample(u: f64) -> SampledWavelengths {
+        let range = LAMBDA_MAX - LAMBDA_MIN;
+        let mut lambda = [0.0; SPECTRUM_SAMPLES];
+        for (i,l) in lambda.iter_mut().enumerate() {
+            let offset = (u + i as f64 / SPECTRUM_SAMPLES as f64).fract();
+            *l = LAMBDA_MIN + offset * range;
+        }
+        SampledWavelengths {
+            lambda,
+            pdf: [1.0 / range; SPECTRUM_SAMPLES]
+        }
+    }
+
+    /// Keep only the hero wavelength, needed once the path takes a
+    /// direction that depends on wavelength such as dispersive refraction
+    pub fn terminate_secondary(&mut self) {
+        if self.secondary_terminated() {
+            return
+        }
+        for pdf in self.pdf.iter_mut().skip(1) {
+            *pdf = 0.0;
+        }
+        // the hero now stands for every sample
+        self.pdf[0] /= SPECTRUM_SAMPLES as f64;
+    }
+
+    /// True if only the hero wavelength is left
+    pub fn secondary_terminated(&self) -> bool {
+        self.pdf.iter().skip(1).all(|pdf| *pdf == 0.0)
+    }
+}
+
+/// # SampledSpectrum
+/// Spectral quantity at the wavelengths carried by a path
+///
+/// # Parameters
+/// * values (one per wavelength)
+#[derive(Clone,Copy)]
+pub struct SampledSpectrum {
+    pub values: [f64; SPECTRUM_SAMPLES]
+}
+
+/// helpful constants
+pub const ZERO: SampledSpectrum = SampledSpectrum{values: [0.0; SPECTRUM_SAMPLES]};
+pub const ONE: SampledSpectrum = SampledSpectrum{values: [1.0; SPECTRUM_SAMPLES]};
+
+/// Add trait
+impl std::ops::Add for SampledSpectrum {
+    type Output = SampledSpectrum;
+    fn add(self,s: SampledSpectrum) -> SampledSpectrum {
+        let mut values = self.values;
+        for (v,w) in values.iter_mut().zip(s.values.iter()) {
+            *v += w;
+        }
+        SampledSpectrum {values}
+    }
+}
+
+/// AddAssign trait, used when accumulating radiance
+impl std::ops::AddAssign for SampledSpectrum {
+    fn add_assign(&mut self,s: SampledSpectrum) {
+        *self = *self + s;
+    }
+}
+
+/// Multiplication trait (component-wise product of two spectra)
+impl std::ops::Mul for SampledSpectrum {
+    type Output = SampledSpectrum;
+    fn mul(self,s: SampledSpectrum) -> SampledSpectrum {
+        let mut values = self.values;
+        for (v,w) in values.iter_mut().zip(s.values.iter()) {
+            *v *= w;
+        }
+        SampledSpectrum {values}
+    }
+}
+
+/// Multiplication trait (scale spectrum by scalar)
+impl std::ops::Mul<f64> for SampledSpectrum {
+    type Output = SampledSpectrum;
+    fn mul(self,factor: f64) -> SampledSpectrum {
+        SampledSpectrum {
+            values: self.values.map(|v| v * factor)
+        }
+    }
+}
+
+/// Division trait (divide spectrum by scalar)
+impl std::ops::Div<f64> for SampledSpectrum {
+    type Output = SampledSpectrum;
+    fn div(self,factor: f64) -> SampledSpectrum {
+        self * (1.0 / factor)
+    }
+}
+
+impl SampledSpectrum {
+    /// Uplift an RGB albedo or radiance to the given wavelengths
+    pub fn from_rgb(rgb: &Spectrum,wavelengths: &SampledWavelengths) -> SampledSpectrum {
+        SampledSpectrum {
+            values: wavelengths.lambda.map(|lambda| rgb_to_spectrum(rgb,lambda))
+        }
+    }
+
+    /// Largest value
+    pub fn max_component(&self) -> f64 {
+        self.values.iter().fold(f64::NEG_INFINITY,|m,&v| m.max(v))
+    }
+
+    /// True if every value is zero
+    pub fn is_black(&self) -> bool {
+        self.values.iter().all(|v| *v == 0.0)
+    }
+
+    /// Monte Carlo estimate of CIE XYZ, a constant unit spectrum has Y = 1
+    pub fn to_xyz(self,wavelengths: &SampledWavelengths) -> (f64,f64,f64) {
+        let (mut x,mut y,mut z) = (0.0,0.0,0.0);
+        for i in 0..SPECTRUM_SAMPLES {
+            let pdf = wavelengths.pdf[i];
+            if pdf == 0.0 {
+                continue;
+            }
+            let lambda = wavelengths.lambda[i];
+            let v = self.values[i] / pdf;
+            x += x_bar(lambda) * v;
+            y += y_bar(lambda) * v;
+            z += z_bar(lambda) * v;
+        }
+        let scale = 1.0 / (SPECTRUM_SAMPLES as f64 * CIE_Y_INTEGRAL);
+        (x * scale,y * scale,z * scale)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::cie::xyz_to_rgb_balanced;
+
+    #[test]
+    // wavelengths should be evenly spaced and stay in range
+    fn test_sample_wavelengths() {
+        let wavelengths = SampledWavelengths::sample(0.9);
+        let range = LAMBDA_MAX - LAMBDA_MIN;
+        assert!((wavelengths.lambda[0] - (LAMBDA_MIN + 0.9 * range)).abs() < 1e-9);
+        assert!((wavelengths.lambda[1] - (LAMBDA_MIN + 0.15 * range)).abs() < 1e-9);
+        assert!(wavelengths.lambda.iter().all(|l| (LAMBDA_MIN..LAMBDA_MAX).contains(l)));
+        assert!(!wavelengths.secondary_terminated());
+    }
+
+    #[test]
+    // terminating should keep the estimate unbiased through the hero alone
+    fn test_terminate_secondary() {
+        let mut wavelengths = SampledWavelengths::sample(0.3);
+        let full = ONE.to_xyz(&wavelengths);
+        wavelengths.terminate_secondary();
+        wavelengths.terminate_secondary();
+        assert!(wavelengths.secondary_terminated());
+        assert_eq!(wavelengths.pdf[1],0.);
+        let hero = ONE.to_xyz(&wavelengths);
+        // the hero alone counts as many times as there were samples
+        let expected = y_bar(wavelengths.lambda[0]) * (LAMBDA_MAX - LAMBDA_MIN) / CIE_Y_INTEGRAL;
+        assert!((hero.1 - expected).abs() < 1e-9);
+        assert!(full.1 != hero.1);
+    }
+
+    #[test]
+    // averaging over many wavelength samples should converge to the color
+    fn test_to_xyz() {
+        let rgb = Spectrum::new(0.8,0.4,0.1);
+        let n = 4000;
+        let (mut x,mut y,mut z) = (0.,0.,0.);
+        for i in 0..n {
+            let wavelengths = SampledWavelengths::sample((i as f64 + 0.5) / n as f64);
+            let xyz = SampledSpectrum::from_rgb(&rgb,&wavelengths).to_xyz(&wavelengths);
+            x += xyz.0;
+            y += xyz.1;
+            z += xyz.2;
+        }
+        let back = xyz_to_rgb_balanced(x / n as f64,y / n as f64,z / n as f64);
+        for c in 0..3 {
+            assert!((back[c] - rgb[c]).abs() < 0.03,"{}",back);
+        }
+        assert!((ONE * 2. / 4.).values.iter().all(|v| *v == 0.5));
+        assert!(ZERO.is_black());
+    }
+}
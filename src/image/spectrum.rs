@@ -0,0 +1,228 @@
+use super::color::Color;
+
+/// # Spectrum
+/// RGB radiance with unbounded range [0,inf)
+/// Unlike Color this is not clamped so it can carry HDR values
+///
+/// # Parameters
+/// * r
+/// * g
+/// * b
+#[derive(Clone,Copy)]
+pub struct Spectrum {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64
+}
+
+/// helpful constants
+pub const BLACK: Spectrum = Spectrum{r: 0.0,g: 0.0,b: 0.0};
+pub const WHITE: Spectrum = Spectrum{r: 1.0,g: 1.0,b: 1.0};
+
+impl Default for Spectrum {
+    fn default() -> Self {
+        BLACK
+    }
+}
+
+/// implement display trait
+impl std::fmt::Display for Spectrum {
+    fn fmt(&self,f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f,"({},{},{})",self.r,self.g,self.b)
+    }
+}
+
+/// Add trait: spectrum + spectrum = spectrum
+impl std::ops::Add for Spectrum {
+    type Output = Spectrum;
+    fn add(self,s: Spectrum) -> Spectrum {
+        Spectrum {
+            r: self.r + s.r,
+            g: self.g + s.g,
+            b: self.b + s.b
+        }
+    }
+}
+
+/// AddAssign trait, used when accumulating radiance
+impl std::ops::AddAssign for Spectrum {
+    fn add_assign(&mut self,s: Spectrum) {
+        self.r += s.r;
+        self.g += s.g;
+        self.b += s.b;
+    }
+}
+
+/// Sub trait: spectrum - spectrum = spectrum
+impl std::ops::Sub for Spectrum {
+    type Output = Spectrum;
+    fn sub(self,s: Spectrum) -> Spectrum {
+        Spectrum {
+            r: self.r - s.r,
+            g: self.g - s.g,
+            b: self.b - s.b
+        }
+    }
+}
+
+/// Multiplication trait (component-wise product of two spectra)
+impl std::ops::Mul for Spectrum {
+    type Output = Spectrum;
+    fn mul(self,s: Spectrum) -> Spectrum {
+        Spectrum {
+            r: self.r * s.r,
+            g: self.g * s.g,
+            b: self.b * s.b
+        }
+    }
+}
+
+/// Multiplication trait (scale spectrum by scalar)
+impl std::ops::Mul<f64> for Spectrum {
+    type Output = Spectrum;
+    fn mul(self,factor: f64) -> Spectrum {
+        Spectrum {
+            r: self.r * factor,
+            g: self.g * factor,
+            b: self.b * factor
+        }
+    }
+}
+
+/// Division trait (divide spectrum by scalar)
+impl std::ops::Div<f64> for Spectrum {
+    type Output = Spectrum;
+    fn div(self,factor: f64) -> Spectrum {
+        let recip = 1.0 / factor;
+        self * recip
+    }
+}
+
+/// Index trait, channel 0 is r, 1 is g and 2 is b
+impl std::ops::Index<usize> for Spectrum {
+    type Output = f64;
+    fn index(&self,i: usize) -> &f64 {
+        match i {
+            0 => &self.r,
+            1 => &self.g,
+            2 => &self.b,
+            _ => panic!("spectrum channel {} out of range",i)
+        }
+    }
+}
+
+impl Spectrum {
+    /// Construct (r,g,b) spectrum
+    pub fn new(r: f64,g: f64,b: f64) -> Spectrum {
+        Spectrum {r,g,b}
+    }
+
+    /// Relative luminance (Rec. 709 weights)
+    pub fn luminance(&self) -> f64 {
+        0.2126*self.r + 0.7152*self.g + 0.0722*self.b
+    }
+
+    /// Largest of the three components
+    pub fn max_component(&self) -> f64 {
+        f64::max(self.r,f64::max(self.g,self.b))
+    }
+
+    /// Mean of the three components
+    pub fn average(&self) -> f64 {
+        (self.r + self.g + self.b) / 3.0
+    }
+
+    /// Component-wise exponential, turns optical depth into transmittance
+    pub fn exp(&self) -> Spectrum {
+        Spectrum {
+            r: self.r.exp(),
+            g: self.g.exp(),
+            b: self.b.exp()
+        }
+    }
+
+    /// True if every component is zero
+    pub fn is_black(&self) -> bool {
+        self.r == 0.0 && self.g == 0.0 && self.b == 0.0
+    }
+
+    /// Clamp to [0,1] and convert to an opaque display color
+    pub fn to_color(self) -> Color {
+        Color {
+            r: self.r.clamp(0.0,1.0),
+            g: self.g.clamp(0.0,1.0),
+            b: self.b.clamp(0.0,1.0),
+            a: 1.0
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // should construct correctly
+    fn test_new() {
+        let s: Spectrum = Spectrum::new(0.5,2.,10.);
+        assert_eq!(s.r,0.5);
+        assert_eq!(s.g,2.);
+        assert_eq!(s.b,10.);
+    }
+
+    #[test]
+    // should add and multiply component-wise
+    fn test_ops() {
+        let a: Spectrum = Spectrum::new(1.,2.,3.);
+        let b: Spectrum = Spectrum::new(2.,0.5,-1.);
+        let sum = a + b;
+        assert_eq!(sum.r,3.);
+        assert_eq!((a - b).g,1.5);
+        assert_eq!(sum.g,2.5);
+        assert_eq!(sum.b,2.);
+        let prod = a * b;
+        assert_eq!(prod.r,2.);
+        assert_eq!(prod.g,1.);
+        assert_eq!(prod.b,-3.);
+        let scaled = a * 2.;
+        assert_eq!(scaled.b,6.);
+        let divided = a / 2.;
+        assert_eq!(divided.g,1.);
+    }
+
+    #[test]
+    // luminance of white should be one
+    fn test_luminance() {
+        assert!((WHITE.luminance() - 1.).abs() < 1e-12);
+        assert_eq!(BLACK.luminance(),0.);
+        assert!(BLACK.is_black());
+        assert!(!WHITE.is_black());
+        assert_eq!(Spectrum::new(0.2,3.,1.).max_component(),3.);
+    }
+
+    #[test]
+    // channels should be indexable, exp should work per channel
+    fn test_channels() {
+        let s = Spectrum::new(0.,1.,-2.);
+        assert_eq!(s[0],0.);
+        assert_eq!(s[2],-2.);
+        assert_eq!(s.average(),-1. / 3.);
+        let e = s.exp();
+        assert_eq!(e.r,1.);
+        assert!((e.b - (-2f64).exp()).abs() < 1e-15);
+    }
+
+    #[test]
+    // should clamp HDR values when converting to color
+    fn test_to_color() {
+        let c: Color = Spectrum::new(4.,0.25,-1.).to_color();
+        assert_eq!(c.r,1.);
+        assert_eq!(c.g,0.25);
+        assert_eq!(c.b,0.);
+        assert_eq!(c.a,1.);
+    }
+}
@@ -0,0 +1,86 @@
+use super::spectrum::Spectrum;
+
+/// Wavelengths (nm) where the basis spectra cross over
+const BLUE_EDGE: f64 = 488.0;
+const RED_EDGE: f64 = 593.0;
+/// Width (nm) of the smooth cross over
+const EDGE_WIDTH: f64 = 10.0;
+
+/// Value at wavelength lambda (nm) of a smooth spectrum whose color is rgb
+///
+/// The spectrum blends three smooth box spectra (blue below 488 nm,
+/// red above 593 nm, green in between) that sum to one everywhere, so
+/// grey stays flat, the result is never negative for non-negative rgb
+/// and albedos in [0,1] stay in [0,1]. Uplifting is linear, the same
+/// basis serves albedos and illuminants since spectral rendering
+/// treats the equal energy spectrum as white. Colors round trip
+/// through XYZ within a few percent
+pub fn rgb_to_spectrum(rgb: &Spectrum,lambda: f64) -> f64 {
+    let (red,green,blue) = basis(lambda);
+    rgb.r*red + rgb.g*green + rgb.b*blue
+}
+
+/// Red, green and blue basis spectra at lambda
+fn basis(lambda: f64) -> (f64,f64,f64) {
+    let sigmoid = |x: f64| 1.0 / (1.0 + f64::exp(-x));
+    let blue = 1.0 - sigmoid((lambda - BLUE_EDGE) / EDGE_WIDTH);
+    let red = sigmoid((lambda - RED_EDGE) / EDGE_WIDTH);
+    (red,1.0 - red - blue,blue)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::cie::{x_bar,y_bar,z_bar,xyz_to_rgb_balanced,CIE_Y_INTEGRAL,LAMBDA_MIN,LAMBDA_MAX};
+
+    // color of the uplifted spectrum
+    fn round_trip(rgb: Spectrum) -> Spectrum {
+        let (mut x,mut y,mut z) = (0.,0.,0.);
+        let mut lambda = LAMBDA_MIN;
+        while lambda <= LAMBDA_MAX {
+            let s = rgb_to_spectrum(&rgb,lambda);
+            x += s * x_bar(lambda);
+            y += s * y_bar(lambda);
+            z += s * z_bar(lambda);
+            lambda += 1.;
+        }
+        xyz_to_rgb_balanced(x / CIE_Y_INTEGRAL,y / CIE_Y_INTEGRAL,z / CIE_Y_INTEGRAL)
+    }
+
+    #[test]
+    // grey should be flat, other spectra should stay within range
+    fn test_rgb_to_spectrum() {
+        let grey = Spectrum::new(0.4,0.4,0.4);
+        let red = Spectrum::new(1.,0.,0.);
+        for i in 0..100 {
+            let lambda = 360. + i as f64 * 4.7;
+            assert!((rgb_to_spectrum(&grey,lambda) - 0.4).abs() < 1e-12);
+            let s = rgb_to_spectrum(&red,lambda);
+            assert!((0. ..=1.).contains(&s));
+        }
+        assert!(rgb_to_spectrum(&red,700.) > 0.99);
+        assert!(rgb_to_spectrum(&red,450.) < 0.01);
+    }
+
+    #[test]
+    // colors should survive the trip to a spectrum and back
+    fn test_round_trip() {
+        for rgb in [
+            Spectrum::new(1.,1.,1.),
+            Spectrum::new(1.,0.,0.),
+            Spectrum::new(0.,1.,0.),
+            Spectrum::new(0.,0.,1.),
+            Spectrum::new(0.63,0.065,0.05),
+            Spectrum::new(0.2,0.5,0.9)
+        ] {
+            let back = round_trip(rgb);
+            for c in 0..3 {
+                assert!((back[c] - rgb[c]).abs() < 0.03,"{} became {}",rgb,back);
+            }
+        }
+    }
+}
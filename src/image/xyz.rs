@@ -0,0 +1,87 @@
+use super::{
+    hdr::HdrImage,
+    spectrum,
+    cie::xyz_to_rgb_balanced
+};
+
+/// # XyzFilm
+/// Film accumulating CIE XYZ samples, spectral integrators estimate
+/// XYZ directly and only convert to RGB once the image is done
+///
+/// # Parameters
+/// * width, height (resolution in pixels)
+/// * xyz (weighted sum of the samples of each pixel)
+/// * weights (sum of the sample weights of each pixel)
+pub struct XyzFilm {
+    pub width: usize,
+    pub height: usize,
+    pub xyz: Vec<(f64,f64,f64)>,
+    pub weights: Vec<f64>
+}
+
+impl XyzFilm {
+    /// Construct empty film
+    pub fn new(width: usize,height: usize) -> XyzFilm {
+        XyzFilm {
+            width,
+            height,
+            xyz: vec![(0.0,0.0,0.0); width*height],
+            weights: vec![0.0; width*height]
+        }
+    }
+
+    /// Add a sample to pixel (x,y) with box filter weight
+    pub fn add_sample(&mut self,x: usize,y: usize,xyz: (f64,f64,f64)) {
+        let index = y*self.width + x;
+        let sum = &mut self.xyz[index];
+        sum.0 += xyz.0;
+        sum.1 += xyz.1;
+        sum.2 += xyz.2;
+        self.weights[index] += 1.0;
+    }
+
+    /// Average of each pixel converted to linear sRGB, pixels
+    /// without samples are black
+    pub fn to_image(&self) -> HdrImage {
+        let mut image = HdrImage::new(self.width,self.height,spectrum::BLACK);
+        for (i,pixel) in image.pixels.iter_mut().enumerate() {
+            let weight = self.weights[i];
+            if weight > 0.0 {
+                let (x,y,z) = self.xyz[i];
+                *pixel = xyz_to_rgb_balanced(x / weight,y / weight,z / weight);
+            }
+        }
+        image
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::cie::{x_bar,y_bar,z_bar,CIE_Y_INTEGRAL,LAMBDA_MIN,LAMBDA_MAX};
+
+    #[test]
+    // samples of the equal energy spectrum should average to white
+    fn test_to_image() {
+        let mut film = XyzFilm::new(2,1);
+        let mut lambda = LAMBDA_MIN;
+        let mut n = 0;
+        while lambda <= LAMBDA_MAX {
+            let scale = (LAMBDA_MAX - LAMBDA_MIN + 1.) / CIE_Y_INTEGRAL;
+            film.add_sample(1,0,(x_bar(lambda) * scale,y_bar(lambda) * scale,z_bar(lambda) * scale));
+            lambda += 1.;
+            n += 1;
+        }
+        assert_eq!(film.weights[1],n as f64);
+        let image = film.to_image();
+        assert_eq!(image.at(0,0).r,0.);
+        let white = image.at(1,0);
+        assert!((white.r - 1.).abs() < 1e-3);
+        assert!((white.g - 1.).abs() < 1e-3);
+        assert!((white.b - 1.).abs() < 1e-3);
+    }
+}
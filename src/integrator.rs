@@ -0,0 +1,12 @@
+// integrator
+pub mod path;
+pub mod whitted;
+pub mod debug;
+pub mod ao;
+pub mod bdpt;
+pub mod photon;
+pub mod sppm;
+pub mod mlt;
+pub mod spectral;
+
+pub mod traits;
@@ -0,0 +1,143 @@
+use super::traits::Integrator;
+use crate::{
+    image::spectrum::{self,Spectrum},
+    math::{
+        ray::Ray,
+        vector::Vector,
+        frame::Frame,
+        sampling::cosine_sample_hemisphere,
+        traits::{Dot,Normalize}
+    },
+    sampler::traits::Sampler,
+    scene::world::World
+};
+
+/// # AoIntegrator
+/// Ambient occlusion: fraction of the cosine weighted hemisphere
+/// above the first hit that is not blocked within max_distance
+///
+/// # Parameters
+/// * max_distance (occluders further away than this are ignored)
+/// * samples (occlusion rays per camera ray)
+/// * bent_normal (output the mean unoccluded direction instead of occlusion)
+pub struct AoIntegrator {
+    pub max_distance: f64,
+    pub samples: usize,
+    pub bent_normal: bool
+}
+
+/// Integrator trait
+impl Integrator for AoIntegrator {
+    fn li(&self,ray: &Ray,world: &World,sampler: &mut dyn Sampler) -> Spectrum {
+        let si = match world.intersect(ray) {
+            Some(si) => si,
+            None => return spectrum::BLACK
+        };
+
+        // hemisphere on the side the ray arrived from
+        let n = si.ns.face_forward(si.wo);
+        let frame = Frame::from_normal(&n);
+
+        let mut unoccluded: usize = 0;
+        let mut bent = Vector::new(0.0,0.0,0.0);
+        for _ in 0..self.samples {
+            let wi = frame.to_world(&cosine_sample_hemisphere(sampler.get_2d()));
+            // shading normals can tilt samples below the geometric surface
+            if wi.dot(si.n) * si.wo.dot(si.n) <= 0.0 {
+                continue;
+            }
+            if !world.occluded(&si.spawn_ray(&wi),self.max_distance) {
+                unoccluded += 1;
+                bent = bent + wi;
+            }
+        }
+
+        if self.bent_normal {
+            match bent.normalize() {
+                Ok(b) => Spectrum::new(0.5*b.x + 0.5,0.5*b.y + 0.5,0.5*b.z + 0.5),
+                Err(_) => spectrum::BLACK
+            }
+        } else {
+            // cosine weighted samples, the pdf cancels the cosine term
+            let ao = unoccluded as f64 / self.samples.max(1) as f64;
+            Spectrum::new(ao,ao,ao)
+        }
+    }
+}
+
+impl AoIntegrator {
+    /// Construct ambient occlusion integrator
+    pub fn new(max_distance: f64,samples: usize) -> AoIntegrator {
+        AoIntegrator {
+            max_distance,
+            samples,
+            bent_normal: false
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{point::Point,normal::Normal},
+        sampler::random::RandomSampler,
+        scene::{plane::Plane,sphere::Sphere}
+    };
+
+    fn floor_world() -> World {
+        let mut world = World::new(2);
+        world.add_primitive(Box::new(Plane::new(&Point::new(0.,0.,0.),&Normal::new(0.,1.,0.))));
+        world
+    }
+
+    fn floor_ray() -> Ray {
+        Ray::new(&Point::new(0.,1.,-1.),&Vector::new(0.,-1.,1.))
+    }
+
+    #[test]
+    // open floor should be fully unoccluded, misses are black
+    fn test_ao_open() {
+        let world = floor_world();
+        let mut sampler = RandomSampler::new(1);
+        let integrator = AoIntegrator::new(1.,64);
+        assert_eq!(integrator.li(&floor_ray(),&world,&mut sampler).r,1.);
+        let miss = Ray::new(&Point::new(0.,1.,0.),&Vector::new(0.,1.,0.));
+        assert!(integrator.li(&miss,&world,&mut sampler).is_black());
+    }
+
+    #[test]
+    // a ceiling within max distance blocks everything, beyond it nothing
+    fn test_ao_max_distance() {
+        let mut world = floor_world();
+        world.add_primitive(Box::new(Plane::new(&Point::new(0.,2.,0.),&Normal::new(0.,-1.,0.))));
+        let mut sampler = RandomSampler::new(1);
+        let ray = floor_ray();
+        assert!(AoIntegrator::new(100.,64).li(&ray,&world,&mut sampler).is_black());
+        // rays to the ceiling travel at least 2
+        assert_eq!(AoIntegrator::new(1.9,64).li(&ray,&world,&mut sampler).r,1.);
+    }
+
+    #[test]
+    // a sphere resting on the floor should darken it partially,
+    // and bend the mean unoccluded direction away from the sphere
+    fn test_ao_partial_and_bent_normal() {
+        let mut world = floor_world();
+        world.add_primitive(Box::new(Sphere::new(1.,Point::new(1.2,1.,0.))));
+        let mut sampler = RandomSampler::new(3);
+        let ray = floor_ray();
+        let ao = AoIntegrator::new(10.,512).li(&ray,&world,&mut sampler);
+        assert!(ao.r > 0.3 && ao.r < 0.95,"ao {}",ao.r);
+
+        let mut integrator = AoIntegrator::new(10.,512);
+        integrator.bent_normal = true;
+        let bent = integrator.li(&ray,&world,&mut sampler);
+        // x component remapped from [-1,1], points away from the sphere at +x
+        assert!(bent.r < 0.5);
+        assert!(bent.g > 0.5);
+    }
+}
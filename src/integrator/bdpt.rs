@@ -0,0 +1,750 @@
+use std::sync::Arc;
+
+use super::traits::{Integrator,for_each_sample};
+use crate::{
+    image::{hdr::HdrImage,spectrum::{self,Spectrum}},
+    material::traits::Bsdf,
+    math::{
+        point::Point,
+        vector::Vector,
+        normal::Normal,
+        ray::Ray,
+        traits::{Dot,Normalize}
+    },
+    sampler::traits::Sampler,
+    scene::{
+        world::World,
+        camera::Camera,
+        interaction::{SurfaceInteraction,offset_ray_origin}
+    }
+};
+
+/// Light tracing contribution and the image position it lands on
+type Splat = ((f64,f64),Spectrum);
+
+/// # BdptIntegrator
+/// Bidirectional path tracer: every prefix of a camera subpath is
+/// connected to every prefix of a light subpath, the resulting
+/// strategies are combined with the power heuristic
+///
+/// Paths with a single camera vertex (light tracing) land on arbitrary
+/// pixels and are only included by render, li uses the other strategies.
+/// Environment lights cannot start light subpaths and are only found
+/// by camera paths escaping the scene
+///
+/// # Parameters
+/// * max_depth (maximum number of bounces)
+pub struct BdptIntegrator {
+    pub max_depth: usize
+}
+
+/// Integrator trait
+impl Integrator for BdptIntegrator {
+    fn li(&self,ray: &Ray,world: &World,sampler: &mut dyn Sampler) -> Spectrum {
+        let context = Context::new(world,None);
+        self.trace(&context,ray,sampler).0
+    }
+
+    fn render(&self,world: &World,camera: &Camera,width: usize,height: usize,spp: usize,seed: u64) -> HdrImage {
+        let context = Context::new(world,Some(camera));
+        let mut image = HdrImage::new(width,height,spectrum::BLACK);
+        let mut splats = HdrImage::new(width,height,spectrum::BLACK);
+        for_each_sample(camera,width,height,spp,seed,|x,y,ray,sampler| {
+            let (li,path_splats) = self.trace(&context,ray,sampler);
+            image.pixels[y*width + x] += li;
+            for ((u,v),splat) in path_splats {
+                let sx = ((u * width as f64) as usize).min(width - 1);
+                let sy = ((v * height as f64) as usize).min(height - 1);
+                splats.pixels[sy*width + sx] += splat;
+            }
+        });
+
+        // one light subpath was traced per camera sample
+        for (pixel,splat) in image.pixels.iter_mut().zip(splats.pixels.iter()) {
+            *pixel = (*pixel + *splat) / spp as f64;
+        }
+        image
+    }
+}
+
+impl BdptIntegrator {
+    /// Construct bidirectional path tracer with given maximum depth
+    pub fn new(max_depth: usize) -> BdptIntegrator {
+        BdptIntegrator {max_depth}
+    }
+
+    /// Trace one camera and one light subpath and connect them, returns the
+    /// radiance along ray and the light tracing splats as (image position,value)
+    fn trace(&self,context: &Context,ray: &Ray,sampler: &mut dyn Sampler) -> (Spectrum,Vec<Splat>) {
+        let (camera_path,escaped) = context.camera_subpath(ray,sampler,self.max_depth + 2);
+        let light_path = context.light_subpath(sampler,self.max_depth + 1);
+
+        let mut l = spectrum::BLACK;
+        let mut splats = vec![];
+
+        // environment radiance, no other strategy can find it
+        if let (Some((ray,beta)),Some(environment)) = (escaped,&context.world.environment) {
+            l += beta * environment.le(&ray);
+        }
+
+        for t in 1..=camera_path.len() {
+            for s in 0..=light_path.len() {
+                let depth = s + t;
+                if (s == 1 && t == 1) || depth < 2 || depth - 2 > self.max_depth {
+                    continue;
+                }
+                if t == 1 && context.camera.is_none() {
+                    continue;
+                }
+                match context.connect(&light_path,&camera_path,s,t,sampler) {
+                    (c,Some(uv)) => splats.push((uv,c)),
+                    (c,None) => l += c
+                }
+            }
+        }
+
+        (l,splats)
+    }
+}
+
+/// Vertex type, lights hold their index in the world
+#[derive(Clone,Copy,PartialEq)]
+enum VertexKind {
+    Camera,
+    Light(usize),
+    Surface
+}
+
+/// # Vertex
+/// Path vertex with the densities needed for MIS weights
+///
+/// # Parameters
+/// * kind
+/// * p (position)
+/// * n (geometric normal, None for points off any surface)
+/// * si (surface interaction for surface vertices)
+/// * bsdf (scattering at surface vertices)
+/// * beta (path throughput up to the vertex)
+/// * delta (vertex was scattered by a delta lobe)
+/// * pdf_fwd (area density of the vertex as sampled along its subpath)
+/// * pdf_rev (area density of the vertex if sampled from the other end)
+pub struct Vertex {
+    kind: VertexKind,
+    p: Point,
+    n: Option<Normal>,
+    si: Option<SurfaceInteraction>,
+    bsdf: Option<Box<dyn Bsdf>>,
+    beta: Spectrum,
+    delta: bool,
+    pdf_fwd: f64,
+    pdf_rev: f64
+}
+
+impl Vertex {
+    /// Construct camera vertex at the eye
+    fn camera(p: Point,beta: Spectrum) -> Vertex {
+        Vertex {
+            kind: VertexKind::Camera,
+            p,
+            n: None,
+            si: None,
+            bsdf: None,
+            beta,
+            delta: false,
+            pdf_fwd: 0.0,
+            pdf_rev: 0.0
+        }
+    }
+
+    /// Construct vertex on the light with the given world index
+    fn light(index: usize,p: Point,n: Option<Normal>,beta: Spectrum,pdf_fwd: f64) -> Vertex {
+        Vertex {
+            kind: VertexKind::Light(index),
+            p,
+            n,
+            si: None,
+            bsdf: None,
+            beta,
+            delta: false,
+            pdf_fwd,
+            pdf_rev: 0.0
+        }
+    }
+
+    /// Construct vertex at a surface interaction
+    fn surface(si: SurfaceInteraction,bsdf: Box<dyn Bsdf>,beta: Spectrum) -> Vertex {
+        Vertex {
+            kind: VertexKind::Surface,
+            p: si.p,
+            n: Some(si.n),
+            si: Some(si),
+            bsdf: Some(bsdf),
+            beta,
+            delta: false,
+            pdf_fwd: 0.0,
+            pdf_rev: 0.0
+        }
+    }
+
+    /// World index of the light at the vertex, if it emits
+    fn light_index(&self) -> Option<usize> {
+        match self.kind {
+            VertexKind::Light(index) => Some(index),
+            VertexKind::Surface => self.si.and_then(|si| si.light),
+            VertexKind::Camera => None
+        }
+    }
+
+    /// True if a connection can be made through the vertex
+    fn is_connectible(&self) -> bool {
+        match &self.bsdf {
+            Some(bsdf) => !bsdf.is_specular(),
+            None => true
+        }
+    }
+
+    /// True if the vertex starts a path at a delta light
+    fn is_delta_light(&self,world: &World) -> bool {
+        match self.kind {
+            VertexKind::Light(index) => world.lights[index].is_delta(),
+            _ => false
+        }
+    }
+
+    /// BSDF for scattering towards next, adjoint for light subpaths
+    fn f(&self,next: &Vertex,adjoint: bool) -> Spectrum {
+        let (si,bsdf) = match (&self.si,&self.bsdf) {
+            (Some(si),Some(bsdf)) => (si,bsdf),
+            _ => return spectrum::BLACK
+        };
+        let wi = match (next.p - self.p).normalize() {
+            Ok(wi) => wi,
+            Err(_) => return spectrum::BLACK
+        };
+        let f = bsdf.f(&si.wo,&wi);
+        if adjoint {
+            f * bsdf.adjoint_scale(&si.wo,&wi)
+        } else {
+            f
+        }
+    }
+
+    /// Convert a solid angle density at this vertex to an area density at next
+    fn convert_density(&self,pdf: f64,next: &Vertex) -> f64 {
+        let w: Vector = next.p - self.p;
+        let dist_sq = w.dot(w);
+        if dist_sq == 0.0 {
+            return 0.0
+        }
+        let mut pdf = pdf / dist_sq;
+        if let Some(n) = next.n {
+            pdf *= n.dot(w).abs() / f64::sqrt(dist_sq);
+        }
+        pdf
+    }
+
+    /// Area density at next of sampling it from this vertex, arrived at from prev
+    fn pdf(&self,context: &Context,prev: Option<&Vertex>,next: &Vertex) -> f64 {
+        if let VertexKind::Light(_) = self.kind {
+            return self.pdf_light(context,next)
+        }
+        let wn = match (next.p - self.p).normalize() {
+            Ok(wn) => wn,
+            Err(_) => return 0.0
+        };
+        let pdf = match (self.kind,&self.bsdf,prev) {
+            (VertexKind::Camera,_,_) => context.camera.map_or(0.0,|camera| camera.pdf_we(&wn)),
+            (_,Some(bsdf),Some(prev)) => match (prev.p - self.p).normalize() {
+                Ok(wp) => bsdf.pdf(&wp,&wn),
+                Err(_) => return 0.0
+            },
+            _ => return 0.0
+        };
+        self.convert_density(pdf,next)
+    }
+
+    /// Area density at next of emitting towards it from this light vertex
+    fn pdf_light(&self,context: &Context,next: &Vertex) -> f64 {
+        let index = match self.light_index() {
+            Some(index) => index,
+            None => return 0.0
+        };
+        let w: Vector = next.p - self.p;
+        let dist_sq = w.dot(w);
+        if dist_sq == 0.0 {
+            return 0.0
+        }
+        let w = w * (1.0 / f64::sqrt(dist_sq));
+        let (_,pdf_dir) = context.world.lights[index].pdf_le(self.n,&w);
+        let mut pdf = pdf_dir / dist_sq;
+        if let Some(n) = next.n {
+            pdf *= n.dot(w).abs();
+        }
+        pdf
+    }
+
+    /// Area density of choosing this light vertex as the start of a light subpath
+    fn pdf_light_origin(&self,context: &Context,next: &Vertex) -> f64 {
+        let index = match self.light_index() {
+            Some(index) => index,
+            None => return 0.0
+        };
+        let w = match (next.p - self.p).normalize() {
+            Ok(w) => w,
+            Err(_) => return 0.0
+        };
+        let (pdf_pos,_) = context.world.lights[index].pdf_le(self.n,&w);
+        pdf_pos * context.light_select_pdf()
+    }
+
+    /// Ray from the vertex towards p and the parametric distance to test
+    fn spawn_ray_to(&self,p: &Point) -> (Ray,f64) {
+        if let Some(si) = &self.si {
+            return si.spawn_ray_to(p)
+        }
+        let origin = match self.n {
+            Some(n) => offset_ray_origin(&self.p,&n,&(*p - self.p)),
+            None => self.p
+        };
+        (Ray::new(&origin,&(*p - origin)),1.0 - 1e-4)
+    }
+}
+
+/// Scene data shared by every path of a render
+///
+/// # Parameters
+/// * world
+/// * camera (None when light tracing is disabled)
+/// * lights (world indices of the lights that can start subpaths)
+pub struct Context<'a> {
+    pub world: &'a World,
+    pub camera: Option<&'a Camera>,
+    pub lights: Vec<usize>
+}
+
+impl<'a> Context<'a> {
+    /// Construct context, every light except the environment can start subpaths
+    pub fn new(world: &'a World,camera: Option<&'a Camera>) -> Context<'a> {
+        let lights = (0..world.lights.len())
+            .filter(|&i| match &world.environment {
+                Some(environment) => !Arc::ptr_eq(environment,&world.lights[i]),
+                None => true
+            })
+            .collect();
+        Context {
+            world,
+            camera,
+            lights
+        }
+    }
+
+    /// Probability of starting a light subpath at any one light
+    fn light_select_pdf(&self) -> f64 {
+        if self.lights.is_empty() {
+            0.0
+        } else {
+            1.0 / self.lights.len() as f64
+        }
+    }
+
+    /// Uniformly choose a light, returns its world index
+    fn select_light(&self,u: f64) -> usize {
+        let n = self.lights.len();
+        self.lights[((u * n as f64) as usize).min(n - 1)]
+    }
+
+    /// Camera subpath starting with ray, also returns the ray and throughput
+    /// of a path that escaped the scene
+    pub fn camera_subpath(&self,ray: &Ray,sampler: &mut dyn Sampler,max_vertices: usize) -> (Vec<Vertex>,Option<(Ray,Spectrum)>) {
+        let mut path = Vec::with_capacity(max_vertices);
+        let pdf_dir = self.camera.map_or(0.0,|camera| camera.pdf_we(&ray.d));
+        path.push(Vertex::camera(ray.o,spectrum::WHITE));
+        let escaped = self.random_walk(*ray,spectrum::WHITE,pdf_dir,max_vertices - 1,false,sampler,&mut path);
+        (path,escaped)
+    }
+
+    /// Light subpath starting at a uniformly chosen light
+    pub fn light_subpath(&self,sampler: &mut dyn Sampler,max_vertices: usize) -> Vec<Vertex> {
+        let mut path = Vec::with_capacity(max_vertices);
+        let u_light = sampler.get_1d();
+        let u_pos = sampler.get_2d();
+        let u_dir = sampler.get_2d();
+        if self.lights.is_empty() || max_vertices == 0 {
+            return path
+        }
+
+        let index = self.select_light(u_light);
+        let light_pdf = self.light_select_pdf();
+        let es = match self.world.lights[index].sample_le(u_pos,u_dir) {
+            Some(es) => es,
+            None => return path
+        };
+        if es.pdf_pos == 0.0 || es.pdf_dir == 0.0 || es.le.is_black() {
+            return path
+        }
+
+        path.push(Vertex::light(index,es.p,es.n,es.le,es.pdf_pos * light_pdf));
+        let (origin,cos) = match es.n {
+            Some(n) => (offset_ray_origin(&es.p,&n,&es.w),n.dot(es.w).abs()),
+            None => (es.p,1.0)
+        };
+        let beta = es.le * (cos / (light_pdf * es.pdf_pos * es.pdf_dir));
+        self.random_walk(Ray::new(&origin,&es.w),beta,es.pdf_dir,max_vertices - 1,true,sampler,&mut path);
+        path
+    }
+
+    /// Extend path by sampling BSDFs, pdf is the solid angle density of ray
+    /// leaving the last vertex, returns the escaping ray and throughput if any
+    #[allow(clippy::too_many_arguments)]
+    fn random_walk(&self,mut ray: Ray,mut beta: Spectrum,pdf: f64,max_vertices: usize,adjoint: bool,sampler: &mut dyn Sampler,path: &mut Vec<Vertex>) -> Option<(Ray,Spectrum)> {
+        let mut pdf_fwd = pdf;
+        for bounces in 1..=max_vertices {
+            let si = match self.world.intersect(&ray) {
+                Some(si) => si,
+                None => return Some((ray,beta))
+            };
+            let bsdf = self.world.materials[si.material].bsdf(&si);
+            let mut vertex = Vertex::surface(si,bsdf,beta);
+            let prev = path.len() - 1;
+            vertex.pdf_fwd = path[prev].convert_density(pdf_fwd,&vertex);
+
+            if bounces == max_vertices {
+                path.push(vertex);
+                break;
+            }
+
+            let sample = match vertex.bsdf.as_ref().and_then(|bsdf| bsdf.sample_f(&si.wo,sampler.get_2d())) {
+                Some(sample) if sample.pdf > 0.0 && !sample.f.is_black() => sample,
+                _ => {
+                    path.push(vertex);
+                    break;
+                }
+            };
+            let bsdf = vertex.bsdf.as_ref().unwrap();
+            let mut pdf_rev = bsdf.pdf(&sample.wi,&si.wo);
+            pdf_fwd = sample.pdf;
+            let mut f = sample.f;
+            if adjoint {
+                f = f * bsdf.adjoint_scale(&si.wo,&sample.wi);
+            }
+            beta = beta * f * (sample.wi.dot(si.ns).abs() / sample.pdf);
+            if sample.specular {
+                vertex.delta = true;
+                pdf_rev = 0.0;
+                pdf_fwd = 0.0;
+            }
+
+            path[prev].pdf_rev = vertex.convert_density(pdf_rev,&path[prev]);
+            ray = si.spawn_ray(&sample.wi);
+            path.push(vertex);
+        }
+        None
+    }
+
+    /// Generalized geometry term between two vertices, zero if occluded
+    fn g(&self,a: &Vertex,b: &Vertex) -> f64 {
+        let d: Vector = a.p - b.p;
+        let dist_sq = d.dot(d);
+        if dist_sq == 0.0 {
+            return 0.0
+        }
+        let d = d * (1.0 / f64::sqrt(dist_sq));
+        let mut g = 1.0 / dist_sq;
+        if let Some(n) = a.n {
+            g *= n.dot(d).abs();
+        }
+        if let Some(n) = b.n {
+            g *= n.dot(d).abs();
+        }
+        if g == 0.0 || !self.unoccluded(a,b) {
+            return 0.0
+        }
+        g
+    }
+
+    /// True if nothing blocks the segment between two vertices
+    fn unoccluded(&self,a: &Vertex,b: &Vertex) -> bool {
+        let (ray,tmax) = a.spawn_ray_to(&b.p);
+        !self.world.occluded(&ray,tmax)
+    }
+
+    /// Contribution of the strategy using s light and t camera vertices,
+    /// light tracing (t = 1) strategies also return their image position
+    pub fn connect(&self,light_path: &[Vertex],camera_path: &[Vertex],s: usize,t: usize,sampler: &mut dyn Sampler) -> (Spectrum,Option<(f64,f64)>) {
+        let mut l = spectrum::BLACK;
+        let mut sampled: Option<Vertex> = None;
+        let mut uv = None;
+
+        if s == 0 {
+            // camera path hit an emitter
+            let pt = &camera_path[t-1];
+            if let (Some(_),Some(si)) = (pt.light_index(),&pt.si) {
+                l = self.world.emitted(si) * pt.beta;
+            }
+        } else if t == 1 {
+            // connect the light subpath to the camera
+            let qs = &light_path[s-1];
+            if let (Some(camera),true) = (self.camera,qs.is_connectible()) {
+                if let Some(cs) = camera.sample_wi(&qs.p) {
+                    if cs.pdf > 0.0 && cs.we > 0.0 {
+                        let vertex = Vertex::camera(camera.eye,spectrum::WHITE * (cs.we / cs.pdf));
+                        l = qs.beta * qs.f(&vertex,true) * vertex.beta;
+                        if let Some(n) = qs.n {
+                            l = l * n.dot(cs.wi).abs();
+                        }
+                        if !l.is_black() && !self.unoccluded(qs,&vertex) {
+                            l = spectrum::BLACK;
+                        }
+                        uv = Some(cs.uv);
+                        sampled = Some(vertex);
+                    }
+                }
+            }
+        } else if s == 1 {
+            // sample a point on a light (next event estimation)
+            let pt = &camera_path[t-1];
+            if pt.is_connectible() && !self.lights.is_empty() {
+                let index = self.select_light(sampler.get_1d());
+                let u = sampler.get_2d();
+                if let Some(ls) = self.world.lights[index].sample_li(&pt.p,u) {
+                    if ls.pdf > 0.0 && !ls.li.is_black() {
+                        let mut vertex = Vertex::light(
+                            index,
+                            pt.p + ls.wi*ls.distance,
+                            ls.n,
+                            ls.li / (ls.pdf * self.light_select_pdf()),
+                            0.0
+                        );
+                        vertex.pdf_fwd = vertex.pdf_light_origin(self,pt);
+                        l = pt.beta * pt.f(&vertex,false) * vertex.beta;
+                        if let Some(n) = pt.n {
+                            l = l * n.dot(ls.wi).abs();
+                        }
+                        if !l.is_black() && !self.unoccluded(pt,&vertex) {
+                            l = spectrum::BLACK;
+                        }
+                        sampled = Some(vertex);
+                    }
+                }
+            }
+        } else {
+            // connect the two subpaths with a segment
+            let qs = &light_path[s-1];
+            let pt = &camera_path[t-1];
+            if qs.is_connectible() && pt.is_connectible() {
+                l = qs.beta * qs.f(pt,true) * pt.f(qs,false) * pt.beta;
+                if !l.is_black() {
+                    l = l * self.g(qs,pt);
+                }
+            }
+        }
+
+        if l.is_black() {
+            return (spectrum::BLACK,uv)
+        }
+        let weight = self.mis_weight(light_path,camera_path,sampled.as_ref(),s,t);
+        (l * weight,uv)
+    }
+
+    /// Power heuristic weight of the strategy (s,t) against every other
+    /// strategy that could have generated the same path
+    fn mis_weight(&self,light_path: &[Vertex],camera_path: &[Vertex],sampled: Option<&Vertex>,s: usize,t: usize) -> f64 {
+        if s + t == 2 {
+            return 1.0
+        }
+        let remap0 = |f: f64| if f != 0.0 { f } else { 1.0 };
+
+        // connection vertices, using the sampled vertex where one was made
+        let qs = match (s,sampled) {
+            (0,_) => None,
+            (1,Some(sampled)) => Some(sampled),
+            _ => Some(&light_path[s-1])
+        };
+        let pt = match (t,sampled) {
+            (1,Some(sampled)) => sampled,
+            _ => &camera_path[t-1]
+        };
+        let qs_minus = if s > 1 { Some(&light_path[s-2]) } else { None };
+        let pt_minus = if t > 1 { Some(&camera_path[t-2]) } else { None };
+
+        // densities along the full path as seen from this strategy
+        let mut camera_fwd: Vec<f64> = camera_path[..t].iter().map(|v| v.pdf_fwd).collect();
+        let mut camera_rev: Vec<f64> = camera_path[..t].iter().map(|v| v.pdf_rev).collect();
+        let mut camera_delta: Vec<bool> = camera_path[..t].iter().map(|v| v.delta).collect();
+        let mut light_fwd: Vec<f64> = light_path[..s].iter().map(|v| v.pdf_fwd).collect();
+        let mut light_rev: Vec<f64> = light_path[..s].iter().map(|v| v.pdf_rev).collect();
+        let mut light_delta: Vec<bool> = light_path[..s].iter().map(|v| v.delta).collect();
+
+        camera_fwd[t-1] = pt.pdf_fwd;
+        camera_delta[t-1] = false;
+        camera_rev[t-1] = match (qs,pt_minus) {
+            (Some(qs),_) => qs.pdf(self,qs_minus,pt),
+            (None,Some(pt_minus)) => pt.pdf_light_origin(self,pt_minus),
+            (None,None) => 0.0
+        };
+        if let Some(pt_minus) = pt_minus {
+            camera_rev[t-2] = match qs {
+                Some(qs) => pt.pdf(self,Some(qs),pt_minus),
+                None => pt.pdf_light(self,pt_minus)
+            };
+        }
+        if let Some(qs) = qs {
+            light_fwd[s-1] = qs.pdf_fwd;
+            light_delta[s-1] = false;
+            light_rev[s-1] = pt.pdf(self,pt_minus,qs);
+            if let Some(qs_minus) = qs_minus {
+                light_rev[s-2] = qs.pdf(self,Some(pt),qs_minus);
+            }
+        }
+
+        // strategies with shorter camera subpaths
+        let mut sum = 0.0;
+        let mut ri = 1.0;
+        for i in (1..t).rev() {
+            ri *= remap0(camera_rev[i]) / remap0(camera_fwd[i]);
+            // light tracing is only available when splatting to an image
+            let available = i > 1 || self.camera.is_some();
+            if available && !camera_delta[i] && !camera_delta[i-1] {
+                sum += ri;
+            }
+        }
+
+        // strategies with shorter light subpaths
+        let mut ri = 1.0;
+        for i in (0..s).rev() {
+            ri *= remap0(light_rev[i]) / remap0(light_fwd[i]);
+            let delta_light_vertex = if i > 0 {
+                light_delta[i-1]
+            } else {
+                qs.filter(|_| s == 1).unwrap_or(&light_path[0]).is_delta_light(self.world)
+            };
+            if !light_delta[i] && !delta_light_vertex {
+                sum += ri;
+            }
+        }
+
+        1.0 / (1.0 + sum)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_1_PI;
+    use crate::{
+        image::hdr::HdrImage,
+        light::{point::PointLight,environment::EnvironmentLight},
+        material::lambertian::Lambertian,
+        sampler::random::RandomSampler,
+        scene::{plane::Plane,sphere::Sphere,cornell::cornell_box}
+    };
+
+    const REFERENCE_SIZE: usize = 24;
+    const REFERENCE_PATH: &str = "image/reference/cornell_box.hdr";
+
+    #[test]
+    // diffuse floor under a point light, every strategy but
+    // the ones hitting the light should add up to direct lighting
+    fn test_li_point_light() {
+        let mut world = World::new(1);
+        let white = world.add_material(Box::new(Lambertian::new(spectrum::WHITE)));
+        world.add_primitive_with_material(Box::new(Plane::new(&Point::new(0.,0.,0.),&Normal::new(0.,1.,0.))),white);
+        world.add_light(Box::new(PointLight::new(Point::new(0.,2.,0.),Spectrum::new(4.,4.,4.))));
+
+        let integrator = BdptIntegrator::new(1);
+        let mut sampler = RandomSampler::new(1);
+        let ray = Ray::new(&Point::new(0.,1.,-1.),&Vector::new(0.,-1.,1.));
+        let l = integrator.li(&ray,&world,&mut sampler);
+        assert!((l.r - FRAC_1_PI).abs() < 1e-9);
+    }
+
+    #[test]
+    // emitters seen directly should be returned unweighted
+    fn test_li_direct_emitter() {
+        let mut world = World::new(1);
+        world.add_area_light(Box::new(Sphere::new(1.,Point::new(0.,0.,5.))),0,Spectrum::new(2.,3.,4.),false);
+        let integrator = BdptIntegrator::new(0);
+        let mut sampler = RandomSampler::new(3);
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
+        let l = integrator.li(&ray,&world,&mut sampler);
+        assert_eq!(l.r,2.);
+        assert_eq!(l.b,4.);
+    }
+
+    #[test]
+    // without light tracing the weights of the remaining strategies
+    // should still sum to one, so li alone converges to the same result
+    fn test_li_matches_path_tracer() {
+        use crate::integrator::path::PathIntegrator;
+
+        let (world,camera) = cornell_box(1.);
+        let ray = camera.generate_ray(0.3,0.6);
+        let n = 4000;
+        let mut sampler = RandomSampler::new(5);
+        let mut bdpt = spectrum::BLACK;
+        let mut path = spectrum::BLACK;
+        for _ in 0..n {
+            bdpt += BdptIntegrator::new(5).li(&ray,&world,&mut sampler);
+            path += PathIntegrator::new(5).li(&ray,&world,&mut sampler);
+        }
+        let (bdpt,path) = (bdpt.luminance() / n as f64,path.luminance() / n as f64);
+        assert!((bdpt - path).abs() < 0.05 * path,"bdpt {} path {}",bdpt,path);
+    }
+
+    #[test]
+    // camera paths escaping the scene should see the environment, a diffuse
+    // sphere under it should converge to the path tracer's radiance
+    fn test_li_environment() {
+        use crate::integrator::path::PathIntegrator;
+
+        let mut world = World::new(1);
+        let grey = world.add_material(Box::new(Lambertian::new(Spectrum::new(0.5,0.5,0.5))));
+        world.add_primitive_with_material(Box::new(Sphere::new(1.,Point::new(0.,0.,5.))),grey);
+        world.set_environment(Box::new(EnvironmentLight::new(HdrImage::new(4,2,Spectrum::new(0.5,0.5,0.5)),0.,1.)));
+
+        let mut sampler = RandomSampler::new(7);
+        let miss = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,-1.));
+        assert_eq!(BdptIntegrator::new(5).li(&miss,&world,&mut sampler).r,0.5);
+
+        let ray = Ray::new(&Point::new(0.,0.3,0.),&Vector::new(0.,0.,1.));
+        let n = 4000;
+        let mut bdpt = spectrum::BLACK;
+        let mut path = spectrum::BLACK;
+        for _ in 0..n {
+            bdpt += BdptIntegrator::new(5).li(&ray,&world,&mut sampler);
+            path += PathIntegrator::new(5).li(&ray,&world,&mut sampler);
+        }
+        let (bdpt,path) = (bdpt.luminance() / n as f64,path.luminance() / n as f64);
+        // a convex sphere only sees the environment, so both give albedo times 0.5
+        assert!((path - 0.25).abs() < 0.0125,"path {}",path);
+        assert!((bdpt - path).abs() < 0.05 * path,"bdpt {} path {}",bdpt,path);
+    }
+
+    #[test]
+    // low sample count render of the cornell box should agree with the
+    // reference rendered by the path tracer
+    fn test_cornell_box_convergence() {
+        let path = format!("{}/{}",env!("CARGO_MANIFEST_DIR"),REFERENCE_PATH);
+        let reference = HdrImage::load(&path).unwrap();
+
+        let (world,camera) = cornell_box(1.);
+        let image = BdptIntegrator::new(8).render(&world,&camera,REFERENCE_SIZE,REFERENCE_SIZE,32,1);
+
+        let mut sum_image = 0.;
+        let mut sum_reference = 0.;
+        let mut abs_error = 0.;
+        for (a,b) in image.pixels.iter().zip(reference.pixels.iter()) {
+            sum_image += a.luminance();
+            sum_reference += b.luminance();
+            abs_error += (a.luminance() - b.luminance()).abs();
+        }
+        let relative_mean = (sum_image - sum_reference).abs() / sum_reference;
+        let relative_error = abs_error / sum_reference;
+        assert!(relative_mean < 0.04,"mean differs by {}",relative_mean);
+        assert!(relative_error < 0.15,"per pixel error {}",relative_error);
+    }
+}
@@ -0,0 +1,207 @@
+use super::traits::Integrator;
+use crate::{
+    image::spectrum::{self,Spectrum},
+    math::{ray::Ray,normal::Normal,traits::Dot},
+    sampler::traits::Sampler,
+    scene::world::World
+};
+
+/// # DebugMode
+/// Quantity visualized by the debug integrator
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum DebugMode {
+    ShadingNormal,
+    GeometricNormal,
+    Uv,
+    Depth,
+    PrimitiveId,
+    MaterialId,
+    Barycentric,
+    FacingRatio,
+    Heatmap
+}
+
+/// every mode in the order they are bound to number keys 1-9
+pub const MODES: [DebugMode; 9] = [
+    DebugMode::ShadingNormal,
+    DebugMode::GeometricNormal,
+    DebugMode::Uv,
+    DebugMode::Depth,
+    DebugMode::PrimitiveId,
+    DebugMode::MaterialId,
+    DebugMode::Barycentric,
+    DebugMode::FacingRatio,
+    DebugMode::Heatmap
+];
+
+/// # DebugIntegrator
+/// Shows geometric information about the first hit instead of radiance
+///
+/// # Parameters
+/// * mode (what to visualize)
+/// * far (distance mapped to black in depth mode)
+/// * heatmap_max (intersection work mapped to red in heatmap mode)
+pub struct DebugIntegrator {
+    pub mode: DebugMode,
+    pub far: f64,
+    pub heatmap_max: usize
+}
+
+/// Integrator trait
+impl Integrator for DebugIntegrator {
+    fn li(&self,ray: &Ray,world: &World,_sampler: &mut dyn Sampler) -> Spectrum {
+        let mut tests: usize = 0;
+        let hit = world.intersect_counted(ray,&mut tests);
+
+        // the heatmap is meaningful for misses too
+        if self.mode == DebugMode::Heatmap {
+            return heatmap(tests as f64 / self.heatmap_max as f64)
+        }
+
+        let si = match hit {
+            Some(si) => si,
+            None => return spectrum::BLACK
+        };
+
+        match self.mode {
+            DebugMode::ShadingNormal => normal_to_spectrum(&si.ns),
+            DebugMode::GeometricNormal => normal_to_spectrum(&si.n),
+            DebugMode::Uv => Spectrum::new(fract(si.uv.0),fract(si.uv.1),0.0),
+            DebugMode::Depth => {
+                let depth = 1.0 - (si.t / self.far).clamp(0.0,1.0);
+                Spectrum::new(depth,depth,depth)
+            },
+            DebugMode::PrimitiveId => id_to_spectrum(si.primitive),
+            DebugMode::MaterialId => id_to_spectrum(si.material),
+            DebugMode::Barycentric => match si.barycentric {
+                Some((b1,b2)) => Spectrum::new(1.0 - b1 - b2,b1,b2),
+                // not built from triangles
+                None => Spectrum::new(1.0,0.0,1.0)
+            },
+            DebugMode::FacingRatio => {
+                let facing = si.n.dot(si.wo).abs();
+                Spectrum::new(facing,facing,facing)
+            },
+            DebugMode::Heatmap => unreachable!()
+        }
+    }
+}
+
+impl DebugIntegrator {
+    /// Construct debug integrator showing the given mode
+    pub fn new(mode: DebugMode) -> DebugIntegrator {
+        DebugIntegrator {
+            mode,
+            far: 10.0,
+            heatmap_max: 16
+        }
+    }
+}
+
+/// Map unit normal components from [-1,1] to [0,1]
+fn normal_to_spectrum(n: &Normal) -> Spectrum {
+    Spectrum::new(0.5*n.x + 0.5,0.5*n.y + 0.5,0.5*n.z + 0.5)
+}
+
+/// Fractional part, keeps tiled uvs in [0,1)
+fn fract(x: f64) -> f64 {
+    x - x.floor()
+}
+
+/// Stable, well separated color for an index
+fn id_to_spectrum(id: usize) -> Spectrum {
+    // integer hash (lowbias32) so neighbouring ids differ
+    let mut h = id as u32 ^ 0x9e37_79b9;
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^= h >> 16;
+    Spectrum::new(
+        (h & 0xff) as f64 / 255.0,
+        ((h >> 8) & 0xff) as f64 / 255.0,
+        ((h >> 16) & 0xff) as f64 / 255.0
+    )
+}
+
+/// Blue (0) to green (0.5) to red (1) ramp
+fn heatmap(x: f64) -> Spectrum {
+    let x = x.clamp(0.0,1.0);
+    if x < 0.5 {
+        Spectrum::new(0.0,2.0*x,1.0 - 2.0*x)
+    } else {
+        Spectrum::new(2.0*x - 1.0,2.0 - 2.0*x,0.0)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{point::Point,vector::Vector},
+        sampler::random::RandomSampler,
+        scene::sphere::Sphere
+    };
+
+    fn test_world() -> World {
+        let mut world = World::new(2);
+        world.add_primitive(Box::new(Sphere::new(1.,Point::new(0.,0.,5.))));
+        world.add_primitive(Box::new(Sphere::new(1.,Point::new(0.,0.,9.))));
+        world
+    }
+
+    fn render(mode: DebugMode,ray: &Ray) -> Spectrum {
+        let mut sampler = RandomSampler::new(0);
+        DebugIntegrator::new(mode).li(ray,&test_world(),&mut sampler)
+    }
+
+    #[test]
+    // normals should be remapped to [0,1]
+    fn test_normals() {
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
+        let n = render(DebugMode::GeometricNormal,&ray);
+        assert_eq!(n.r,0.5);
+        assert_eq!(n.g,0.5);
+        assert_eq!(n.b,0.);
+        let ns = render(DebugMode::ShadingNormal,&ray);
+        assert_eq!(ns.b,0.);
+    }
+
+    #[test]
+    // depth should fade with distance and facing ratio should be one head on
+    fn test_depth_and_facing() {
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
+        assert!((render(DebugMode::Depth,&ray).r - 0.6).abs() < 1e-12);
+        assert!((render(DebugMode::FacingRatio,&ray).r - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    // ids should be deterministic and differ between primitives
+    fn test_ids() {
+        assert_eq!(id_to_spectrum(3).r,id_to_spectrum(3).r);
+        let a = id_to_spectrum(0);
+        let b = id_to_spectrum(1);
+        assert!(a.r != b.r || a.g != b.g || a.b != b.b);
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
+        let p = render(DebugMode::PrimitiveId,&ray);
+        assert_eq!(p.g,a.g);
+        // no barycentrics on spheres
+        assert_eq!(render(DebugMode::Barycentric,&ray).g,0.);
+    }
+
+    #[test]
+    // heatmap should count every primitive test, even on a miss
+    fn test_heatmap() {
+        let miss = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,-1.));
+        let h = render(DebugMode::Heatmap,&miss);
+        // 2 of 16 tests
+        assert_eq!(h.b,0.75);
+        assert_eq!(heatmap(1.).r,1.);
+        assert_eq!(heatmap(0.).b,1.);
+        assert!(render(DebugMode::Uv,&miss).is_black());
+    }
+}
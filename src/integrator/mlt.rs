@@ -0,0 +1,288 @@
+use std::thread;
+
+use rand::{Rng,SeedableRng,rngs::StdRng};
+
+use super::{
+    traits::Integrator,
+    bdpt::{BdptIntegrator,Context}
+};
+use crate::{
+    image::{hdr::HdrImage,spectrum::{self,Spectrum}},
+    math::{ray::Ray,sampling::Distribution1D},
+    sampler::{traits::Sampler,mlt::MltSampler},
+    scene::{world::World,camera::Camera}
+};
+
+/// Sample streams of the primary sample space
+const CAMERA_STREAM: usize = 0;
+const LIGHT_STREAM: usize = 1;
+const CONNECTION_STREAM: usize = 2;
+const STREAM_COUNT: usize = 3;
+
+/// # MltIntegrator
+/// Primary sample space Metropolis light transport over bidirectional
+/// path tracing: each Markov chain mutates the random numbers fed to a
+/// single BDPT strategy for one path depth and splats the result
+///
+/// The image is normalized by the mean luminance of bootstrap samples.
+/// Chains are independent and run on separate threads. Metropolis
+/// sampling works on whole images, li falls back to plain BDPT
+///
+/// # Parameters
+/// * max_depth (maximum number of bounces)
+/// * bootstrap_samples (samples per depth used to seed chains and normalize)
+/// * chains (number of independent Markov chains)
+/// * sigma (standard deviation of small step mutations)
+/// * large_step_probability (chance of an independent large step mutation)
+pub struct MltIntegrator {
+    pub max_depth: usize,
+    pub bootstrap_samples: usize,
+    pub chains: usize,
+    pub sigma: f64,
+    pub large_step_probability: f64
+}
+
+/// Integrator trait
+impl Integrator for MltIntegrator {
+    fn li(&self,ray: &Ray,world: &World,sampler: &mut dyn Sampler) -> Spectrum {
+        BdptIntegrator::new(self.max_depth).li(ray,world,sampler)
+    }
+
+    /// Render with spp mutations per pixel on average
+    fn render(&self,world: &World,camera: &Camera,width: usize,height: usize,spp: usize,seed: u64) -> HdrImage {
+        let context = Context::new(world,Some(camera));
+        let depths = self.max_depth + 1;
+
+        // bootstrap, one independent large step per sample and depth
+        let bootstrap: Vec<f64> = (0..self.bootstrap_samples * depths)
+            .map(|index| {
+                let mut sampler = self.chain_sampler(seed,index);
+                self.l(&context,&mut sampler,index % depths).0.luminance()
+            })
+            .collect();
+        let b = bootstrap.iter().sum::<f64>() / self.bootstrap_samples as f64;
+        let mut image = HdrImage::new(width,height,spectrum::BLACK);
+        if b == 0.0 {
+            return image
+        }
+        let distribution = Distribution1D::new(&bootstrap);
+
+        // split the mutations between chains, grouped onto threads
+        let total = spp * width * height;
+        let threads = thread::available_parallelism().map_or(1,|n| n.get()).min(self.chains).max(1);
+        let splats: Vec<Vec<Spectrum>> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|thread_index| {
+                    let context = &context;
+                    let distribution = &distribution;
+                    scope.spawn(move || {
+                        let mut splats = vec![spectrum::BLACK; width*height];
+                        for chain in (thread_index..self.chains).step_by(threads) {
+                            let mutations = (chain + 1) * total / self.chains - chain * total / self.chains;
+                            self.run_chain(context,distribution,seed,chain,mutations,width,height,&mut splats);
+                        }
+                        splats
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+
+        // sum in a fixed order so renders are reproducible
+        for thread_splats in splats {
+            for (pixel,splat) in image.pixels.iter_mut().zip(thread_splats.iter()) {
+                *pixel += *splat;
+            }
+        }
+        let scale = b / spp as f64;
+        for pixel in image.pixels.iter_mut() {
+            *pixel = *pixel * scale;
+        }
+        image
+    }
+}
+
+impl MltIntegrator {
+    /// Construct Metropolis integrator with common mutation settings
+    pub fn new(max_depth: usize,bootstrap_samples: usize,chains: usize) -> MltIntegrator {
+        MltIntegrator {
+            max_depth,
+            bootstrap_samples,
+            chains,
+            sigma: 0.01,
+            large_step_probability: 0.3
+        }
+    }
+
+    /// Sampler of bootstrap sample index, chains restart from the same state
+    fn chain_sampler(&self,seed: u64,index: usize) -> MltSampler {
+        let seed = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15).wrapping_add(index as u64);
+        MltSampler::new(seed,self.sigma,self.large_step_probability,STREAM_COUNT)
+    }
+
+    /// Run one Markov chain started from a bootstrap sample,
+    /// accumulating its splats into the image
+    #[allow(clippy::too_many_arguments)]
+    fn run_chain(&self,context: &Context,distribution: &Distribution1D,seed: u64,chain: usize,mutations: usize,width: usize,height: usize,splats: &mut [Spectrum]) {
+        let depths = self.max_depth + 1;
+        let mut rng = StdRng::seed_from_u64(seed ^ (chain as u64 + 1).wrapping_mul(0xbf58_476d_1ce4_e5b9));
+        let (index,_) = distribution.sample_discrete(rng.gen::<f64>());
+        let depth = index % depths;
+
+        let mut sampler = self.chain_sampler(seed,index);
+        let (mut l_current,mut uv_current) = self.l(context,&mut sampler,depth);
+        let mut splat = |(u,v): (f64,f64),l: Spectrum| {
+            let x = ((u * width as f64) as usize).min(width - 1);
+            let y = ((v * height as f64) as usize).min(height - 1);
+            splats[y*width + x] += l;
+        };
+
+        for _ in 0..mutations {
+            sampler.start_iteration();
+            let (l_proposed,uv_proposed) = self.l(context,&mut sampler,depth);
+            let y_current = l_current.luminance();
+            let y_proposed = l_proposed.luminance();
+            let accept = if y_current > 0.0 { f64::min(1.0,y_proposed / y_current) } else { 1.0 };
+
+            // expected values of both states
+            if accept > 0.0 && y_proposed > 0.0 {
+                splat(uv_proposed,l_proposed * (accept / y_proposed));
+            }
+            if accept < 1.0 {
+                splat(uv_current,l_current * ((1.0 - accept) / y_current));
+            }
+
+            if rng.gen::<f64>() < accept {
+                l_current = l_proposed;
+                uv_current = uv_proposed;
+                sampler.accept();
+            } else {
+                sampler.reject();
+            }
+        }
+    }
+
+    /// Radiance of a path of the given depth built from the sampler's
+    /// primary samples, with the image position it lands on. A single
+    /// strategy is chosen so the result is scaled by the strategy count
+    fn l(&self,context: &Context,sampler: &mut MltSampler,depth: usize) -> (Spectrum,(f64,f64)) {
+        sampler.start_stream(CAMERA_STREAM);
+        let (strategies,s,t) = if depth == 0 {
+            (1,0,2)
+        } else {
+            let strategies = depth + 2;
+            let s = ((sampler.get_1d() * strategies as f64) as usize).min(strategies - 1);
+            (strategies,s,strategies - s)
+        };
+        let uv = sampler.get_2d();
+        let camera = match context.camera {
+            Some(camera) => camera,
+            None => return (spectrum::BLACK,uv)
+        };
+        let ray = camera.generate_ray(uv.0,uv.1);
+
+        let (camera_path,escaped) = context.camera_subpath(&ray,sampler,t);
+        if camera_path.len() != t {
+            // a path escaping one vertex early is lit by the environment
+            if let (0,Some((ray,beta)),Some(environment)) = (s,escaped,&context.world.environment) {
+                if camera_path.len() + 1 == t {
+                    return (beta * environment.le(&ray) * strategies as f64,uv)
+                }
+            }
+            return (spectrum::BLACK,uv)
+        }
+
+        sampler.start_stream(LIGHT_STREAM);
+        let light_path = context.light_subpath(sampler,s);
+        if light_path.len() != s {
+            return (spectrum::BLACK,uv)
+        }
+
+        sampler.start_stream(CONNECTION_STREAM);
+        let (l,splat_uv) = context.connect(&light_path,&camera_path,s,t,sampler);
+        (l * strategies as f64,splat_uv.unwrap_or(uv))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        light::environment::EnvironmentLight,
+        material::lambertian::Lambertian,
+        math::{point::Point,vector::Vector},
+        scene::{cornell::cornell_box,sphere::Sphere}
+    };
+
+    const REFERENCE_SIZE: usize = 24;
+    const REFERENCE_PATH: &str = "image/reference/cornell_box.hdr";
+
+    #[test]
+    // bootstrap normalization should keep the overall brightness of the
+    // reference, chains should cover the whole image
+    fn test_cornell_box_convergence() {
+        let path = format!("{}/{}",env!("CARGO_MANIFEST_DIR"),REFERENCE_PATH);
+        let reference = HdrImage::load(&path).unwrap();
+
+        let (world,camera) = cornell_box(1.);
+        let image = MltIntegrator::new(8,20000,256).render(&world,&camera,REFERENCE_SIZE,REFERENCE_SIZE,64,1);
+
+        // compare 4x4 pixel blocks, single pixels are noisy at this mutation count
+        let block = |image: &HdrImage,bx: usize,by: usize| -> f64 {
+            let mut sum = 0.;
+            for y in by*4..by*4 + 4 {
+                for x in bx*4..bx*4 + 4 {
+                    sum += image.at(x,y).luminance();
+                }
+            }
+            sum
+        };
+        let mut sum_image = 0.;
+        let mut sum_reference = 0.;
+        let mut abs_error = 0.;
+        for by in 0..REFERENCE_SIZE/4 {
+            for bx in 0..REFERENCE_SIZE/4 {
+                let (a,b) = (block(&image,bx,by),block(&reference,bx,by));
+                sum_image += a;
+                sum_reference += b;
+                abs_error += (a - b).abs();
+            }
+        }
+        let relative_mean = (sum_image - sum_reference).abs() / sum_reference;
+        let relative_error = abs_error / sum_reference;
+        assert!(relative_mean < 0.05,"mean differs by {}",relative_mean);
+        assert!(relative_error < 0.2,"per block error {}",relative_error);
+    }
+
+    #[test]
+    // a scene without lights should render black
+    fn test_render_dark() {
+        let world = World::new(0);
+        let (_,camera) = cornell_box(1.);
+        let image = MltIntegrator::new(4,100,2).render(&world,&camera,4,4,4,1);
+        assert!(image.pixels.iter().all(|p| p.is_black()));
+    }
+
+    #[test]
+    // an environment lit sphere should keep its sky, the overall
+    // brightness should match the path tracer
+    fn test_render_environment() {
+        use crate::integrator::path::PathIntegrator;
+
+        let mut world = World::new(1);
+        let grey = world.add_material(Box::new(Lambertian::new(Spectrum::new(0.5,0.5,0.5))));
+        world.add_primitive_with_material(Box::new(Sphere::new(1.,Point::new(0.,0.,5.))),grey);
+        world.set_environment(Box::new(EnvironmentLight::new(HdrImage::new(4,2,Spectrum::new(0.5,0.5,0.5)),0.,1.)));
+        let camera = Camera::new(Point::new(0.,0.,0.),Point::new(0.,0.,5.),Vector::new(0.,1.,0.),30.,1.).unwrap();
+
+        let image = MltIntegrator::new(4,4000,16).render(&world,&camera,8,8,64,1);
+        let reference = PathIntegrator::new(4).render(&world,&camera,8,8,64,1);
+        let mean = |image: &HdrImage| image.pixels.iter().map(|p| p.luminance()).sum::<f64>() / image.pixels.len() as f64;
+        assert!(!image.at(0,0).is_black());
+        assert!((mean(&image) - mean(&reference)).abs() < 0.05 * mean(&reference),"mlt {} path {}",mean(&image),mean(&reference));
+    }
+}
@@ -0,0 +1,533 @@
+use std::ops::{AddAssign,Div,Mul};
+
+use super::traits::Integrator;
+use crate::{
+    image::spectrum::{self,Spectrum},
+    light::traits::{Light,LightSample},
+    material::traits::{Bsdf,Material},
+    math::{
+        point::Point,
+        ray::Ray,
+        sampling::power_heuristic,
+        traits::Dot
+    },
+    medium::traits::MediumInteraction,
+    sampler::traits::Sampler,
+    scene::{world::World,interaction::SurfaceInteraction}
+};
+
+/// # PathIntegrator
+/// Unidirectional path tracer with next event estimation,
+/// combining light and BSDF sampling with multiple importance sampling
+///
+/// Paths track the medium they travel through, starting in the camera
+/// medium. Scattering events are sampled inside media and lit with
+/// next event estimation through the phase function, shadow rays pick
+/// up the transmittance of every medium they cross
+///
+/// # Parameters
+/// * max_depth (maximum number of bounces)
+/// * rr_depth (bounces before russian roulette starts)
+pub struct PathIntegrator {
+    pub max_depth: usize,
+    pub rr_depth: usize
+}
+
+/// Integrator trait
+impl Integrator for PathIntegrator {
+    fn li(&self,ray: &Ray,world: &World,sampler: &mut dyn Sampler) -> Spectrum {
+        self.trace::<Spectrum>(ray,world,sampler,&mut ())
+    }
+}
+
+impl PathIntegrator {
+    /// Construct path tracer with given maximum depth
+    pub fn new(max_depth: usize) -> PathIntegrator {
+        PathIntegrator {
+            max_depth,
+            rr_depth: 3
+        }
+    }
+
+    /// Radiance along ray carried as S, the path loop shared by the RGB
+    /// and spectral path tracers. RGB quantities of the scene are
+    /// converted with context before they enter the path
+    pub fn trace<S: PathSpectrum>(&self,ray: &Ray,world: &World,sampler: &mut dyn Sampler,context: &mut S::Context) -> S {
+        let mut l = S::ZERO;
+        let mut beta = S::ONE;
+        let mut ray: Ray = *ray;
+        let mut depth: usize = 0;
+        let mut medium: Option<usize> = world.camera_medium;
+
+        // state of the previous bounce, needed to weight emission found by BSDF sampling
+        let mut specular_bounce = false;
+        let mut scatter_pdf: f64 = 0.0;
+        let mut prev: Option<Point> = None;
+
+        loop {
+            let hit = world.intersect(&ray);
+
+            // scattering inside the current medium before the hit
+            if let Some(m) = medium {
+                let sample = world.media[m].sample(&ray,hit.as_ref().map_or(f64::INFINITY,|si| si.t),sampler);
+                l += beta * S::from_rgb(&sample.le,context);
+                beta = beta * S::from_rgb(&sample.beta,context);
+                if beta.is_black() {
+                    break;
+                }
+                if let Some(mi) = sample.interaction {
+                    if depth >= self.max_depth {
+                        break;
+                    }
+                    if let Some(ds) = sample_direct_medium(&mi,medium,world,sampler) {
+                        l += beta * S::from_direct(&ds,context);
+                    }
+
+                    // phase function sampling is exact, beta is unchanged
+                    let (wi,pdf) = mi.phase.sample_p(&mi.wo,sampler.get_2d());
+                    specular_bounce = false;
+                    scatter_pdf = pdf;
+                    ray = Ray::new(&mi.p,&wi);
+                    prev = Some(mi.p);
+                    depth += 1;
+                    if !self.russian_roulette(depth,&mut beta,sampler) {
+                        break;
+                    }
+                    continue;
+                }
+            }
+
+            let si = match hit {
+                Some(si) => si,
+                None => {
+                    // escaped, add environment radiance
+                    if let Some(environment) = &world.environment {
+                        let le = S::from_rgb(&environment.le(&ray),context);
+                        match prev {
+                            Some(prev) if !specular_bounce => {
+                                let light_pdf = environment.pdf_li(&prev,&ray.d) * light_select_pdf(world);
+                                l += beta * le * power_heuristic(1.0,scatter_pdf,1.0,light_pdf);
+                            },
+                            _ => l += beta * le
+                        }
+                    }
+                    break;
+                }
+            };
+
+            // invisible boundary, only the medium changes
+            if world.is_boundary(&si) {
+                medium = si.medium(&ray.d,medium);
+                ray = si.spawn_ray(&ray.d);
+                continue;
+            }
+
+            // emission from a hit area light
+            if let Some(light) = si.light {
+                let le = S::from_rgb(&world.emitted(&si),context);
+                match prev {
+                    Some(prev) if !specular_bounce => {
+                        let light_pdf = world.lights[light].pdf_li(&prev,&ray.d) * light_select_pdf(world);
+                        l += beta * le * power_heuristic(1.0,scatter_pdf,1.0,light_pdf);
+                    },
+                    _ => l += beta * le
+                }
+            }
+
+            if depth >= self.max_depth {
+                break;
+            }
+
+            let bsdf = S::bsdf(world.materials[si.material].as_ref(),&si,context);
+
+            // next event estimation
+            if !bsdf.is_specular() {
+                if let Some(ds) = sample_direct(&si,bsdf.as_ref(),medium,world,sampler) {
+                    l += beta * S::from_direct(&ds,context);
+                }
+            }
+
+            // continue path by sampling the BSDF
+            let sample = match bsdf.sample_f(&si.wo,sampler.get_2d()) {
+                Some(sample) => sample,
+                None => break
+            };
+            if sample.f.is_black() || sample.pdf == 0.0 {
+                break;
+            }
+            beta = beta * S::from_rgb(&sample.f,context) * (sample.wi.dot(si.ns).abs() / sample.pdf);
+            specular_bounce = sample.specular;
+            scatter_pdf = sample.pdf;
+            medium = si.medium(&sample.wi,medium);
+            ray = if sample.specular {
+                si.spawn_specular_ray(&ray,&sample.wi,sample.eta)
+            } else {
+                si.spawn_ray(&sample.wi)
+            };
+            prev = Some(si.p);
+            depth += 1;
+
+            if !self.russian_roulette(depth,&mut beta,sampler) {
+                break;
+            }
+        }
+
+        l
+    }
+
+    /// Russian roulette after rr_depth bounces, returns false if the
+    /// path is terminated, survivors are reweighted
+    fn russian_roulette<S: PathSpectrum>(&self,depth: usize,beta: &mut S,sampler: &mut dyn Sampler) -> bool {
+        if depth >= self.rr_depth {
+            let q = f64::max(0.05,1.0 - beta.max_component());
+            if sampler.get_1d() < q {
+                return false
+            }
+            *beta = *beta / (1.0 - q);
+        }
+        true
+    }
+}
+
+/// Probability of picking any one light when sampling uniformly
+pub fn light_select_pdf(world: &World) -> f64 {
+    if world.lights.is_empty() {
+        0.0
+    } else {
+        1.0 / world.lights.len() as f64
+    }
+}
+
+/// # DirectSample
+/// Factors of a direct lighting estimate, kept apart so spectral
+/// integrators can convert each of them to their wavelengths
+///
+/// # Parameters
+/// * f (BSDF times cosine, or phase function, towards the light)
+/// * tr (transmittance of the shadow ray)
+/// * li (incident radiance from the light)
+/// * weight (MIS weight divided by the light sampling pdf)
+pub struct DirectSample {
+    pub f: Spectrum,
+    pub tr: Spectrum,
+    pub li: Spectrum,
+    pub weight: f64
+}
+
+/// # PathSpectrum
+/// Radiance and throughput carried along a path, RGB paths carry
+/// Spectrum as is while spectral paths convert it to their wavelengths
+pub trait PathSpectrum: Copy + AddAssign + Mul<Output = Self> + Mul<f64,Output = Self> + Div<f64,Output = Self> {
+    /// State the conversion from RGB depends on
+    type Context;
+
+    const ZERO: Self;
+    const ONE: Self;
+
+    /// Convert an RGB quantity of the scene
+    fn from_rgb(rgb: &Spectrum,context: &Self::Context) -> Self;
+
+    /// Direct lighting estimate, each factor is converted on its own
+    fn from_direct(ds: &DirectSample,context: &Self::Context) -> Self {
+        Self::from_rgb(&ds.f,context) * Self::from_rgb(&ds.tr,context) * Self::from_rgb(&ds.li,context) * ds.weight
+    }
+
+    /// Scattering of material at si, the context may be narrowed for it
+    fn bsdf(material: &dyn Material,si: &SurfaceInteraction,_context: &mut Self::Context) -> Box<dyn Bsdf> {
+        material.bsdf(si)
+    }
+
+    fn max_component(&self) -> f64;
+    fn is_black(&self) -> bool;
+}
+
+/// RGB paths need no conversion
+impl PathSpectrum for Spectrum {
+    type Context = ();
+
+    const ZERO: Spectrum = spectrum::BLACK;
+    const ONE: Spectrum = spectrum::WHITE;
+
+    fn from_rgb(rgb: &Spectrum,_context: &()) -> Spectrum {
+        *rgb
+    }
+
+    fn max_component(&self) -> f64 {
+        Spectrum::max_component(self)
+    }
+
+    fn is_black(&self) -> bool {
+        Spectrum::is_black(self)
+    }
+}
+
+/// Direct lighting at a surface point from one uniformly chosen light,
+/// the light sample is weighted against BSDF sampling with the power heuristic.
+/// medium is the one the path arrived through, None if the sample contributes nothing
+pub fn sample_direct(si: &SurfaceInteraction,bsdf: &dyn Bsdf,medium: Option<usize>,world: &World,sampler: &mut dyn Sampler) -> Option<DirectSample> {
+    let (light,ls,light_pdf) = sample_light(&si.p,world,sampler)?;
+
+    let f = bsdf.f(&si.wo,&ls.wi) * ls.wi.dot(si.ns).abs();
+    if f.is_black() {
+        return None
+    }
+
+    // shadow ray
+    let (shadow,tmax) = if ls.distance.is_infinite() {
+        (si.spawn_ray(&ls.wi),f64::INFINITY)
+    } else {
+        si.spawn_ray_to(&(si.p + ls.wi*ls.distance))
+    };
+    let tr = world.transmittance(&shadow,tmax,si.medium(&ls.wi,medium),sampler);
+    if tr.is_black() {
+        return None
+    }
+
+    let weight = if light.is_delta() {
+        1.0
+    } else {
+        power_heuristic(1.0,light_pdf,1.0,bsdf.pdf(&si.wo,&ls.wi))
+    };
+    Some(DirectSample {f,tr,li: ls.li,weight: weight / light_pdf})
+}
+
+/// Direct lighting at a scattering event inside medium, the light sample
+/// is weighted against phase function sampling, None if it contributes nothing
+pub fn sample_direct_medium(mi: &MediumInteraction,medium: Option<usize>,world: &World,sampler: &mut dyn Sampler) -> Option<DirectSample> {
+    let (light,ls,light_pdf) = sample_light(&mi.p,world,sampler)?;
+
+    let phase = mi.phase.p(&mi.wo,&ls.wi);
+    if phase == 0.0 {
+        return None
+    }
+
+    // shadow ray, no offset needed away from surfaces
+    let (shadow,tmax) = if ls.distance.is_infinite() {
+        (Ray::new(&mi.p,&ls.wi),f64::INFINITY)
+    } else {
+        (Ray::new(&mi.p,&(ls.wi*ls.distance)),1.0 - 1e-4)
+    };
+    let tr = world.transmittance(&shadow,tmax,medium,sampler);
+    if tr.is_black() {
+        return None
+    }
+
+    let weight = if light.is_delta() {
+        1.0
+    } else {
+        power_heuristic(1.0,light_pdf,1.0,phase)
+    };
+    Some(DirectSample {f: spectrum::WHITE * phase,tr,li: ls.li,weight: weight / light_pdf})
+}
+
+/// Sample incident light at p from one uniformly chosen light,
+/// returns the light, its sample and the combined pdf of both choices
+fn sample_light<'a>(p: &Point,world: &'a World,sampler: &mut dyn Sampler) -> Option<(&'a dyn Light,LightSample,f64)> {
+    let u_light = sampler.get_1d();
+    let u = sampler.get_2d();
+    if world.lights.is_empty() {
+        return None
+    }
+
+    let n = world.lights.len();
+    let light = &world.lights[((u_light * n as f64) as usize).min(n - 1)];
+    let ls = light.sample_li(p,u)?;
+    if ls.pdf == 0.0 || ls.li.is_black() {
+        return None
+    }
+    let light_pdf = ls.pdf * light_select_pdf(world);
+    Some((light.as_ref(),ls,light_pdf))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        image::hdr::HdrImage,
+        math::{point::Point,vector::Vector},
+        sampler::random::RandomSampler,
+        scene::{sphere::Sphere,cornell::cornell_box}
+    };
+
+    const REFERENCE_SIZE: usize = 24;
+    const REFERENCE_PATH: &str = "image/reference/cornell_box.hdr";
+
+    fn reference_path() -> String {
+        format!("{}/{}",env!("CARGO_MANIFEST_DIR"),REFERENCE_PATH)
+    }
+
+    #[test]
+    // camera rays that miss everything see the environment
+    fn test_li_environment() {
+        use crate::light::environment::EnvironmentLight;
+
+        let mut world = World::new(0);
+        world.set_environment(Box::new(EnvironmentLight::new(
+            HdrImage::new(2,2,Spectrum::new(0.5,0.5,0.5)),0.,1.
+        )));
+        let integrator = PathIntegrator::new(5);
+        let mut sampler = RandomSampler::new(1);
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
+        let l = integrator.li(&ray,&world,&mut sampler);
+        assert_eq!(l.r,0.5);
+    }
+
+    #[test]
+    // a white diffuse sphere inside a uniform environment
+    // with unit radiance should converge to one (furnace test)
+    fn test_li_furnace() {
+        use crate::light::environment::EnvironmentLight;
+        use crate::material::lambertian::Lambertian;
+
+        let mut world = World::new(1);
+        let white = world.add_material(Box::new(Lambertian::new(spectrum::WHITE)));
+        world.add_primitive_with_material(Box::new(Sphere::new(1.,Point::new(0.,0.,5.))),white);
+        world.set_environment(Box::new(EnvironmentLight::new(
+            HdrImage::new(4,2,spectrum::WHITE),0.,1.
+        )));
+
+        let integrator = PathIntegrator { max_depth: 50,rr_depth: 50 };
+        let mut sampler = RandomSampler::new(2);
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
+        let n = 2000;
+        let mut l = spectrum::BLACK;
+        for _ in 0..n {
+            l += integrator.li(&ray,&world,&mut sampler);
+        }
+        let mean = l.r / n as f64;
+        assert!((mean - 1.).abs() < 0.02,"furnace mean {}",mean);
+    }
+
+    // uniform white environment around a unit sphere boundary filled with a medium
+    fn medium_furnace(sigma_a: f64,sigma_s: f64,g: f64) -> World {
+        use crate::light::environment::EnvironmentLight;
+        use crate::medium::{homogeneous::HomogeneousMedium,traits::MediumInterface};
+
+        let mut world = World::new(1);
+        let medium = world.add_medium(Box::new(HomogeneousMedium::new(spectrum::WHITE * sigma_a,spectrum::WHITE * sigma_s,g)));
+        world.add_medium_boundary(Box::new(Sphere::new(1.,Point::new(0.,0.,5.))),MediumInterface::new(Some(medium),None));
+        world.set_environment(Box::new(EnvironmentLight::new(
+            HdrImage::new(4,2,spectrum::WHITE),0.,1.
+        )));
+        world
+    }
+
+    // mean radiance of n paths along ray
+    fn mean_li(integrator: &PathIntegrator,ray: &Ray,world: &World,seed: u64,n: usize) -> Spectrum {
+        let mut sampler = RandomSampler::new(seed);
+        let mut l = spectrum::BLACK;
+        for _ in 0..n {
+            l += integrator.li(ray,world,&mut sampler);
+        }
+        l / n as f64
+    }
+
+    #[test]
+    // a purely absorbing medium should attenuate by Beer's law
+    fn test_li_absorbing_medium() {
+        let world = medium_furnace(0.5,0.,0.);
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
+        let l = mean_li(&PathIntegrator::new(5),&ray,&world,4,4000);
+        assert!((l.r - (-1f64).exp()).abs() < 0.03,"{}",l.r);
+    }
+
+    #[test]
+    // a non absorbing medium in a uniform environment
+    // should converge to the environment radiance (furnace test)
+    fn test_li_scattering_medium_furnace() {
+        let integrator = PathIntegrator { max_depth: 100,rr_depth: 100 };
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
+        for g in [0.,0.7] {
+            let world = medium_furnace(0.,2.,g);
+            let mean = mean_li(&integrator,&ray,&world,5,4000).g;
+            assert!((mean - 1.).abs() < 0.02,"g {} furnace mean {}",g,mean);
+        }
+    }
+
+    #[test]
+    // a hot purely absorbing grid should glow by its absorbed fraction
+    fn test_li_emissive_medium() {
+        use crate::{
+            image::blackbody::blackbody,
+            math::matrix::Matrix,
+            medium::{grid::GridMedium,voxel::VoxelGrid,traits::MediumInterface}
+        };
+
+        let mut world = World::new(1);
+        let placement = Matrix::translate(&Vector::new(-0.5,-0.5,4.5));
+        let mut fire = GridMedium::new(1.,spectrum::BLACK,0.,VoxelGrid::new((1,1,1),vec![1.]),&placement).unwrap();
+        fire.set_temperature(VoxelGrid::new((1,1,1),vec![1500.]),1. / blackbody(1500.).r);
+        let fire = world.add_medium(Box::new(fire));
+        world.add_medium_boundary(Box::new(Sphere::new(1.,Point::new(0.,0.,5.))),MediumInterface::new(Some(fire),None));
+
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
+        let l = mean_li(&PathIntegrator::new(5),&ray,&world,7,4000);
+        assert!((l.r - (1. - (-1f64).exp())).abs() < 0.03,"{}",l.r);
+    }
+
+    #[test]
+    // the camera can sit inside a medium
+    fn test_li_camera_medium() {
+        use crate::medium::homogeneous::HomogeneousMedium;
+
+        let mut world = World::new(1);
+        world.add_area_light(Box::new(Sphere::new(1.,Point::new(0.,0.,5.))),0,spectrum::WHITE,false);
+        let fog = world.add_medium(Box::new(HomogeneousMedium::new(spectrum::WHITE * 0.1,spectrum::BLACK,0.)));
+        world.camera_medium = Some(fog);
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
+        let l = mean_li(&PathIntegrator::new(0),&ray,&world,6,4000);
+        assert!((l.b - (-0.4f64).exp()).abs() < 0.03,"{}",l.b);
+    }
+
+    #[test]
+    // emitters seen directly should be returned unweighted
+    fn test_li_direct_emitter() {
+        let mut world = World::new(1);
+        world.add_area_light(Box::new(Sphere::new(1.,Point::new(0.,0.,5.))),0,Spectrum::new(2.,3.,4.),false);
+        let integrator = PathIntegrator::new(0);
+        let mut sampler = RandomSampler::new(3);
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
+        let l = integrator.li(&ray,&world,&mut sampler);
+        assert_eq!(l.r,2.);
+        assert_eq!(l.g,3.);
+        assert_eq!(l.b,4.);
+    }
+
+    #[test]
+    // low sample count render of the cornell box should agree with the stored reference
+    fn test_cornell_box_convergence() {
+        let reference = HdrImage::load(&reference_path()).unwrap();
+        assert_eq!(reference.width,REFERENCE_SIZE);
+
+        let (world,camera) = cornell_box(1.);
+        let image = PathIntegrator::new(8).render(&world,&camera,REFERENCE_SIZE,REFERENCE_SIZE,128,1);
+
+        // mean radiance should match closely, per pixel error should be small
+        let mut sum_image = 0.;
+        let mut sum_reference = 0.;
+        let mut abs_error = 0.;
+        for (a,b) in image.pixels.iter().zip(reference.pixels.iter()) {
+            sum_image += a.luminance();
+            sum_reference += b.luminance();
+            abs_error += (a.luminance() - b.luminance()).abs();
+        }
+        let relative_mean = (sum_image - sum_reference).abs() / sum_reference;
+        let relative_error = abs_error / sum_reference;
+        assert!(relative_mean < 0.04,"mean differs by {}",relative_mean);
+        assert!(relative_error < 0.15,"per pixel error {}",relative_error);
+    }
+
+    #[test]
+    #[ignore]
+    // regenerate the stored reference image:
+    // cargo test --release generate_cornell_reference -- --ignored
+    fn generate_cornell_reference() {
+        let (world,camera) = cornell_box(1.);
+        let image = PathIntegrator::new(8).render(&world,&camera,REFERENCE_SIZE,REFERENCE_SIZE,16384,42);
+        image.save(&reference_path()).unwrap();
+    }
+}
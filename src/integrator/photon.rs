@@ -0,0 +1,352 @@
+use std::f64::consts::PI;
+
+use super::traits::Integrator;
+use crate::{
+    image::spectrum::{self,Spectrum},
+    material::traits::Bsdf,
+    math::{
+        vector::Vector,
+        ray::Ray,
+        kdtree::KdTree,
+        traits::Dot
+    },
+    sampler::{traits::Sampler,random::RandomSampler},
+    scene::{world::World,interaction::{SurfaceInteraction,offset_ray_origin}}
+};
+
+/// # Photon
+/// Packet of light power deposited on a surface
+///
+/// # Parameters
+/// * wi (unit direction the photon arrived from)
+/// * power (flux carried, before dividing by the number of emitted photons)
+#[derive(Clone,Copy)]
+pub struct Photon {
+    pub wi: Vector,
+    pub power: Spectrum
+}
+
+/// # PhotonHit
+/// Photon arriving at a non-specular surface while tracing from the lights
+///
+/// # Parameters
+/// * si (surface interaction)
+/// * bsdf (scattering at the surface)
+/// * photon (arrival direction and power)
+/// * depth (number of bounces before arriving, 0 for direct lighting)
+/// * caustic (every bounce so far was specular)
+pub struct PhotonHit<'a> {
+    pub si: &'a SurfaceInteraction,
+    pub bsdf: &'a dyn Bsdf,
+    pub photon: Photon,
+    pub depth: usize,
+    pub caustic: bool
+}
+
+/// # PhotonMapIntegrator
+/// Two pass photon mapping: photons are traced from the lights into a
+/// global and a caustic map, camera rays then combine direct lighting,
+/// caustic map lookups and final gathering into the global map
+///
+/// Without final gather samples the global map is shown directly.
+/// Lights at infinity emit no photons and only add direct lighting
+///
+/// # Parameters
+/// * global (photons at every non-specular hit)
+/// * caustic (photons that only bounced off specular surfaces)
+/// * emitted (number of photons emitted to build the maps)
+/// * lookup_count (photons used by each radiance estimate)
+/// * max_radius (largest search radius of a radiance estimate)
+/// * final_gather_samples (indirect rays per camera hit, 0 to disable)
+/// * max_depth (recursion limit for specular bounces of camera rays)
+pub struct PhotonMapIntegrator {
+    pub global: KdTree<Photon>,
+    pub caustic: KdTree<Photon>,
+    pub emitted: usize,
+    pub lookup_count: usize,
+    pub max_radius: f64,
+    pub final_gather_samples: usize,
+    pub max_depth: usize
+}
+
+/// Integrator trait
+impl Integrator for PhotonMapIntegrator {
+    fn li(&self,ray: &Ray,world: &World,sampler: &mut dyn Sampler) -> Spectrum {
+        self.trace(ray,world,sampler,0)
+    }
+}
+
+impl PhotonMapIntegrator {
+    /// Construct integrator by emitting photon_count photons into world
+    pub fn new(world: &World,photon_count: usize,seed: u64) -> PhotonMapIntegrator {
+        let mut global = vec![];
+        let mut caustic = vec![];
+        let mut sampler = RandomSampler::new(seed);
+        shoot_photons(world,photon_count,16,&mut sampler,|hit| {
+            if hit.caustic && hit.depth > 0 {
+                caustic.push((hit.si.p,hit.photon));
+            }
+            global.push((hit.si.p,hit.photon));
+        });
+
+        PhotonMapIntegrator {
+            global: KdTree::new(global),
+            caustic: KdTree::new(caustic),
+            emitted: photon_count,
+            lookup_count: 50,
+            max_radius: 0.25,
+            final_gather_samples: 32,
+            max_depth: 5
+        }
+    }
+
+    /// Radiance along ray at the given specular recursion depth
+    fn trace(&self,ray: &Ray,world: &World,sampler: &mut dyn Sampler,depth: usize) -> Spectrum {
+        let si = match world.intersect(ray) {
+            Some(si) => si,
+            None => return world.le(ray)
+        };
+        let mut l = world.emitted(&si);
+        let bsdf = world.materials[si.material].bsdf(&si);
+
+        // specular surfaces reflect whatever they see
+        if bsdf.is_specular() {
+            if depth < self.max_depth {
+                for lobe in bsdf.specular_lobes(&si.wo) {
+                    let weight = lobe.f * lobe.wi.dot(si.ns).abs();
+                    if !weight.is_black() {
+                        l += weight * self.trace(&si.spawn_specular_ray(ray,&lobe.wi,lobe.eta),world,sampler,depth + 1);
+                    }
+                }
+            }
+            return l
+        }
+
+        if self.final_gather_samples == 0 {
+            return l + self.estimate(&self.global,&si,bsdf.as_ref())
+        }
+
+        l += direct_lighting(&si,bsdf.as_ref(),world,sampler);
+        l += self.estimate(&self.caustic,&si,bsdf.as_ref());
+
+        // final gather, indirect light reflected by other diffuse surfaces
+        let mut indirect = spectrum::BLACK;
+        for _ in 0..self.final_gather_samples {
+            let sample = match bsdf.sample_f(&si.wo,sampler.get_2d()) {
+                Some(sample) if sample.pdf > 0.0 && !sample.f.is_black() => sample,
+                _ => continue
+            };
+            let gather = match world.intersect(&si.spawn_ray(&sample.wi)) {
+                Some(gather) => gather,
+                None => continue
+            };
+            let gather_bsdf = world.materials[gather.material].bsdf(&gather);
+            // specular hits are caustic paths, already in the caustic map
+            if gather_bsdf.is_specular() {
+                continue;
+            }
+            let lg = self.estimate(&self.global,&gather,gather_bsdf.as_ref());
+            indirect += sample.f * lg * (sample.wi.dot(si.ns).abs() / sample.pdf);
+        }
+        l + indirect / self.final_gather_samples as f64
+    }
+
+    /// Density estimate of reflected radiance from the nearest photons in map
+    pub fn estimate(&self,map: &KdTree<Photon>,si: &SurfaceInteraction,bsdf: &dyn Bsdf) -> Spectrum {
+        let max_sq = self.max_radius * self.max_radius;
+        let nearest = map.nearest(&si.p,self.lookup_count,max_sq);
+        if nearest.is_empty() {
+            return spectrum::BLACK
+        }
+        // the disc reaches the furthest photon once enough are found
+        let r_sq = if nearest.len() == self.lookup_count {
+            nearest[nearest.len() - 1].0
+        } else {
+            max_sq
+        };
+        if r_sq == 0.0 {
+            return spectrum::BLACK
+        }
+
+        let mut l = spectrum::BLACK;
+        for (_,photon) in nearest {
+            l += bsdf.f(&si.wo,&photon.wi) * photon.power;
+        }
+        l / (self.emitted as f64 * PI * r_sq)
+    }
+}
+
+/// Emit count photons from uniformly chosen lights and follow them
+/// through the scene, f is called at every non-specular surface hit.
+/// Paths end at max_depth bounces or by russian roulette
+pub fn shoot_photons<F: FnMut(PhotonHit)>(world: &World,count: usize,max_depth: usize,sampler: &mut dyn Sampler,mut f: F) {
+    if world.lights.is_empty() {
+        return
+    }
+    let n = world.lights.len();
+    let select_pdf = 1.0 / n as f64;
+
+    for _ in 0..count {
+        let u_light = sampler.get_1d();
+        let u_pos = sampler.get_2d();
+        let u_dir = sampler.get_2d();
+        let light = &world.lights[((u_light * n as f64) as usize).min(n - 1)];
+        let es = match light.sample_le(u_pos,u_dir) {
+            Some(es) if es.pdf_pos > 0.0 && es.pdf_dir > 0.0 && !es.le.is_black() => es,
+            _ => continue
+        };
+
+        let (origin,cos) = match es.n {
+            Some(n) => (offset_ray_origin(&es.p,&n,&es.w),n.dot(es.w).abs()),
+            None => (es.p,1.0)
+        };
+        let mut beta = es.le * (cos / (select_pdf * es.pdf_pos * es.pdf_dir));
+        let mut ray = Ray::new(&origin,&es.w);
+        let mut caustic = true;
+
+        for depth in 0..max_depth {
+            let si = match world.intersect(&ray) {
+                Some(si) => si,
+                None => break
+            };
+            let bsdf = world.materials[si.material].bsdf(&si);
+            if !bsdf.is_specular() {
+                f(PhotonHit {
+                    si: &si,
+                    bsdf: bsdf.as_ref(),
+                    photon: Photon {wi: -ray.d,power: beta},
+                    depth,
+                    caustic
+                });
+            }
+
+            let sample = match bsdf.sample_f(&si.wo,sampler.get_2d()) {
+                Some(sample) if sample.pdf > 0.0 && !sample.f.is_black() => sample,
+                _ => break
+            };
+            let f_adjoint = sample.f * bsdf.adjoint_scale(&si.wo,&sample.wi);
+            let beta_new = beta * f_adjoint * (sample.wi.dot(si.ns).abs() / sample.pdf);
+
+            // russian roulette keeping photon power roughly constant
+            let q = f64::max(0.0,1.0 - beta_new.luminance() / beta.luminance());
+            if sampler.get_1d() < q {
+                break;
+            }
+            beta = beta_new / (1.0 - q);
+            caustic &= sample.specular;
+            ray = si.spawn_ray(&sample.wi);
+        }
+    }
+}
+
+/// Direct lighting at a surface point from one uniformly chosen light
+/// using light sampling only
+pub fn direct_lighting(si: &SurfaceInteraction,bsdf: &dyn Bsdf,world: &World,sampler: &mut dyn Sampler) -> Spectrum {
+    let u_light = sampler.get_1d();
+    let u = sampler.get_2d();
+    if world.lights.is_empty() {
+        return spectrum::BLACK
+    }
+    let n = world.lights.len();
+    let light = &world.lights[((u_light * n as f64) as usize).min(n - 1)];
+
+    let ls = match light.sample_li(&si.p,u) {
+        Some(ls) if ls.pdf > 0.0 && !ls.li.is_black() => ls,
+        _ => return spectrum::BLACK
+    };
+    let f = bsdf.f(&si.wo,&ls.wi) * ls.wi.dot(si.ns).abs();
+    if f.is_black() {
+        return spectrum::BLACK
+    }
+    let occluded = if ls.distance.is_infinite() {
+        world.occluded(&si.spawn_ray(&ls.wi),f64::INFINITY)
+    } else {
+        let (shadow,tmax) = si.spawn_ray_to(&(si.p + ls.wi*ls.distance));
+        world.occluded(&shadow,tmax)
+    };
+    if occluded {
+        return spectrum::BLACK
+    }
+    f * ls.li * (n as f64 / ls.pdf)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_1_PI;
+    use crate::{
+        light::point::PointLight,
+        material::{lambertian::Lambertian,glass::Glass},
+        math::{point::Point,normal::Normal},
+        scene::{plane::Plane,sphere::Sphere,cornell::cornell_box}
+    };
+
+    // white floor lit by a point light straight above the origin
+    fn floor_world() -> World {
+        let mut world = World::new(2);
+        let white = world.add_material(Box::new(Lambertian::new(spectrum::WHITE)));
+        world.add_primitive_with_material(Box::new(Plane::new(&Point::new(0.,0.,0.),&Normal::new(0.,1.,0.))),white);
+        world.add_light(Box::new(PointLight::new(Point::new(0.,2.,0.),Spectrum::new(4.,4.,4.))));
+        world
+    }
+
+    #[test]
+    // the global map alone should estimate direct lighting on the floor
+    fn test_global_estimate() {
+        let world = floor_world();
+        let mut integrator = PhotonMapIntegrator::new(&world,100000,1);
+        integrator.final_gather_samples = 0;
+        assert!(integrator.caustic.is_empty());
+        let mut sampler = RandomSampler::new(1);
+        let ray = Ray::new(&Point::new(0.,1.,-1.),&Vector::new(0.,-1.,1.));
+        let l = integrator.li(&ray,&world,&mut sampler);
+        assert!((l.r - FRAC_1_PI).abs() < 0.15 * FRAC_1_PI,"estimate {}",l.r);
+    }
+
+    #[test]
+    // a glass sphere should focus photons into a caustic below it
+    fn test_caustic() {
+        let mut world = floor_world();
+        let glass = world.add_material(Box::new(Glass::new(1.5)));
+        world.add_primitive_with_material(Box::new(Sphere::new(0.4,Point::new(0.,1.,0.))),glass);
+        let integrator = PhotonMapIntegrator::new(&world,20000,2);
+        assert!(!integrator.caustic.is_empty());
+
+        let bsdf_at = |p: Point| {
+            let si = SurfaceInteraction::new(p,Normal::new(0.,1.,0.),(0.,0.),Vector::new(1.,0.,0.),Vector::new(0.,0.,1.),1.,Vector::new(0.,1.,0.));
+            let bsdf = world.materials[1].bsdf(&si);
+            integrator.estimate(&integrator.caustic,&si,bsdf.as_ref())
+        };
+        let focus = bsdf_at(Point::new(0.,0.,0.));
+        let outside = bsdf_at(Point::new(1.5,0.,0.));
+        // brighter than the unobstructed floor would be
+        assert!(focus.r > FRAC_1_PI,"caustic {}",focus.r);
+        assert!(outside.is_black());
+    }
+
+    #[test]
+    // final gathering should agree with the path tracer on indirect light
+    fn test_final_gather_matches_path_tracer() {
+        use crate::integrator::path::PathIntegrator;
+
+        let (world,camera) = cornell_box(1.);
+        let integrator = PhotonMapIntegrator::new(&world,50000,3);
+        let ray = camera.generate_ray(0.3,0.6);
+        let n = 500;
+        let mut sampler = RandomSampler::new(4);
+        let mut photon = spectrum::BLACK;
+        let mut path = spectrum::BLACK;
+        for _ in 0..n {
+            photon += integrator.li(&ray,&world,&mut sampler);
+        }
+        for _ in 0..n*16 {
+            path += PathIntegrator::new(8).li(&ray,&world,&mut sampler);
+        }
+        let (photon,path) = (photon.luminance() / n as f64,path.luminance() / (16*n) as f64);
+        assert!((photon - path).abs() < 0.1 * path,"photon {} path {}",photon,path);
+    }
+}
@@ -0,0 +1,200 @@
+use super::{
+    traits::{Integrator,for_each_sample},
+    path::{PathIntegrator,PathSpectrum}
+};
+use crate::{
+    image::{
+        hdr::HdrImage,
+        spectrum::Spectrum,
+        cie::xyz_to_rgb_balanced,
+        xyz::XyzFilm,
+        sampled::{self,SampledSpectrum,SampledWavelengths}
+    },
+    material::traits::{Bsdf,Material},
+    math::ray::Ray,
+    sampler::traits::Sampler,
+    scene::{world::World,camera::Camera,interaction::SurfaceInteraction}
+};
+
+/// # SpectralPathIntegrator
+/// Path tracer carrying radiance at a few sampled wavelengths instead
+/// of RGB, light transport is otherwise the same as PathIntegrator
+///
+/// Each path samples a hero wavelength and evenly spaced companions.
+/// RGB albedos, emission and media coefficients are uplifted to smooth
+/// spectra at those wavelengths, estimates go to CIE XYZ. Dispersive
+/// materials refract each wavelength differently, the companions are
+/// dropped there and the path follows the hero wavelength alone
+///
+/// # Parameters
+/// * max_depth (maximum number of bounces)
+/// * rr_depth (bounces before russian roulette starts)
+pub struct SpectralPathIntegrator {
+    pub max_depth: usize,
+    pub rr_depth: usize
+}
+
+/// Integrator trait
+impl Integrator for SpectralPathIntegrator {
+    /// Radiance as linear sRGB, estimated from a single wavelength sample
+    fn li(&self,ray: &Ray,world: &World,sampler: &mut dyn Sampler) -> Spectrum {
+        let mut wavelengths = SampledWavelengths::sample(sampler.get_1d());
+        let l = self.li_spectral(ray,world,sampler,&mut wavelengths);
+        let (x,y,z) = l.to_xyz(&wavelengths);
+        xyz_to_rgb_balanced(x,y,z)
+    }
+
+    /// Same sampling as the default render, samples are accumulated in XYZ
+    fn render(&self,world: &World,camera: &Camera,width: usize,height: usize,spp: usize,seed: u64) -> HdrImage {
+        let mut film = XyzFilm::new(width,height);
+        for_each_sample(camera,width,height,spp,seed,|x,y,ray,sampler| {
+            let mut wavelengths = SampledWavelengths::sample(sampler.get_1d());
+            let l = self.li_spectral(ray,world,sampler,&mut wavelengths);
+            film.add_sample(x,y,l.to_xyz(&wavelengths));
+        });
+        film.to_image()
+    }
+}
+
+impl SpectralPathIntegrator {
+    /// Construct spectral path tracer with given maximum depth
+    pub fn new(max_depth: usize) -> SpectralPathIntegrator {
+        SpectralPathIntegrator {
+            max_depth,
+            rr_depth: 3
+        }
+    }
+
+    /// Radiance along ray at the sampled wavelengths, which
+    /// lose their companions if the path meets a dispersive material
+    pub fn li_spectral(&self,ray: &Ray,world: &World,sampler: &mut dyn Sampler,wavelengths: &mut SampledWavelengths) -> SampledSpectrum {
+        let path = PathIntegrator {
+            max_depth: self.max_depth,
+            rr_depth: self.rr_depth
+        };
+        path.trace(ray,world,sampler,wavelengths)
+    }
+}
+
+/// Sampled wavelengths carried along spectral paths
+impl PathSpectrum for SampledSpectrum {
+    type Context = SampledWavelengths;
+
+    const ZERO: SampledSpectrum = sampled::ZERO;
+    const ONE: SampledSpectrum = sampled::ONE;
+
+    fn from_rgb(rgb: &Spectrum,wavelengths: &SampledWavelengths) -> SampledSpectrum {
+        SampledSpectrum::from_rgb(rgb,wavelengths)
+    }
+
+    /// Dispersive materials refract each wavelength differently,
+    /// the path follows the hero wavelength alone from there
+    fn bsdf(material: &dyn Material,si: &SurfaceInteraction,wavelengths: &mut SampledWavelengths) -> Box<dyn Bsdf> {
+        if material.is_dispersive() {
+            wavelengths.terminate_secondary();
+            material.bsdf_at_wavelength(si,wavelengths.lambda[0])
+        } else {
+            material.bsdf(si)
+        }
+    }
+
+    fn max_component(&self) -> f64 {
+        SampledSpectrum::max_component(self)
+    }
+
+    fn is_black(&self) -> bool {
+        SampledSpectrum::is_black(self)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        image::spectrum,
+        light::environment::EnvironmentLight,
+        material::{lambertian::Lambertian,glass::Glass,dispersion::Ior,traits::Material},
+        math::{point::Point,vector::Vector},
+        sampler::random::RandomSampler,
+        scene::sphere::Sphere
+    };
+
+    // sphere made of material inside a uniform white environment
+    fn furnace(material: Box<dyn Material>) -> World {
+        let mut world = World::new(1);
+        let material = world.add_material(material);
+        world.add_primitive_with_material(Box::new(Sphere::new(1.,Point::new(0.,0.,5.))),material);
+        world.set_environment(Box::new(EnvironmentLight::new(
+            HdrImage::new(4,2,spectrum::WHITE),0.,1.
+        )));
+        world
+    }
+
+    // mean radiance of n paths along ray
+    fn mean_li(integrator: &SpectralPathIntegrator,world: &World,seed: u64,n: usize) -> Spectrum {
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
+        let mut sampler = RandomSampler::new(seed);
+        let mut l = spectrum::BLACK;
+        for _ in 0..n {
+            l += integrator.li(&ray,world,&mut sampler);
+        }
+        l / n as f64
+    }
+
+    #[test]
+    // a white diffuse sphere in a white environment should stay white
+    fn test_li_furnace() {
+        let world = furnace(Box::new(Lambertian::new(spectrum::WHITE)));
+        let integrator = SpectralPathIntegrator { max_depth: 50,rr_depth: 50 };
+        let l = mean_li(&integrator,&world,1,4000);
+        for c in 0..3 {
+            assert!((l[c] - 1.).abs() < 0.03,"furnace {}",l);
+        }
+    }
+
+    #[test]
+    // dispersion only redirects light, a clear prism in a white
+    // environment should still converge to white
+    fn test_li_dispersive_furnace() {
+        let world = furnace(Box::new(Glass::dispersive(Ior::bk7())));
+        let integrator = SpectralPathIntegrator { max_depth: 50,rr_depth: 50 };
+        let l = mean_li(&integrator,&world,2,40000);
+        for c in 0..3 {
+            assert!((l[c] - 1.).abs() < 0.05,"furnace {}",l);
+        }
+    }
+
+    #[test]
+    // a colored environment seen directly should keep its color
+    fn test_li_environment() {
+        let color = Spectrum::new(0.8,0.4,0.1);
+        let mut world = World::new(0);
+        world.set_environment(Box::new(EnvironmentLight::new(HdrImage::new(2,2,color),0.,1.)));
+        let integrator = SpectralPathIntegrator::new(5);
+        let l = mean_li(&integrator,&world,3,4000);
+        for c in 0..3 {
+            assert!((l[c] - color[c]).abs() < 0.03,"{}",l);
+        }
+    }
+
+    #[test]
+    // rendering should fill every pixel through the XYZ film
+    fn test_render() {
+        let world = furnace(Box::new(Lambertian::new(spectrum::WHITE * 0.5)));
+        let camera = Camera::new(
+            Point::new(0.,0.,0.),
+            Point::new(0.,0.,5.),
+            Vector::new(0.,1.,0.),
+            60.,
+            1.
+        ).unwrap();
+        let image = SpectralPathIntegrator::new(5).render(&world,&camera,4,4,64,7);
+        for pixel in &image.pixels {
+            assert!(pixel.g > 0.3 && pixel.g < 1.1,"{}",pixel);
+        }
+    }
+}
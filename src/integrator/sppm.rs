@@ -0,0 +1,239 @@
+use std::f64::consts::PI;
+
+use super::{
+    traits::Integrator,
+    photon::{shoot_photons,direct_lighting}
+};
+use crate::{
+    image::{hdr::HdrImage,spectrum::{self,Spectrum}},
+    material::traits::Bsdf,
+    math::{ray::Ray,kdtree::KdTree,traits::Dot},
+    sampler::{traits::Sampler,random::RandomSampler},
+    scene::{world::World,camera::Camera,interaction::SurfaceInteraction}
+};
+
+/// # SppmIntegrator
+/// Stochastic progressive photon mapping: each iteration traces camera
+/// paths to their first non-specular hit (visible point) and then photons
+/// from the lights, photons landing near a visible point add to it while
+/// the gather radius shrinks, so the estimate converges
+///
+/// # Parameters
+/// * iterations (camera and photon passes)
+/// * photons_per_iteration (photons emitted per pass)
+/// * initial_radius (gather radius of every visible point before the first pass)
+/// * alpha (fraction of new photons kept per pass, controls radius reduction)
+/// * max_depth (maximum bounces of camera and photon paths)
+pub struct SppmIntegrator {
+    pub iterations: usize,
+    pub photons_per_iteration: usize,
+    pub initial_radius: f64,
+    pub alpha: f64,
+    pub max_depth: usize
+}
+
+/// Integrator trait
+impl Integrator for SppmIntegrator {
+    /// Runs every pass for the single pixel seen along ray
+    fn li(&self,ray: &Ray,world: &World,sampler: &mut dyn Sampler) -> Spectrum {
+        self.estimate(world,1,&mut |_,_| *ray,sampler)[0]
+    }
+
+    fn render(&self,world: &World,camera: &Camera,width: usize,height: usize,_spp: usize,seed: u64) -> HdrImage {
+        let mut sampler = RandomSampler::new(seed);
+        let mut generate_ray = |pixel: usize,sampler: &mut dyn Sampler| {
+            let (dx,dy) = sampler.get_2d();
+            camera.generate_ray(
+                ((pixel % width) as f64 + dx) / width as f64,
+                ((pixel / width) as f64 + dy) / height as f64
+            )
+        };
+        let pixels = self.estimate(world,width*height,&mut generate_ray,&mut sampler);
+        HdrImage {
+            width,
+            height,
+            pixels
+        }
+    }
+}
+
+impl SppmIntegrator {
+    /// Construct SPPM integrator with the usual alpha of 2/3
+    pub fn new(iterations: usize,photons_per_iteration: usize,initial_radius: f64) -> SppmIntegrator {
+        SppmIntegrator {
+            iterations,
+            photons_per_iteration,
+            initial_radius,
+            alpha: 2.0 / 3.0,
+            max_depth: 8
+        }
+    }
+
+    /// Radiance estimate of pixel_count pixels, generate_ray makes
+    /// a new camera ray for a pixel on every pass
+    fn estimate(&self,world: &World,pixel_count: usize,generate_ray: &mut dyn FnMut(usize,&mut dyn Sampler) -> Ray,sampler: &mut dyn Sampler) -> Vec<Spectrum> {
+        let mut pixels: Vec<SppmPixel> = (0..pixel_count).map(|_| SppmPixel::new(self.initial_radius)).collect();
+
+        for _ in 0..self.iterations {
+            // camera pass
+            let visible: Vec<Option<VisiblePoint>> = pixels.iter_mut()
+                .enumerate()
+                .map(|(i,pixel)| {
+                    let ray = generate_ray(i,sampler);
+                    self.visible_point(&ray,world,sampler,&mut pixel.ld)
+                })
+                .collect();
+
+            let max_radius = pixels.iter().fold(0.0,|r: f64,pixel| r.max(pixel.radius));
+            let tree = KdTree::new(visible.iter()
+                .enumerate()
+                .filter_map(|(i,vp)| vp.as_ref().map(|vp| (vp.si.p,i)))
+                .collect());
+
+            // photon pass, direct lighting is already in ld
+            shoot_photons(world,self.photons_per_iteration,self.max_depth,sampler,|hit| {
+                if hit.depth == 0 {
+                    return
+                }
+                tree.within(&hit.si.p,max_radius*max_radius,|_,&i,dist_sq| {
+                    let pixel = &mut pixels[i];
+                    if dist_sq > pixel.radius*pixel.radius {
+                        return
+                    }
+                    if let Some(vp) = &visible[i] {
+                        pixel.phi += vp.beta * vp.bsdf.f(&vp.si.wo,&hit.photon.wi) * hit.photon.power;
+                        pixel.m += 1;
+                    }
+                });
+            });
+
+            // shrink radii, keeping alpha of the new photons
+            for pixel in pixels.iter_mut() {
+                if pixel.m > 0 {
+                    let n_new = pixel.n + self.alpha * pixel.m as f64;
+                    let radius_new = pixel.radius * f64::sqrt(n_new / (pixel.n + pixel.m as f64));
+                    pixel.tau = (pixel.tau + pixel.phi) * ((radius_new*radius_new) / (pixel.radius*pixel.radius));
+                    pixel.n = n_new;
+                    pixel.radius = radius_new;
+                    pixel.phi = spectrum::BLACK;
+                    pixel.m = 0;
+                }
+            }
+        }
+
+        let iterations = self.iterations.max(1) as f64;
+        let photons = iterations * self.photons_per_iteration as f64;
+        pixels.iter()
+            .map(|pixel| pixel.ld / iterations + pixel.tau / (photons * PI * pixel.radius*pixel.radius))
+            .collect()
+    }
+
+    /// Follow ray through specular bounces to the first non-specular surface,
+    /// emitted and direct light found on the way is added to ld
+    fn visible_point(&self,ray: &Ray,world: &World,sampler: &mut dyn Sampler,ld: &mut Spectrum) -> Option<VisiblePoint> {
+        let mut ray = *ray;
+        let mut beta = spectrum::WHITE;
+        for _ in 0..self.max_depth {
+            let si = match world.intersect(&ray) {
+                Some(si) => si,
+                None => {
+                    *ld += beta * world.le(&ray);
+                    return None
+                }
+            };
+            *ld += beta * world.emitted(&si);
+
+            let bsdf = world.materials[si.material].bsdf(&si);
+            if !bsdf.is_specular() {
+                *ld += beta * direct_lighting(&si,bsdf.as_ref(),world,sampler);
+                return Some(VisiblePoint {si,bsdf,beta})
+            }
+
+            let sample = bsdf.sample_f(&si.wo,sampler.get_2d())?;
+            if sample.pdf == 0.0 || sample.f.is_black() {
+                return None
+            }
+            beta = beta * sample.f * (sample.wi.dot(si.ns).abs() / sample.pdf);
+            ray = si.spawn_ray(&sample.wi);
+        }
+        None
+    }
+}
+
+/// # SppmPixel
+/// Progressive statistics of one pixel
+///
+/// # Parameters
+/// * ld (sum of emitted and direct light over all passes)
+/// * radius (current gather radius)
+/// * n (accumulated photon count)
+/// * tau (accumulated flux, scaled to the current radius)
+/// * phi (flux gathered during the current pass)
+/// * m (photons gathered during the current pass)
+struct SppmPixel {
+    ld: Spectrum,
+    radius: f64,
+    n: f64,
+    tau: Spectrum,
+    phi: Spectrum,
+    m: usize
+}
+
+impl SppmPixel {
+    fn new(radius: f64) -> SppmPixel {
+        SppmPixel {
+            ld: spectrum::BLACK,
+            radius,
+            n: 0.0,
+            tau: spectrum::BLACK,
+            phi: spectrum::BLACK,
+            m: 0
+        }
+    }
+}
+
+/// First non-specular hit of a camera path and the throughput to it
+struct VisiblePoint {
+    si: SurfaceInteraction,
+    bsdf: Box<dyn Bsdf>,
+    beta: Spectrum
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        integrator::path::PathIntegrator,
+        scene::cornell::cornell_box
+    };
+
+    #[test]
+    // progressive estimate should agree with the path tracer
+    fn test_li_matches_path_tracer() {
+        let (world,camera) = cornell_box(1.);
+        let ray = camera.generate_ray(0.3,0.6);
+        let mut sampler = RandomSampler::new(1);
+        let sppm = SppmIntegrator::new(32,4000,0.2).li(&ray,&world,&mut sampler);
+
+        let n = 8000;
+        let mut path = spectrum::BLACK;
+        for _ in 0..n {
+            path += PathIntegrator::new(8).li(&ray,&world,&mut sampler);
+        }
+        let (sppm,path) = (sppm.luminance(),path.luminance() / n as f64);
+        assert!((sppm - path).abs() < 0.1 * path,"sppm {} path {}",sppm,path);
+    }
+
+    #[test]
+    // every pixel of the box should receive light
+    fn test_render() {
+        let (world,camera) = cornell_box(1.);
+        let image = SppmIntegrator::new(4,2000,0.2).render(&world,&camera,4,4,1,1);
+        assert_eq!(image.pixels.len(),16);
+        assert!(image.pixels.iter().all(|p| p.luminance() > 0.));
+    }
+}
@@ -0,0 +1,54 @@
+use crate::{
+    image::{hdr::HdrImage,spectrum::{self,Spectrum}},
+    math::ray::Ray,
+    sampler::{traits::Sampler,random::RandomSampler},
+    scene::{world::World,camera::Camera}
+};
+
+/// Light transport algorithm computing radiance along camera rays
+pub trait Integrator {
+    /// Radiance arriving at the ray origin along the ray
+    fn li(&self,ray: &Ray,world: &World,sampler: &mut dyn Sampler) -> Spectrum;
+
+    /// Render an image averaging spp jittered samples per pixel
+    fn render(&self,world: &World,camera: &Camera,width: usize,height: usize,spp: usize,seed: u64) -> HdrImage {
+        let mut image = HdrImage::new(width,height,spectrum::BLACK);
+        for_each_sample(camera,width,height,spp,seed,|x,y,ray,sampler| {
+            image.pixels[y*width + x] += self.li(ray,world,sampler);
+        });
+        for pixel in image.pixels.iter_mut() {
+            *pixel = *pixel / spp as f64;
+        }
+        image
+    }
+}
+
+/// Generate spp jittered camera rays for every pixel in scanline order and
+/// hand each to sample together with its pixel and the sampler to continue with
+pub fn for_each_sample<F>(camera: &Camera,width: usize,height: usize,spp: usize,seed: u64,mut sample: F)
+where F: FnMut(usize,usize,&Ray,&mut dyn Sampler) {
+    for y in 0..height {
+        // one sampler per row so rows are reproducible on their own
+        let mut sampler = RandomSampler::new(seed.wrapping_add(y as u64));
+        for x in 0..width {
+            for _ in 0..spp {
+                let (dx,dy) = sampler.get_2d();
+                let ray = pixel_ray(camera,x as f64 + dx,y as f64 + dy,width,height,spp);
+                sample(x,y,&ray,&mut sampler);
+            }
+        }
+    }
+}
+
+/// Camera ray through image position (x,y) in pixels, its differentials
+/// span the share of a pixel covered by one of spp samples
+pub fn pixel_ray(camera: &Camera,x: f64,y: f64,width: usize,height: usize,spp: usize) -> Ray {
+    let mut ray = camera.generate_ray_differential(
+        x / width as f64,
+        y / height as f64,
+        1.0 / width as f64,
+        1.0 / height as f64
+    );
+    ray.scale_differentials(f64::max(0.125,1.0 / (spp as f64).sqrt()));
+    ray
+}
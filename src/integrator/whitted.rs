@@ -0,0 +1,145 @@
+use super::traits::Integrator;
+use crate::{
+    image::spectrum::Spectrum,
+    math::{ray::Ray,traits::Dot},
+    sampler::traits::Sampler,
+    scene::world::World
+};
+
+/// # WhittedIntegrator
+/// Classic recursive ray tracer: direct lighting with shadow rays
+/// plus perfect specular reflection and refraction, no indirect diffuse
+///
+/// # Parameters
+/// * max_depth (recursion limit for specular bounces)
+pub struct WhittedIntegrator {
+    pub max_depth: usize
+}
+
+/// Integrator trait
+impl Integrator for WhittedIntegrator {
+    fn li(&self,ray: &Ray,world: &World,sampler: &mut dyn Sampler) -> Spectrum {
+        self.trace(ray,world,sampler,0)
+    }
+}
+
+impl WhittedIntegrator {
+    /// Construct whitted integrator with given recursion limit
+    pub fn new(max_depth: usize) -> WhittedIntegrator {
+        WhittedIntegrator {max_depth}
+    }
+
+    /// Radiance along ray at the given recursion depth
+    fn trace(&self,ray: &Ray,world: &World,sampler: &mut dyn Sampler,depth: usize) -> Spectrum {
+        let si = match world.intersect(ray) {
+            Some(si) => si,
+            None => return world.le(ray)
+        };
+
+        let mut l: Spectrum = world.emitted(&si);
+        let bsdf = world.materials[si.material].bsdf(&si);
+
+        // direct lighting, one shadow ray per light
+        for light in world.lights.iter() {
+            let ls = match light.sample_li(&si.p,sampler.get_2d()) {
+                Some(ls) => ls,
+                None => continue
+            };
+            if ls.pdf == 0.0 || ls.li.is_black() {
+                continue;
+            }
+            let f = bsdf.f(&si.wo,&ls.wi) * ls.wi.dot(si.ns).abs();
+            if f.is_black() {
+                continue;
+            }
+            let occluded = if ls.distance.is_infinite() {
+                world.occluded(&si.spawn_ray(&ls.wi),f64::INFINITY)
+            } else {
+                let (shadow,tmax) = si.spawn_ray_to(&(si.p + ls.wi*ls.distance));
+                world.occluded(&shadow,tmax)
+            };
+            if !occluded {
+                l += f * ls.li / ls.pdf;
+            }
+        }
+
+        // perfect specular reflection and refraction
+        if depth < self.max_depth {
+            for lobe in bsdf.specular_lobes(&si.wo) {
+                let weight = lobe.f * lobe.wi.dot(si.ns).abs();
+                if weight.is_black() {
+                    continue;
+                }
+                let li = self.trace(&si.spawn_specular_ray(ray,&lobe.wi,lobe.eta),world,sampler,depth + 1);
+                l += weight * li;
+            }
+        }
+
+        l
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_1_PI;
+    use crate::{
+        image::spectrum,
+        light::point::PointLight,
+        material::{lambertian::Lambertian,mirror::Mirror},
+        math::{point::Point,vector::Vector,normal::Normal},
+        sampler::random::RandomSampler,
+        scene::{plane::Plane,sphere::Sphere}
+    };
+
+    // white floor lit by a point light straight above the origin
+    fn floor_world() -> World {
+        let mut world = World::new(2);
+        let white = world.add_material(Box::new(Lambertian::new(spectrum::WHITE)));
+        world.add_primitive_with_material(Box::new(Plane::new(&Point::new(0.,0.,0.),&Normal::new(0.,1.,0.))),white);
+        world.add_light(Box::new(PointLight::new(Point::new(0.,2.,0.),Spectrum::new(4.,4.,4.))));
+        world
+    }
+
+    #[test]
+    // diffuse floor under a point light should get I cos / (pi r^2)
+    fn test_direct_lighting() {
+        let world = floor_world();
+        let integrator = WhittedIntegrator::new(5);
+        let mut sampler = RandomSampler::new(1);
+        let ray = Ray::new(&Point::new(0.,1.,-1.),&Vector::new(0.,-1.,1.));
+        let l = integrator.li(&ray,&world,&mut sampler);
+        assert!((l.r - FRAC_1_PI).abs() < 1e-9);
+    }
+
+    #[test]
+    // blockers between the point and the light should cast shadows
+    fn test_shadow() {
+        let mut world = floor_world();
+        world.add_primitive(Box::new(Sphere::new(0.5,Point::new(0.,1.,0.))));
+        let integrator = WhittedIntegrator::new(5);
+        let mut sampler = RandomSampler::new(1);
+        let ray = Ray::new(&Point::new(0.,1.,-3.),&Vector::new(0.,-1.,3.));
+        assert!(integrator.li(&ray,&world,&mut sampler).is_black());
+    }
+
+    #[test]
+    // mirror should show the lit floor, until the recursion limit is hit
+    fn test_specular_recursion() {
+        let mut world = floor_world();
+        let mirror = world.add_material(Box::new(Mirror::new(spectrum::WHITE)));
+        world.add_primitive_with_material(Box::new(Plane::new(&Point::new(0.,0.,1.),&Normal::new(0.,0.,-1.))),mirror);
+        let mut sampler = RandomSampler::new(1);
+
+        // hits the mirror first, reflection lands on the floor at the origin
+        let ray = Ray::new(&Point::new(0.,1.,0.),&Vector::new(0.,-0.5,1.));
+        let reflected = WhittedIntegrator::new(5).li(&ray,&world,&mut sampler);
+        assert!(reflected.r > 0.);
+        let limited = WhittedIntegrator::new(0).li(&ray,&world,&mut sampler);
+        assert!(limited.is_black());
+    }
+}
@@ -0,0 +1,10 @@
+pub mod math;
+pub mod scene;
+pub mod image;
+pub mod light;
+pub mod material;
+pub mod medium;
+pub mod texture;
+pub mod sdf;
+pub mod sampler;
+pub mod integrator;
@@ -0,0 +1,6 @@
+// light
+pub mod environment;
+pub mod area;
+pub mod point;
+
+pub mod traits;
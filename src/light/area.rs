@@ -0,0 +1,200 @@
+use std::sync::Arc;
+
+use super::traits::{Light,LightSample,EmissionSample};
+use crate::{
+    image::spectrum::{self,Spectrum},
+    math::{
+        point::Point,
+        vector::Vector,
+        normal::Normal,
+        ray::Ray,
+        frame::Frame,
+        sampling::{cosine_sample_hemisphere,cosine_hemisphere_pdf},
+        traits::{Dot,Normalize}
+    },
+    scene::traits::Primitive
+};
+
+/// # DiffuseAreaLight
+/// Emits constant radiance from the surface of a primitive
+///
+/// # Parameters
+/// * shape (emitting primitive, shared with the world)
+/// * le (emitted radiance)
+/// * two_sided (emit from both sides of the surface)
+pub struct DiffuseAreaLight {
+    pub shape: Arc<dyn Primitive>,
+    pub le: Spectrum,
+    pub two_sided: bool
+}
+
+/// Light trait
+impl Light for DiffuseAreaLight {
+    fn sample_li(&self,p: &Point,u: (f64,f64)) -> Option<LightSample> {
+        let (q,n) = self.shape.sample(u)?;
+        let d: Vector = q - *p;
+        let dist_sq = d.dot(d);
+        if dist_sq == 0.0 {
+            return None
+        }
+        let wi = d.normalize().ok()?;
+        let cos_light = n.dot(-wi).abs();
+        if cos_light == 0.0 {
+            return None
+        }
+        Some(LightSample {
+            wi,
+            li: self.l(&n,&(-wi)),
+            // convert area density to solid angle
+            pdf: dist_sq / (cos_light * self.shape.area()),
+            distance: f64::sqrt(dist_sq),
+            n: Some(n)
+        })
+    }
+
+    fn pdf_li(&self,p: &Point,wi: &Vector) -> f64 {
+        let ray = Ray::new(p,wi);
+        match self.shape.intersect(&ray,f64::INFINITY) {
+            Some(si) => {
+                let cos_light = si.n.dot(-*wi).abs();
+                if cos_light == 0.0 {
+                    return 0.0
+                }
+                let dist_sq = p.distance_sq(si.p);
+                dist_sq / (cos_light * self.shape.area())
+            },
+            None => 0.0
+        }
+    }
+
+    fn l(&self,n: &Normal,w: &Vector) -> Spectrum {
+        if !self.two_sided && n.dot(*w) <= 0.0 {
+            return spectrum::BLACK
+        }
+        self.le
+    }
+
+    fn sample_le(&self,u_pos: (f64,f64),u_dir: (f64,f64)) -> Option<EmissionSample> {
+        let (p,n) = self.shape.sample(u_pos)?;
+
+        // cosine weighted direction about the normal, two sided
+        // lights pick the side with the first dimension
+        let (u_dir,side) = if self.two_sided {
+            if u_dir.0 < 0.5 {
+                ((2.0*u_dir.0,u_dir.1),1.0)
+            } else {
+                ((2.0*u_dir.0 - 1.0,u_dir.1),-1.0)
+            }
+        } else {
+            (u_dir,1.0)
+        };
+        let mut local = cosine_sample_hemisphere(u_dir);
+        local.z *= side;
+        let w = Frame::from_normal(&n).to_world(&local);
+
+        let (pdf_pos,pdf_dir) = self.pdf_le(Some(n),&w);
+        if pdf_dir == 0.0 {
+            return None
+        }
+        Some(EmissionSample {
+            p,
+            n: Some(n),
+            w,
+            le: self.l(&n,&w),
+            pdf_pos,
+            pdf_dir
+        })
+    }
+
+    fn pdf_le(&self,n: Option<Normal>,w: &Vector) -> (f64,f64) {
+        let cos = match n {
+            Some(n) => n.dot(*w),
+            None => return (0.0,0.0)
+        };
+        let pdf_dir = if self.two_sided {
+            0.5 * cosine_hemisphere_pdf(cos.abs())
+        } else if cos > 0.0 {
+            cosine_hemisphere_pdf(cos)
+        } else {
+            0.0
+        };
+        (1.0 / self.shape.area(),pdf_dir)
+    }
+}
+
+impl DiffuseAreaLight {
+    /// Construct area light over the given primitive
+    pub fn new(shape: Arc<dyn Primitive>,le: Spectrum,two_sided: bool) -> DiffuseAreaLight {
+        DiffuseAreaLight {
+            shape,
+            le,
+            two_sided
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::sphere::Sphere;
+
+    fn test_light() -> DiffuseAreaLight {
+        DiffuseAreaLight::new(
+            Arc::new(Sphere::new(1.,Point::new(0.,5.,0.))),
+            Spectrum::new(2.,2.,2.),
+            false
+        )
+    }
+
+    #[test]
+    // should only emit from the outside
+    fn test_l() {
+        let light = test_light();
+        let n = Normal::new(0.,-1.,0.);
+        assert_eq!(light.l(&n,&Vector::new(0.,-1.,0.)).r,2.);
+        assert!(light.l(&n,&Vector::new(0.,1.,0.)).is_black());
+    }
+
+    #[test]
+    // sampled pdf should match pdf_li for visible samples
+    fn test_sample_li() {
+        let light = test_light();
+        let p = Point::new(0.,0.,0.);
+        let mut visible = 0;
+        for i in 0..8 {
+            let u = (0.4,(i as f64 + 0.5) / 8.);
+            let sample = light.sample_li(&p,u).unwrap();
+            assert!(sample.distance >= 4. - 1e-9);
+            if sample.li.is_black() {
+                continue; // back facing sample
+            }
+            visible += 1;
+            // pdf_li finds the nearest (front facing) point along wi
+            let pdf = light.pdf_li(&p,&sample.wi);
+            assert!((pdf - sample.pdf).abs() < 1e-6 * pdf);
+        }
+        assert!(visible > 0);
+    }
+
+    #[test]
+    // emitted rays should leave the front side with matching densities
+    fn test_sample_le() {
+        let light = test_light();
+        for i in 0..8 {
+            let u = ((i as f64 + 0.5) / 8.,0.3);
+            let sample = light.sample_le(u,(0.7,u.0)).unwrap();
+            let n = sample.n.unwrap();
+            assert!(n.dot(sample.w) > 0.);
+            assert_eq!(sample.le.r,2.);
+            let (pdf_pos,pdf_dir) = light.pdf_le(Some(n),&sample.w);
+            assert_eq!(pdf_pos,sample.pdf_pos);
+            assert!((pdf_dir - sample.pdf_dir).abs() < 1e-12);
+            // no emission from the back
+            assert_eq!(light.pdf_le(Some(n),&(-sample.w)).1,0.);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use std::f64::consts::PI;
+
+use super::traits::{Light,LightSample};
+use crate::{
+    image::{hdr::HdrImage,spectrum::{self,Spectrum}},
+    math::{
+        point::Point,
+        vector::Vector,
+        matrix::Matrix,
+        ray::Ray,
+        sampling::Distribution2D,
+        traits::Normalize
+    }
+};
+
+/// # EnvironmentLight
+/// Infinitely distant light surrounding the scene, radiance is
+/// looked up from a lat-long (equirectangular) HDR image
+///
+/// The image maps u to azimuth phi in [0,2pi) and v to polar
+/// angle theta in [0,pi] measured from the +y (up) axis
+///
+/// # Parameters
+/// * image (lat-long radiance map)
+/// * scale (intensity multiplier)
+/// * light_to_world (rotation about the up axis)
+/// * world_to_light (inverse rotation)
+/// * distribution (luminance distribution used for importance sampling)
+pub struct EnvironmentLight {
+    pub image: HdrImage,
+    pub scale: f64,
+    pub light_to_world: Matrix,
+    pub world_to_light: Matrix,
+    pub distribution: Distribution2D
+}
+
+/// Light trait
+impl Light for EnvironmentLight {
+    fn sample_li(&self,_p: &Point,u: (f64,f64)) -> Option<LightSample> {
+        let ((su,sv),map_pdf) = self.distribution.sample_continuous(u.0,u.1);
+        if map_pdf == 0.0 {
+            return None
+        }
+
+        let theta = sv * PI;
+        let sin_theta = f64::sin(theta);
+        if sin_theta == 0.0 {
+            return None
+        }
+
+        let wi = self.light_to_world * direction_from_uv(su,sv);
+        Some(LightSample {
+            wi,
+            li: self.lookup(su,sv),
+            // change of variables from (u,v) to solid angle
+            pdf: map_pdf / (2.0*PI*PI*sin_theta),
+            distance: f64::INFINITY,
+            n: None
+        })
+    }
+
+    fn pdf_li(&self,_p: &Point,wi: &Vector) -> f64 {
+        let (u,v) = match uv_from_direction(&(self.world_to_light * *wi)) {
+            Some(uv) => uv,
+            None => return 0.0
+        };
+        let sin_theta = f64::sin(v * PI);
+        if sin_theta == 0.0 {
+            return 0.0
+        }
+        self.distribution.pdf(u,v) / (2.0*PI*PI*sin_theta)
+    }
+
+    fn le(&self,ray: &Ray) -> Spectrum {
+        match uv_from_direction(&(self.world_to_light * ray.d)) {
+            Some((u,v)) => self.lookup(u,v),
+            None => spectrum::BLACK
+        }
+    }
+}
+
+impl EnvironmentLight {
+    /// Construct environment light from lat-long image
+    /// rotation is in radians about the up (y) axis
+    pub fn new(image: HdrImage,rotation: f64,scale: f64) -> EnvironmentLight {
+        // weight luminance by sin(theta) to account for
+        // the stretching of rows near the poles
+        let mut func: Vec<f64> = Vec::with_capacity(image.width*image.height);
+        for y in 0..image.height {
+            let sin_theta = f64::sin(PI * (y as f64 + 0.5) / image.height as f64);
+            for x in 0..image.width {
+                func.push(image.at(x,y).luminance() * sin_theta);
+            }
+        }
+        let distribution = Distribution2D::new(&func,image.width,image.height);
+
+        let light_to_world = Matrix::rotate_y(rotation);
+        EnvironmentLight {
+            image,
+            scale,
+            light_to_world,
+            world_to_light: light_to_world.transpose(),
+            distribution
+        }
+    }
+
+    /// Load environment light from a Radiance .hdr file
+    pub fn load(path: &str,rotation: f64,scale: f64) -> Result<EnvironmentLight,String> {
+        Ok(EnvironmentLight::new(HdrImage::load(path)?,rotation,scale))
+    }
+
+    /// Scaled radiance at image coordinates (u,v) in [0,1)^2
+    pub fn lookup(&self,u: f64,v: f64) -> Spectrum {
+        let x = ((u * self.image.width as f64) as usize).min(self.image.width - 1);
+        let y = ((v * self.image.height as f64) as usize).min(self.image.height - 1);
+        self.image.at(x,y) * self.scale
+    }
+}
+
+/// Unit direction in light space for image coordinates (u,v)
+fn direction_from_uv(u: f64,v: f64) -> Vector {
+    let phi = u * 2.0 * PI;
+    let theta = v * PI;
+    let (sin_theta,cos_theta) = f64::sin_cos(theta);
+    Vector::new(sin_theta*f64::cos(phi),cos_theta,sin_theta*f64::sin(phi))
+}
+
+/// Image coordinates (u,v) for a light space direction
+fn uv_from_direction(d: &Vector) -> Option<(f64,f64)> {
+    let w = d.normalize().ok()?;
+    let theta = f64::acos(w.y.clamp(-1.0,1.0));
+    let mut phi = f64::atan2(w.z,w.x);
+    if phi < 0.0 {
+        phi += 2.0 * PI;
+    }
+    Some((phi / (2.0*PI),theta / PI))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 4x2 map with a single bright texel in the top row
+    fn bright_spot_image() -> HdrImage {
+        let mut image = HdrImage::new(4,2,Spectrum::new(0.1,0.1,0.1));
+        image.pixels[1] = Spectrum::new(10.,10.,10.);
+        image
+    }
+
+    #[test]
+    // direction to uv mapping should round trip
+    fn test_uv_round_trip() {
+        let (u,v) = (0.3,0.6);
+        let d = direction_from_uv(u,v);
+        let (u2,v2) = uv_from_direction(&d).unwrap();
+        assert!((u - u2).abs() < 1e-12);
+        assert!((v - v2).abs() < 1e-12);
+    }
+
+    #[test]
+    // escaping ray should return scaled radiance
+    fn test_le() {
+        let light = EnvironmentLight::new(bright_spot_image(),0.,2.);
+        // straight down lands in the bottom row
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,-1.,0.));
+        let le = light.le(&ray);
+        assert!((le.r - 0.2).abs() < 1e-12);
+    }
+
+    #[test]
+    // rotation should move the bright texel
+    fn test_rotation() {
+        // texel (1,0) is centered on phi = 3pi/4, theta = pi/4
+        let d = direction_from_uv(0.375,0.25);
+        let ray = Ray::new(&Point::new(0.,0.,0.),&d);
+        let light = EnvironmentLight::new(bright_spot_image(),0.,1.);
+        assert_eq!(light.le(&ray).r,10.);
+        let rotated = EnvironmentLight::new(bright_spot_image(),std::f64::consts::FRAC_PI_2,1.);
+        assert_eq!(rotated.le(&ray).r,0.1);
+    }
+
+    #[test]
+    // sampled pdf should agree with pdf_li and favour the bright texel
+    fn test_sample_li() {
+        let light = EnvironmentLight::new(bright_spot_image(),0.5,1.);
+        let p = Point::new(0.,0.,0.);
+        let mut bright = 0;
+        for i in 0..16 {
+            let u = ((i as f64 + 0.5) / 16.,0.25);
+            let sample = light.sample_li(&p,u).unwrap();
+            let pdf = light.pdf_li(&p,&sample.wi);
+            assert!((pdf - sample.pdf).abs() < 1e-9 * pdf);
+            if sample.li.r == 10. {
+                bright += 1;
+            }
+        }
+        assert!(bright > 8);
+    }
+}
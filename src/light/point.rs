@@ -0,0 +1,111 @@
+use super::traits::{Light,LightSample,EmissionSample};
+use crate::{
+    image::spectrum::Spectrum,
+    math::{
+        point::Point,
+        vector::Vector,
+        normal::Normal,
+        sampling::{uniform_sample_sphere,uniform_sphere_pdf},
+        traits::{Len,Normalize}
+    }
+};
+
+/// # PointLight
+/// Isotropic light emitting from a single point
+///
+/// # Parameters
+/// * position
+/// * intensity (radiant intensity, power per unit solid angle)
+pub struct PointLight {
+    pub position: Point,
+    pub intensity: Spectrum
+}
+
+/// Light trait
+impl Light for PointLight {
+    fn sample_li(&self,p: &Point,_u: (f64,f64)) -> Option<LightSample> {
+        let d: Vector = self.position - *p;
+        let distance = d.len();
+        if distance == 0.0 {
+            return None
+        }
+        Some(LightSample {
+            wi: d.normalize().ok()?,
+            // inverse square falloff
+            li: self.intensity / (distance*distance),
+            pdf: 1.0,
+            distance,
+            n: None
+        })
+    }
+
+    fn pdf_li(&self,_p: &Point,_wi: &Vector) -> f64 {
+        // delta distribution, cannot be hit by sampled directions
+        0.0
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+
+    fn sample_le(&self,_u_pos: (f64,f64),u_dir: (f64,f64)) -> Option<EmissionSample> {
+        Some(EmissionSample {
+            p: self.position,
+            n: None,
+            w: uniform_sample_sphere(u_dir),
+            le: self.intensity,
+            pdf_pos: 1.0,
+            pdf_dir: uniform_sphere_pdf()
+        })
+    }
+
+    fn pdf_le(&self,_n: Option<Normal>,_w: &Vector) -> (f64,f64) {
+        // the position is a delta distribution
+        (0.0,uniform_sphere_pdf())
+    }
+}
+
+impl PointLight {
+    /// Construct point light
+    pub fn new(position: Point,intensity: Spectrum) -> PointLight {
+        PointLight {
+            position,
+            intensity
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // incident radiance should fall off with squared distance
+    fn test_sample_li() {
+        let light = PointLight::new(Point::new(0.,4.,0.),Spectrum::new(16.,16.,16.));
+        let sample = light.sample_li(&Point::new(0.,2.,0.),(0.5,0.5)).unwrap();
+        assert_eq!(sample.wi.y,1.);
+        assert_eq!(sample.li.r,4.);
+        assert_eq!(sample.pdf,1.);
+        assert_eq!(sample.distance,2.);
+        assert!(light.is_delta());
+        assert_eq!(light.pdf_li(&Point::new(0.,0.,0.),&sample.wi),0.);
+        // sampling at the light position is undefined
+        assert!(light.sample_li(&Point::new(0.,4.,0.),(0.5,0.5)).is_none());
+    }
+
+    #[test]
+    // emitted rays should start at the light and cover the sphere uniformly
+    fn test_sample_le() {
+        let light = PointLight::new(Point::new(0.,4.,0.),Spectrum::new(16.,16.,16.));
+        let sample = light.sample_le((0.,0.),(0.2,0.9)).unwrap();
+        assert_eq!(sample.p.y,4.);
+        assert!(sample.n.is_none());
+        assert_eq!(sample.le.r,16.);
+        assert_eq!(sample.pdf_dir,light.pdf_le(None,&sample.w).1);
+    }
+}
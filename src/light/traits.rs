@@ -0,0 +1,76 @@
+use crate::{
+    image::spectrum::{self,Spectrum},
+    math::{point::Point,vector::Vector,normal::Normal,ray::Ray}
+};
+
+/// # LightSample
+/// Incident radiance sampled from a light towards a point
+///
+/// # Parameters
+/// * wi (unit direction from the point towards the light)
+/// * li (incident radiance)
+/// * pdf (solid angle density of wi)
+/// * distance (distance to the light, infinite for distant lights)
+/// * n (surface normal at the sampled point, None for lights without a surface)
+pub struct LightSample {
+    pub wi: Vector,
+    pub li: Spectrum,
+    pub pdf: f64,
+    pub distance: f64,
+    pub n: Option<Normal>
+}
+
+/// # EmissionSample
+/// Ray leaving a light, used to start paths at the light
+///
+/// # Parameters
+/// * p (origin on the light)
+/// * n (surface normal at p, None for lights without a surface)
+/// * w (unit direction of emission)
+/// * le (emitted radiance, or intensity for point lights)
+/// * pdf_pos (area density of p, 0 for delta positions)
+/// * pdf_dir (solid angle density of w given p)
+pub struct EmissionSample {
+    pub p: Point,
+    pub n: Option<Normal>,
+    pub w: Vector,
+    pub le: Spectrum,
+    pub pdf_pos: f64,
+    pub pdf_dir: f64
+}
+
+pub trait Light: Send + Sync {
+    /// Sample incident radiance at p given two uniform values
+    fn sample_li(&self,p: &Point,u: (f64,f64)) -> Option<LightSample>;
+
+    /// Solid angle density of sample_li choosing direction wi at p
+    fn pdf_li(&self,p: &Point,wi: &Vector) -> f64;
+
+    /// Radiance carried along a ray that escapes the scene
+    fn le(&self,_ray: &Ray) -> Spectrum {
+        spectrum::BLACK
+    }
+
+    /// True if the light is described by a delta distribution
+    /// and so can only be reached by sample_li
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    /// Radiance emitted in direction w from a surface point with normal n
+    fn l(&self,_n: &Normal,_w: &Vector) -> Spectrum {
+        spectrum::BLACK
+    }
+
+    /// Sample a ray leaving the light given two pairs of uniform values,
+    /// lights at infinity cannot start paths
+    fn sample_le(&self,_u_pos: (f64,f64),_u_dir: (f64,f64)) -> Option<EmissionSample> {
+        None
+    }
+
+    /// Densities (pdf_pos,pdf_dir) of sample_le emitting in direction w
+    /// from a point with normal n
+    fn pdf_le(&self,_n: Option<Normal>,_w: &Vector) -> (f64,f64) {
+        (0.0,0.0)
+    }
+}
@@ -1,17 +1,27 @@
-mod math;
-mod scene;
 mod view;
-mod image;
 
-use crate::{
-    view::window::Window,
-    image::film::Film
+use view::window::Window;
+use rust_raytracer::{
+    image::film::Film,
+    light::environment::EnvironmentLight
 };
 
 fn main() {
     const WIDTH: usize = 800;
     const HEIGHT: usize = 800;
     let mut film: Film = Film::new(WIDTH,HEIGHT);
-    let window: Window = Window::new("Rust Raytracing Demo".to_string(),WIDTH as u32,HEIGHT as u32,&mut film);
+    let mut window: Window = Window::new("Rust Raytracing Demo".to_string(),WIDTH as u32,HEIGHT as u32,&mut film);
+
+    // optional HDR environment map: <file.hdr> [rotation in degrees] [intensity scale]
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 {
+        let rotation: f64 = args.get(2).and_then(|a| a.parse().ok()).unwrap_or(0.0);
+        let scale: f64 = args.get(3).and_then(|a| a.parse().ok()).unwrap_or(1.0);
+        match EnvironmentLight::load(&args[1],rotation.to_radians(),scale) {
+            Ok(environment) => window.set_environment(Box::new(environment)),
+            Err(e) => eprintln!("could not load environment map, rendering without it: {}",e)
+        }
+    }
+
     window.run().unwrap();
 }
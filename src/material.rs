@@ -0,0 +1,12 @@
+// material
+pub mod lambertian;
+pub mod mirror;
+pub mod glass;
+pub mod fresnel;
+pub mod interface;
+pub mod dispersion;
+pub mod bump;
+pub mod normalmap;
+pub mod hair;
+
+pub mod traits;
@@ -0,0 +1,169 @@
+use std::sync::Arc;
+
+use super::traits::{Material,Bsdf};
+use crate::{
+    math::{
+        vector::Vector,
+        normal::Normal,
+        traits::{Dot,Cross,Normalize}
+    },
+    scene::interaction::SurfaceInteraction,
+    texture::traits::Texture
+};
+
+/// uv step of the finite differences when there is no pixel footprint
+const DEFAULT_DELTA: f64 = 0.0005;
+
+/// # BumpMap
+/// Material whose shading normal follows a height texture displacing
+/// the surface along its normal, the geometry itself is unchanged
+///
+/// # Parameters
+/// * material (material being bumped)
+/// * height (displacement, the average of the channels is used)
+/// * scale (displacement per unit of height)
+pub struct BumpMap {
+    pub material: Box<dyn Material>,
+    pub height: Arc<dyn Texture>,
+    pub scale: f64
+}
+
+/// Material trait
+impl Material for BumpMap {
+    fn apply_shading(&self,si: &mut SurfaceInteraction) {
+        self.material.apply_shading(si);
+        self.bump(si);
+    }
+
+    fn bsdf(&self,si: &SurfaceInteraction) -> Box<dyn Bsdf> {
+        self.material.bsdf(si)
+    }
+
+    fn bsdf_at_wavelength(&self,si: &SurfaceInteraction,lambda: f64) -> Box<dyn Bsdf> {
+        self.material.bsdf_at_wavelength(si,lambda)
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.material.is_dispersive()
+    }
+}
+
+impl BumpMap {
+    /// Construct bump mapped material
+    pub fn new(material: Box<dyn Material>,height: Arc<dyn Texture>,scale: f64) -> BumpMap {
+        BumpMap {material,height,scale}
+    }
+
+    /// Perturb the shading frame of si, the partial derivatives of the
+    /// displaced surface are estimated by finite differences in u and v
+    /// over half the pixel footprint
+    pub fn bump(&self,si: &mut SurfaceInteraction) {
+        let step = |d: (f64,f64)| {
+            let delta = 0.5 * (d.0.abs() + d.1.abs());
+            if delta > 0.0 { delta } else { DEFAULT_DELTA }
+        };
+        let du = step((si.duvdx.0,si.duvdy.0));
+        let dv = step((si.duvdx.1,si.duvdy.1));
+
+        let height = |si: &SurfaceInteraction| self.height.evaluate(si).average() * self.scale;
+        let mut shifted = *si;
+        shifted.p = si.p + si.dpdu * du;
+        shifted.uv = (si.uv.0 + du,si.uv.1);
+        let height_u = height(&shifted);
+        shifted.p = si.p + si.dpdv * dv;
+        shifted.uv = (si.uv.0,si.uv.1 + dv);
+        let height_v = height(&shifted);
+        let height_0 = height(si);
+
+        let ns = Vector::from(si.ns);
+        let dpdu = si.dpdu + ns * ((height_u - height_0) / du);
+        let dpdv = si.dpdv + ns * ((height_v - height_0) / dv);
+
+        // keep the orientation the unperturbed partials had relative to ns
+        let sign = if si.dpdu.cross(si.dpdv).dot(ns) < 0.0 { -1.0 } else { 1.0 };
+        if let Ok(n) = (dpdu.cross(dpdv) * sign).normalize() {
+            si.set_shading_geometry(&Normal::from(n),&dpdu);
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        image::spectrum::{self,Spectrum},
+        math::{point::Point,traits::LenSq},
+        material::lambertian::Lambertian,
+        texture::constant::ConstantTexture
+    };
+
+    // height equal to the u coordinate
+    struct Ramp;
+
+    impl Texture for Ramp {
+        fn evaluate(&self,si: &SurfaceInteraction) -> Spectrum {
+            spectrum::WHITE * si.uv.0
+        }
+    }
+
+    // height equal to u squared, forward differences overestimate its slope by the step
+    struct Parabola;
+
+    impl Texture for Parabola {
+        fn evaluate(&self,si: &SurfaceInteraction) -> Spectrum {
+            spectrum::WHITE * (si.uv.0 * si.uv.0)
+        }
+    }
+
+    fn test_interaction() -> SurfaceInteraction {
+        SurfaceInteraction::new(
+            Point::new(0.,0.,0.),
+            Normal::new(0.,1.,0.),
+            (0.5,0.5),
+            Vector::new(1.,0.,0.),
+            Vector::new(0.,0.,1.),
+            1.,
+            Vector::new(0.,1.,0.)
+        )
+    }
+
+    #[test]
+    // a ramp should tilt the shading normal like the displaced surface
+    fn test_bump() {
+        let material = BumpMap::new(Box::new(Lambertian::new(spectrum::WHITE)),Arc::new(Ramp),0.1);
+        let mut si = test_interaction();
+        material.apply_shading(&mut si);
+        let expected = Vector::new(-0.1,1.,0.).normalize().unwrap();
+        assert!((Vector::from(si.ns) - expected).len_sq() < 1e-12,"{}",si.ns);
+        assert_eq!(si.n.y,1.);
+        // the shading frame should follow the new normal
+        let frame = si.shading_frame();
+        assert!((frame.n - expected).len_sq() < 1e-12);
+        assert!(frame.s.dot(frame.n).abs() < 1e-12);
+        // a flat height leaves the surface alone
+        let material = BumpMap::new(
+            Box::new(Lambertian::new(spectrum::WHITE)),
+            Arc::new(ConstantTexture::new(spectrum::WHITE)),
+            1.
+        );
+        let mut si = test_interaction();
+        material.apply_shading(&mut si);
+        assert!((si.ns.y - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    // the finite difference step should follow the pixel footprint
+    fn test_footprint() {
+        let material = BumpMap::new(Box::new(Lambertian::new(spectrum::WHITE)),Arc::new(Parabola),0.1);
+        let mut si = test_interaction();
+        si.duvdx = (0.02,0.);
+        si.duvdy = (0.,0.02);
+        material.apply_shading(&mut si);
+        let slope = 0.1 * 1.01;
+        assert!((si.ns.x + slope / f64::sqrt(1. + slope*slope)).abs() < 1e-9,"{}",si.ns);
+    }
+}
@@ -0,0 +1,81 @@
+/// Wavelength (nm) of the sodium D line, where catalogues quote a single index
+pub const SODIUM_D: f64 = 589.3;
+
+/// # Ior
+/// Index of refraction as a function of wavelength
+///
+/// # Variants
+/// * Constant (same index at every wavelength)
+/// * Cauchy (n = a + b/λ², b in µm²)
+/// * Sellmeier (n² = 1 + Σ bᵢλ²/(λ² - cᵢ), c in µm²)
+#[derive(Clone,Copy,Debug)]
+pub enum Ior {
+    Constant(f64),
+    Cauchy {a: f64,b: f64},
+    Sellmeier {b: [f64; 3],c: [f64; 3]}
+}
+
+impl Ior {
+    /// Index of refraction at wavelength lambda (nm)
+    pub fn at(&self,lambda: f64) -> f64 {
+        // both fits are expressed in micrometers
+        let l2 = (lambda * 1e-3) * (lambda * 1e-3);
+        match *self {
+            Ior::Constant(n) => n,
+            Ior::Cauchy {a,b} => a + b / l2,
+            Ior::Sellmeier {b,c} => {
+                let sum: f64 = (0..3).map(|i| b[i] * l2 / (l2 - c[i])).sum();
+                f64::sqrt(1.0 + sum)
+            }
+        }
+    }
+
+    /// Schott N-BK7 crown glass
+    pub fn bk7() -> Ior {
+        Ior::Sellmeier {
+            b: [1.03961212,0.231792344,1.01046945],
+            c: [0.00600069867,0.0200179144,103.560653]
+        }
+    }
+
+    /// Two term Cauchy fit of BK7
+    pub fn bk7_cauchy() -> Ior {
+        Ior::Cauchy {a: 1.5046,b: 0.00420}
+    }
+
+    /// True if the index changes with wavelength
+    pub fn is_dispersive(&self) -> bool {
+        !matches!(self,Ior::Constant(_))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // BK7 should match the catalogue values at the Fraunhofer lines
+    fn test_bk7() {
+        let bk7 = Ior::bk7();
+        assert!((bk7.at(SODIUM_D) - 1.5168).abs() < 1e-4);
+        assert!((bk7.at(486.1) - 1.5224).abs() < 1e-4);
+        assert!((bk7.at(656.3) - 1.5143).abs() < 1e-4);
+        assert!((Ior::bk7_cauchy().at(SODIUM_D) - 1.5168).abs() < 1e-3);
+    }
+
+    #[test]
+    // normal dispersion, blue light bends more than red
+    fn test_dispersion() {
+        for ior in [Ior::bk7(),Ior::bk7_cauchy()] {
+            assert!(ior.is_dispersive());
+            assert!(ior.at(400.) > ior.at(550.));
+            assert!(ior.at(550.) > ior.at(700.));
+        }
+        assert!(!Ior::Constant(1.5).is_dispersive());
+        assert_eq!(Ior::Constant(1.5).at(400.),1.5);
+    }
+}
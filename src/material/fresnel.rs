@@ -0,0 +1,103 @@
+use crate::math::vector::Vector;
+
+/// Fresnel reflectance of a dielectric interface for unpolarized light
+///
+/// cos_theta_i is measured against the normal on the incident side
+/// of the interface when positive, eta is the ratio of the index of
+/// refraction inside the surface over the index outside
+pub fn fresnel_dielectric(cos_theta_i: f64,eta: f64) -> f64 {
+    let mut cos_i = cos_theta_i.clamp(-1.0,1.0);
+    let mut eta = eta;
+    if cos_i < 0.0 {
+        // ray is leaving the surface, swap sides
+        eta = 1.0 / eta;
+        cos_i = -cos_i;
+    }
+
+    // Snell's law
+    let sin2_i = f64::max(0.0,1.0 - cos_i*cos_i);
+    let sin2_t = sin2_i / (eta*eta);
+    if sin2_t >= 1.0 {
+        return 1.0 // total internal reflection
+    }
+    let cos_t = f64::sqrt(1.0 - sin2_t);
+
+    let r_parl = (eta*cos_i - cos_t) / (eta*cos_i + cos_t);
+    let r_perp = (cos_i - eta*cos_t) / (cos_i + eta*cos_t);
+    (r_parl*r_parl + r_perp*r_perp) / 2.0
+}
+
+/// Reflect local space direction about the +z axis
+pub fn reflect(wo: &Vector) -> Vector {
+    Vector::new(-wo.x,-wo.y,wo.z)
+}
+
+/// Refract local space direction through an interface with normal +z
+/// returns (transmitted direction,relative eta along the path)
+/// or None on total internal reflection
+pub fn refract(wo: &Vector,eta: f64) -> Option<(Vector,f64)> {
+    let (cos_i,eta) = if wo.z < 0.0 {
+        (-wo.z,1.0 / eta)
+    } else {
+        (wo.z,eta)
+    };
+
+    let sin2_i = f64::max(0.0,1.0 - cos_i*cos_i);
+    let sin2_t = sin2_i / (eta*eta);
+    if sin2_t >= 1.0 {
+        return None
+    }
+    let cos_t = f64::sqrt(1.0 - sin2_t);
+
+    // transmitted direction lies on the opposite side of wo
+    let z = if wo.z < 0.0 { cos_t } else { -cos_t };
+    Some((Vector::new(-wo.x / eta,-wo.y / eta,z),eta))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // normal incidence reflectance should be ((eta-1)/(eta+1))^2
+    fn test_fresnel_normal_incidence() {
+        let r = fresnel_dielectric(1.,1.5);
+        assert!((r - 0.04).abs() < 1e-12);
+        // same from inside
+        let r = fresnel_dielectric(-1.,1.5);
+        assert!((r - 0.04).abs() < 1e-12);
+    }
+
+    #[test]
+    // grazing angles from inside should totally internally reflect
+    fn test_fresnel_tir() {
+        assert_eq!(fresnel_dielectric(-0.1,1.5),1.);
+        assert!(fresnel_dielectric(0.1,1.5) < 1.);
+    }
+
+    #[test]
+    // reflection should mirror about the normal
+    fn test_reflect() {
+        let r = reflect(&Vector::new(0.6,0.,0.8));
+        assert_eq!(r.x,-0.6);
+        assert_eq!(r.z,0.8);
+    }
+
+    #[test]
+    // refraction should obey Snell's law
+    fn test_refract() {
+        let s = f64::sqrt(0.5);
+        let (wt,eta) = refract(&Vector::new(s,0.,s),1.5).unwrap();
+        assert_eq!(eta,1.5);
+        assert!(wt.z < 0.);
+        // sin_t = sin_i / eta
+        assert!((-wt.x - s / 1.5).abs() < 1e-12);
+        assert!(((wt.x*wt.x + wt.y*wt.y + wt.z*wt.z) - 1.).abs() < 1e-12);
+        // leaving at a grazing angle is total internal reflection
+        assert!(refract(&Vector::new(0.9,0.,-f64::sqrt(1. - 0.81)),1.5).is_none());
+    }
+}
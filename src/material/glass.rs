@@ -0,0 +1,253 @@
+use super::{
+    traits::{Material,Bsdf,BsdfSample},
+    fresnel::{fresnel_dielectric,reflect,refract},
+    dispersion::{Ior,SODIUM_D}
+};
+use crate::{
+    image::spectrum::{self,Spectrum},
+    math::{vector::Vector,frame::Frame},
+    scene::interaction::SurfaceInteraction
+};
+
+/// # Glass
+/// Smooth dielectric with specular reflection and refraction
+///
+/// # Parameters
+/// * ior (index of refraction inside the surface)
+/// * reflectance (tint of reflected light)
+/// * transmittance (tint of transmitted light)
+/// * dispersion (index as a function of wavelength, used in spectral mode)
+pub struct Glass {
+    pub ior: f64,
+    pub reflectance: Spectrum,
+    pub transmittance: Spectrum,
+    pub dispersion: Option<Ior>
+}
+
+/// Material trait
+impl Material for Glass {
+    fn bsdf(&self,si: &SurfaceInteraction) -> Box<dyn Bsdf> {
+        self.bsdf_with_eta(si,self.ior)
+    }
+
+    fn bsdf_at_wavelength(&self,si: &SurfaceInteraction,lambda: f64) -> Box<dyn Bsdf> {
+        match &self.dispersion {
+            Some(ior) => self.bsdf_with_eta(si,ior.at(lambda)),
+            None => self.bsdf(si)
+        }
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.dispersion.is_some_and(|ior| ior.is_dispersive())
+    }
+}
+
+impl Glass {
+    /// Construct clear glass with given index of refraction
+    pub fn new(ior: f64) -> Glass {
+        Glass {
+            ior,
+            reflectance: spectrum::WHITE,
+            transmittance: spectrum::WHITE,
+            dispersion: None
+        }
+    }
+
+    /// Construct clear dispersive glass, RGB rendering
+    /// uses the index at the sodium D line
+    pub fn dispersive(ior: Ior) -> Glass {
+        Glass {
+            ior: ior.at(SODIUM_D),
+            reflectance: spectrum::WHITE,
+            transmittance: spectrum::WHITE,
+            dispersion: Some(ior)
+        }
+    }
+
+    fn bsdf_with_eta(&self,si: &SurfaceInteraction,eta: f64) -> Box<dyn Bsdf> {
+        Box::new(GlassBsdf {
+            frame: si.shading_frame(),
+            eta,
+            reflectance: self.reflectance,
+            transmittance: self.transmittance
+        })
+    }
+}
+
+/// # GlassBsdf
+/// Fresnel weighted choice between delta reflection and refraction,
+/// the frame normal points out of the surface
+pub struct GlassBsdf {
+    pub frame: Frame,
+    pub eta: f64,
+    pub reflectance: Spectrum,
+    pub transmittance: Spectrum
+}
+
+/// Bsdf trait
+impl Bsdf for GlassBsdf {
+    fn f(&self,_wo: &Vector,_wi: &Vector) -> Spectrum {
+        // delta distribution, only reachable through sample_f
+        spectrum::BLACK
+    }
+
+    fn sample_f(&self,wo: &Vector,u: (f64,f64)) -> Option<BsdfSample> {
+        let wo_local = self.frame.to_local(wo);
+        let fr = fresnel_dielectric(wo_local.z,self.eta);
+
+        if u.0 < fr {
+            // specular reflection
+            let wi = reflect(&wo_local);
+            if wi.z == 0.0 {
+                return None
+            }
+            return Some(BsdfSample {
+                wi: self.frame.to_world(&wi),
+                f: self.reflectance * (fr / wi.z.abs()),
+                pdf: fr,
+                specular: true,
+                eta: 1.0
+            })
+        }
+
+        // specular transmission, radiance is scaled by 1/eta^2
+        let (wi,eta) = refract(&wo_local,self.eta)?;
+        if wi.z == 0.0 {
+            return None
+        }
+        let ft = self.transmittance * ((1.0 - fr) / (wi.z.abs() * eta * eta));
+        Some(BsdfSample {
+            wi: self.frame.to_world(&wi),
+            f: ft,
+            pdf: 1.0 - fr,
+            specular: true,
+            eta
+        })
+    }
+
+    fn pdf(&self,_wo: &Vector,_wi: &Vector) -> f64 {
+        0.0
+    }
+
+    fn is_specular(&self) -> bool {
+        true
+    }
+
+    fn specular_lobes(&self,wo: &Vector) -> Vec<BsdfSample> {
+        // u = 0 always picks reflection, u = 1 always picks refraction
+        [0.0,1.0].iter()
+            .filter_map(|u| self.sample_f(wo,(*u,0.0)))
+            .map(|lobe| BsdfSample {pdf: 1.0,..lobe})
+            .collect()
+    }
+
+    fn adjoint_scale(&self,wo: &Vector,wi: &Vector) -> f64 {
+        let wo = self.frame.to_local(wo);
+        let wi = self.frame.to_local(wi);
+        if wo.z * wi.z >= 0.0 {
+            return 1.0
+        }
+        // undo the 1/eta^2 radiance scaling of refraction
+        let eta = if wo.z < 0.0 { 1.0 / self.eta } else { self.eta };
+        eta * eta
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{point::Point,normal::Normal};
+
+    fn test_bsdf() -> Box<dyn Bsdf> {
+        let si = SurfaceInteraction::new(
+            Point::new(0.,0.,0.),
+            Normal::new(0.,0.,1.),
+            (0.,0.),
+            Vector::new(1.,0.,0.),
+            Vector::new(0.,1.,0.),
+            1.,
+            Vector::new(0.,0.,1.)
+        );
+        Glass::new(1.5).bsdf(&si)
+    }
+
+    #[test]
+    // low u should reflect, high u should refract into the surface
+    fn test_sample_f() {
+        let bsdf = test_bsdf();
+        let wo = Vector::new(0.,0.,1.);
+        let reflected = bsdf.sample_f(&wo,(0.01,0.5)).unwrap();
+        assert!(reflected.specular);
+        assert!((reflected.pdf - 0.04).abs() < 1e-12);
+        assert!((reflected.wi.z - 1.).abs() < 1e-12);
+        let refracted = bsdf.sample_f(&wo,(0.5,0.5)).unwrap();
+        assert!((refracted.wi.z + 1.).abs() < 1e-12);
+        assert!((refracted.pdf - 0.96).abs() < 1e-12);
+        // throughput f*cos/pdf is 1/eta^2
+        assert!((refracted.f.r / refracted.pdf - 1. / 2.25).abs() < 1e-12);
+    }
+
+    #[test]
+    // lobes should split energy by the fresnel term
+    fn test_specular_lobes() {
+        let bsdf = test_bsdf();
+        let wo = Vector::new(0.,0.,1.);
+        let lobes = bsdf.specular_lobes(&wo);
+        assert_eq!(lobes.len(),2);
+        assert!((lobes[0].f.r - 0.04).abs() < 1e-12);
+        assert!((lobes[1].f.r - 0.96 / 2.25).abs() < 1e-12);
+        // only reflection under total internal reflection
+        let grazing = Vector::new(0.9,0.,-f64::sqrt(1. - 0.81));
+        assert_eq!(bsdf.specular_lobes(&grazing).len(),1);
+    }
+
+    #[test]
+    // grazing rays leaving the glass should always reflect
+    fn test_total_internal_reflection() {
+        let bsdf = test_bsdf();
+        let wo = Vector::new(0.9,0.,-f64::sqrt(1. - 0.81));
+        let sample = bsdf.sample_f(&wo,(0.99,0.5)).unwrap();
+        assert!(sample.wi.z < 0.);
+        assert_eq!(sample.pdf,1.);
+    }
+
+    #[test]
+    // adjoint refraction should cancel the radiance scaling
+    fn test_adjoint_scale() {
+        let bsdf = test_bsdf();
+        let wo = Vector::new(0.,0.,1.);
+        let refracted = bsdf.sample_f(&wo,(0.5,0.5)).unwrap();
+        let adjoint = refracted.f.r * bsdf.adjoint_scale(&wo,&refracted.wi) / refracted.pdf;
+        assert!((adjoint - 1.).abs() < 1e-12);
+        let reflected = bsdf.sample_f(&wo,(0.01,0.5)).unwrap();
+        assert_eq!(bsdf.adjoint_scale(&wo,&reflected.wi),1.);
+    }
+
+    #[test]
+    // dispersive glass should refract blue light more strongly than red
+    fn test_dispersive() {
+        let si = SurfaceInteraction::new(
+            Point::new(0.,0.,0.),
+            Normal::new(0.,0.,1.),
+            (0.,0.),
+            Vector::new(1.,0.,0.),
+            Vector::new(0.,1.,0.),
+            1.,
+            Vector::new(0.,0.,1.)
+        );
+        let glass = Glass::dispersive(Ior::bk7());
+        assert!(glass.is_dispersive());
+        assert!(!Glass::new(1.5).is_dispersive());
+        assert!((glass.ior - 1.5168).abs() < 1e-4);
+
+        let wo = Vector::new(f64::sqrt(0.5),0.,f64::sqrt(0.5));
+        let blue = glass.bsdf_at_wavelength(&si,420.).sample_f(&wo,(0.99,0.5)).unwrap();
+        let red = glass.bsdf_at_wavelength(&si,680.).sample_f(&wo,(0.99,0.5)).unwrap();
+        // sine of the refracted angle is smaller for the larger index
+        assert!(blue.wi.x.abs() < red.wi.x.abs());
+    }
+}
@@ -0,0 +1,498 @@
+use std::f64::consts::PI;
+
+use super::{
+    traits::{Material,Bsdf,BsdfSample},
+    fresnel::fresnel_dielectric
+};
+use crate::{
+    image::spectrum::{self,Spectrum},
+    math::{
+        vector::Vector,
+        frame::Frame
+    },
+    scene::interaction::SurfaceInteraction
+};
+
+/// Lobes followed explicitly, R, TT and TRT, the rest are lumped together
+const P_MAX: usize = 3;
+
+/// Absorption coefficient of eumelanin, the pigment of brown and black hair
+const EUMELANIN: Spectrum = Spectrum {r: 0.419,g: 0.697,b: 1.37};
+
+/// Absorption coefficient of pheomelanin, the pigment of red hair
+const PHEOMELANIN: Spectrum = Spectrum {r: 0.187,g: 0.4,b: 1.05};
+
+/// # Hair
+/// Scattering from a dielectric fiber with an absorbing interior
+/// (Chiang et al. "A Practical and Controllable Hair and Fur Model for
+/// Production Path Tracing"). Light is reflected at the surface (R),
+/// transmitted through the fiber (TT) or reflected once inside it (TRT),
+/// each lobe split into a longitudinal part spread by beta_m and an
+/// azimuthal part spread by beta_n. Meant for curves, which report the
+/// offset across the fiber in v and run the tangent along it
+///
+/// # Parameters
+/// * sigma_a (absorption coefficient inside the fiber, per unit diameter)
+/// * eta (index of refraction of the fiber)
+/// * beta_m (longitudinal roughness in [0,1])
+/// * beta_n (azimuthal roughness in [0,1])
+/// * alpha (tilt of the cuticle scales in degrees)
+pub struct Hair {
+    pub sigma_a: Spectrum,
+    pub eta: f64,
+    pub beta_m: f64,
+    pub beta_n: f64,
+    pub alpha: f64
+}
+
+/// Material trait
+impl Material for Hair {
+    fn bsdf(&self,si: &SurfaceInteraction) -> Box<dyn Bsdf> {
+        Box::new(HairBsdf::new(
+            si.shading_frame(),
+            -1.0 + 2.0 * si.uv.1,
+            self
+        ))
+    }
+}
+
+impl Hair {
+    /// Construct hair with the usual index of refraction and scale tilt
+    pub fn new(sigma_a: Spectrum,beta_m: f64,beta_n: f64) -> Hair {
+        Hair {
+            sigma_a,
+            eta: 1.55,
+            beta_m: beta_m.clamp(0.0,1.0),
+            beta_n: beta_n.clamp(0.0,1.0),
+            alpha: 2.0
+        }
+    }
+
+    /// Construct hair colored by concentrations of eumelanin (about 8 for
+    /// black hair, 0.3 for blonde) and pheomelanin
+    pub fn from_melanin(eumelanin: f64,pheomelanin: f64,beta_m: f64,beta_n: f64) -> Hair {
+        Hair::new(EUMELANIN * eumelanin + PHEOMELANIN * pheomelanin,beta_m,beta_n)
+    }
+
+    /// Construct hair whose multiple scattering gives roughly the color c
+    pub fn from_color(c: Spectrum,beta_m: f64,beta_n: f64) -> Hair {
+        let b = beta_n.clamp(0.0,1.0);
+        let fit = 5.969 - 0.215*b + 2.532*b.powi(2) - 10.73*b.powi(3) + 5.574*b.powi(4) + 0.245*b.powi(5);
+        let sigma = |c: f64| (c.max(1e-4).ln() / fit).powi(2);
+        Hair::new(Spectrum::new(sigma(c.r),sigma(c.g),sigma(c.b)),beta_m,beta_n)
+    }
+}
+
+/// # HairBsdf
+/// Hair scattering in a local frame with x along the fiber and z facing
+/// the viewer, the azimuth is measured from z towards y
+///
+/// # Parameters
+/// * frame (shading frame)
+/// * h (offset across the fiber in [-1,1])
+/// * gamma_o (angle of incidence in the plane across the fiber)
+/// * eta (index of refraction)
+/// * sigma_a (absorption coefficient)
+/// * v (longitudinal variance of each lobe)
+/// * s (logistic scale of the azimuthal lobes)
+/// * sin_2k_alpha, cos_2k_alpha (scale tilt doubled k times for the lobes)
+pub struct HairBsdf {
+    pub frame: Frame,
+    pub h: f64,
+    pub gamma_o: f64,
+    pub eta: f64,
+    pub sigma_a: Spectrum,
+    pub v: [f64; P_MAX + 1],
+    pub s: f64,
+    pub sin_2k_alpha: [f64; 3],
+    pub cos_2k_alpha: [f64; 3]
+}
+
+/// Bsdf trait
+impl Bsdf for HairBsdf {
+    fn f(&self,wo: &Vector,wi: &Vector) -> Spectrum {
+        let wo = self.frame.to_local(wo);
+        let wi = self.frame.to_local(wi);
+        let (sin_theta_o,cos_theta_o,phi_o) = angles(&wo);
+        let (sin_theta_i,cos_theta_i,phi_i) = angles(&wi);
+
+        let gamma_t = self.gamma_t(sin_theta_o,cos_theta_o);
+        let ap = attenuation(cos_theta_o,self.eta,self.h,self.transmittance(sin_theta_o,cos_theta_o));
+        let phi = phi_i - phi_o;
+        let mut sum = spectrum::BLACK;
+        for (p,&a) in ap.iter().enumerate().take(P_MAX) {
+            let (sin_op,cos_op) = self.tilt(p,sin_theta_o,cos_theta_o);
+            let m = longitudinal(cos_theta_i,cos_op,sin_theta_i,sin_op,self.v[p]);
+            sum += a * (m * azimuthal(phi,p,self.s,self.gamma_o,gamma_t));
+        }
+        let m = longitudinal(cos_theta_i,cos_theta_o,sin_theta_i,sin_theta_o,self.v[P_MAX]);
+        sum += ap[P_MAX] * (m / (2.0 * PI));
+        // the integrators weigh by the cosine to the normal, which does
+        // not apply to fibers
+        if wi.z.abs() > 0.0 {
+            sum = sum * (1.0 / wi.z.abs());
+        }
+        sum
+    }
+
+    fn sample_f(&self,wo: &Vector,u: (f64,f64)) -> Option<BsdfSample> {
+        let wo_local = self.frame.to_local(wo);
+        let (sin_theta_o,cos_theta_o,phi_o) = angles(&wo_local);
+        // four numbers out of two
+        let (mut u_lobe,u_phi) = demux(u.0);
+        let (u_theta,u_rotation) = demux(u.1);
+
+        let pdfs = self.lobe_pdfs(sin_theta_o,cos_theta_o);
+        let mut p = 0;
+        while p < P_MAX && u_lobe >= pdfs[p] {
+            u_lobe -= pdfs[p];
+            p += 1;
+        }
+
+        // longitudinal angle around the tilted reflection of wo
+        let (sin_op,cos_op) = self.tilt(p,sin_theta_o,cos_theta_o);
+        let u_theta = u_theta.max(1e-5);
+        let v = self.v[p];
+        let cos_theta = 1.0 + v * f64::ln(u_theta + (1.0 - u_theta) * f64::exp(-2.0 / v));
+        let sin_theta = safe_sqrt(1.0 - cos_theta*cos_theta);
+        let cos_phi = f64::cos(2.0 * PI * u_rotation);
+        let sin_theta_i = -cos_theta * sin_op + sin_theta * cos_phi * cos_op;
+        let cos_theta_i = safe_sqrt(1.0 - sin_theta_i*sin_theta_i);
+
+        let dphi = if p < P_MAX {
+            let gamma_t = self.gamma_t(sin_theta_o,cos_theta_o);
+            shift(p,self.gamma_o,gamma_t) + sample_trimmed_logistic(u_phi,self.s,-PI,PI)
+        } else {
+            2.0 * PI * u_phi
+        };
+        let phi_i = phi_o + dphi;
+        let wi = Vector::new(sin_theta_i,cos_theta_i * f64::cos(phi_i),cos_theta_i * f64::sin(phi_i));
+        let wi = self.frame.to_world(&wi);
+
+        let pdf = self.pdf(wo,&wi);
+        if !(pdf.is_finite() && pdf > 0.0) {
+            return None
+        }
+        Some(BsdfSample {
+            f: self.f(wo,&wi),
+            wi,
+            pdf,
+            specular: false,
+            eta: 1.0
+        })
+    }
+
+    fn pdf(&self,wo: &Vector,wi: &Vector) -> f64 {
+        let wo = self.frame.to_local(wo);
+        let wi = self.frame.to_local(wi);
+        let (sin_theta_o,cos_theta_o,phi_o) = angles(&wo);
+        let (sin_theta_i,cos_theta_i,phi_i) = angles(&wi);
+
+        let gamma_t = self.gamma_t(sin_theta_o,cos_theta_o);
+        let pdfs = self.lobe_pdfs(sin_theta_o,cos_theta_o);
+        let phi = phi_i - phi_o;
+        let mut pdf = 0.0;
+        for (p,&lobe) in pdfs.iter().enumerate().take(P_MAX) {
+            let (sin_op,cos_op) = self.tilt(p,sin_theta_o,cos_theta_o);
+            pdf += longitudinal(cos_theta_i,cos_op,sin_theta_i,sin_op,self.v[p])
+                * lobe * azimuthal(phi,p,self.s,self.gamma_o,gamma_t);
+        }
+        pdf += longitudinal(cos_theta_i,cos_theta_o,sin_theta_i,sin_theta_o,self.v[P_MAX])
+            * pdfs[P_MAX] / (2.0 * PI);
+        pdf
+    }
+}
+
+impl HairBsdf {
+    /// Construct BSDF at offset h across a fiber of the given hair
+    pub fn new(frame: Frame,h: f64,hair: &Hair) -> HairBsdf {
+        let h = h.clamp(-1.0,1.0);
+        let beta_m = hair.beta_m;
+        let beta_n = hair.beta_n;
+        let v0 = (0.726*beta_m + 0.812*beta_m.powi(2) + 3.7*beta_m.powi(20)).powi(2);
+        let s = f64::sqrt(PI / 8.0) * (0.265*beta_n + 1.194*beta_n.powi(2) + 5.372*beta_n.powi(22));
+
+        let mut sin_2k_alpha = [f64::sin(hair.alpha.to_radians()),0.0,0.0];
+        let mut cos_2k_alpha = [safe_sqrt(1.0 - sin_2k_alpha[0]*sin_2k_alpha[0]),0.0,0.0];
+        for i in 1..3 {
+            sin_2k_alpha[i] = 2.0 * cos_2k_alpha[i - 1] * sin_2k_alpha[i - 1];
+            cos_2k_alpha[i] = cos_2k_alpha[i - 1].powi(2) - sin_2k_alpha[i - 1].powi(2);
+        }
+
+        HairBsdf {
+            frame,
+            h,
+            gamma_o: safe_asin(h),
+            eta: hair.eta,
+            sigma_a: hair.sigma_a,
+            // smooth fibers would make the longitudinal lobes a delta
+            v: [v0,0.25 * v0,4.0 * v0,4.0 * v0].map(|v| v.max(1e-4)),
+            s: s.max(1e-4),
+            sin_2k_alpha,
+            cos_2k_alpha
+        }
+    }
+
+    /// Angle of the refracted ray inside the fiber across it
+    fn gamma_t(&self,sin_theta_o: f64,cos_theta_o: f64) -> f64 {
+        let etap = safe_sqrt(self.eta*self.eta - sin_theta_o*sin_theta_o) / cos_theta_o;
+        safe_asin(self.h / etap)
+    }
+
+    /// Transmittance of one pass through the fiber
+    fn transmittance(&self,sin_theta_o: f64,cos_theta_o: f64) -> Spectrum {
+        let sin_theta_t = sin_theta_o / self.eta;
+        let cos_theta_t = safe_sqrt(1.0 - sin_theta_t*sin_theta_t);
+        let cos_gamma_t = f64::cos(self.gamma_t(sin_theta_o,cos_theta_o));
+        (self.sigma_a * -(2.0 * cos_gamma_t / cos_theta_t)).exp()
+    }
+
+    /// Outgoing angle rotated by the scale tilt of lobe p, R is tilted
+    /// by -2 alpha, TT by alpha and TRT by 4 alpha
+    fn tilt(&self,p: usize,sin_theta_o: f64,cos_theta_o: f64) -> (f64,f64) {
+        let (sin,cos) = (self.sin_2k_alpha,self.cos_2k_alpha);
+        let (sin_op,cos_op) = match p {
+            0 => (sin_theta_o*cos[1] - cos_theta_o*sin[1],cos_theta_o*cos[1] + sin_theta_o*sin[1]),
+            1 => (sin_theta_o*cos[0] + cos_theta_o*sin[0],cos_theta_o*cos[0] - sin_theta_o*sin[0]),
+            2 => (sin_theta_o*cos[2] + cos_theta_o*sin[2],cos_theta_o*cos[2] - sin_theta_o*sin[2]),
+            _ => (sin_theta_o,cos_theta_o)
+        };
+        (sin_op,cos_op.abs())
+    }
+
+    /// Probability of sampling each lobe, following its luminance
+    fn lobe_pdfs(&self,sin_theta_o: f64,cos_theta_o: f64) -> [f64; P_MAX + 1] {
+        let ap = attenuation(cos_theta_o,self.eta,self.h,self.transmittance(sin_theta_o,cos_theta_o));
+        let total: f64 = ap.iter().map(|a| a.luminance()).sum();
+        if total <= 0.0 {
+            return [1.0,0.0,0.0,0.0]
+        }
+        ap.map(|a| a.luminance() / total)
+    }
+}
+
+/// (sin theta,cos theta,phi) of a local direction, theta measured from
+/// the plane normal to the fiber
+fn angles(w: &Vector) -> (f64,f64,f64) {
+    let sin_theta = w.x.clamp(-1.0,1.0);
+    (sin_theta,safe_sqrt(1.0 - sin_theta*sin_theta),f64::atan2(w.y,w.z))
+}
+
+/// Fraction of light leaving through each lobe, the last one sums
+/// every longer path as a geometric series
+fn attenuation(cos_theta_o: f64,eta: f64,h: f64,t: Spectrum) -> [Spectrum; P_MAX + 1] {
+    let cos_gamma_o = safe_sqrt(1.0 - h*h);
+    let f = fresnel_dielectric(cos_theta_o * cos_gamma_o,eta);
+    let mut ap = [spectrum::BLACK; P_MAX + 1];
+    ap[0] = Spectrum::new(f,f,f);
+    ap[1] = t * (1.0 - f).powi(2);
+    for p in 2..P_MAX {
+        ap[p] = ap[p - 1] * t * f;
+    }
+    let tf = t * f;
+    ap[P_MAX] = Spectrum::new(
+        ap[P_MAX - 1].r * tf.r / (1.0 - tf.r),
+        ap[P_MAX - 1].g * tf.g / (1.0 - tf.g),
+        ap[P_MAX - 1].b * tf.b / (1.0 - tf.b)
+    );
+    ap
+}
+
+/// Longitudinal scattering for variance v, normalized over the sphere
+/// (d'Eon et al. "An Energy-Conserving Hair Reflectance Model")
+fn longitudinal(cos_theta_i: f64,cos_theta_o: f64,sin_theta_i: f64,sin_theta_o: f64,v: f64) -> f64 {
+    let a = cos_theta_i * cos_theta_o / v;
+    let b = sin_theta_i * sin_theta_o / v;
+    if v <= 0.1 {
+        // in logarithms, the terms alone overflow for narrow lobes
+        f64::exp(log_i0(a) - b - 1.0 / v + std::f64::consts::LN_2 + f64::ln(1.0 / (2.0 * v)))
+    } else {
+        f64::exp(-b) * i0(a) / (f64::sinh(1.0 / v) * 2.0 * v)
+    }
+}
+
+/// Azimuthal scattering of lobe p at relative azimuth phi
+fn azimuthal(phi: f64,p: usize,s: f64,gamma_o: f64,gamma_t: f64) -> f64 {
+    let mut dphi = phi - shift(p,gamma_o,gamma_t);
+    while dphi > PI {
+        dphi -= 2.0 * PI;
+    }
+    while dphi < -PI {
+        dphi += 2.0 * PI;
+    }
+    trimmed_logistic(dphi,s,-PI,PI)
+}
+
+/// Azimuth by which lobe p leaves the fiber for a perfectly smooth one
+fn shift(p: usize,gamma_o: f64,gamma_t: f64) -> f64 {
+    2.0 * p as f64 * gamma_t - 2.0 * gamma_o + p as f64 * PI
+}
+
+/// Modified Bessel function of the first kind of order zero
+fn i0(x: f64) -> f64 {
+    let mut value = 0.0;
+    let mut x2i = 1.0;
+    let mut factorial: f64 = 1.0;
+    let mut four_i = 1.0;
+    for i in 0..10 {
+        if i > 1 {
+            factorial *= i as f64;
+        }
+        value += x2i / (four_i * factorial * factorial);
+        x2i *= x * x;
+        four_i *= 4.0;
+    }
+    value
+}
+
+/// Logarithm of i0, asymptotic for large x
+fn log_i0(x: f64) -> f64 {
+    if x > 12.0 {
+        x + 0.5 * (-f64::ln(2.0 * PI) + f64::ln(1.0 / x) + 1.0 / (8.0 * x))
+    } else {
+        f64::ln(i0(x))
+    }
+}
+
+fn logistic(x: f64,s: f64) -> f64 {
+    let e = f64::exp(-x.abs() / s);
+    e / (s * (1.0 + e).powi(2))
+}
+
+fn logistic_cdf(x: f64,s: f64) -> f64 {
+    1.0 / (1.0 + f64::exp(-x / s))
+}
+
+/// Logistic distribution restricted to [a,b]
+fn trimmed_logistic(x: f64,s: f64,a: f64,b: f64) -> f64 {
+    logistic(x,s) / (logistic_cdf(b,s) - logistic_cdf(a,s))
+}
+
+fn sample_trimmed_logistic(u: f64,s: f64,a: f64,b: f64) -> f64 {
+    let k = logistic_cdf(b,s) - logistic_cdf(a,s);
+    let x = -s * f64::ln(1.0 / (u * k + logistic_cdf(a,s)) - 1.0);
+    x.clamp(a,b)
+}
+
+/// Two uniform numbers from the even and odd bits of one
+fn demux(u: f64) -> (f64,f64) {
+    let bits = (u.clamp(0.0,1.0) * (1u64 << 32) as f64) as u64;
+    let compact = |mut x: u64| {
+        x &= 0x5555_5555;
+        x = (x ^ (x >> 1)) & 0x3333_3333;
+        x = (x ^ (x >> 2)) & 0x0f0f_0f0f;
+        x = (x ^ (x >> 4)) & 0x00ff_00ff;
+        x = (x ^ (x >> 8)) & 0x0000_ffff;
+        x as f64 / 65536.0
+    };
+    (compact(bits),compact(bits >> 1))
+}
+
+fn safe_sqrt(x: f64) -> f64 {
+    f64::sqrt(x.max(0.0))
+}
+
+fn safe_asin(x: f64) -> f64 {
+    f64::asin(x.clamp(-1.0,1.0))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{normal::Normal,traits::{Len,Normalize}};
+
+    // fiber along x seen from +z
+    fn bsdf(hair: &Hair,h: f64) -> HairBsdf {
+        let frame = Frame::from_normal_tangent(&Normal::new(0.,0.,1.),&Vector::new(1.,0.,0.));
+        HairBsdf::new(frame,h,hair)
+    }
+
+    // stratified directions over the sphere
+    fn sphere(n: usize) -> impl Iterator<Item = Vector> {
+        (0..n*n).map(move |k| {
+            let (u0,u1) = (((k % n) as f64 + 0.5) / n as f64,((k / n) as f64 + 0.5) / n as f64);
+            let z = 1.0 - 2.0 * u0;
+            let r = safe_sqrt(1.0 - z*z);
+            Vector::new(r * f64::cos(2.0 * PI * u1),r * f64::sin(2.0 * PI * u1),z)
+        })
+    }
+
+    #[test]
+    // without absorption all light should leave the fiber again
+    fn test_white_furnace() {
+        for (beta_m,beta_n) in [(0.3,0.3),(0.6,0.5),(0.9,0.9)] {
+            let hair = Hair::new(spectrum::BLACK,beta_m,beta_n);
+            let wo = Vector::new(0.3,0.4,0.866).normalize().unwrap();
+            for h in [-0.7,0.,0.4] {
+                let bsdf = bsdf(&hair,h);
+                let n = 300;
+                let total: f64 = sphere(n)
+                    .map(|wi| bsdf.f(&wo,&wi).g * wi.z.abs())
+                    .sum::<f64>() * 4.0 * PI / (n*n) as f64;
+                assert!((total - 1.).abs() < 0.05,"{} {} {} {}",beta_m,beta_n,h,total);
+            }
+        }
+    }
+
+    #[test]
+    // the pdf should integrate to one and agree with sampling
+    fn test_sample_f() {
+        let hair = Hair::from_melanin(1.3,0.,0.3,0.3);
+        let bsdf = bsdf(&hair,0.3);
+        let wo = Vector::new(-0.5,0.2,0.8).normalize().unwrap();
+        let n = 300;
+        let total: f64 = sphere(n).map(|wi| bsdf.pdf(&wo,&wi)).sum::<f64>() * 4.0 * PI / (n*n) as f64;
+        assert!((total - 1.).abs() < 0.05,"{}",total);
+        for i in 0..20 {
+            let u = (0.05 * i as f64 + 0.01,(0.37 * i as f64).fract());
+            let sample = bsdf.sample_f(&wo,u).unwrap();
+            assert!((sample.wi.len() - 1.).abs() < 1e-9);
+            assert!((bsdf.pdf(&wo,&sample.wi) - sample.pdf).abs() < 1e-9 * sample.pdf.max(1.));
+            let f = bsdf.f(&wo,&sample.wi);
+            assert!((f.r - sample.f.r).abs() < 1e-12 && !sample.specular);
+        }
+    }
+
+    #[test]
+    // absorbing pigment should darken and tint the fiber
+    fn test_pigment() {
+        let wo = Vector::new(0.2,0.,1.).normalize().unwrap();
+        let albedo = |hair: &Hair| {
+            let bsdf = bsdf(hair,0.2);
+            let n = 200;
+            sphere(n).fold(spectrum::BLACK,|sum,wi| sum + bsdf.f(&wo,&wi) * wi.z.abs()) * (4.0 * PI / (n*n) as f64)
+        };
+        let blonde = albedo(&Hair::from_melanin(0.3,0.,0.3,0.3));
+        let black = albedo(&Hair::from_melanin(8.,0.,0.3,0.3));
+        assert!(black.g < blonde.g && blonde.g < 1.);
+        assert!(blonde.r > blonde.b);
+        let tinted = Hair::from_color(Spectrum::new(0.5,0.3,0.1),0.,0.3);
+        assert!(tinted.sigma_a.r < tinted.sigma_a.g && tinted.sigma_a.g < tinted.sigma_a.b);
+        assert_eq!(demux(0.75),(0.5,0.5));
+    }
+
+    #[test]
+    // perfectly smooth fibers should still give finite values
+    fn test_smooth() {
+        let hair = Hair::from_color(Spectrum::new(0.5,0.3,0.1),0.,0.3);
+        let bsdf = bsdf(&hair,0.3);
+        let wo = Vector::new(-0.5,0.2,0.8).normalize().unwrap();
+        for wi in sphere(20) {
+            let f = bsdf.f(&wo,&wi);
+            assert!(f.r.is_finite() && f.g.is_finite() && f.b.is_finite());
+            assert!(bsdf.pdf(&wo,&wi).is_finite());
+        }
+        let samples: Vec<BsdfSample> = (0..20)
+            .filter_map(|i| bsdf.sample_f(&wo,(0.05 * i as f64 + 0.01,(0.37 * i as f64).fract())))
+            .collect();
+        assert!(samples.len() > 10);
+        for sample in samples {
+            assert!(sample.pdf.is_finite() && sample.pdf > 0.);
+            assert!(sample.f.r.is_finite() && sample.f.b.is_finite());
+        }
+    }
+}
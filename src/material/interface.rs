@@ -0,0 +1,90 @@
+use super::traits::{Material,Bsdf,BsdfSample};
+use crate::{
+    image::spectrum::{self,Spectrum},
+    math::{vector::Vector,normal::Normal,traits::Dot},
+    scene::interaction::SurfaceInteraction
+};
+
+/// # Interface
+/// Invisible surface that only marks the boundary between two media,
+/// light passes straight through it
+pub struct Interface;
+
+/// Material trait
+impl Material for Interface {
+    fn bsdf(&self,si: &SurfaceInteraction) -> Box<dyn Bsdf> {
+        Box::new(InterfaceBsdf {n: si.n})
+    }
+}
+
+/// # InterfaceBsdf
+/// Delta transmission without change of direction
+pub struct InterfaceBsdf {
+    pub n: Normal
+}
+
+/// Bsdf trait
+impl Bsdf for InterfaceBsdf {
+    fn f(&self,_wo: &Vector,_wi: &Vector) -> Spectrum {
+        // delta distribution, only reachable through sample_f
+        spectrum::BLACK
+    }
+
+    fn sample_f(&self,wo: &Vector,_u: (f64,f64)) -> Option<BsdfSample> {
+        let cos = wo.dot(self.n).abs();
+        if cos == 0.0 {
+            return None
+        }
+        Some(BsdfSample {
+            wi: -*wo,
+            // divide out the cosine applied by the integrator
+            f: spectrum::WHITE * (1.0 / cos),
+            pdf: 1.0,
+            specular: true,
+            eta: 1.0
+        })
+    }
+
+    fn pdf(&self,_wo: &Vector,_wi: &Vector) -> f64 {
+        0.0
+    }
+
+    fn is_specular(&self) -> bool {
+        true
+    }
+
+    fn specular_lobes(&self,wo: &Vector) -> Vec<BsdfSample> {
+        self.sample_f(wo,(0.0,0.0)).into_iter().collect()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::point::Point;
+
+    #[test]
+    // light should continue in the same direction unchanged
+    fn test_sample_f() {
+        let si = SurfaceInteraction::new(
+            Point::new(0.,0.,0.),
+            Normal::new(0.,1.,0.),
+            (0.,0.),
+            Vector::new(1.,0.,0.),
+            Vector::new(0.,0.,1.),
+            1.,
+            Vector::new(0.,1.,0.)
+        );
+        let bsdf = Interface.bsdf(&si);
+        let wo = Vector::new(0.6,0.8,0.);
+        let sample = bsdf.sample_f(&wo,(0.5,0.5)).unwrap();
+        assert_eq!(sample.wi.x,-0.6);
+        assert_eq!(sample.wi.y,-0.8);
+        assert!((sample.f.r * 0.8 - 1.).abs() < 1e-12);
+        assert!(sample.specular);
+    }
+}
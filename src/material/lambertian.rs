@@ -0,0 +1,163 @@
+use std::{f64::consts::FRAC_1_PI,sync::Arc};
+
+use super::traits::{Material,Bsdf,BsdfSample};
+use crate::{
+    image::spectrum::{self,Spectrum},
+    math::{
+        vector::Vector,
+        frame::Frame,
+        sampling::{cosine_sample_hemisphere,cosine_hemisphere_pdf}
+    },
+    scene::interaction::SurfaceInteraction,
+    texture::{traits::Texture,constant::ConstantTexture}
+};
+
+/// # Lambertian
+/// Ideal diffuse material scattering equally in all directions
+///
+/// # Parameters
+/// * albedo (fraction of light reflected, may vary over the surface)
+pub struct Lambertian {
+    pub albedo: Arc<dyn Texture>
+}
+
+/// Material trait
+impl Material for Lambertian {
+    fn bsdf(&self,si: &SurfaceInteraction) -> Box<dyn Bsdf> {
+        Box::new(LambertianBsdf {
+            frame: si.shading_frame(),
+            albedo: self.albedo.evaluate(si)
+        })
+    }
+}
+
+impl Lambertian {
+    /// Construct lambertian material with given albedo
+    pub fn new(albedo: Spectrum) -> Lambertian {
+        Lambertian::textured(Arc::new(ConstantTexture::new(albedo)))
+    }
+
+    /// Construct lambertian material with textured albedo
+    pub fn textured(albedo: Arc<dyn Texture>) -> Lambertian {
+        Lambertian {albedo}
+    }
+}
+
+/// # LambertianBsdf
+/// Two sided diffuse reflection in a local shading frame
+pub struct LambertianBsdf {
+    pub frame: Frame,
+    pub albedo: Spectrum
+}
+
+/// Bsdf trait
+impl Bsdf for LambertianBsdf {
+    fn f(&self,wo: &Vector,wi: &Vector) -> Spectrum {
+        let wo = self.frame.to_local(wo);
+        let wi = self.frame.to_local(wi);
+        if wo.z * wi.z <= 0.0 {
+            return spectrum::BLACK
+        }
+        self.albedo * FRAC_1_PI
+    }
+
+    fn sample_f(&self,wo: &Vector,u: (f64,f64)) -> Option<BsdfSample> {
+        let wo_local = self.frame.to_local(wo);
+        let mut wi = cosine_sample_hemisphere(u);
+        if wo_local.z < 0.0 {
+            wi.z = -wi.z;
+        }
+        let pdf = cosine_hemisphere_pdf(wi.z.abs());
+        if pdf == 0.0 {
+            return None
+        }
+        Some(BsdfSample {
+            wi: self.frame.to_world(&wi),
+            f: self.albedo * FRAC_1_PI,
+            pdf,
+            specular: false,
+            eta: 1.0
+        })
+    }
+
+    fn pdf(&self,wo: &Vector,wi: &Vector) -> f64 {
+        let wo = self.frame.to_local(wo);
+        let wi = self.frame.to_local(wi);
+        if wo.z * wi.z <= 0.0 {
+            return 0.0
+        }
+        cosine_hemisphere_pdf(wi.z.abs())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{point::Point,normal::Normal,traits::Dot};
+
+    fn test_bsdf() -> Box<dyn Bsdf> {
+        let si = SurfaceInteraction::new(
+            Point::new(0.,0.,0.),
+            Normal::new(0.,1.,0.),
+            (0.,0.),
+            Vector::new(1.,0.,0.),
+            Vector::new(0.,0.,1.),
+            1.,
+            Vector::new(0.,1.,0.)
+        );
+        Lambertian::new(Spectrum::new(0.5,0.5,0.5)).bsdf(&si)
+    }
+
+    #[test]
+    // should reflect albedo / pi in the same hemisphere only
+    fn test_f() {
+        let bsdf = test_bsdf();
+        let wo = Vector::new(0.,1.,0.);
+        let f = bsdf.f(&wo,&Vector::new(0.6,0.8,0.));
+        assert_eq!(f.r,0.5 * FRAC_1_PI);
+        assert!(bsdf.f(&wo,&Vector::new(0.6,-0.8,0.)).is_black());
+    }
+
+    #[test]
+    // sampled directions should follow wo's hemisphere with matching pdf
+    fn test_sample_f() {
+        let bsdf = test_bsdf();
+        let wo = Vector::new(0.,-1.,0.);
+        let sample = bsdf.sample_f(&wo,(0.3,0.7)).unwrap();
+        assert!(sample.wi.y < 0.);
+        assert!(!sample.specular);
+        assert!((bsdf.pdf(&wo,&sample.wi) - sample.pdf).abs() < 1e-12);
+        assert!((sample.pdf - sample.wi.dot(wo) * FRAC_1_PI).abs() < 1e-12);
+    }
+
+    #[test]
+    // textured albedo should be looked up at the interaction
+    fn test_textured() {
+        use crate::texture::{checkerboard::CheckerboardTexture,mapping::TextureMapping};
+
+        let checks = CheckerboardTexture::new(
+            Arc::new(ConstantTexture::new(spectrum::WHITE)),
+            Arc::new(ConstantTexture::new(spectrum::BLACK)),
+            TextureMapping::Uv {scale: (2.,2.),offset: (0.,0.)}
+        );
+        let material = Lambertian::textured(Arc::new(checks));
+        let mut si = SurfaceInteraction::new(
+            Point::new(0.,0.,0.),
+            Normal::new(0.,1.,0.),
+            (0.25,0.25),
+            Vector::new(1.,0.,0.),
+            Vector::new(0.,0.,1.),
+            1.,
+            Vector::new(0.,1.,0.)
+        );
+        let wo = Vector::new(0.,1.,0.);
+        let wi = Vector::new(0.6,0.8,0.);
+        assert_eq!(material.bsdf(&si).f(&wo,&wi).r,FRAC_1_PI);
+        si.uv = (0.75,0.25);
+        assert!(material.bsdf(&si).f(&wo,&wi).is_black());
+    }
+}
@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use super::{
+    traits::{Material,Bsdf,BsdfSample},
+    fresnel::reflect
+};
+use crate::{
+    image::spectrum::{self,Spectrum},
+    math::{vector::Vector,frame::Frame},
+    scene::interaction::SurfaceInteraction,
+    texture::{traits::Texture,constant::ConstantTexture}
+};
+
+/// # Mirror
+/// Perfectly specular reflector
+///
+/// # Parameters
+/// * reflectance (fraction of light reflected, may vary over the surface)
+pub struct Mirror {
+    pub reflectance: Arc<dyn Texture>
+}
+
+/// Material trait
+impl Material for Mirror {
+    fn bsdf(&self,si: &SurfaceInteraction) -> Box<dyn Bsdf> {
+        Box::new(MirrorBsdf {
+            frame: si.shading_frame(),
+            reflectance: self.reflectance.evaluate(si)
+        })
+    }
+}
+
+impl Mirror {
+    /// Construct mirror with given reflectance
+    pub fn new(reflectance: Spectrum) -> Mirror {
+        Mirror::textured(Arc::new(ConstantTexture::new(reflectance)))
+    }
+
+    /// Construct mirror with textured reflectance
+    pub fn textured(reflectance: Arc<dyn Texture>) -> Mirror {
+        Mirror {reflectance}
+    }
+}
+
+/// # MirrorBsdf
+/// Delta reflection about the surface normal
+pub struct MirrorBsdf {
+    pub frame: Frame,
+    pub reflectance: Spectrum
+}
+
+/// Bsdf trait
+impl Bsdf for MirrorBsdf {
+    fn f(&self,_wo: &Vector,_wi: &Vector) -> Spectrum {
+        // delta distribution, only reachable through sample_f
+        spectrum::BLACK
+    }
+
+    fn sample_f(&self,wo: &Vector,_u: (f64,f64)) -> Option<BsdfSample> {
+        let wi = reflect(&self.frame.to_local(wo));
+        if wi.z == 0.0 {
+            return None
+        }
+        Some(BsdfSample {
+            wi: self.frame.to_world(&wi),
+            // divide out the cosine applied by the integrator
+            f: self.reflectance * (1.0 / wi.z.abs()),
+            pdf: 1.0,
+            specular: true,
+            eta: 1.0
+        })
+    }
+
+    fn pdf(&self,_wo: &Vector,_wi: &Vector) -> f64 {
+        0.0
+    }
+
+    fn is_specular(&self) -> bool {
+        true
+    }
+
+    fn specular_lobes(&self,wo: &Vector) -> Vec<BsdfSample> {
+        self.sample_f(wo,(0.0,0.0)).into_iter().collect()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{point::Point,normal::Normal};
+
+    #[test]
+    // should reflect about the normal with reflectance / cos
+    fn test_sample_f() {
+        let si = SurfaceInteraction::new(
+            Point::new(0.,0.,0.),
+            Normal::new(0.,1.,0.),
+            (0.,0.),
+            Vector::new(1.,0.,0.),
+            Vector::new(0.,0.,1.),
+            1.,
+            Vector::new(0.,1.,0.)
+        );
+        let bsdf = Mirror::new(Spectrum::new(0.9,0.9,0.9)).bsdf(&si);
+        assert!(bsdf.is_specular());
+        let wo = Vector::new(0.6,0.8,0.);
+        let sample = bsdf.sample_f(&wo,(0.5,0.5)).unwrap();
+        assert!((sample.wi.x + 0.6).abs() < 1e-12);
+        assert!((sample.wi.y - 0.8).abs() < 1e-12);
+        assert!((sample.f.r - 0.9 / 0.8).abs() < 1e-12);
+        assert_eq!(bsdf.pdf(&wo,&sample.wi),0.);
+        assert!(bsdf.f(&wo,&sample.wi).is_black());
+        assert_eq!(bsdf.specular_lobes(&wo).len(),1);
+    }
+}
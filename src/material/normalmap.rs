@@ -0,0 +1,231 @@
+use std::sync::Arc;
+
+use super::traits::{Material,Bsdf};
+use crate::{
+    math::{
+        point::Point,
+        vector::Vector,
+        normal::Normal,
+        frame::Frame,
+        traits::{Dot,Cross,Normalize}
+    },
+    scene::interaction::SurfaceInteraction,
+    texture::traits::Texture
+};
+
+/// # NormalMap
+/// Material whose shading normal is read from a tangent space normal
+/// map, channels in [0,1] encode the tangent, bitangent and normal
+/// components in [-1,1] so that (0.5,0.5,1) leaves the surface flat
+///
+/// # Parameters
+/// * material (material being perturbed)
+/// * normals (tangent space normal map, stored linearly)
+/// * strength (scale of the tangential components, 1 as authored)
+pub struct NormalMap {
+    pub material: Box<dyn Material>,
+    pub normals: Arc<dyn Texture>,
+    pub strength: f64
+}
+
+/// Material trait
+impl Material for NormalMap {
+    fn apply_shading(&self,si: &mut SurfaceInteraction) {
+        self.material.apply_shading(si);
+        self.perturb(si);
+    }
+
+    fn bsdf(&self,si: &SurfaceInteraction) -> Box<dyn Bsdf> {
+        self.material.bsdf(si)
+    }
+
+    fn bsdf_at_wavelength(&self,si: &SurfaceInteraction,lambda: f64) -> Box<dyn Bsdf> {
+        self.material.bsdf_at_wavelength(si,lambda)
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.material.is_dispersive()
+    }
+}
+
+impl NormalMap {
+    /// Construct normal mapped material
+    pub fn new(material: Box<dyn Material>,normals: Arc<dyn Texture>) -> NormalMap {
+        NormalMap {material,normals,strength: 1.0}
+    }
+
+    /// Replace the shading normal of si by the normal map expressed in
+    /// its tangent frame, taken from the interpolated vertex tangent when
+    /// there is one and from dpdu and dpdv otherwise
+    pub fn perturb(&self,si: &mut SurfaceInteraction) {
+        let c = self.normals.evaluate(si);
+        let local = Vector::new(
+            (2.0 * c.r - 1.0) * self.strength,
+            (2.0 * c.g - 1.0) * self.strength,
+            2.0 * c.b - 1.0
+        );
+        let local = match local.normalize() {
+            Ok(local) => local,
+            Err(_) => return
+        };
+
+        let n = Vector::from(si.ns);
+        let (tangent,sign) = match si.tangent {
+            Some(tangent) => tangent,
+            None => (si.dpdu,handedness(&n,&si.dpdu,&si.dpdv))
+        };
+        let tangent = match (tangent - n * n.dot(tangent)).normalize() {
+            Ok(tangent) => tangent,
+            Err(_) => return
+        };
+        let bitangent = n.cross(tangent) * sign;
+
+        if let Ok(ns) = (tangent * local.x + bitangent * local.y + n * local.z).normalize() {
+            si.set_shading_geometry(&Normal::from(ns),&tangent);
+        }
+    }
+}
+
+/// Sign making n x tangent point along the v direction
+fn handedness(n: &Vector,tangent: &Vector,bitangent: &Vector) -> f64 {
+    if n.cross(*tangent).dot(*bitangent) < 0.0 { -1.0 } else { 1.0 }
+}
+
+/// Per-vertex tangents of an indexed triangle mesh in the manner of
+/// MikkTSpace, each triangle contributes the directions of increasing u
+/// and v weighted by its angle at the vertex, the sum is made orthogonal
+/// to the vertex normal and the sign of the bitangent records mirrored uvs
+pub fn generate_tangents(positions: &[Point],normals: &[Normal],uvs: &[(f64,f64)],indices: &[[usize; 3]]) -> Vec<(Vector,f64)> {
+    let zero = Vector::new(0.0,0.0,0.0);
+    let mut tangents = vec![zero; positions.len()];
+    let mut bitangents = vec![zero; positions.len()];
+
+    for triangle in indices {
+        let [i0,i1,i2] = *triangle;
+        let e1 = positions[i1] - positions[i0];
+        let e2 = positions[i2] - positions[i0];
+        let (du1,dv1) = (uvs[i1].0 - uvs[i0].0,uvs[i1].1 - uvs[i0].1);
+        let (du2,dv2) = (uvs[i2].0 - uvs[i0].0,uvs[i2].1 - uvs[i0].1);
+        let det = du1*dv2 - du2*dv1;
+        if det == 0.0 {
+            continue
+        }
+        let (tangent,bitangent) = match (
+            ((e1 * dv2 - e2 * dv1) * (1.0 / det)).normalize(),
+            ((e2 * du1 - e1 * du2) * (1.0 / det)).normalize()
+        ) {
+            (Ok(t),Ok(b)) => (t,b),
+            _ => continue
+        };
+        for corner in 0..3 {
+            let i = triangle[corner];
+            let a = positions[triangle[(corner + 1) % 3]] - positions[i];
+            let b = positions[triangle[(corner + 2) % 3]] - positions[i];
+            let angle = match (a.normalize(),b.normalize()) {
+                (Ok(a),Ok(b)) => a.dot(b).clamp(-1.0,1.0).acos(),
+                _ => 0.0
+            };
+            tangents[i] = tangents[i] + tangent * angle;
+            bitangents[i] = bitangents[i] + bitangent * angle;
+        }
+    }
+
+    normals.iter().enumerate().map(|(i,normal)| {
+        let n = Vector::from(*normal);
+        match (tangents[i] - n * n.dot(tangents[i])).normalize() {
+            Ok(tangent) => (tangent,handedness(&n,&tangent,&bitangents[i])),
+            // vertices without usable uvs get an arbitrary tangent
+            Err(_) => (Frame::from_normal(normal).s,1.0)
+        }
+    }).collect()
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        image::spectrum::{self,Spectrum},
+        material::lambertian::Lambertian,
+        math::traits::LenSq,
+        texture::constant::ConstantTexture
+    };
+
+    fn test_interaction() -> SurfaceInteraction {
+        SurfaceInteraction::new(
+            Point::new(0.,0.,0.),
+            Normal::new(0.,1.,0.),
+            (0.5,0.5),
+            Vector::new(1.,0.,0.),
+            Vector::new(0.,0.,1.),
+            1.,
+            Vector::new(0.,1.,0.)
+        )
+    }
+
+    fn normal_map(color: Spectrum) -> NormalMap {
+        NormalMap::new(Box::new(Lambertian::new(spectrum::WHITE)),Arc::new(ConstantTexture::new(color)))
+    }
+
+    fn assert_close(a: Vector,b: Vector) {
+        assert!((a - b).len_sq() < 1e-12,"{} {}",a,b);
+    }
+
+    #[test]
+    // the map should be read in the frame of dpdu and dpdv
+    fn test_perturb() {
+        let mut si = test_interaction();
+        normal_map(Spectrum::new(0.5,0.5,1.)).apply_shading(&mut si);
+        assert_close(Vector::from(si.ns),Vector::new(0.,1.,0.));
+        let mut si = test_interaction();
+        normal_map(Spectrum::new(0.8,0.5,0.9)).apply_shading(&mut si);
+        assert_close(Vector::from(si.ns),Vector::new(0.6,0.8,0.));
+        assert_close(si.shading_frame().n,Vector::new(0.6,0.8,0.));
+        // bitangent along dpdv
+        let mut si = test_interaction();
+        normal_map(Spectrum::new(0.5,0.8,0.9)).apply_shading(&mut si);
+        assert_close(Vector::from(si.ns),Vector::new(0.,0.8,0.6));
+        // zero strength flattens the map
+        let mut si = test_interaction();
+        let mut material = normal_map(Spectrum::new(0.8,0.5,0.9));
+        material.strength = 0.;
+        material.apply_shading(&mut si);
+        assert_close(Vector::from(si.ns),Vector::new(0.,1.,0.));
+    }
+
+    #[test]
+    // vertex tangents and their sign should take precedence over dpdu
+    fn test_vertex_tangent() {
+        let mut si = test_interaction();
+        si.tangent = Some((Vector::new(0.,0.,1.),-1.));
+        normal_map(Spectrum::new(0.5,0.8,0.9)).apply_shading(&mut si);
+        assert_close(Vector::from(si.ns),Vector::new(-0.6,0.8,0.));
+        assert_close(si.dpdus,Vector::new(0.,0.,1.));
+    }
+
+    #[test]
+    // tangents should follow u, with the sign flipping for mirrored uvs
+    fn test_generate_tangents() {
+        let positions = [Point::new(0.,0.,0.),Point::new(1.,0.,0.),Point::new(1.,0.,1.),Point::new(0.,0.,1.)];
+        let normals = [Normal::new(0.,1.,0.); 4];
+        let indices = [[0,2,1],[0,3,2]];
+        let uvs: Vec<(f64,f64)> = positions.iter().map(|p| (p.x,p.z)).collect();
+        for (tangent,sign) in generate_tangents(&positions,&normals,&uvs,&indices) {
+            assert_close(tangent,Vector::new(1.,0.,0.));
+            assert_eq!(sign,-1.);
+        }
+        let mirrored: Vec<(f64,f64)> = positions.iter().map(|p| (-p.x,p.z)).collect();
+        for (tangent,sign) in generate_tangents(&positions,&normals,&mirrored,&indices) {
+            assert_close(tangent,Vector::new(-1.,0.,0.));
+            assert_eq!(sign,1.);
+        }
+        // degenerate uvs fall back to a tangent orthogonal to the normal
+        let flat = [(0.,0.); 4];
+        for (tangent,_) in generate_tangents(&positions,&normals,&flat,&indices) {
+            assert!(tangent.y.abs() < 1e-12);
+        }
+    }
+}
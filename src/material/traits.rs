@@ -0,0 +1,73 @@
+use crate::{
+    image::spectrum::Spectrum,
+    math::vector::Vector,
+    scene::interaction::SurfaceInteraction
+};
+
+/// # BsdfSample
+/// Direction sampled from a BSDF
+///
+/// # Parameters
+/// * wi (unit incident direction in world space)
+/// * f (BSDF value for the pair wo,wi)
+/// * pdf (solid angle density, 1 for specular lobes)
+/// * specular (true if sampled from a delta distribution)
+/// * eta (relative index of refraction for refracted samples, 1 otherwise)
+pub struct BsdfSample {
+    pub wi: Vector,
+    pub f: Spectrum,
+    pub pdf: f64,
+    pub specular: bool,
+    pub eta: f64
+}
+
+/// Scattering function at a surface point, all directions
+/// are unit vectors in world space pointing away from the surface
+pub trait Bsdf {
+    /// Value of the BSDF for the pair wo,wi
+    fn f(&self,wo: &Vector,wi: &Vector) -> Spectrum;
+
+    /// Sample incident direction given outgoing direction wo
+    fn sample_f(&self,wo: &Vector,u: (f64,f64)) -> Option<BsdfSample>;
+
+    /// Solid angle density of sample_f choosing wi given wo
+    fn pdf(&self,wo: &Vector,wi: &Vector) -> f64;
+
+    /// True if the BSDF only contains delta distributions
+    fn is_specular(&self) -> bool {
+        false
+    }
+
+    /// Every delta lobe for wo at once with pdf 1, used by integrators
+    /// that branch on specular surfaces instead of sampling one lobe
+    fn specular_lobes(&self,_wo: &Vector) -> Vec<BsdfSample> {
+        vec![]
+    }
+
+    /// Factor turning the value for wo,wi into the adjoint BSDF used when
+    /// paths carry importance from the lights, only refraction is not symmetric
+    fn adjoint_scale(&self,_wo: &Vector,_wi: &Vector) -> f64 {
+        1.0
+    }
+}
+
+pub trait Material: Send + Sync {
+    /// Perturb the shading normal and tangent of si, called by the world
+    /// after every intersection so all integrators see the same frame
+    fn apply_shading(&self,_si: &mut SurfaceInteraction) {}
+
+    /// Construct the BSDF at a surface interaction
+    fn bsdf(&self,si: &SurfaceInteraction) -> Box<dyn Bsdf>;
+
+    /// Construct the BSDF for light of a single wavelength lambda (nm),
+    /// only dispersive materials need to differ from bsdf
+    fn bsdf_at_wavelength(&self,si: &SurfaceInteraction,_lambda: f64) -> Box<dyn Bsdf> {
+        self.bsdf(si)
+    }
+
+    /// True if scattered directions depend on wavelength, spectral
+    /// integrators then follow the hero wavelength alone
+    fn is_dispersive(&self) -> bool {
+        false
+    }
+}
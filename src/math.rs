@@ -4,5 +4,10 @@ pub mod point;
 pub mod normal;
 pub mod matrix;
 pub mod ray;
+pub mod sampling;
+pub mod frame;
+pub mod kdtree;
+pub mod noise;
+pub mod polynomial;
 
 pub mod traits;
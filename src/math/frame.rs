@@ -0,0 +1,110 @@
+use super::{
+    vector::Vector,
+    normal::Normal,
+    traits::{Dot,Cross,Normalize}
+};
+
+/// # Frame
+/// Orthonormal basis used to move directions between
+/// world space and a local space where n is the +z axis
+///
+/// # Parameters
+/// * s (tangent)
+/// * t (bitangent)
+/// * n (normal)
+#[derive(Clone,Copy)]
+pub struct Frame {
+    pub s: Vector,
+    pub t: Vector,
+    pub n: Vector
+}
+
+impl Frame {
+    /// Construct frame from a unit normal, tangents are chosen arbitrarily
+    /// (Duff et al. "Building an Orthonormal Basis, Revisited")
+    pub fn from_normal(n: &Normal) -> Frame {
+        let sign = f64::copysign(1.0,n.z);
+        let a = -1.0 / (sign + n.z);
+        let b = n.x * n.y * a;
+        Frame {
+            s: Vector::new(1.0 + sign*n.x*n.x*a,sign*b,-sign*n.x),
+            t: Vector::new(b,sign + n.y*n.y*a,-n.y),
+            n: Vector::from(*n)
+        }
+    }
+
+    /// Construct frame from a unit normal and a tangent direction,
+    /// the tangent is orthogonalized against the normal
+    pub fn from_normal_tangent(n: &Normal,tangent: &Vector) -> Frame {
+        let nv = Vector::from(*n);
+        match (*tangent - nv * nv.dot(*tangent)).normalize() {
+            Ok(s) => Frame {
+                s,
+                t: nv.cross(s),
+                n: nv
+            },
+            Err(_) => Frame::from_normal(n)
+        }
+    }
+
+    /// Transform world space direction to local space
+    pub fn to_local(self,v: &Vector) -> Vector {
+        Vector::new(v.dot(self.s),v.dot(self.t),v.dot(self.n))
+    }
+
+    /// Transform local space direction to world space
+    pub fn to_world(self,v: &Vector) -> Vector {
+        self.s * v.x + self.t * v.y + self.n * v.z
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // frame axes should be unit length and mutually orthogonal
+    fn assert_orthonormal(f: &Frame) {
+        assert!((f.s.dot(f.s) - 1.).abs() < 1e-12);
+        assert!((f.t.dot(f.t) - 1.).abs() < 1e-12);
+        assert!((f.n.dot(f.n) - 1.).abs() < 1e-12);
+        assert!(f.s.dot(f.t).abs() < 1e-12);
+        assert!(f.s.dot(f.n).abs() < 1e-12);
+        assert!(f.t.dot(f.n).abs() < 1e-12);
+    }
+
+    #[test]
+    // should build orthonormal basis for any normal
+    fn test_from_normal() {
+        assert_orthonormal(&Frame::from_normal(&Normal::new(0.,0.,1.)));
+        assert_orthonormal(&Frame::from_normal(&Normal::new(0.,0.,-1.)));
+        let n = Normal::new(1.,2.,-3.).normalize().unwrap();
+        assert_orthonormal(&Frame::from_normal(&n));
+    }
+
+    #[test]
+    // should keep tangent direction
+    fn test_from_normal_tangent() {
+        let f = Frame::from_normal_tangent(&Normal::new(0.,1.,0.),&Vector::new(1.,1.,0.));
+        assert_orthonormal(&f);
+        assert!((f.s.x - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    // local and world transforms should round trip
+    fn test_round_trip() {
+        let n = Normal::new(-1.,0.5,2.).normalize().unwrap();
+        let f = Frame::from_normal(&n);
+        let v = Vector::new(0.3,-0.2,0.9);
+        let w = f.to_world(&f.to_local(&v));
+        assert!((v.x - w.x).abs() < 1e-12);
+        assert!((v.y - w.y).abs() < 1e-12);
+        assert!((v.z - w.z).abs() < 1e-12);
+        // normal maps to +z
+        let local_n = f.to_local(&Vector::from(n));
+        assert!((local_n.z - 1.).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,225 @@
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap
+};
+
+use super::point::Point;
+
+/// # KdTree
+/// Balanced 3D kd-tree over points carrying a value each, stored
+/// implicitly: the node splitting a range sits at its middle index
+///
+/// # Parameters
+/// * items (points and values, ordered as the tree)
+/// * axes (split axis of the node at each index)
+pub struct KdTree<T> {
+    pub items: Vec<(Point,T)>,
+    axes: Vec<usize>
+}
+
+impl<T> KdTree<T> {
+    /// Build tree, splitting each range at the median of its widest axis
+    pub fn new(items: Vec<(Point,T)>) -> KdTree<T> {
+        let mut tree = KdTree {
+            axes: vec![0; items.len()],
+            items
+        };
+        let n = tree.items.len();
+        tree.build(0,n);
+        tree
+    }
+
+    /// Number of items
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// True if the tree holds no items
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Call f with (point,value,squared distance) for every item within
+    /// squared distance r_sq of p
+    pub fn within<F: FnMut(&Point,&T,f64)>(&self,p: &Point,r_sq: f64,mut f: F) {
+        self.within_range(0,self.items.len(),p,r_sq,&mut f);
+    }
+
+    /// Up to k items nearest to p within squared distance max_sq,
+    /// returned as (squared distance,value) sorted by distance
+    pub fn nearest(&self,p: &Point,k: usize,max_sq: f64) -> Vec<(f64,&T)> {
+        let mut heap: BinaryHeap<Candidate> = BinaryHeap::with_capacity(k + 1);
+        if k > 0 {
+            self.nearest_range(0,self.items.len(),p,k,max_sq,&mut heap);
+        }
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|c| (c.dist_sq,&self.items[c.index].1))
+            .collect()
+    }
+
+    fn build(&mut self,lo: usize,hi: usize) {
+        if hi <= lo {
+            return
+        }
+
+        // widest extent of the range
+        let mut min = [f64::INFINITY; 3];
+        let mut max = [f64::NEG_INFINITY; 3];
+        for (p,_) in &self.items[lo..hi] {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(coord(p,axis));
+                max[axis] = max[axis].max(coord(p,axis));
+            }
+        }
+        let axis = (0..3)
+            .max_by(|&a,&b| (max[a] - min[a]).total_cmp(&(max[b] - min[b])))
+            .unwrap();
+
+        let mid = (lo + hi) / 2;
+        self.items[lo..hi].select_nth_unstable_by(mid - lo,|a,b| coord(&a.0,axis).total_cmp(&coord(&b.0,axis)));
+        self.axes[mid] = axis;
+        self.build(lo,mid);
+        self.build(mid + 1,hi);
+    }
+
+    fn within_range<F: FnMut(&Point,&T,f64)>(&self,lo: usize,hi: usize,p: &Point,r_sq: f64,f: &mut F) {
+        if hi <= lo {
+            return
+        }
+        let mid = (lo + hi) / 2;
+        let (q,value) = &self.items[mid];
+        let dist_sq = p.distance_sq(*q);
+        if dist_sq <= r_sq {
+            f(q,value,dist_sq);
+        }
+
+        // near side first, far side only if the sphere crosses the split
+        let d = coord(p,self.axes[mid]) - coord(q,self.axes[mid]);
+        let (near,far) = if d < 0.0 { ((lo,mid),(mid + 1,hi)) } else { ((mid + 1,hi),(lo,mid)) };
+        self.within_range(near.0,near.1,p,r_sq,f);
+        if d*d <= r_sq {
+            self.within_range(far.0,far.1,p,r_sq,f);
+        }
+    }
+
+    fn nearest_range(&self,lo: usize,hi: usize,p: &Point,k: usize,max_sq: f64,heap: &mut BinaryHeap<Candidate>) {
+        if hi <= lo {
+            return
+        }
+        let mid = (lo + hi) / 2;
+        let q = &self.items[mid].0;
+        let dist_sq = p.distance_sq(*q);
+        if dist_sq <= search_radius_sq(heap,k,max_sq) {
+            heap.push(Candidate {dist_sq,index: mid});
+            if heap.len() > k {
+                heap.pop();
+            }
+        }
+
+        let d = coord(p,self.axes[mid]) - coord(q,self.axes[mid]);
+        let (near,far) = if d < 0.0 { ((lo,mid),(mid + 1,hi)) } else { ((mid + 1,hi),(lo,mid)) };
+        self.nearest_range(near.0,near.1,p,k,max_sq,heap);
+        if d*d <= search_radius_sq(heap,k,max_sq) {
+            self.nearest_range(far.0,far.1,p,k,max_sq,heap);
+        }
+    }
+}
+
+/// Squared radius still worth searching, shrinks once k items are found
+fn search_radius_sq(heap: &BinaryHeap<Candidate>,k: usize,max_sq: f64) -> f64 {
+    if heap.len() < k {
+        max_sq
+    } else {
+        heap.peek().map_or(max_sq,|c| c.dist_sq)
+    }
+}
+
+/// Coordinate of p along axis 0 (x), 1 (y) or 2 (z)
+fn coord(p: &Point,axis: usize) -> f64 {
+    match axis {
+        0 => p.x,
+        1 => p.y,
+        _ => p.z
+    }
+}
+
+/// Item found by a nearest neighbour search, ordered by distance
+struct Candidate {
+    dist_sq: f64,
+    index: usize
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self,other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self,other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self,other: &Self) -> Ordering {
+        self.dist_sq.total_cmp(&other.dist_sq)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // deterministic scattered points
+    fn test_points(n: usize) -> Vec<(Point,usize)> {
+        (0..n).map(|i| {
+            let f = i as f64;
+            (Point::new((f * 0.618).fract(),(f * 0.414).fract(),(f * 0.732).fract()),i)
+        }).collect()
+    }
+
+    #[test]
+    // radius queries should match brute force
+    fn test_within() {
+        let points = test_points(500);
+        let tree = KdTree::new(points.clone());
+        assert_eq!(tree.len(),500);
+        let p = Point::new(0.4,0.5,0.6);
+        let r_sq = 0.04;
+        let mut found: Vec<usize> = vec![];
+        tree.within(&p,r_sq,|_,i,_| found.push(*i));
+        found.sort();
+        let expected: Vec<usize> = points.iter()
+            .filter(|(q,_)| p.distance_sq(*q) <= r_sq)
+            .map(|(_,i)| *i)
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(found,expected);
+    }
+
+    #[test]
+    // nearest neighbours should match brute force, nearest first
+    fn test_nearest() {
+        let points = test_points(500);
+        let tree = KdTree::new(points.clone());
+        let p = Point::new(0.1,0.9,0.3);
+        let nearest = tree.nearest(&p,10,f64::INFINITY);
+        let mut expected: Vec<f64> = points.iter().map(|(q,_)| p.distance_sq(*q)).collect();
+        expected.sort_by(|a,b| a.total_cmp(b));
+        assert_eq!(nearest.len(),10);
+        for (found,expected) in nearest.iter().zip(expected.iter()) {
+            assert_eq!(found.0,*expected);
+        }
+        // limited by the maximum radius
+        assert!(tree.nearest(&p,10,expected[2]).len() == 3);
+        assert!(KdTree::<usize>::new(vec![]).nearest(&p,3,1.).is_empty());
+    }
+}
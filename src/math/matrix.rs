@@ -2,7 +2,7 @@ use super::{
     point::Point,
     vector::Vector,
     normal::Normal,
-    ray::Ray
+    ray::{Ray,RayDifferential}
 };
 
 use super::traits::{Dot};
@@ -157,11 +157,23 @@ impl std::ops::Mul<Ray> for Matrix {
     fn mul(self,r: Ray) -> Ray {
         Ray {
             o: self * r.o,
-            d: self * r.d
+            d: self * r.d,
+            differentials: r.differentials.map(|rd| RayDifferential {
+                rx_o: self * rd.rx_o,
+                rx_d: self * rd.rx_d,
+                ry_o: self * rd.ry_o,
+                ry_d: self * rd.ry_d
+            })
         }
     }
 }
 
+impl Default for Matrix {
+    fn default() -> Matrix {
+        Matrix::new()
+    }
+}
+
 impl Matrix {
     pub fn new() -> Matrix {
         Matrix {
@@ -174,6 +186,75 @@ impl Matrix {
         }
     }
 
+    /// Rotation of theta radians about the y axis
+    pub fn rotate_y(theta: f64) -> Matrix {
+        let (sin,cos) = f64::sin_cos(theta);
+        Matrix {
+            m: [
+                cos,0.0,sin,0.0,
+                0.0,1.0,0.0,0.0,
+                -sin,0.0,cos,0.0,
+                0.0,0.0,0.0,1.0
+            ]
+        }
+    }
+
+    /// Translation by v
+    pub fn translate(v: &Vector) -> Matrix {
+        Matrix {
+            m: [
+                1.0,0.0,0.0,v.x,
+                0.0,1.0,0.0,v.y,
+                0.0,0.0,1.0,v.z,
+                0.0,0.0,0.0,1.0
+            ]
+        }
+    }
+
+    /// Scaling along the axes
+    pub fn scale(x: f64,y: f64,z: f64) -> Matrix {
+        Matrix {
+            m: [
+                x,0.0,0.0,0.0,
+                0.0,y,0.0,0.0,
+                0.0,0.0,z,0.0,
+                0.0,0.0,0.0,1.0
+            ]
+        }
+    }
+
+    /// Inverse by Gauss-Jordan elimination with partial pivoting
+    pub fn inverse(&self) -> Result<Matrix,String> {
+        let mut a = self.m;
+        let mut inv = IDENTITY.m;
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i,&j| a[i*4 + col].abs().total_cmp(&a[j*4 + col].abs()))
+                .unwrap();
+            if a[pivot*4 + col].abs() < 1e-12 {
+                return Err("matrix is singular".to_string())
+            }
+            for k in 0..4 {
+                a.swap(col*4 + k,pivot*4 + k);
+                inv.swap(col*4 + k,pivot*4 + k);
+            }
+
+            let recip = 1.0 / a[col*4 + col];
+            for k in 0..4 {
+                a[col*4 + k] *= recip;
+                inv[col*4 + k] *= recip;
+            }
+            for row in (0..4).filter(|&row| row != col) {
+                let factor = a[row*4 + col];
+                for k in 0..4 {
+                    a[row*4 + k] -= factor * a[col*4 + k];
+                    inv[row*4 + k] -= factor * inv[col*4 + k];
+                }
+            }
+        }
+        Ok(Matrix {m: inv})
+    }
+
     /// Access (row i, column j) of matrix
     pub fn at(&self,i: usize,j: usize) -> Result<f64,String> {
         if i<4 && j<4 {
@@ -585,7 +666,7 @@ mod test {
         );
         let o: Point = Point{x:4.,y:3.,z:2.};
         let d: Vector = Vector{x:4.,y:3.,z:2.};
-        let mut r: Ray = Ray{o,d};
+        let mut r: Ray = Ray::new(&o,&d);
         r = m * r;
         // ray origin
         let w: f64 = 140.;
@@ -631,4 +712,44 @@ mod test {
         assert_eq!(t.m[14],12.);
         assert_eq!(t.m[15],16.);
     }
+
+    #[test]
+    // rotating about y should spin x towards -z
+    fn test_rotate_y() {
+        let m: Matrix = Matrix::rotate_y(std::f64::consts::FRAC_PI_2);
+        let v: Vector = m * Vector::new(1.,0.,0.);
+        assert!(v.x.abs() < 1e-12);
+        assert_eq!(v.y,0.);
+        assert!((v.z + 1.).abs() < 1e-12);
+        // inverse rotation is the transpose
+        let w: Vector = m.transpose() * v;
+        assert!((w.x - 1.).abs() < 1e-12);
+        assert!(w.z.abs() < 1e-12);
+    }
+
+    #[test]
+    // translation moves points but not vectors
+    fn test_translate_scale() {
+        let m: Matrix = Matrix::translate(&Vector::new(1.,2.,3.)) * Matrix::scale(2.,3.,4.);
+        let p: Point = m * Point::new(1.,1.,1.);
+        assert_eq!(p.x,3.);
+        assert_eq!(p.y,5.);
+        assert_eq!(p.z,7.);
+        let v: Vector = m * Vector::new(1.,1.,1.);
+        assert_eq!(v.x,2.);
+        assert_eq!(v.z,4.);
+    }
+
+    #[test]
+    // product with the inverse should be the identity
+    fn test_inverse() {
+        let m: Matrix = Matrix::translate(&Vector::new(1.,-2.,3.)) * Matrix::rotate_y(0.7) * Matrix::scale(2.,0.5,3.);
+        let product: Matrix = m * m.inverse().unwrap();
+        for i in 0..16 {
+            assert!((product.m[i] - IDENTITY.m[i]).abs() < 1e-12);
+        }
+        let p: Point = m.inverse().unwrap() * (m * Point::new(0.3,0.4,-5.));
+        assert!((p.z + 5.).abs() < 1e-12);
+        assert!(Matrix::scale(1.,0.,1.).inverse().is_err());
+    }
 }
@@ -0,0 +1,377 @@
+/// Squared radius of the kernel around each simplex vertex, small
+/// enough that contributions vanish before the opposite face in any dimension
+const SIMPLEX_RADIUS_SQ: f64 = 0.5;
+
+/// # Noise
+/// Seedable gradient, simplex and cellular noise in any dimension,
+/// points are arrays so the same code serves 2D, 3D and 4D. Lattice
+/// values come from hashing the cell coordinates with the seed, the
+/// output only depends on the seed and the point
+///
+/// # Parameters
+/// * seed
+#[derive(Clone,Copy,Debug)]
+pub struct Noise {
+    pub seed: u64
+}
+
+impl Noise {
+    /// Construct noise generator
+    pub fn new(seed: u64) -> Noise {
+        Noise {seed}
+    }
+
+    /// Perlin gradient noise in about [-1,1], zero at lattice points
+    pub fn perlin<const N: usize>(&self,p: [f64; N]) -> f64 {
+        let cell = p.map(f64::floor);
+        let mut frac = [0.0; N];
+        let mut fade = [0.0; N];
+        for i in 0..N {
+            frac[i] = p[i] - cell[i];
+            fade[i] = quintic(frac[i]);
+        }
+
+        let mut sum = 0.0;
+        for corner in 0..(1usize << N) {
+            let mut lattice = [0i64; N];
+            let mut offset = [0.0; N];
+            let mut weight = 1.0;
+            for i in 0..N {
+                let bit = (corner >> i) & 1;
+                lattice[i] = cell[i] as i64 + bit as i64;
+                offset[i] = frac[i] - bit as f64;
+                weight *= if bit == 1 { fade[i] } else { 1.0 - fade[i] };
+            }
+            sum += weight * dot(&self.gradient(&lattice),&offset);
+        }
+        // a unit gradient reaches at most sqrt(N)/2 at the cell center
+        (sum * 2.0 / (N as f64).sqrt()).clamp(-1.0,1.0)
+    }
+
+    /// Simplex noise in about [-1,1], cheaper than perlin in higher
+    /// dimensions since only N+1 vertices contribute
+    pub fn simplex<const N: usize>(&self,p: [f64; N]) -> f64 {
+        let n = N as f64;
+        let skew = ((n + 1.0).sqrt() - 1.0) / n;
+        let unskew = (1.0 - 1.0 / (n + 1.0).sqrt()) / n;
+
+        // cell of the skewed lattice and offset from its origin
+        let s = p.iter().sum::<f64>() * skew;
+        let cell = p.map(|x| (x + s).floor() as i64);
+        let t = cell.iter().sum::<i64>() as f64 * unskew;
+        let mut x0 = [0.0; N];
+        for i in 0..N {
+            x0[i] = p[i] - (cell[i] as f64 - t);
+        }
+
+        // vertices are visited stepping along axes by decreasing offset
+        let mut order: [usize; N] = std::array::from_fn(|i| i);
+        order.sort_by(|a,b| x0[*b].total_cmp(&x0[*a]));
+
+        let mut sum = 0.0;
+        let mut lattice = cell;
+        for k in 0..=N {
+            if k > 0 {
+                lattice[order[k - 1]] += 1;
+            }
+            let mut x = [0.0; N];
+            for i in 0..N {
+                x[i] = x0[i] - (lattice[i] - cell[i]) as f64 + k as f64 * unskew;
+            }
+            let falloff = SIMPLEX_RADIUS_SQ - dot(&x,&x);
+            if falloff > 0.0 {
+                let falloff = falloff * falloff;
+                sum += falloff * falloff * dot(&self.gradient(&lattice),&x);
+            }
+        }
+        (sum * SIMPLEX_SCALE[N.min(4)]).clamp(-1.0,1.0)
+    }
+
+    /// Worley cellular noise, distances from p to the nearest and
+    /// second nearest of the feature points scattered one per cell
+    pub fn worley<const N: usize>(&self,p: [f64; N]) -> (f64,f64) {
+        let cell = p.map(|x| x.floor() as i64);
+        let (mut f1,mut f2) = (f64::INFINITY,f64::INFINITY);
+        for neighbour in 0..3usize.pow(N as u32) {
+            let mut lattice = cell;
+            let mut index = neighbour;
+            for l in lattice.iter_mut() {
+                *l += (index % 3) as i64 - 1;
+                index /= 3;
+            }
+            let mut d2 = 0.0;
+            for i in 0..N {
+                let feature = lattice[i] as f64 + unit(self.hash(&lattice,i as u64 + N as u64));
+                d2 += (feature - p[i]) * (feature - p[i]);
+            }
+            if d2 < f1 {
+                f2 = f1;
+                f1 = d2;
+            } else if d2 < f2 {
+                f2 = d2;
+            }
+        }
+        (f1.sqrt(),f2.sqrt())
+    }
+
+    /// Pseudo random unit gradient at a lattice point
+    fn gradient<const N: usize>(&self,lattice: &[i64; N]) -> [f64; N] {
+        let mut g = [0.0; N];
+        for (i,c) in g.iter_mut().enumerate() {
+            *c = 2.0 * unit(self.hash(lattice,i as u64)) - 1.0;
+        }
+        let len = dot(&g,&g).sqrt();
+        if len < 1e-6 {
+            g = [0.0; N];
+            g[0] = 1.0;
+            return g
+        }
+        g.map(|c| c / len)
+    }
+
+    /// Hash of a lattice point, a channel and the seed
+    fn hash<const N: usize>(&self,lattice: &[i64; N],channel: u64) -> u64 {
+        let mut h = mix(self.seed ^ channel.wrapping_mul(0xd6e8feb86659fd93));
+        for c in lattice {
+            h = mix(h ^ (*c as u64).wrapping_mul(0x9e3779b97f4a7c15));
+        }
+        h
+    }
+}
+
+/// Empirical factors bringing simplex noise to about [-1,1], by dimension
+const SIMPLEX_SCALE: [f64; 5] = [1.0,99.0,99.0,108.0,109.0];
+
+/// # Fractal
+/// Sums of octaves of a noise basis, each octave scales
+/// frequency by lacunarity and amplitude by gain
+///
+/// # Parameters
+/// * octaves (number of octaves)
+/// * lacunarity (frequency ratio between octaves)
+/// * gain (amplitude ratio between octaves)
+#[derive(Clone,Copy,Debug)]
+pub struct Fractal {
+    pub octaves: usize,
+    pub lacunarity: f64,
+    pub gain: f64
+}
+
+impl Default for Fractal {
+    fn default() -> Self {
+        Fractal {
+            octaves: 6,
+            lacunarity: 2.0,
+            gain: 0.5
+        }
+    }
+}
+
+impl Fractal {
+    /// Construct fractal with given octaves, lacunarity and gain
+    pub fn new(octaves: usize,lacunarity: f64,gain: f64) -> Fractal {
+        Fractal {octaves,lacunarity,gain}
+    }
+
+    /// Fractional brownian motion, normalized to the range of basis
+    pub fn fbm<const N: usize,F: Fn([f64; N]) -> f64>(&self,p: [f64; N],basis: F) -> f64 {
+        self.sum(p,|value| value,basis)
+    }
+
+    /// Sum of absolute octaves in [0,1], billowy like smoke and fire
+    pub fn turbulence<const N: usize,F: Fn([f64; N]) -> f64>(&self,p: [f64; N],basis: F) -> f64 {
+        self.sum(p,|value| value.abs(),basis)
+    }
+
+    /// Ridged multifractal in [0,1], sharp crests where the basis
+    /// crosses zero, each octave is damped in valleys of the last
+    pub fn ridged<const N: usize,F: Fn([f64; N]) -> f64>(&self,p: [f64; N],basis: F) -> f64 {
+        let mut weight = 1.0;
+        self.sum(p,|value| {
+            let ridge = 1.0 - value.abs();
+            let signal = ridge * ridge * weight;
+            weight = (signal * 2.0).clamp(0.0,1.0);
+            signal
+        },basis)
+    }
+
+    /// Domain warping, p displaced by fbm of basis in every axis
+    /// scaled by strength. Feeding the result to another lookup
+    /// gives the swirls of marble and clouds
+    pub fn warp<const N: usize,F: Fn([f64; N]) -> f64>(&self,p: [f64; N],strength: f64,basis: F) -> [f64; N] {
+        let mut warped = p;
+        for (i,w) in warped.iter_mut().enumerate() {
+            // decorrelate the axes with a different offset for each
+            let shifted = p.map(|x| x + 5.2 * (i + 1) as f64);
+            *w += strength * self.fbm(shifted,&basis);
+        }
+        warped
+    }
+
+    /// Octave sum of shaped basis values normalized by the total amplitude
+    fn sum<const N: usize,S: FnMut(f64) -> f64,F: Fn([f64; N]) -> f64>(&self,p: [f64; N],mut shape: S,basis: F) -> f64 {
+        let mut total = 0.0;
+        let mut norm = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        for octave in 0..self.octaves {
+            // shift octaves apart so they do not share lattice points
+            let q = p.map(|x| x * frequency + octave as f64 * 19.19);
+            total += amplitude * shape(basis(q));
+            norm += amplitude;
+            amplitude *= self.gain;
+            frequency *= self.lacunarity;
+        }
+        if norm > 0.0 { total / norm } else { 0.0 }
+    }
+}
+
+/// Smooth interpolant with zero first and second derivatives at 0 and 1
+fn quintic(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn dot<const N: usize>(a: &[f64; N],b: &[f64; N]) -> f64 {
+    a.iter().zip(b.iter()).map(|(x,y)| x * y).sum()
+}
+
+/// Hash to [0,1)
+fn unit(h: u64) -> f64 {
+    (h >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// SplitMix64 finalizer
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // pseudo random points spread over a few cells
+    fn points<const N: usize>(count: usize) -> Vec<[f64; N]> {
+        (0..count).map(|i| std::array::from_fn(|j| 8.0 * unit(mix((i * N + j) as u64)) - 4.0)).collect()
+    }
+
+    // smallest and largest value over the points, and their mean
+    fn stats<const N: usize>(f: impl Fn([f64; N]) -> f64) -> (f64,f64,f64) {
+        let values: Vec<f64> = points::<N>(20000).into_iter().map(f).collect();
+        let min = values.iter().fold(f64::INFINITY,|m,v| m.min(*v));
+        let max = values.iter().fold(f64::NEG_INFINITY,|m,v| m.max(*v));
+        (min,max,values.iter().sum::<f64>() / values.len() as f64)
+    }
+
+    #[test]
+    // same seed should give the same noise, another seed different noise
+    fn test_deterministic() {
+        let a = Noise::new(7);
+        let b = Noise::new(8);
+        let p = [0.3,1.7,-2.2];
+        assert_eq!(a.perlin(p),Noise::new(7).perlin(p));
+        assert_eq!(a.simplex(p),Noise::new(7).simplex(p));
+        assert_eq!(a.worley(p),Noise::new(7).worley(p));
+        assert!(a.perlin(p) != b.perlin(p));
+        assert!(a.simplex(p) != b.simplex(p));
+        assert!(a.worley(p) != b.worley(p));
+    }
+
+    #[test]
+    // perlin noise vanishes on the lattice and spans about [-1,1]
+    fn test_perlin() {
+        let noise = Noise::new(1);
+        assert_eq!(noise.perlin([3.,-2.]),0.);
+        assert_eq!(noise.perlin([1.,2.,3.,4.]),0.);
+        let (min,max,mean) = stats::<2>(|p| noise.perlin(p));
+        assert!(min > -1. && max < 1. && min < -0.5 && max > 0.5,"{} {}",min,max);
+        assert!(mean.abs() < 0.05);
+        let (min,max,_) = stats::<3>(|p| noise.perlin(p));
+        assert!(min < -0.4 && max > 0.4,"{} {}",min,max);
+        let (min,max,_) = stats::<4>(|p| noise.perlin(p));
+        assert!(min < -0.3 && max > 0.3,"{} {}",min,max);
+    }
+
+    #[test]
+    // perlin noise should be continuous across cell borders
+    fn test_perlin_continuous() {
+        let noise = Noise::new(2);
+        for x in [1.,2.,-3.] {
+            let left = noise.perlin([x - 1e-7,0.37,0.81]);
+            let right = noise.perlin([x + 1e-7,0.37,0.81]);
+            assert!((left - right).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    // simplex noise should span about [-1,1] in every dimension
+    fn test_simplex() {
+        let noise = Noise::new(3);
+        for (min,max,mean) in [
+            stats::<2>(|p| noise.simplex(p)),
+            stats::<3>(|p| noise.simplex(p)),
+            stats::<4>(|p| noise.simplex(p))
+        ] {
+            assert!(min >= -1. && max <= 1.);
+            assert!(min < -0.5 && max > 0.5,"{} {}",min,max);
+            assert!(mean.abs() < 0.05);
+        }
+        // continuous across simplex borders
+        let a = noise.simplex([0.5,0.5 - 1e-7]);
+        let b = noise.simplex([0.5,0.5 + 1e-7]);
+        assert!((a - b).abs() < 1e-5);
+    }
+
+    #[test]
+    // worley distances should be ordered and bounded by the cell size
+    fn test_worley() {
+        let noise = Noise::new(4);
+        for p in points::<3>(1000) {
+            let (f1,f2) = noise.worley(p);
+            assert!(f1 <= f2);
+            assert!(f2 < 3f64.sqrt() * 2.);
+        }
+        let (f1,_) = noise.worley([0.5,0.5]);
+        let (g1,_) = noise.worley([0.5 + 1e-7,0.5]);
+        assert!((f1 - g1).abs() < 1e-6);
+        let (f1,f2) = noise.worley([0.1,0.2,0.3,0.4]);
+        assert!(f1 <= f2 && f1 > 0.);
+    }
+
+    #[test]
+    // fractal sums should keep the range of their basis
+    fn test_fractal() {
+        let noise = Noise::new(5);
+        let fractal = Fractal::default();
+        let (min,max,_) = stats::<3>(|p| fractal.fbm(p,|q| noise.perlin(q)));
+        assert!(min >= -1. && max <= 1. && max > 0.3);
+        let (min,max,_) = stats::<3>(|p| fractal.turbulence(p,|q| noise.perlin(q)));
+        assert!(min >= 0. && max <= 1.);
+        let (min,max,_) = stats::<2>(|p| fractal.ridged(p,|q| noise.simplex(q)));
+        assert!(min >= 0. && max <= 1. && max > 0.3);
+        // a single octave is the basis itself
+        let single = Fractal::new(1,2.,0.5);
+        let p = [0.3,0.4];
+        assert_eq!(single.fbm(p,|q| noise.perlin(q)),noise.perlin(p));
+    }
+
+    #[test]
+    // warping should move points by at most strength per axis
+    fn test_warp() {
+        let noise = Noise::new(6);
+        let fractal = Fractal::default();
+        let p = [0.3,0.4,0.5];
+        let warped = fractal.warp(p,0.5,|q| noise.perlin(q));
+        assert!(warped != p);
+        for i in 0..3 {
+            assert!((warped[i] - p[i]).abs() <= 0.5);
+        }
+        assert_eq!(fractal.warp(p,0.,|q| noise.perlin(q)),p);
+    }
+}
@@ -73,6 +73,17 @@ impl std::ops::Mul<f64> for Normal {
     }
 }
 
+/// Convert vector to normal
+impl From<Vector> for Normal {
+    fn from(v: Vector) -> Normal {
+        Normal {
+            x: v.x,
+            y: v.y,
+            z: v.z
+        }
+    }
+}
+
 /// Dot product of normal and vector
 impl Dot<Vector> for Normal {
     fn dot(&self,v: Vector) -> f64 {
@@ -120,6 +131,15 @@ impl Normal {
     pub fn new(x: f64,y: f64,z: f64) -> Normal {
         Normal {x,y,z}
     }
+
+    /// Flip normal so that it lies in the same hemisphere as v
+    pub fn face_forward(&self,v: Vector) -> Normal {
+        if self.dot(v) < 0.0 {
+            -*self
+        } else {
+            *self
+        }
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -189,4 +209,14 @@ mod tests {
         let dot: f64 = n.dot(v);
         assert_eq!(dot,4.);
     }
+
+    #[test]
+    // should flip normal into the hemisphere of v
+    fn test_face_forward() {
+        let n: Normal = Normal::new(0.,1.,0.);
+        let up: Normal = n.face_forward(Vector::new(1.,2.,0.));
+        assert_eq!(up.y,1.);
+        let down: Normal = n.face_forward(Vector::new(1.,-2.,0.));
+        assert_eq!(down.y,-1.);
+    }
 }
@@ -0,0 +1,179 @@
+/// Iterations allowed when polishing a bracketed root
+const MAX_ITERATIONS: usize = 100;
+
+/// Real roots (t0 <= t1) of a t^2 + b t + c, the smaller magnitude root
+/// is recovered from the product of the roots to avoid cancellation,
+/// a linear equation returns its single root twice
+pub fn solve_quadratic(a: f64,b: f64,c: f64) -> Option<(f64,f64)> {
+    if a == 0.0 {
+        if b == 0.0 {
+            return None
+        }
+        let t = -c / b;
+        return Some((t,t))
+    }
+    let discrim = b*b - 4.0*a*c;
+    if discrim < 0.0 {
+        return None
+    }
+    let root = f64::sqrt(discrim);
+    let q = if b < 0.0 { -0.5 * (b - root) } else { -0.5 * (b + root) };
+    let t0 = q / a;
+    let t1 = if q != 0.0 { c / q } else { t0 };
+    Some(if t0 <= t1 { (t0,t1) } else { (t1,t0) })
+}
+
+/// Value of the polynomial sum coefficients[i] x^i at x by Horner's rule
+pub fn evaluate(coefficients: &[f64],x: f64) -> f64 {
+    coefficients.iter().rev().fold(0.0,|value,c| value * x + c)
+}
+
+/// Coefficients of the derivative, in the same lowest power first order
+pub fn derivative(coefficients: &[f64]) -> Vec<f64> {
+    coefficients.iter().enumerate().skip(1).map(|(i,c)| c * i as f64).collect()
+}
+
+/// Sorted real roots in [lo,hi] of the polynomial sum coefficients[i] x^i,
+/// of any degree. The roots of the derivative split the interval into
+/// pieces where the polynomial is monotonic, so each sign change holds
+/// exactly one root, found by Newton steps kept inside the bracket by
+/// bisection (Yuksel, "High-Performance Polynomial Root Finding for
+/// Graphics"). Critical points where the polynomial vanishes to rounding
+/// are reported as well, so that tangent roots of even multiplicity are
+/// not lost
+pub fn roots_in(coefficients: &[f64],lo: f64,hi: f64) -> Vec<f64> {
+    let degree = match coefficients.iter().rposition(|&c| c != 0.0) {
+        Some(degree) => degree,
+        None => return vec![]
+    };
+    let coefficients = &coefficients[..=degree];
+    let in_range = |t: &f64| *t >= lo && *t <= hi;
+    match degree {
+        0 => vec![],
+        1 => [-coefficients[0] / coefficients[1]].into_iter().filter(in_range).collect(),
+        2 => match solve_quadratic(coefficients[2],coefficients[1],coefficients[0]) {
+            Some((t0,t1)) if t0 == t1 => [t0].into_iter().filter(in_range).collect(),
+            Some((t0,t1)) => [t0,t1].into_iter().filter(in_range).collect(),
+            None => vec![]
+        },
+        _ => {
+            let mut points = vec![lo];
+            points.extend(roots_in(&derivative(coefficients),lo,hi));
+            points.push(hi);
+
+            // scale of the terms at x, to judge a vanishing value
+            let magnitude = |x: f64| coefficients.iter().rev().fold(0.0,|m,c| m * x.abs() + c.abs());
+            let mut roots: Vec<f64> = vec![];
+            let push = |t: f64,roots: &mut Vec<f64>| {
+                if roots.last().is_none_or(|&last| t - last > 1e-12 * (1.0 + t.abs())) {
+                    roots.push(t);
+                }
+            };
+            for (i,&a) in points.iter().enumerate() {
+                let fa = evaluate(coefficients,a);
+                if fa.abs() <= 1e-12 * magnitude(a) {
+                    push(a,&mut roots);
+                    continue
+                }
+                if let Some(&b) = points.get(i + 1) {
+                    let fb = evaluate(coefficients,b);
+                    if fa * fb < 0.0 && fb.abs() > 1e-12 * magnitude(b) {
+                        push(bracketed_root(coefficients,a,b,fa),&mut roots);
+                    }
+                }
+            }
+            roots
+        }
+    }
+}
+
+/// Root of the polynomial in [a,b] where it changes sign exactly once,
+/// fa being its value at a
+fn bracketed_root(coefficients: &[f64],mut a: f64,mut b: f64,fa: f64) -> f64 {
+    let slope = derivative(coefficients);
+    let rising = fa < 0.0;
+    let mut x = 0.5 * (a + b);
+    for _ in 0..MAX_ITERATIONS {
+        let f = evaluate(coefficients,x);
+        if f == 0.0 {
+            return x
+        }
+        if (f < 0.0) == rising {
+            a = x;
+        } else {
+            b = x;
+        }
+        let df = evaluate(&slope,x);
+        let newton = x - f / df;
+        let next = if df != 0.0 && newton > a && newton < b { newton } else { 0.5 * (a + b) };
+        if (next - x).abs() <= 1e-15 * (1.0 + x.abs()) {
+            return next
+        }
+        x = next;
+    }
+    x
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // roots should be ordered and accurate even when b dominates
+    fn test_solve_quadratic() {
+        assert_eq!(solve_quadratic(1.,-3.,2.),Some((1.,2.)));
+        assert_eq!(solve_quadratic(-1.,3.,-2.),Some((1.,2.)));
+        assert_eq!(solve_quadratic(1.,0.,1.),None);
+        assert_eq!(solve_quadratic(0.,2.,-1.),Some((0.5,0.5)));
+        assert_eq!(solve_quadratic(0.,0.,1.),None);
+        let (t0,t1) = solve_quadratic(1.,1e9,1.).unwrap();
+        assert!((t0 + 1e9).abs() < 1e-3);
+        assert!((t1 + 1e-9).abs() < 1e-21);
+    }
+
+    #[test]
+    // evaluation and derivative should follow the lowest power first order
+    fn test_evaluate() {
+        let p = [1.,-3.,0.,2.];
+        assert_eq!(evaluate(&p,2.),11.);
+        assert_eq!(derivative(&p),vec![-3.,0.,6.]);
+        assert_eq!(evaluate(&[],2.),0.);
+    }
+
+    #[test]
+    // every real root in the interval should be found once, in order
+    fn test_roots_in() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        let p = [24.,-50.,35.,-10.,1.];
+        let roots = roots_in(&p,0.,10.);
+        assert_eq!(roots.len(),4);
+        for (root,expected) in roots.iter().zip([1.,2.,3.,4.]) {
+            assert!((root - expected).abs() < 1e-12,"{:?}",roots);
+        }
+        assert_eq!(roots_in(&p,1.5,3.5).len(),2);
+        // x^4 + 1 has no real roots
+        assert!(roots_in(&[1.,0.,0.,0.,1.],-10.,10.).is_empty());
+        // trailing zero coefficients lower the degree
+        assert_eq!(roots_in(&[-2.,1.,0.,0.],0.,5.),vec![2.]);
+        // closely spaced roots of a wide range of magnitudes
+        let p = [1e-6 * 1.001,-(1e-3 + 1.001e-3),1.];
+        let roots = roots_in(&[0.,p[0],p[1],p[2]],-1.,1.);
+        assert_eq!(roots.len(),3);
+        assert!((roots[2] - 1.001e-3).abs() < 1e-15);
+    }
+
+    #[test]
+    // double roots where the polynomial only touches zero should be kept
+    fn test_tangent_roots() {
+        // (x - 1)^2 (x + 2)^2
+        let p = [4.,-4.,-3.,2.,1.];
+        let roots = roots_in(&p,-5.,5.);
+        assert_eq!(roots.len(),2,"{:?}",roots);
+        assert!((roots[0] + 2.).abs() < 1e-9);
+        assert!((roots[1] - 1.).abs() < 1e-9);
+    }
+}
@@ -9,10 +9,27 @@ use super::{
 /// # Parameters
 /// * o (origin)
 /// * d (direction)
+/// * differentials (offset rays one pixel over in x and y, used to filter textures)
 #[derive(Clone,Copy)]
 pub struct Ray {
     pub o: Point,
-    pub d: Vector
+    pub d: Vector,
+    pub differentials: Option<RayDifferential>
+}
+
+/// # RayDifferential
+/// Two auxiliary rays offset from the main ray by one pixel
+/// in x and in y on the image
+///
+/// # Parameters
+/// * rx_o, rx_d (origin and direction of the ray offset in x)
+/// * ry_o, ry_d (origin and direction of the ray offset in y)
+#[derive(Clone,Copy)]
+pub struct RayDifferential {
+    pub rx_o: Point,
+    pub rx_d: Vector,
+    pub ry_o: Point,
+    pub ry_d: Vector
 }
 
 /// implement display trait
@@ -27,14 +44,33 @@ impl Ray {
     pub fn new(o: &Point,d: &Vector) -> Ray {
         Ray {
             o: Point{x: o.x,y: o.y,z: o.z},
-            d: Vector{x: d.x,y: d.y,z: d.z}
+            d: Vector{x: d.x,y: d.y,z: d.z},
+            differentials: None
+        }
+    }
+
+    /// Same ray carrying the given differentials
+    pub fn with_differentials(self,differentials: RayDifferential) -> Ray {
+        Ray {
+            differentials: Some(differentials),
+            ..self
+        }
+    }
+
+    /// Move the offset rays closer to the main ray by factor s,
+    /// with several samples per pixel they are a fraction of a pixel apart
+    pub fn scale_differentials(&mut self,s: f64) {
+        if let Some(rd) = &mut self.differentials {
+            rd.rx_o = self.o + (rd.rx_o - self.o) * s;
+            rd.ry_o = self.o + (rd.ry_o - self.o) * s;
+            rd.rx_d = self.d + (rd.rx_d - self.d) * s;
+            rd.ry_d = self.d + (rd.ry_d - self.d) * s;
         }
     }
 
     /// Get point on ray
     pub fn at(&self,t: f64) -> Point {
-        // self.o.add_vector(&self.d.scale(t))
-        super::point::ZERO
+        self.o + self.d*t
     }
 }
 
@@ -62,6 +98,7 @@ mod tests {
         assert_eq!(r.d.z,-7.);
     }
 
+    #[test]
     // should get correct point on ray
     fn test_at() {
         let o: Point = Point::new(2.,-1.,0.);
@@ -73,4 +110,25 @@ mod tests {
         assert_eq!(p.y,3.);
         assert_eq!(p.z,6.);
     }
+
+    #[test]
+    // scaling should pull the offset rays towards the main ray
+    fn test_scale_differentials() {
+        let mut r: Ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
+        assert!(r.differentials.is_none());
+        r.scale_differentials(0.5);
+        assert!(r.differentials.is_none());
+        let mut r = r.with_differentials(RayDifferential {
+            rx_o: Point::new(2.,0.,0.),
+            rx_d: Vector::new(1.,0.,1.),
+            ry_o: Point::new(0.,0.,0.),
+            ry_d: Vector::new(0.,-1.,1.)
+        });
+        r.scale_differentials(0.25);
+        let rd = r.differentials.unwrap();
+        assert_eq!(rd.rx_o.x,0.5);
+        assert_eq!(rd.rx_d.x,0.25);
+        assert_eq!(rd.ry_d.y,-0.25);
+        assert_eq!(rd.ry_d.z,1.);
+    }
 }
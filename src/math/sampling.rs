@@ -0,0 +1,304 @@
+use std::f64::consts::PI;
+
+use super::vector::Vector;
+
+/// # Distribution1D
+/// Piecewise constant 1D distribution over [0,1)
+/// built from a tabulated non-negative function
+///
+/// # Parameters
+/// * func (function values)
+/// * cdf (cumulative distribution, n+1 entries)
+/// * func_int (integral of func over [0,1))
+pub struct Distribution1D {
+    pub func: Vec<f64>,
+    pub cdf: Vec<f64>,
+    pub func_int: f64
+}
+
+impl Distribution1D {
+    /// Construct distribution from function values
+    pub fn new(func: &[f64]) -> Distribution1D {
+        let n = func.len();
+        let mut cdf: Vec<f64> = vec![0.0; n+1];
+        for i in 1..=n {
+            cdf[i] = cdf[i-1] + func[i-1].abs() / n as f64;
+        }
+
+        let func_int = cdf[n];
+        if func_int == 0.0 {
+            // degenerate function, fall back to uniform
+            for (i,c) in cdf.iter_mut().enumerate() {
+                *c = i as f64 / n as f64;
+            }
+        } else {
+            for c in cdf.iter_mut() {
+                *c /= func_int;
+            }
+        }
+
+        Distribution1D {
+            func: func.iter().map(|f| f.abs()).collect(),
+            cdf,
+            func_int
+        }
+    }
+
+    /// Number of pieces
+    pub fn count(&self) -> usize {
+        self.func.len()
+    }
+
+    /// Sample continuous value in [0,1) given uniform u
+    /// returns (value,pdf,offset of the piece that was sampled)
+    pub fn sample_continuous(&self,u: f64) -> (f64,f64,usize) {
+        let offset = self.find_interval(u);
+
+        // offset within the piece
+        let mut du = u - self.cdf[offset];
+        let width = self.cdf[offset+1] - self.cdf[offset];
+        if width > 0.0 {
+            du /= width;
+        }
+
+        let pdf = self.pdf_at(offset);
+        ((offset as f64 + du) / self.count() as f64,pdf,offset)
+    }
+
+    /// Sample a piece index given uniform u
+    /// returns (index,probability of that index)
+    pub fn sample_discrete(&self,u: f64) -> (usize,f64) {
+        let offset = self.find_interval(u);
+        (offset,self.discrete_pdf(offset))
+    }
+
+    /// Probability of choosing piece i with sample_discrete
+    pub fn discrete_pdf(&self,i: usize) -> f64 {
+        self.cdf[i+1] - self.cdf[i]
+    }
+
+    /// Density of the continuous distribution in piece i
+    pub fn pdf_at(&self,i: usize) -> f64 {
+        if self.func_int > 0.0 {
+            self.func[i] / self.func_int
+        } else {
+            1.0
+        }
+    }
+
+    /// Find largest index i such that cdf[i] <= u
+    fn find_interval(&self,u: f64) -> usize {
+        // cdf[0] = 0 so partition point is at least 1
+        let i = self.cdf.partition_point(|c| *c <= u);
+        i.saturating_sub(1).min(self.count() - 1)
+    }
+}
+
+/// # Distribution2D
+/// Piecewise constant 2D distribution over [0,1)^2
+/// sampled with a marginal over rows (v) and a conditional per row (u)
+///
+/// # Parameters
+/// * conditional (one distribution per row)
+/// * marginal (distribution over rows)
+pub struct Distribution2D {
+    pub conditional: Vec<Distribution1D>,
+    pub marginal: Distribution1D
+}
+
+impl Distribution2D {
+    /// Construct from row major function values (nu columns, nv rows)
+    pub fn new(func: &[f64],nu: usize,nv: usize) -> Distribution2D {
+        let conditional: Vec<Distribution1D> = (0..nv)
+            .map(|v| Distribution1D::new(&func[v*nu..(v+1)*nu]))
+            .collect();
+        let marginal_func: Vec<f64> = conditional.iter().map(|d| d.func_int).collect();
+        Distribution2D {
+            conditional,
+            marginal: Distribution1D::new(&marginal_func)
+        }
+    }
+
+    /// Sample (u,v) given two uniform values, returns ((u,v),pdf)
+    pub fn sample_continuous(&self,u0: f64,u1: f64) -> ((f64,f64),f64) {
+        let (v,pdf_v,row) = self.marginal.sample_continuous(u1);
+        let (u,pdf_u,_) = self.conditional[row].sample_continuous(u0);
+        ((u,v),pdf_u * pdf_v)
+    }
+
+    /// Density of sampling (u,v)
+    pub fn pdf(&self,u: f64,v: f64) -> f64 {
+        let nu = self.conditional[0].count();
+        let nv = self.marginal.count();
+        let iu = ((u * nu as f64) as usize).min(nu - 1);
+        let iv = ((v * nv as f64) as usize).min(nv - 1);
+        if self.marginal.func_int == 0.0 {
+            return 1.0
+        }
+        self.conditional[iv].func[iu] / self.marginal.func_int
+    }
+}
+
+/// Map uniform square to unit disk (Shirley-Chiu concentric mapping)
+pub fn concentric_sample_disk(u: (f64,f64)) -> (f64,f64) {
+    let ox = 2.0*u.0 - 1.0;
+    let oy = 2.0*u.1 - 1.0;
+    if ox == 0.0 && oy == 0.0 {
+        return (0.0,0.0)
+    }
+    let (r,theta) = if ox.abs() > oy.abs() {
+        (ox,(PI/4.0) * (oy/ox))
+    } else {
+        (oy,(PI/2.0) - (PI/4.0) * (ox/oy))
+    };
+    (r*f64::cos(theta),r*f64::sin(theta))
+}
+
+/// Cosine weighted direction on the +z hemisphere
+pub fn cosine_sample_hemisphere(u: (f64,f64)) -> Vector {
+    let (x,y) = concentric_sample_disk(u);
+    let z = f64::sqrt(f64::max(0.0,1.0 - x*x - y*y));
+    Vector::new(x,y,z)
+}
+
+/// Density of cosine_sample_hemisphere
+pub fn cosine_hemisphere_pdf(cos_theta: f64) -> f64 {
+    cos_theta * std::f64::consts::FRAC_1_PI
+}
+
+/// Uniformly distributed direction on the unit sphere
+pub fn uniform_sample_sphere(u: (f64,f64)) -> Vector {
+    let z = 1.0 - 2.0*u.0;
+    let r = f64::sqrt(f64::max(0.0,1.0 - z*z));
+    let phi = 2.0 * PI * u.1;
+    Vector::new(r*f64::cos(phi),r*f64::sin(phi),z)
+}
+
+/// Density of uniform_sample_sphere
+pub fn uniform_sphere_pdf() -> f64 {
+    1.0 / (4.0 * PI)
+}
+
+/// Power heuristic (beta = 2) for multiple importance sampling
+pub fn power_heuristic(nf: f64,f_pdf: f64,ng: f64,g_pdf: f64) -> f64 {
+    let f = nf * f_pdf;
+    let g = ng * g_pdf;
+    if f == 0.0 && g == 0.0 {
+        return 0.0
+    }
+    if f.is_infinite() {
+        return 1.0
+    }
+    (f*f) / (f*f + g*g)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // should build normalized cdf
+    fn test_distribution_1d_new() {
+        let d = Distribution1D::new(&[1.,3.]);
+        assert_eq!(d.count(),2);
+        assert_eq!(d.func_int,2.);
+        assert_eq!(d.cdf[0],0.);
+        assert_eq!(d.cdf[1],0.25);
+        assert_eq!(d.cdf[2],1.);
+    }
+
+    #[test]
+    // should sample pieces in proportion to function value
+    fn test_distribution_1d_sample() {
+        let d = Distribution1D::new(&[1.,3.]);
+        let (x,pdf,offset) = d.sample_continuous(0.125);
+        assert_eq!(offset,0);
+        assert_eq!(x,0.25);
+        assert_eq!(pdf,0.5);
+        let (x,pdf,offset) = d.sample_continuous(0.625);
+        assert_eq!(offset,1);
+        assert_eq!(x,0.75);
+        assert_eq!(pdf,1.5);
+        let (i,p) = d.sample_discrete(0.9);
+        assert_eq!(i,1);
+        assert_eq!(p,0.75);
+    }
+
+    #[test]
+    // zero function should fall back to uniform
+    fn test_distribution_1d_zero() {
+        let d = Distribution1D::new(&[0.,0.,0.,0.]);
+        let (x,pdf,_) = d.sample_continuous(0.6);
+        assert!((x - 0.6).abs() < 1e-12);
+        assert_eq!(pdf,1.);
+    }
+
+    #[test]
+    // 2D pdf should match sampled pdf and integrate to one
+    fn test_distribution_2d() {
+        let func = [1.,2.,3.,4.,0.,0.];
+        let d = Distribution2D::new(&func,2,3);
+        let ((u,v),pdf) = d.sample_continuous(0.5,0.5);
+        assert!((d.pdf(u,v) - pdf).abs() < 1e-12);
+        // last row is zero, should never be sampled
+        let ((_,v),_) = d.sample_continuous(0.3,0.999);
+        assert!(v < 2. / 3.);
+        // integrate pdf over cells
+        let mut integral = 0.;
+        for iv in 0..3 {
+            for iu in 0..2 {
+                integral += d.pdf((iu as f64 + 0.5) / 2.,(iv as f64 + 0.5) / 3.) / 6.;
+            }
+        }
+        assert!((integral - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    // disk samples should stay inside the unit disk
+    fn test_concentric_sample_disk() {
+        assert_eq!(concentric_sample_disk((0.5,0.5)),(0.,0.));
+        let (x,y) = concentric_sample_disk((1.,0.5));
+        assert!((x - 1.).abs() < 1e-12);
+        assert!(y.abs() < 1e-12);
+        for i in 0..10 {
+            for j in 0..10 {
+                let (x,y) = concentric_sample_disk((i as f64 / 10.,j as f64 / 10.));
+                assert!(x*x + y*y <= 1. + 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    // hemisphere samples should be unit vectors above the xy plane
+    fn test_cosine_sample_hemisphere() {
+        for i in 0..10 {
+            let v = cosine_sample_hemisphere((i as f64 / 10.,0.7));
+            assert!(v.z >= 0.);
+            assert!(((v.x*v.x + v.y*v.y + v.z*v.z) - 1.).abs() < 1e-12);
+        }
+        assert_eq!(cosine_hemisphere_pdf(1.),std::f64::consts::FRAC_1_PI);
+    }
+
+    #[test]
+    // sphere samples should be unit vectors
+    fn test_uniform_sample_sphere() {
+        let v = uniform_sample_sphere((0.,0.));
+        assert_eq!(v.z,1.);
+        let v = uniform_sample_sphere((0.3,0.8));
+        assert!(((v.x*v.x + v.y*v.y + v.z*v.z) - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    // power heuristic weights should sum to one
+    fn test_power_heuristic() {
+        let a = power_heuristic(1.,0.5,1.,1.5);
+        let b = power_heuristic(1.,1.5,1.,0.5);
+        assert!((a + b - 1.).abs() < 1e-12);
+        assert_eq!(power_heuristic(1.,f64::INFINITY,1.,1.),1.);
+        assert_eq!(power_heuristic(1.,0.,1.,0.),0.);
+    }
+}
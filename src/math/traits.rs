@@ -1,3 +1,5 @@
+// euclidean length, there is no notion of being empty
+#[allow(clippy::len_without_is_empty)]
 pub trait Len {
     fn len(&self) -> f64;
 }
@@ -98,6 +98,17 @@ impl std::ops::Mul<f64> for Vector {
     }
 }
 
+/// Convert normal to vector
+impl From<Normal> for Vector {
+    fn from(n: Normal) -> Vector {
+        Vector {
+            x: n.x,
+            y: n.y,
+            z: n.z
+        }
+    }
+}
+
 /// Dot product of two vectors
 impl Dot<Vector> for Vector {
     fn dot(&self,v: Vector) -> f64 {
@@ -316,4 +327,13 @@ mod tests {
         assert_eq!(v_norm.y,v.y / len);
         assert_eq!(v_norm.z,v.z / len);
     }
+
+    #[test]
+    // should convert normal to vector
+    fn test_from_normal() {
+        let v: Vector = Vector::from(Normal::new(1.,-2.,3.));
+        assert_eq!(v.x,1.);
+        assert_eq!(v.y,-2.);
+        assert_eq!(v.z,3.);
+    }
 }
@@ -0,0 +1,7 @@
+// medium
+pub mod homogeneous;
+pub mod grid;
+pub mod voxel;
+pub mod phase;
+
+pub mod traits;
@@ -0,0 +1,392 @@
+use super::{
+    traits::{Medium,MediumSample,MediumInteraction},
+    phase::HenyeyGreenstein,
+    voxel::{VoxelGrid,MajorantGrid}
+};
+use crate::{
+    image::{spectrum::{self,Spectrum},blackbody::blackbody},
+    math::{point::Point,ray::Ray,matrix::Matrix,traits::Len},
+    sampler::traits::Sampler
+};
+
+/// Cells per axis of the majorant grid
+const MAJORANT_RESOLUTION: usize = 16;
+
+/// # GridMedium
+/// Heterogeneous medium whose density is given by a voxel grid over
+/// the unit cube, placed in the world by a transform. Distances are
+/// sampled with delta tracking and transmittance is estimated with
+/// ratio tracking, both walk a coarse majorant grid so empty and thin
+/// regions are crossed in few steps
+///
+/// Extinction is the same in every channel so tracking stays scalar,
+/// color comes from the single scattering albedo. An optional
+/// temperature grid (K) makes the absorbed fraction glow as a blackbody
+///
+/// # Parameters
+/// * sigma_t (extinction coefficient per unit length at density one)
+/// * albedo (fraction of extinction that is scattering)
+/// * phase (phase function of scattering events)
+/// * density (density voxels)
+/// * temperature (temperature voxels, no emission if None)
+/// * emission_scale (factor applied to blackbody radiance)
+/// * world_to_medium (inverse of the placement transform)
+/// * majorants (bounds of density for tracking)
+pub struct GridMedium {
+    pub sigma_t: f64,
+    pub albedo: Spectrum,
+    pub phase: HenyeyGreenstein,
+    pub density: VoxelGrid,
+    pub temperature: Option<VoxelGrid>,
+    pub emission_scale: f64,
+    world_to_medium: Matrix,
+    majorants: MajorantGrid
+}
+
+/// Medium trait
+impl Medium for GridMedium {
+    /// Ratio tracking
+    fn tr(&self,ray: &Ray,tmax: f64,sampler: &mut dyn Sampler) -> Spectrum {
+        let local = self.world_to_medium * *ray;
+        let (t0,t1) = match clip_unit_cube(&local,tmax) {
+            Some(range) => range,
+            None => return spectrum::WHITE
+        };
+        let rate = self.sigma_t * ray.d.len();
+        let mut tr = 1.0;
+        for (start,end,majorant) in MajorantSegments::new(&self.majorants,&local,t0,t1) {
+            if majorant <= 0.0 {
+                continue;
+            }
+            let mut t = start;
+            loop {
+                t -= (1.0 - sampler.get_1d()).ln() / (majorant * rate);
+                if t >= end {
+                    break;
+                }
+                tr *= 1.0 - self.density.lookup(&local.at(t)) / majorant;
+                if tr <= 0.0 {
+                    return spectrum::BLACK
+                }
+            }
+        }
+        spectrum::WHITE * tr
+    }
+
+    /// Delta tracking, a collision both scatters with the albedo as
+    /// weight and adds the emission of the absorbed fraction
+    fn sample(&self,ray: &Ray,tmax: f64,sampler: &mut dyn Sampler) -> MediumSample {
+        let passed = MediumSample {beta: spectrum::WHITE,le: spectrum::BLACK,interaction: None};
+        let local = self.world_to_medium * *ray;
+        let (t0,t1) = match clip_unit_cube(&local,tmax) {
+            Some(range) => range,
+            None => return passed
+        };
+        let length = ray.d.len();
+        let rate = self.sigma_t * length;
+        for (start,end,majorant) in MajorantSegments::new(&self.majorants,&local,t0,t1) {
+            if majorant <= 0.0 {
+                continue;
+            }
+            let mut t = start;
+            loop {
+                t -= (1.0 - sampler.get_1d()).ln() / (majorant * rate);
+                if t >= end {
+                    break;
+                }
+                let p = local.at(t);
+                if self.density.lookup(&p) / majorant > sampler.get_1d() {
+                    return MediumSample {
+                        beta: self.albedo,
+                        le: self.emission(&p),
+                        interaction: Some(MediumInteraction {
+                            p: ray.at(t),
+                            wo: -ray.d * (1.0 / length),
+                            phase: self.phase
+                        })
+                    }
+                }
+            }
+        }
+        passed
+    }
+}
+
+impl GridMedium {
+    /// Construct medium from density voxels, medium_to_world
+    /// places the unit cube holding them in the scene
+    pub fn new(sigma_t: f64,albedo: Spectrum,g: f64,density: VoxelGrid,medium_to_world: &Matrix) -> Result<GridMedium,String> {
+        Ok(GridMedium {
+            sigma_t,
+            albedo,
+            phase: HenyeyGreenstein::new(g),
+            world_to_medium: medium_to_world.inverse()?,
+            majorants: MajorantGrid::new(&density,MAJORANT_RESOLUTION),
+            density,
+            temperature: None,
+            emission_scale: 0.0
+        })
+    }
+
+    /// Make the medium glow as a blackbody of the given temperatures,
+    /// scale converts blackbody radiance to scene units
+    pub fn set_temperature(&mut self,temperature: VoxelGrid,scale: f64) {
+        self.temperature = Some(temperature);
+        self.emission_scale = scale;
+    }
+
+    /// Density at a world space point, zero outside the medium
+    pub fn density_at(&self,p: &Point) -> f64 {
+        self.density.lookup(&(self.world_to_medium * *p))
+    }
+
+    /// Radiance emitted by the absorbed fraction at a medium space point
+    fn emission(&self,p: &Point) -> Spectrum {
+        match &self.temperature {
+            Some(temperature) if self.emission_scale > 0.0 => {
+                (spectrum::WHITE - self.albedo) * blackbody(temperature.lookup(p)) * self.emission_scale
+            },
+            _ => spectrum::BLACK
+        }
+    }
+}
+
+/// Ray parameter range inside the unit cube and before tmax (slab test),
+/// None if the ray misses it
+fn clip_unit_cube(ray: &Ray,tmax: f64) -> Option<(f64,f64)> {
+    let mut t0: f64 = 0.0;
+    let mut t1 = tmax;
+    for (o,d) in [(ray.o.x,ray.d.x),(ray.o.y,ray.d.y),(ray.o.z,ray.d.z)] {
+        let inv = 1.0 / d;
+        let (mut near,mut far) = (-o * inv,(1.0 - o) * inv);
+        if near > far {
+            std::mem::swap(&mut near,&mut far);
+        }
+        // parallel rays inside the slab give infinite bounds, NaN if on its plane
+        if near.is_nan() || far.is_nan() {
+            continue;
+        }
+        t0 = t0.max(near);
+        t1 = t1.min(far);
+        if t0 > t1 {
+            return None
+        }
+    }
+    Some((t0,t1))
+}
+
+/// # MajorantSegments
+/// 3D DDA through the majorant grid, yields (start,end,majorant)
+/// for every cell the ray crosses between t0 and t1
+struct MajorantSegments<'a> {
+    grid: &'a MajorantGrid,
+    cell: [i64; 3],
+    step: [i64; 3],
+    next: [f64; 3],
+    delta: [f64; 3],
+    t: f64,
+    t1: f64
+}
+
+impl<'a> MajorantSegments<'a> {
+    fn new(grid: &'a MajorantGrid,ray: &Ray,t0: f64,t1: f64) -> MajorantSegments<'a> {
+        let (mx,my,mz) = grid.resolution;
+        let resolution = [mx as f64,my as f64,mz as f64];
+        let o = [ray.o.x,ray.o.y,ray.o.z];
+        let d = [ray.d.x,ray.d.y,ray.d.z];
+        let mut segments = MajorantSegments {
+            grid,
+            cell: [0; 3],
+            step: [0; 3],
+            next: [f64::INFINITY; 3],
+            delta: [f64::INFINITY; 3],
+            t: t0,
+            t1
+        };
+        for axis in 0..3 {
+            // position in cell units where the ray enters
+            let g = (o[axis] + d[axis]*t0) * resolution[axis];
+            let cell = (g.floor() as i64).clamp(0,resolution[axis] as i64 - 1);
+            segments.cell[axis] = cell;
+            if d[axis] > 0.0 {
+                segments.step[axis] = 1;
+                segments.delta[axis] = 1.0 / (d[axis] * resolution[axis]);
+                segments.next[axis] = t0 + ((cell + 1) as f64 - g) / (d[axis] * resolution[axis]);
+            } else if d[axis] < 0.0 {
+                segments.step[axis] = -1;
+                segments.delta[axis] = -1.0 / (d[axis] * resolution[axis]);
+                segments.next[axis] = t0 + (cell as f64 - g) / (d[axis] * resolution[axis]);
+            }
+        }
+        segments
+    }
+}
+
+impl Iterator for MajorantSegments<'_> {
+    type Item = (f64,f64,f64);
+
+    fn next(&mut self) -> Option<(f64,f64,f64)> {
+        let (mx,my,mz) = self.grid.resolution;
+        let limits = [mx as i64,my as i64,mz as i64];
+        if self.t >= self.t1 || (0..3).any(|a| self.cell[a] < 0 || self.cell[a] >= limits[a]) {
+            return None
+        }
+
+        let majorant = self.grid.at(self.cell[0] as usize,self.cell[1] as usize,self.cell[2] as usize);
+        let axis = (0..3).min_by(|&a,&b| self.next[a].total_cmp(&self.next[b])).unwrap();
+        let start = self.t;
+        let end = self.next[axis].min(self.t1);
+        self.t = end;
+        self.cell[axis] += self.step[axis];
+        self.next[axis] += self.delta[axis];
+        Some((start,end,majorant))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::vector::Vector,
+        sampler::random::RandomSampler
+    };
+
+    // transform placing the unit cube at the box min,max
+    fn place(min: Point,max: Point) -> Matrix {
+        let size = max - min;
+        Matrix::translate(&Vector::new(min.x,min.y,min.z)) * Matrix::scale(size.x,size.y,size.z)
+    }
+
+    // unit cube holding a 2x1x1 grid with densities 0 and 1
+    fn ramp() -> GridMedium {
+        let density = VoxelGrid::new((2,1,1),vec![0.,1.]);
+        GridMedium::new(1.,spectrum::WHITE,0.,density,&place(Point::new(0.,0.,0.),Point::new(1.,1.,1.))).unwrap()
+    }
+
+    // cube from -1 to 1 of constant density
+    fn constant(sigma_t: f64) -> GridMedium {
+        let density = VoxelGrid::new((2,2,2),vec![1.; 8]);
+        GridMedium::new(sigma_t,Spectrum::new(0.5,0.5,0.5),0.,density,&place(Point::new(-1.,-1.,-1.),Point::new(1.,1.,1.))).unwrap()
+    }
+
+    // slab of density one in the middle third along x of a sparse 30x1x1 grid
+    fn slab(sigma_t: f64) -> GridMedium {
+        let density = VoxelGrid::new((30,1,1),(0..30).map(|i| if (10..20).contains(&i) { 1. } else { 0. }).collect());
+        GridMedium::new(sigma_t,spectrum::WHITE,0.,density,&place(Point::new(0.,0.,0.),Point::new(3.,1.,1.))).unwrap()
+    }
+
+    #[test]
+    // density should interpolate between voxel centers
+    fn test_density() {
+        let medium = ramp();
+        assert_eq!(medium.density_at(&Point::new(0.25,0.5,0.5)),0.);
+        assert_eq!(medium.density_at(&Point::new(0.75,0.5,0.5)),1.);
+        assert_eq!(medium.density_at(&Point::new(0.5,0.2,0.9)),0.5);
+        assert_eq!(medium.density_at(&Point::new(0.1,0.5,0.5)),0.);
+        assert_eq!(medium.density_at(&Point::new(2.,0.5,0.5)),0.);
+    }
+
+    #[test]
+    // ratio tracking should average to Beer's law for constant density
+    fn test_tr() {
+        let medium = constant(0.8);
+        let mut sampler = RandomSampler::new(1);
+        // starts outside the box, unnormalized direction
+        let ray = Ray::new(&Point::new(-3.,0.,0.),&Vector::new(2.,0.,0.));
+        let n = 20000;
+        let mut sum = 0.;
+        for _ in 0..n {
+            sum += medium.tr(&ray,f64::INFINITY,&mut sampler).r;
+        }
+        let expected = (-0.8f64 * 2.).exp();
+        assert!((sum / n as f64 - expected).abs() < 0.01,"tr {}",sum / n as f64);
+
+        // rays missing the box are unaffected
+        let miss = Ray::new(&Point::new(-3.,2.,0.),&Vector::new(1.,0.,0.));
+        assert_eq!(medium.tr(&miss,f64::INFINITY,&mut sampler).r,1.);
+    }
+
+    #[test]
+    // delta tracking should scatter with probability 1 - transmittance
+    fn test_sample() {
+        let medium = constant(0.5);
+        let mut sampler = RandomSampler::new(2);
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
+        let n = 20000;
+        let mut scattered = 0;
+        for _ in 0..n {
+            let sample = medium.sample(&ray,f64::INFINITY,&mut sampler);
+            if let Some(mi) = sample.interaction {
+                assert!(mi.p.z > 0. && mi.p.z < 1.);
+                assert_eq!(sample.beta.r,0.5);
+                scattered += 1;
+            }
+        }
+        let expected = 1. - (-0.5f64).exp();
+        assert!((scattered as f64 / n as f64 - expected).abs() < 0.01);
+    }
+
+    #[test]
+    // segments should tile the clipped range and cover the cells in order
+    fn test_majorant_segments() {
+        let medium = slab(1.);
+        let ray = Ray::new(&Point::new(-0.5,0.5,0.5),&Vector::new(1.,0.,0.));
+        let local = medium.world_to_medium * ray;
+        let (t0,t1) = clip_unit_cube(&local,f64::INFINITY).unwrap();
+        assert!((t0 - 0.5).abs() < 1e-12 && (t1 - 3.5).abs() < 1e-12);
+        let segments: Vec<(f64,f64,f64)> = MajorantSegments::new(&medium.majorants,&local,t0,t1).collect();
+        assert_eq!(segments.len(),16);
+        assert_eq!(segments[0].0,t0);
+        assert!((segments[15].1 - t1).abs() < 1e-12);
+        for pair in segments.windows(2) {
+            assert!((pair[0].1 - pair[1].0).abs() < 1e-12);
+        }
+        // cells well outside the slab are empty
+        assert_eq!(segments[0].2,0.);
+        assert_eq!(segments[8].2,1.);
+        assert_eq!(segments[15].2,0.);
+    }
+
+    #[test]
+    // tracking through empty majorant cells should stay unbiased
+    // and transforms should place the grid
+    fn test_tr_sparse() {
+        let medium = slab(0.7);
+        let mut sampler = RandomSampler::new(3);
+        let ray = Ray::new(&Point::new(-1.,0.3,0.6),&Vector::new(1.,0.,0.));
+        let n = 20000;
+        let mut sum = 0.;
+        for _ in 0..n {
+            sum += medium.tr(&ray,f64::INFINITY,&mut sampler).g;
+        }
+        // full density between the outer voxel centers plus two linear ramps of one voxel
+        let expected = f64::exp(-0.7 * (0.9 + 0.1));
+        assert!((sum / n as f64 - expected).abs() < 0.01,"tr {} expected {}",sum / n as f64,expected);
+        assert_eq!(medium.density_at(&Point::new(1.5,0.5,0.5)),1.);
+        assert_eq!(medium.density_at(&Point::new(0.5,0.5,0.5)),0.);
+    }
+
+    #[test]
+    // hot absorbing voxels should glow, cold or scattering ones should not
+    fn test_emission() {
+        let density = VoxelGrid::new((1,1,1),vec![1.]);
+        let mut medium = GridMedium::new(5.,spectrum::BLACK,0.,density,&place(Point::new(0.,0.,0.),Point::new(1.,1.,1.))).unwrap();
+        let mut sampler = RandomSampler::new(4);
+        let ray = Ray::new(&Point::new(0.5,0.5,-1.),&Vector::new(0.,0.,1.));
+        assert!(medium.sample(&ray,f64::INFINITY,&mut sampler).le.is_black());
+
+        medium.set_temperature(VoxelGrid::new((1,1,1),vec![1500.]),1.);
+        let sample = medium.sample(&ray,f64::INFINITY,&mut sampler);
+        assert!(sample.interaction.is_some());
+        assert!(sample.beta.is_black());
+        assert!(sample.le.r > sample.le.b);
+        assert!((sample.le.r - blackbody(1500.).r).abs() < 1e-9);
+
+        medium.albedo = spectrum::WHITE;
+        assert!(medium.sample(&ray,f64::INFINITY,&mut sampler).le.is_black());
+    }
+}
@@ -0,0 +1,144 @@
+use super::{
+    traits::{Medium,MediumSample,MediumInteraction},
+    phase::HenyeyGreenstein
+};
+use crate::{
+    image::spectrum::{self,Spectrum},
+    math::{ray::Ray,traits::Len},
+    sampler::traits::Sampler
+};
+
+/// # HomogeneousMedium
+/// Medium with constant coefficients everywhere, transmittance
+/// follows Beer's law and distances are sampled analytically
+///
+/// # Parameters
+/// * sigma_a (absorption coefficient per unit length)
+/// * sigma_s (scattering coefficient per unit length)
+/// * phase (phase function of scattering events)
+pub struct HomogeneousMedium {
+    pub sigma_a: Spectrum,
+    pub sigma_s: Spectrum,
+    pub phase: HenyeyGreenstein
+}
+
+/// Medium trait
+impl Medium for HomogeneousMedium {
+    fn tr(&self,ray: &Ray,tmax: f64,_sampler: &mut dyn Sampler) -> Spectrum {
+        (self.sigma_t() * -distance(ray,tmax)).exp()
+    }
+
+    /// Distances are sampled for one randomly chosen channel and
+    /// weighted by the density averaged over all channels
+    fn sample(&self,ray: &Ray,tmax: f64,sampler: &mut dyn Sampler) -> MediumSample {
+        let sigma_t = self.sigma_t();
+        let channel = ((sampler.get_1d() * 3.0) as usize).min(2);
+        let length = ray.d.len();
+        let dist = -(1.0 - sampler.get_1d()).ln() / sigma_t[channel];
+        let t = f64::min(dist / length,tmax);
+        let sampled = t < tmax;
+
+        let tr = (sigma_t * -distance(ray,t)).exp();
+        let density = if sampled { sigma_t * tr } else { tr };
+        let pdf = density.average();
+        if pdf == 0.0 {
+            return MediumSample {beta: spectrum::BLACK,le: spectrum::BLACK,interaction: None}
+        }
+
+        if sampled {
+            MediumSample {
+                beta: tr * self.sigma_s / pdf,
+                le: spectrum::BLACK,
+                interaction: Some(MediumInteraction {
+                    p: ray.at(t),
+                    wo: -ray.d * (1.0 / length),
+                    phase: self.phase
+                })
+            }
+        } else {
+            MediumSample {beta: tr / pdf,le: spectrum::BLACK,interaction: None}
+        }
+    }
+}
+
+impl HomogeneousMedium {
+    /// Construct medium from its coefficients and phase asymmetry g
+    pub fn new(sigma_a: Spectrum,sigma_s: Spectrum,g: f64) -> HomogeneousMedium {
+        HomogeneousMedium {
+            sigma_a,
+            sigma_s,
+            phase: HenyeyGreenstein::new(g)
+        }
+    }
+
+    /// Extinction coefficient
+    pub fn sigma_t(&self) -> Spectrum {
+        self.sigma_a + self.sigma_s
+    }
+}
+
+/// World space length of the ray up to parameter t, kept finite
+/// so media without extinction give zero optical depth
+fn distance(ray: &Ray,t: f64) -> f64 {
+    f64::min(t * ray.d.len(),f64::MAX)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::{point::Point,vector::Vector},
+        sampler::random::RandomSampler
+    };
+
+    #[test]
+    // transmittance should follow Beer's law in world space distance
+    fn test_tr() {
+        let medium = HomogeneousMedium::new(Spectrum::new(0.5,1.,0.),Spectrum::new(0.5,0.,0.),0.);
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,2.,0.));
+        let mut sampler = RandomSampler::new(1);
+        let tr = medium.tr(&ray,1.,&mut sampler);
+        assert!((tr.r - (-2f64).exp()).abs() < 1e-12);
+        assert!((tr.g - (-2f64).exp()).abs() < 1e-12);
+        assert_eq!(tr.b,1.);
+        assert_eq!(medium.tr(&ray,f64::INFINITY,&mut sampler).b,1.);
+    }
+
+    #[test]
+    // weights of passing and scattering samples should match
+    // transmittance and single scattering albedo times absorbed fraction
+    fn test_sample() {
+        let sigma_a = Spectrum::new(0.2,0.5,1.);
+        let sigma_s = Spectrum::new(0.8,0.5,1.);
+        let medium = HomogeneousMedium::new(sigma_a,sigma_s,0.);
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(1.,0.,0.));
+        let tmax = 1.5;
+        let mut sampler = RandomSampler::new(2);
+
+        let n = 100000;
+        let mut passed = spectrum::BLACK;
+        let mut scattered = spectrum::BLACK;
+        for _ in 0..n {
+            let sample = medium.sample(&ray,tmax,&mut sampler);
+            match sample.interaction {
+                Some(mi) => {
+                    assert!(mi.p.x < tmax);
+                    assert_eq!(mi.wo.x,-1.);
+                    scattered += sample.beta;
+                },
+                None => passed += sample.beta
+            }
+        }
+        let sigma_t = medium.sigma_t();
+        for c in 0..3 {
+            let tr = (-sigma_t[c] * tmax).exp();
+            let expected = sigma_s[c] / sigma_t[c] * (1. - tr);
+            assert!((passed[c] / n as f64 - tr).abs() < 0.01,"channel {} passed",c);
+            assert!((scattered[c] / n as f64 - expected).abs() < 0.01,"channel {} scattered",c);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::f64::consts::PI;
+
+use crate::math::{
+    vector::Vector,
+    normal::Normal,
+    frame::Frame,
+    traits::Dot
+};
+
+/// # HenyeyGreenstein
+/// Phase function with a single parameter controlling the
+/// preferred scattering direction, all directions are unit vectors
+/// pointing away from the scattering point like for BSDFs
+///
+/// # Parameters
+/// * g (mean cosine of the scattering angle, positive scatters forward)
+#[derive(Clone,Copy)]
+pub struct HenyeyGreenstein {
+    pub g: f64
+}
+
+impl HenyeyGreenstein {
+    /// Construct phase function, g is kept inside (-1,1)
+    pub fn new(g: f64) -> HenyeyGreenstein {
+        HenyeyGreenstein {
+            g: g.clamp(-0.999,0.999)
+        }
+    }
+
+    /// Value of the phase function for light arriving from wi
+    /// and leaving towards wo, it is also the sampling density
+    pub fn p(&self,wo: &Vector,wi: &Vector) -> f64 {
+        hg(-wo.dot(*wi),self.g)
+    }
+
+    /// Sample wi given wo, returns (wi,pdf)
+    pub fn sample_p(&self,wo: &Vector,u: (f64,f64)) -> (Vector,f64) {
+        // cosine between the propagation directions -wo and wi
+        let g = self.g;
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0*u.0
+        } else {
+            let s = (1.0 - g*g) / (1.0 - g + 2.0*g*u.0);
+            (1.0 + g*g - s*s) / (2.0*g)
+        }.clamp(-1.0,1.0);
+        let sin_theta = f64::sqrt(f64::max(0.0,1.0 - cos_theta*cos_theta));
+        let phi = 2.0*PI*u.1;
+
+        let frame = Frame::from_normal(&Normal::from(-*wo));
+        let wi = frame.to_world(&Vector::new(sin_theta*phi.cos(),sin_theta*phi.sin(),cos_theta));
+        (wi,hg(cos_theta,g))
+    }
+}
+
+/// Henyey-Greenstein density for the cosine of the scattering angle
+fn hg(cos_theta: f64,g: f64) -> f64 {
+    let denom = 1.0 + g*g - 2.0*g*cos_theta;
+    (1.0 - g*g) / (4.0*PI * denom * denom.sqrt())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::sampling::{uniform_sample_sphere,uniform_sphere_pdf};
+
+    #[test]
+    // phase function should integrate to one over the sphere
+    fn test_normalized() {
+        let wo = Vector::new(0.,0.,1.);
+        for g in [-0.7,0.,0.3,0.6] {
+            let phase = HenyeyGreenstein::new(g);
+            let n = 200;
+            let mut sum = 0.;
+            for i in 0..n {
+                for j in 0..n {
+                    let u = ((i as f64 + 0.5) / n as f64,(j as f64 + 0.5) / n as f64);
+                    sum += phase.p(&wo,&uniform_sample_sphere(u)) / uniform_sphere_pdf();
+                }
+            }
+            let integral = sum / (n*n) as f64;
+            assert!((integral - 1.).abs() < 0.01,"g {} integral {}",g,integral);
+        }
+    }
+
+    #[test]
+    // samples should have mean cosine g and report their density
+    fn test_sample_p() {
+        let wo = Vector::new(0.,1.,0.);
+        let phase = HenyeyGreenstein::new(0.6);
+        let n = 100;
+        let mut mean_cos = 0.;
+        for i in 0..n {
+            for j in 0..n {
+                let u = ((i as f64 + 0.5) / n as f64,(j as f64 + 0.5) / n as f64);
+                let (wi,pdf) = phase.sample_p(&wo,u);
+                assert!((pdf - phase.p(&wo,&wi)).abs() < 1e-9 * pdf.max(1.));
+                mean_cos += -wo.dot(wi);
+            }
+        }
+        mean_cos /= (n*n) as f64;
+        assert!((mean_cos - 0.6).abs() < 0.01,"mean cosine {}",mean_cos);
+    }
+}
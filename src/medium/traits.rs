@@ -0,0 +1,72 @@
+use super::phase::HenyeyGreenstein;
+use crate::{
+    image::spectrum::Spectrum,
+    math::{point::Point,vector::Vector,normal::Normal,ray::Ray,traits::Dot},
+    sampler::traits::Sampler
+};
+
+/// # MediumInteraction
+/// Scattering event sampled inside a participating medium
+///
+/// # Parameters
+/// * p (scattering point)
+/// * wo (unit direction back towards the ray origin)
+/// * phase (phase function at p)
+#[derive(Clone,Copy)]
+pub struct MediumInteraction {
+    pub p: Point,
+    pub wo: Vector,
+    pub phase: HenyeyGreenstein
+}
+
+/// # MediumSample
+/// Outcome of sampling a distance along a ray through a medium
+///
+/// # Parameters
+/// * beta (transmittance, times the scattering coefficient at an interaction, divided by the sampling density)
+/// * le (radiance emitted by the medium towards the ray origin, weighted like beta but added before it is applied)
+/// * interaction (scattering event before tmax, None if the ray passed through)
+pub struct MediumSample {
+    pub beta: Spectrum,
+    pub le: Spectrum,
+    pub interaction: Option<MediumInteraction>
+}
+
+/// # MediumInterface
+/// Media on both sides of a primitive's surface, referenced by
+/// their index in the world, None is vacuum
+///
+/// # Parameters
+/// * inside (medium on the side the normal points away from)
+/// * outside (medium on the side the normal points towards)
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub struct MediumInterface {
+    pub inside: Option<usize>,
+    pub outside: Option<usize>
+}
+
+impl MediumInterface {
+    /// Construct interface between two media
+    pub fn new(inside: Option<usize>,outside: Option<usize>) -> MediumInterface {
+        MediumInterface {inside,outside}
+    }
+
+    /// Medium entered by a direction w leaving a surface with normal n
+    pub fn medium(&self,n: &Normal,w: &Vector) -> Option<usize> {
+        if w.dot(*n) > 0.0 {
+            self.outside
+        } else {
+            self.inside
+        }
+    }
+}
+
+/// Volume absorbing and scattering light along rays, distances are
+/// measured in ray parameter so ray directions need not be unit length
+pub trait Medium: Send + Sync {
+    /// Transmittance along ray between parameters 0 and tmax
+    fn tr(&self,ray: &Ray,tmax: f64,sampler: &mut dyn Sampler) -> Spectrum;
+
+    /// Sample a scattering event along ray before tmax
+    fn sample(&self,ray: &Ray,tmax: f64,sampler: &mut dyn Sampler) -> MediumSample;
+}
@@ -0,0 +1,260 @@
+use crate::math::point::Point;
+
+/// File signature of the voxel grid format
+const MAGIC: &[u8; 4] = b"VOXG";
+
+/// # VoxelGrid
+/// Dense scalar grid over the unit cube, values sit at voxel
+/// centers and are trilinearly interpolated
+///
+/// Grids are stored on disk as little endian binary:
+/// * 4 bytes "VOXG"
+/// * 3 u32 resolution nx, ny, nz
+/// * nx*ny*nz f32 values, x varies fastest then y then z
+///
+/// Headerless dumps holding only the f32 values are read with load_raw
+///
+/// # Parameters
+/// * resolution (voxel count along x, y and z)
+/// * values (x varies fastest then y then z)
+#[derive(Clone)]
+pub struct VoxelGrid {
+    pub resolution: (usize,usize,usize),
+    pub values: Vec<f64>
+}
+
+impl VoxelGrid {
+    /// Construct grid, values must hold one entry per voxel
+    pub fn new(resolution: (usize,usize,usize),values: Vec<f64>) -> VoxelGrid {
+        let (nx,ny,nz) = resolution;
+        assert_eq!(values.len(),nx*ny*nz,"voxel count does not match the resolution");
+        assert!(nx > 0 && ny > 0 && nz > 0,"empty voxel grid");
+        VoxelGrid {resolution,values}
+    }
+
+    /// Load grid from a file with header
+    pub fn load(path: &str) -> Result<VoxelGrid,String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("{}: {}",path,e))?;
+        VoxelGrid::from_bytes(&bytes)
+    }
+
+    /// Load headerless f32 values of the given resolution
+    pub fn load_raw(path: &str,resolution: (usize,usize,usize)) -> Result<VoxelGrid,String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("{}: {}",path,e))?;
+        VoxelGrid::from_raw(&bytes,resolution)
+    }
+
+    /// Save grid with header
+    pub fn save(&self,path: &str) -> Result<(),String> {
+        std::fs::write(path,self.to_bytes()).map_err(|e| format!("{}: {}",path,e))
+    }
+
+    /// Decode grid with header
+    pub fn from_bytes(bytes: &[u8]) -> Result<VoxelGrid,String> {
+        if bytes.len() < 16 || &bytes[0..4] != MAGIC {
+            return Err("not a voxel grid file".to_string())
+        }
+        let dim = |i: usize| u32::from_le_bytes([bytes[i],bytes[i + 1],bytes[i + 2],bytes[i + 3]]) as usize;
+        VoxelGrid::from_raw(&bytes[16..],(dim(4),dim(8),dim(12)))
+    }
+
+    /// Decode headerless f32 values
+    pub fn from_raw(bytes: &[u8],resolution: (usize,usize,usize)) -> Result<VoxelGrid,String> {
+        let (nx,ny,nz) = resolution;
+        let overflow = || format!("voxel grid resolution {}x{}x{} is too large",nx,ny,nz);
+        let count = nx.checked_mul(ny).and_then(|n| n.checked_mul(nz)).ok_or_else(overflow)?;
+        if count == 0 {
+            return Err("empty voxel grid".to_string())
+        }
+        if bytes.len() != count.checked_mul(4).ok_or_else(overflow)? {
+            return Err(format!("expected {} voxels, found {} bytes",count,bytes.len()))
+        }
+        let values = bytes.chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0],b[1],b[2],b[3]]) as f64)
+            .collect();
+        Ok(VoxelGrid {resolution,values})
+    }
+
+    /// Encode grid with header
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (nx,ny,nz) = self.resolution;
+        let mut bytes = MAGIC.to_vec();
+        for n in [nx,ny,nz] {
+            bytes.extend_from_slice(&(n as u32).to_le_bytes());
+        }
+        for value in &self.values {
+            bytes.extend_from_slice(&(*value as f32).to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Voxel value, indices outside the grid are clamped to its border
+    pub fn at(&self,x: i64,y: i64,z: i64) -> f64 {
+        let (nx,ny,nz) = self.resolution;
+        let x = x.clamp(0,nx as i64 - 1) as usize;
+        let y = y.clamp(0,ny as i64 - 1) as usize;
+        let z = z.clamp(0,nz as i64 - 1) as usize;
+        self.values[(z*ny + y)*nx + x]
+    }
+
+    /// Trilinearly interpolated value at a point of the unit cube,
+    /// zero outside it
+    pub fn lookup(&self,p: &Point) -> f64 {
+        if [p.x,p.y,p.z].iter().any(|c| !(0.0..=1.0).contains(c)) {
+            return 0.0
+        }
+
+        // continuous voxel coordinates with voxel centers at integers
+        let (nx,ny,nz) = self.resolution;
+        let gx = p.x * nx as f64 - 0.5;
+        let gy = p.y * ny as f64 - 0.5;
+        let gz = p.z * nz as f64 - 0.5;
+        let (ix,iy,iz) = (gx.floor(),gy.floor(),gz.floor());
+        let (dx,dy,dz) = (gx - ix,gy - iy,gz - iz);
+        let (ix,iy,iz) = (ix as i64,iy as i64,iz as i64);
+
+        let lerp = |t: f64,a: f64,b: f64| (1.0 - t)*a + t*b;
+        let d00 = lerp(dx,self.at(ix,iy,iz),self.at(ix + 1,iy,iz));
+        let d10 = lerp(dx,self.at(ix,iy + 1,iz),self.at(ix + 1,iy + 1,iz));
+        let d01 = lerp(dx,self.at(ix,iy,iz + 1),self.at(ix + 1,iy,iz + 1));
+        let d11 = lerp(dx,self.at(ix,iy + 1,iz + 1),self.at(ix + 1,iy + 1,iz + 1));
+        lerp(dz,lerp(dy,d00,d10),lerp(dy,d01,d11))
+    }
+
+    /// Largest value
+    pub fn max_value(&self) -> f64 {
+        self.values.iter().fold(f64::NEG_INFINITY,|m,&v| m.max(v))
+    }
+}
+
+/// # MajorantGrid
+/// Coarse grid over the unit cube bounding a voxel grid from above,
+/// each cell holds the largest value interpolation can reach inside it
+///
+/// # Parameters
+/// * resolution (cell count along x, y and z)
+/// * values (x varies fastest then y then z)
+pub struct MajorantGrid {
+    pub resolution: (usize,usize,usize),
+    pub values: Vec<f64>
+}
+
+impl MajorantGrid {
+    /// Build majorants of grid with at most max_resolution cells per axis
+    pub fn new(grid: &VoxelGrid,max_resolution: usize) -> MajorantGrid {
+        let (nx,ny,nz) = grid.resolution;
+        let resolution = (nx.min(max_resolution),ny.min(max_resolution),nz.min(max_resolution));
+        let (mx,my,mz) = resolution;
+
+        // voxels whose centers can be interpolated inside a cell, one extra on each side
+        let range = |i: usize,m: usize,n: usize| -> (i64,i64) {
+            let lo = (i as f64 / m as f64 * n as f64 - 0.5).floor() as i64;
+            let hi = ((i + 1) as f64 / m as f64 * n as f64 - 0.5).floor() as i64 + 1;
+            (lo,hi)
+        };
+        let mut values = Vec::with_capacity(mx*my*mz);
+        for z in 0..mz {
+            let (z0,z1) = range(z,mz,nz);
+            for y in 0..my {
+                let (y0,y1) = range(y,my,ny);
+                for x in 0..mx {
+                    let (x0,x1) = range(x,mx,nx);
+                    let mut max = 0.0f64;
+                    for vz in z0..=z1 {
+                        for vy in y0..=y1 {
+                            for vx in x0..=x1 {
+                                max = max.max(grid.at(vx,vy,vz));
+                            }
+                        }
+                    }
+                    values.push(max);
+                }
+            }
+        }
+        MajorantGrid {resolution,values}
+    }
+
+    /// Majorant of cell (x,y,z)
+    pub fn at(&self,x: usize,y: usize,z: usize) -> f64 {
+        let (mx,my,_) = self.resolution;
+        self.values[(z*my + y)*mx + x]
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 4x3x2 grid with distinct values
+    fn test_grid() -> VoxelGrid {
+        VoxelGrid::new((4,3,2),(0..24).map(|i| (i as f64 * 0.37).fract()).collect())
+    }
+
+    #[test]
+    // encoding should round trip, malformed data should be rejected
+    fn test_bytes() {
+        let grid = test_grid();
+        let bytes = grid.to_bytes();
+        assert_eq!(bytes.len(),16 + 24*4);
+        let decoded = VoxelGrid::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.resolution,(4,3,2));
+        for (a,b) in decoded.values.iter().zip(grid.values.iter()) {
+            assert!((a - b).abs() < 1e-6);
+        }
+        assert_eq!(VoxelGrid::from_raw(&bytes[16..],(4,3,2)).unwrap().values.len(),24);
+        assert!(VoxelGrid::from_bytes(&bytes[..40]).is_err());
+        assert!(VoxelGrid::from_bytes(b"nope, not a grid").is_err());
+        assert!(VoxelGrid::from_raw(&bytes[16..],(4,3,3)).is_err());
+        let mut huge = MAGIC.to_vec();
+        huge.extend_from_slice(&[0xff;12]);
+        assert!(VoxelGrid::from_bytes(&huge).is_err());
+        assert!(VoxelGrid::from_raw(&[],(usize::MAX,2,1)).is_err());
+        assert!(VoxelGrid::from_raw(&[],(usize::MAX / 2,1,1)).is_err());
+    }
+
+    #[test]
+    // should save and load from disk
+    fn test_save_load() {
+        let path = std::env::temp_dir().join("voxel_grid_test.vox");
+        let path = path.to_str().unwrap();
+        test_grid().save(path).unwrap();
+        let grid = VoxelGrid::load(path).unwrap();
+        assert_eq!(grid.values.len(),24);
+        std::fs::remove_file(path).unwrap();
+        assert!(VoxelGrid::load(path).is_err());
+    }
+
+    #[test]
+    // lookups should hit voxel centers exactly and blend in between
+    fn test_lookup() {
+        let grid = VoxelGrid::new((2,1,1),vec![0.,1.]);
+        assert_eq!(grid.lookup(&Point::new(0.25,0.5,0.5)),0.);
+        assert_eq!(grid.lookup(&Point::new(0.75,0.5,0.5)),1.);
+        assert_eq!(grid.lookup(&Point::new(0.5,0.2,0.9)),0.5);
+        assert_eq!(grid.lookup(&Point::new(0.1,0.5,0.5)),0.);
+        assert_eq!(grid.lookup(&Point::new(1.5,0.5,0.5)),0.);
+    }
+
+    #[test]
+    // majorants should bound every interpolated value in their cell
+    fn test_majorant() {
+        let grid = test_grid();
+        let majorants = MajorantGrid::new(&grid,2);
+        assert_eq!(majorants.resolution,(2,2,2));
+        let n = 40;
+        for i in 0..n {
+            for j in 0..n {
+                for k in 0..n {
+                    let p = Point::new((i as f64 + 0.5) / n as f64,(j as f64 + 0.5) / n as f64,(k as f64 + 0.5) / n as f64);
+                    let cell = ((p.x * 2.) as usize,(p.y * 2.) as usize,(p.z * 2.) as usize);
+                    assert!(grid.lookup(&p) <= majorants.at(cell.0,cell.1,cell.2) + 1e-12);
+                }
+            }
+        }
+        assert!(majorants.values.iter().all(|&m| m <= grid.max_value()));
+    }
+}
@@ -0,0 +1,5 @@
+// sampler
+pub mod random;
+pub mod mlt;
+
+pub mod traits;
@@ -0,0 +1,201 @@
+use std::f64::consts::PI;
+
+use rand::{Rng,SeedableRng,rngs::StdRng};
+
+use super::traits::Sampler;
+
+/// # PrimarySample
+/// One coordinate of the primary sample space vector
+///
+/// # Parameters
+/// * value (current value in [0,1))
+/// * last_modified (iteration of the last mutation)
+/// * value_backup (value before the current iteration)
+/// * modified_backup (last_modified before the current iteration)
+#[derive(Clone,Copy,Default)]
+struct PrimarySample {
+    value: f64,
+    last_modified: usize,
+    value_backup: f64,
+    modified_backup: usize
+}
+
+/// # MltSampler
+/// Primary sample space sampler driving a Markov chain (Kelemen et al.),
+/// samples are mutated lazily when requested: large steps draw fresh
+/// uniform values, small steps perturb the previous value
+///
+/// Samples are split into interleaved streams so the consumers of each
+/// stream always read the same coordinates however many samples the
+/// others take
+///
+/// # Parameters
+/// * sigma (standard deviation of small step perturbations)
+/// * large_step_probability (chance that an iteration is a large step)
+/// * stream_count (number of interleaved streams)
+pub struct MltSampler {
+    pub sigma: f64,
+    pub large_step_probability: f64,
+    pub stream_count: usize,
+    rng: StdRng,
+    x: Vec<PrimarySample>,
+    iteration: usize,
+    large_step: bool,
+    last_large_step: usize,
+    stream: usize,
+    sample: usize
+}
+
+/// Sampler trait
+impl Sampler for MltSampler {
+    fn get_1d(&mut self) -> f64 {
+        let index = self.stream + self.sample * self.stream_count;
+        self.sample += 1;
+        self.ensure_ready(index);
+        self.x[index].value
+    }
+}
+
+impl MltSampler {
+    /// Construct sampler, the first iteration is always a large step
+    pub fn new(seed: u64,sigma: f64,large_step_probability: f64,stream_count: usize) -> MltSampler {
+        MltSampler {
+            sigma,
+            large_step_probability,
+            stream_count,
+            rng: StdRng::seed_from_u64(seed),
+            x: vec![],
+            iteration: 0,
+            large_step: true,
+            last_large_step: 0,
+            stream: 0,
+            sample: 0
+        }
+    }
+
+    /// Begin proposing a new state
+    pub fn start_iteration(&mut self) {
+        self.iteration += 1;
+        self.large_step = self.rng.gen::<f64>() < self.large_step_probability;
+    }
+
+    /// Read samples from the given stream, starting at its first sample
+    pub fn start_stream(&mut self,stream: usize) {
+        self.stream = stream;
+        self.sample = 0;
+    }
+
+    /// Keep the proposed state
+    pub fn accept(&mut self) {
+        if self.large_step {
+            self.last_large_step = self.iteration;
+        }
+    }
+
+    /// Return to the state before the current iteration
+    pub fn reject(&mut self) {
+        for xi in self.x.iter_mut() {
+            if xi.last_modified == self.iteration {
+                xi.value = xi.value_backup;
+                xi.last_modified = xi.modified_backup;
+            }
+        }
+        self.iteration -= 1;
+    }
+
+    /// Bring the sample at index up to the current iteration
+    fn ensure_ready(&mut self,index: usize) {
+        if index >= self.x.len() {
+            self.x.resize(index + 1,PrimarySample::default());
+        }
+        let xi = &mut self.x[index];
+
+        // reset to a uniform value if it missed the last accepted large step
+        if xi.last_modified < self.last_large_step {
+            xi.value = self.rng.gen::<f64>();
+            xi.last_modified = self.last_large_step;
+        }
+
+        xi.value_backup = xi.value;
+        xi.modified_backup = xi.last_modified;
+        if self.large_step {
+            xi.value = self.rng.gen::<f64>();
+        } else {
+            // catch up on the small steps skipped since the last modification
+            let steps = (self.iteration - xi.last_modified) as f64;
+            let u1 = 1.0 - self.rng.gen::<f64>();
+            let u2 = self.rng.gen::<f64>();
+            let normal = f64::sqrt(-2.0 * u1.ln()) * f64::cos(2.0*PI*u2);
+            xi.value += normal * self.sigma * steps.sqrt();
+            xi.value -= xi.value.floor();
+            if xi.value >= 1.0 {
+                xi.value = 0.0;
+            }
+        }
+        xi.last_modified = self.iteration;
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(sampler: &mut MltSampler,n: usize) -> Vec<f64> {
+        sampler.start_stream(0);
+        (0..n).map(|_| sampler.get_1d()).collect()
+    }
+
+    #[test]
+    // small steps should stay close, rejection should restore the state
+    fn test_small_step_and_reject() {
+        let mut sampler = MltSampler::new(1,0.01,0.,2);
+        let initial = read(&mut sampler,4);
+        sampler.accept();
+
+        sampler.start_iteration();
+        let proposed = read(&mut sampler,4);
+        for (a,b) in initial.iter().zip(proposed.iter()) {
+            let d = (a - b).abs();
+            assert!(d > 0. && f64::min(d,1. - d) < 0.1);
+        }
+        sampler.reject();
+
+        // the rejected values are gone, the next proposal starts from the initial state
+        sampler.start_iteration();
+        let again = read(&mut sampler,4);
+        assert!(again.iter().zip(initial.iter()).all(|(a,b)| a != b));
+        assert!(again.iter().zip(proposed.iter()).all(|(a,b)| a != b));
+    }
+
+    #[test]
+    // streams should interleave so each reads its own coordinates
+    fn test_streams() {
+        let mut sampler = MltSampler::new(2,0.01,1.,3);
+        sampler.start_stream(1);
+        let a = sampler.get_1d();
+        sampler.start_stream(0);
+        sampler.get_1d();
+        sampler.get_1d();
+        // stream 0 used coordinates 0 and 3, stream 1 coordinate 1
+        assert_eq!(sampler.x.len(),4);
+        assert_eq!(sampler.x[1].value,a);
+        assert_eq!(sampler.x[2].last_modified,0);
+    }
+
+    #[test]
+    // accepted large steps should replace every value
+    fn test_large_step() {
+        let mut sampler = MltSampler::new(3,0.01,1.,1);
+        let initial = read(&mut sampler,3);
+        sampler.accept();
+        sampler.start_iteration();
+        let proposed = read(&mut sampler,3);
+        sampler.accept();
+        assert!(initial.iter().zip(proposed.iter()).all(|(a,b)| a != b));
+        assert!(proposed.iter().all(|u| (0. ..1.).contains(u)));
+    }
+}
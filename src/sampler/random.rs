@@ -0,0 +1,47 @@
+use rand::{Rng,SeedableRng,rngs::StdRng};
+
+use super::traits::Sampler;
+
+/// # RandomSampler
+/// Independent uniform samples from a seeded generator,
+/// the same seed always produces the same sequence
+pub struct RandomSampler {
+    rng: StdRng
+}
+
+/// Sampler trait
+impl Sampler for RandomSampler {
+    fn get_1d(&mut self) -> f64 {
+        self.rng.gen::<f64>()
+    }
+}
+
+impl RandomSampler {
+    /// Construct sampler with given seed
+    pub fn new(seed: u64) -> RandomSampler {
+        RandomSampler {
+            rng: StdRng::seed_from_u64(seed)
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // samples should be in [0,1) and repeatable for a given seed
+    fn test_get_1d() {
+        let mut a = RandomSampler::new(7);
+        let mut b = RandomSampler::new(7);
+        for _ in 0..100 {
+            let u = a.get_1d();
+            assert!((0. ..1.).contains(&u));
+            assert_eq!(u,b.get_1d());
+        }
+    }
+}
@@ -0,0 +1,12 @@
+/// Source of sample values in [0,1) consumed by integrators
+pub trait Sampler {
+    /// Next 1D sample
+    fn get_1d(&mut self) -> f64;
+
+    /// Next 2D sample
+    fn get_2d(&mut self) -> (f64,f64) {
+        let u = self.get_1d();
+        let v = self.get_1d();
+        (u,v)
+    }
+}
@@ -2,5 +2,26 @@
 pub mod world;
 pub mod sphere;
 pub mod plane;
+pub mod disk;
+pub mod cylinder;
+pub mod cone;
+pub mod paraboloid;
+pub mod hyperboloid;
+pub mod torus;
+pub mod cuboid;
+pub mod rectangle;
+pub mod bilinear;
+pub mod csg;
+pub mod implicit;
+pub mod triangle;
+pub mod heightfield;
+pub mod curve;
+pub mod mesh;
+pub mod subdivision;
+pub mod quadric;
+pub mod transform;
+pub mod interaction;
+pub mod camera;
+pub mod cornell;
 
-mod traits;
+pub mod traits;
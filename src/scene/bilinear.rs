@@ -0,0 +1,301 @@
+use super::{
+    traits::Primitive,
+    interaction::SurfaceInteraction
+};
+use crate::math::{
+    point::Point,
+    vector::Vector,
+    normal::Normal,
+    ray::Ray,
+    traits::{Cross,Dot,Len,Normalize}
+};
+
+/// Number of intervals tabulating the area along u
+const AREA_INTERVALS: usize = 32;
+
+/// Simpson intervals integrating the area density across v
+const V_INTERVALS: usize = 16;
+
+/// # BilinearPatch
+/// Surface interpolating four arbitrary corners, p(u,v) = lerp of the
+/// edges p00 p10 and p01 p11. The normal dp/du x dp/dv = a + u b + v c is
+/// affine in u and v, so the area density is the square root of a
+/// quadratic, tabulated along u for sampling
+///
+/// # Parameters
+/// * p00 (corner at uv (0,0))
+/// * p10 (corner at uv (1,0))
+/// * p01 (corner at uv (0,1))
+/// * p11 (corner at uv (1,1))
+pub struct BilinearPatch {
+    pub p00: Point,
+    pub p10: Point,
+    pub p01: Point,
+    pub p11: Point,
+    a: Vector,
+    b: Vector,
+    c: Vector,
+    cdf: Vec<f64>
+}
+
+/// Primitive trait
+impl Primitive for BilinearPatch {
+    fn intersect(&self,ray: &Ray,tmax: f64) -> Option<SurfaceInteraction> {
+        let (t,u,v) = intersect_patch(&self.p00,&self.p10,&self.p01,&self.p11,ray,tmax)?;
+        let (dpdu,dpdv) = self.derivatives(u,v);
+        Some(SurfaceInteraction::new(
+            ray.at(t),
+            self.normal(u,v)?,
+            (u,v),
+            dpdu,
+            dpdv,
+            t,
+            (-ray.d).normalize().ok()?
+        ))
+    }
+
+    fn area(&self) -> f64 {
+        self.cdf[AREA_INTERVALS]
+    }
+
+    fn sample(&self,u: (f64,f64)) -> Option<(Point,Normal)> {
+        let (s,t) = if self.b.len() == 0.0 && self.c.len() == 0.0 {
+            // flat parallelogram, the density is constant
+            u
+        } else {
+            // marginal in u from the table, then the conditional in v
+            let target = u.0 * self.cdf[AREA_INTERVALS];
+            let i = (self.cdf.partition_point(|&c| c <= target).max(1) - 1).min(AREA_INTERVALS - 1);
+            let du = 1.0 / AREA_INTERVALS as f64;
+            let s = invert_integral(|x| self.marginal(x),i as f64 * du,(i + 1) as f64 * du,target - self.cdf[i]);
+            let density = |y: f64| self.density(s,y);
+            let total = simpson(density,0.0,1.0,V_INTERVALS);
+            (s,invert_integral(density,0.0,1.0,u.1 * total))
+        };
+        Some((self.point(s,t),self.normal(s,t)?))
+    }
+}
+
+impl BilinearPatch {
+    /// Construct patch from its four corners
+    pub fn new(p00: Point,p10: Point,p01: Point,p11: Point) -> BilinearPatch {
+        let e10 = p10 - p00;
+        let e00 = p01 - p00;
+        let q = (p11 - p10) - e00;
+        let mut patch = BilinearPatch {
+            p00,
+            p10,
+            p01,
+            p11,
+            a: e10.cross(e00),
+            b: e10.cross(q),
+            c: q.cross(e00),
+            cdf: vec![]
+        };
+        let du = 1.0 / AREA_INTERVALS as f64;
+        let mut cdf = vec![0.0; AREA_INTERVALS + 1];
+        for i in 0..AREA_INTERVALS {
+            cdf[i + 1] = cdf[i] + simpson(|x| patch.marginal(x),i as f64 * du,(i + 1) as f64 * du,4);
+        }
+        patch.cdf = cdf;
+        patch
+    }
+
+    /// Point on the patch at (u,v)
+    pub fn point(&self,u: f64,v: f64) -> Point {
+        let bottom = self.p00 + (self.p10 - self.p00) * u;
+        let top = self.p01 + (self.p11 - self.p01) * u;
+        bottom + (top - bottom) * v
+    }
+
+    /// Partial derivatives dp/du and dp/dv at (u,v)
+    fn derivatives(&self,u: f64,v: f64) -> (Vector,Vector) {
+        let dpdu = (self.p10 - self.p00) + ((self.p11 - self.p01) - (self.p10 - self.p00)) * v;
+        let dpdv = (self.p01 - self.p00) + ((self.p11 - self.p10) - (self.p01 - self.p00)) * u;
+        (dpdu,dpdv)
+    }
+
+    /// Unit normal dp/du x dp/dv at (u,v), none where the patch degenerates
+    fn normal(&self,u: f64,v: f64) -> Option<Normal> {
+        Some(Normal::from((self.a + self.b * u + self.c * v).normalize().ok()?))
+    }
+
+    /// Area per unit of u and v
+    fn density(&self,u: f64,v: f64) -> f64 {
+        (self.a + self.b * u + self.c * v).len()
+    }
+
+    /// Area per unit of u, integrated across v
+    fn marginal(&self,u: f64) -> f64 {
+        simpson(|v| self.density(u,v),0.0,1.0,V_INTERVALS)
+    }
+}
+
+/// Nearest hit (t,u,v) of ray with the bilinear patch through the four
+/// corners, with t in (0,tmax)
+pub fn intersect_patch(p00: &Point,p10: &Point,p01: &Point,p11: &Point,ray: &Ray,tmax: f64) -> Option<(f64,f64,f64)> {
+    // Reshetov, "Cool Patches: A Geometric Approach to Ray/Bilinear
+    // Patch Intersections", a quadratic in u then v and t along the
+    // segment of the patch at that u
+    let e10 = *p10 - *p00;
+    let e11 = *p11 - *p10;
+    let e00 = *p01 - *p00;
+    let qn = e10.cross(*p01 - *p11);
+    let q00 = *p00 - ray.o;
+    let q10 = *p10 - ray.o;
+    let a = q00.cross(ray.d).dot(e00);
+    let c = qn.dot(ray.d);
+    let b = q10.cross(ray.d).dot(e11) - (a + c);
+
+    let roots = if c == 0.0 {
+        if b == 0.0 {
+            return None
+        }
+        [-a / b,-1.0]
+    } else {
+        let discrim = b*b - 4.0*a*c;
+        if discrim < 0.0 {
+            return None
+        }
+        let q = -0.5 * (b + f64::sqrt(discrim).copysign(b));
+        [q / c,if q != 0.0 { a / q } else { -1.0 }]
+    };
+
+    let mut nearest: Option<(f64,f64,f64)> = None;
+    for u in roots.into_iter().filter(|u| (0.0..=1.0).contains(u)) {
+        let pa = q00 + (q10 - q00) * u;
+        let pb = e00 + (e11 - e00) * u;
+        let n = ray.d.cross(pb);
+        let det = n.dot(n);
+        if det == 0.0 {
+            continue
+        }
+        let n = n.cross(pa);
+        let t = n.dot(pb) / det;
+        let v = n.dot(ray.d) / det;
+        if t > 0.0 && t < tmax && (0.0..=1.0).contains(&v) && nearest.is_none_or(|(best,_,_)| t < best) {
+            nearest = Some((t,u,v));
+        }
+    }
+    nearest
+}
+
+/// Integral of f from x0 to x1 by composite Simpson's rule over an even
+/// number of intervals
+fn simpson(f: impl Fn(f64) -> f64,x0: f64,x1: f64,intervals: usize) -> f64 {
+    let h = (x1 - x0) / intervals as f64;
+    let inner: f64 = (1..intervals)
+        .map(|k| if k % 2 == 1 { 4.0 } else { 2.0 } * f(x0 + k as f64 * h))
+        .sum();
+    (h / 3.0) * (f(x0) + inner + f(x1))
+}
+
+/// Point x in [lo,hi] where the integral of the non negative f from lo
+/// reaches target, by Newton steps on the integral kept inside the
+/// bracket by bisection
+fn invert_integral(f: impl Fn(f64) -> f64,lo: f64,hi: f64,target: f64) -> f64 {
+    let (mut a,mut b) = (lo,hi);
+    let total = simpson(&f,lo,hi,4);
+    let mut x = if total > 0.0 { lo + (hi - lo) * (target / total).clamp(0.0,1.0) } else { 0.5 * (lo + hi) };
+    for _ in 0..40 {
+        let g = simpson(&f,lo,x,4) - target;
+        if g < 0.0 { a = x } else { b = x }
+        let slope = f(x);
+        let newton = x - g / slope;
+        let next = if slope > 0.0 && newton > a && newton < b { newton } else { 0.5 * (a + b) };
+        if (next - x).abs() <= 1e-14 * (1.0 + x.abs()) {
+            return next
+        }
+        x = next;
+    }
+    x
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // saddle y = x z over the unit square
+    fn saddle() -> BilinearPatch {
+        BilinearPatch::new(
+            Point::new(0.,0.,0.),
+            Point::new(1.,0.,0.),
+            Point::new(0.,0.,1.),
+            Point::new(1.,1.,1.)
+        )
+    }
+
+    #[test]
+    // hits should land on the saddle with the normal dp/du x dp/dv
+    fn test_intersect() {
+        let patch = saddle();
+        let ray = Ray::new(&Point::new(0.5,2.,0.5),&Vector::new(0.,-1.,0.));
+        let si = patch.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.t - 1.75).abs() < 1e-12);
+        assert!((si.uv.0 - 0.5).abs() < 1e-12 && (si.uv.1 - 0.5).abs() < 1e-12);
+        let expected = Vector::new(0.5,-1.,0.5).normalize().unwrap();
+        assert!((Vector::from(si.n) - expected).len() < 1e-12);
+        assert!(si.n.dot(si.dpdu).abs() < 1e-12 && si.n.dot(si.dpdv).abs() < 1e-12);
+        let outside = Ray::new(&Point::new(1.5,2.,0.5),&Vector::new(0.,-1.,0.));
+        assert!(patch.intersect(&outside,f64::INFINITY).is_none());
+    }
+
+    #[test]
+    // a ray crossing the saddle twice should report the nearer hit first
+    fn test_two_hits() {
+        let patch = saddle();
+        // x = s, z = 1 - s at height 0.1 meets x z = 0.1 twice
+        let ray = Ray::new(&Point::new(0.,0.1,1.),&Vector::new(1.,0.,-1.));
+        let root = f64::sqrt(0.6);
+        let si = patch.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.t - 0.5 * (1. - root)).abs() < 1e-12);
+        let beyond = Ray::new(&ray.at(0.5),&ray.d);
+        let si = patch.intersect(&beyond,f64::INFINITY).unwrap();
+        assert!((si.t - 0.5 * root).abs() < 1e-12);
+        assert!(patch.intersect(&beyond,0.3).is_none());
+    }
+
+    #[test]
+    // coplanar corners should behave as a flat quad
+    fn test_flat() {
+        let patch = BilinearPatch::new(
+            Point::new(0.,0.,0.),
+            Point::new(2.,0.,0.),
+            Point::new(0.,1.,0.),
+            Point::new(3.,1.,0.)
+        );
+        assert!((patch.area() - 2.5).abs() < 1e-9);
+        let ray = Ray::new(&Point::new(2.4,0.8,-1.),&Vector::new(0.,0.,1.));
+        let si = patch.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.uv.1 - 0.8).abs() < 1e-12);
+        assert!((si.uv.0 - 0.857142857142857).abs() < 1e-12);
+        assert!((si.n.z - 1.).abs() < 1e-12);
+        let ray = Ray::new(&Point::new(2.9,0.2,-1.),&Vector::new(0.,0.,1.));
+        assert!(patch.intersect(&ray,f64::INFINITY).is_none());
+    }
+
+    #[test]
+    // samples should lie on the saddle and spread uniformly by area
+    fn test_sample() {
+        let patch = saddle();
+        // integral of sqrt(1 + x^2 + z^2) over the unit square
+        assert!((patch.area() - 1.2807885824983276).abs() < 1e-6);
+        let mut near = 0;
+        for i in 0..1000 {
+            let (p,n) = patch.sample(((i as f64 + 0.5) / 1000.,0.3)).unwrap();
+            assert!((p.y - p.x * p.z).abs() < 1e-12);
+            assert!((Vector::from(n) - Vector::new(p.z,-1.,p.x).normalize().unwrap()).len() < 1e-12);
+            if p.x < 0.5 {
+                near += 1;
+            }
+        }
+        // share of the area with x < 0.5
+        assert!((near as f64 / 1000. - 0.462014760843756).abs() < 0.002);
+        let (p,_) = patch.sample((0.5,1.)).unwrap();
+        assert!((p.z - 1.).abs() < 1e-9);
+    }
+}
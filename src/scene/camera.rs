@@ -0,0 +1,237 @@
+use crate::math::{
+    point::Point,
+    vector::Vector,
+    ray::{Ray,RayDifferential},
+    traits::{Dot,Cross,Normalize}
+};
+
+/// # CameraSample
+/// Importance arriving at the camera from a point in the scene
+///
+/// # Parameters
+/// * wi (unit direction from the point towards the camera)
+/// * we (importance carried along -wi)
+/// * pdf (solid angle density of wi at the point)
+/// * distance (distance from the point to the camera)
+/// * uv (image position hit by the ray in [0,1]^2)
+pub struct CameraSample {
+    pub wi: Vector,
+    pub we: f64,
+    pub pdf: f64,
+    pub distance: f64,
+    pub uv: (f64,f64)
+}
+
+/// # Camera
+/// Pinhole perspective camera
+///
+/// # Parameters
+/// * eye (position)
+/// * forward (unit viewing direction)
+/// * right (unit vector towards the right of the image)
+/// * up (unit vector towards the top of the image)
+/// * tan_half_fov (tangent of half the vertical field of view)
+/// * aspect (image width / height)
+#[derive(Clone,Copy)]
+pub struct Camera {
+    pub eye: Point,
+    pub forward: Vector,
+    pub right: Vector,
+    pub up: Vector,
+    pub tan_half_fov: f64,
+    pub aspect: f64
+}
+
+impl Camera {
+    /// Construct camera at eye looking at a target,
+    /// fov is the vertical field of view in degrees
+    pub fn new(eye: Point,target: Point,up: Vector,fov: f64,aspect: f64) -> Result<Camera,String> {
+        let forward = (target - eye).normalize()?;
+        let right = up.cross(forward).normalize()?;
+        let up = forward.cross(right);
+        Ok(Camera {
+            eye,
+            forward,
+            right,
+            up,
+            tan_half_fov: f64::tan(fov.to_radians() / 2.0),
+            aspect
+        })
+    }
+
+    /// Generate ray through the image at (u,v) in [0,1]^2,
+    /// (0,0) is the top left corner of the image
+    pub fn generate_ray(&self,u: f64,v: f64) -> Ray {
+        let x = (2.0*u - 1.0) * self.tan_half_fov * self.aspect;
+        let y = (1.0 - 2.0*v) * self.tan_half_fov;
+        let d = self.forward + self.right*x + self.up*y;
+        Ray::new(&self.eye,&d.normalize().unwrap())
+    }
+
+    /// Generate ray at (u,v) with differentials towards (u+du,v) and (u,v+dv),
+    /// du and dv are usually one pixel
+    pub fn generate_ray_differential(&self,u: f64,v: f64,du: f64,dv: f64) -> Ray {
+        let rx = self.generate_ray(u + du,v);
+        let ry = self.generate_ray(u,v + dv);
+        self.generate_ray(u,v).with_differentials(RayDifferential {
+            rx_o: rx.o,
+            rx_d: rx.d,
+            ry_o: ry.o,
+            ry_d: ry.d
+        })
+    }
+
+    /// Area of the image plane at unit distance from the eye
+    pub fn film_area(&self) -> f64 {
+        4.0 * self.tan_half_fov * self.tan_half_fov * self.aspect
+    }
+
+    /// Image position (inverse of generate_ray) of a unit direction
+    /// leaving the eye, None outside the image
+    pub fn uv(&self,d: &Vector) -> Option<(f64,f64)> {
+        let cos = d.dot(self.forward);
+        if cos <= 0.0 {
+            return None
+        }
+        let x = d.dot(self.right) / cos;
+        let y = d.dot(self.up) / cos;
+        let u = 0.5 * (x / (self.tan_half_fov * self.aspect) + 1.0);
+        let v = 0.5 * (1.0 - y / self.tan_half_fov);
+        if (0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v) {
+            Some((u,v))
+        } else {
+            None
+        }
+    }
+
+    /// Importance emitted along a unit direction leaving the eye,
+    /// normalized so it integrates to one over the image
+    pub fn we(&self,d: &Vector) -> f64 {
+        if self.uv(d).is_none() {
+            return 0.0
+        }
+        let cos = d.dot(self.forward);
+        1.0 / (self.film_area() * cos*cos*cos*cos)
+    }
+
+    /// Solid angle density of generate_ray choosing direction d
+    /// for uniformly distributed image positions
+    pub fn pdf_we(&self,d: &Vector) -> f64 {
+        if self.uv(d).is_none() {
+            return 0.0
+        }
+        let cos = d.dot(self.forward);
+        1.0 / (self.film_area() * cos*cos*cos)
+    }
+
+    /// Sample importance arriving at p, the pinhole is a delta
+    /// distribution so there is a single possible direction
+    pub fn sample_wi(&self,p: &Point) -> Option<CameraSample> {
+        let d: Vector = self.eye - *p;
+        let distance = f64::sqrt(d.dot(d));
+        let wi = d.normalize().ok()?;
+        let uv = self.uv(&(-wi))?;
+        let cos = wi.dot(self.forward).abs();
+        Some(CameraSample {
+            wi,
+            we: self.we(&(-wi)),
+            pdf: distance*distance / cos,
+            distance,
+            uv
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_camera() -> Camera {
+        Camera::new(
+            Point::new(0.,0.,0.),
+            Point::new(0.,0.,1.),
+            Vector::new(0.,1.,0.),
+            90.,
+            2.
+        ).unwrap()
+    }
+
+    #[test]
+    // should build basis matching the window (+x right, +y up)
+    fn test_new() {
+        let camera = test_camera();
+        assert_eq!(camera.forward.z,1.);
+        assert_eq!(camera.right.x,1.);
+        assert_eq!(camera.up.y,1.);
+        assert!((camera.tan_half_fov - 1.).abs() < 1e-12);
+        // degenerate up vector
+        assert!(Camera::new(Point::new(0.,0.,0.),Point::new(0.,1.,0.),Vector::new(0.,1.,0.),45.,1.).is_err());
+    }
+
+    #[test]
+    // center and corners of the image should map to expected directions
+    fn test_generate_ray() {
+        let camera = test_camera();
+        let center = camera.generate_ray(0.5,0.5);
+        assert_eq!(center.d.z,1.);
+        let top_left = camera.generate_ray(0.,0.);
+        assert!(top_left.d.x < 0.);
+        assert!(top_left.d.y > 0.);
+        // aspect stretches x
+        assert!((top_left.d.x / top_left.d.z + 2.).abs() < 1e-12);
+        assert!((top_left.d.y / top_left.d.z - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    // differentials should point at the neighbouring pixels
+    fn test_generate_ray_differential() {
+        let camera = test_camera();
+        let ray = camera.generate_ray_differential(0.5,0.5,0.25,0.5);
+        let rd = ray.differentials.unwrap();
+        let rx = camera.generate_ray(0.75,0.5);
+        assert_eq!((rd.rx_d.x,rd.rx_d.z),(rx.d.x,rx.d.z));
+        assert!(rd.ry_d.y < 0.);
+        assert_eq!(rd.rx_o.x,ray.o.x);
+    }
+
+    #[test]
+    // image positions should invert generate_ray
+    fn test_uv() {
+        let camera = test_camera();
+        let ray = camera.generate_ray(0.2,0.7);
+        let (u,v) = camera.uv(&ray.d).unwrap();
+        assert!((u - 0.2).abs() < 1e-12);
+        assert!((v - 0.7).abs() < 1e-12);
+        assert!(camera.uv(&Vector::new(0.,0.,-1.)).is_none());
+        assert!(camera.uv(&Vector::new(0.,2.,1.)).is_none());
+    }
+
+    #[test]
+    // importance should integrate to one over the image
+    fn test_we() {
+        let camera = test_camera();
+        let n = 64;
+        let mut sum = 0.;
+        for j in 0..n {
+            for i in 0..n {
+                let ray = camera.generate_ray((i as f64 + 0.5) / n as f64,(j as f64 + 0.5) / n as f64);
+                let cos = ray.d.dot(camera.forward);
+                sum += camera.we(&ray.d) * cos / camera.pdf_we(&ray.d);
+            }
+        }
+        let mean = sum / (n*n) as f64;
+        assert!((mean - 1.).abs() < 1e-9);
+        let center = camera.generate_ray(0.5,0.5);
+        assert!((camera.we(&center.d) - 1. / camera.film_area()).abs() < 1e-12);
+
+        let sample = camera.sample_wi(&Point::new(0.,0.,2.)).unwrap();
+        assert_eq!(sample.wi.z,-1.);
+        assert_eq!(sample.pdf,4.);
+        assert_eq!(sample.uv,(0.5,0.5));
+        assert!(camera.sample_wi(&Point::new(0.,0.,-2.)).is_none());
+    }
+}
@@ -0,0 +1,147 @@
+use std::f64::consts::PI;
+
+use super::{
+    traits::Primitive,
+    interaction::SurfaceInteraction,
+    transform::Transform,
+    quadric::{azimuth,nearest_root}
+};
+use crate::math::{
+    point::Point,
+    vector::Vector,
+    normal::Normal,
+    matrix::Matrix,
+    ray::Ray,
+    traits::Normalize
+};
+
+/// # Cone
+/// Open cone about the y axis of object space with its base circle
+/// at y = 0 and its apex at y = height, swept from phi = 0 to phi_max
+///
+/// # Parameters
+/// * radius (radius of the base)
+/// * height (y coordinate of the apex)
+/// * phi_max (sweep angle in (0,2 pi])
+/// * transform (placement in the world)
+pub struct Cone {
+    pub radius: f64,
+    pub height: f64,
+    pub phi_max: f64,
+    pub transform: Transform
+}
+
+/// Primitive trait
+impl Primitive for Cone {
+    fn intersect(&self,ray: &Ray,tmax: f64) -> Option<SurfaceInteraction> {
+        let r = self.transform.ray_to_object(ray);
+        let (o,d) = (r.o,r.d);
+        let k = (self.radius / self.height) * (self.radius / self.height);
+        let oy = o.y - self.height;
+        let coefficients = (
+            d.x*d.x + d.z*d.z - k*d.y*d.y,
+            2.0 * (d.x*o.x + d.z*o.z - k*d.y*oy),
+            o.x*o.x + o.z*o.z - k*oy*oy
+        );
+        let (t,p) = nearest_root(&r,tmax,coefficients,|p| {
+            p.y >= 0.0 && p.y <= self.height && azimuth(p.x,p.z) <= self.phi_max
+        })?;
+
+        // the slope along v does not change with height, nor does the normal
+        let phi = azimuth(p.x,p.z);
+        let (sin_phi,cos_phi) = f64::sin_cos(phi);
+        let n = Normal::new(self.height * cos_phi,self.radius,self.height * sin_phi).normalize().ok()?;
+        let si = SurfaceInteraction::new(
+            p,
+            n,
+            (phi / self.phi_max,p.y / self.height),
+            Vector::new(-self.phi_max * p.z,0.0,self.phi_max * p.x),
+            Vector::new(-self.radius * cos_phi,self.height,-self.radius * sin_phi),
+            t,
+            -d
+        );
+        self.transform.interaction_to_world(&si,ray)
+    }
+
+    fn area(&self) -> f64 {
+        0.5 * self.phi_max * self.radius * f64::sqrt(self.height*self.height + self.radius*self.radius)
+    }
+
+    fn sample(&self,u: (f64,f64)) -> Option<(Point,Normal)> {
+        // area grows linearly with the distance from the apex
+        let s = f64::sqrt(u.0);
+        let (sin_phi,cos_phi) = f64::sin_cos(u.1 * self.phi_max);
+        let r = self.radius * s;
+        let n = Normal::new(self.height * cos_phi,self.radius,self.height * sin_phi).normalize().ok()?;
+        Some(self.transform.sample_to_world((
+            Point::new(r * cos_phi,self.height * (1.0 - s),r * sin_phi),
+            n
+        )))
+    }
+}
+
+impl Cone {
+    /// Construct cone about the y axis
+    pub fn new(radius: f64,height: f64,phi_max: f64) -> Cone {
+        Cone {
+            radius,
+            height,
+            phi_max: phi_max.clamp(0.0,2.0 * PI),
+            transform: Transform::new()
+        }
+    }
+
+    /// Place the cone in the world
+    pub fn set_object_to_world(&mut self,object_to_world: &Matrix) -> Result<(),String> {
+        self.transform = Transform::rigid_from_matrix(object_to_world)?;
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::traits::Dot;
+
+    #[test]
+    // hits should lie on the slanted side with normals perpendicular to it
+    fn test_intersect() {
+        let cone = Cone::new(1.,2.,2. * PI);
+        let ray = Ray::new(&Point::new(-5.,1.,0.),&Vector::new(1.,0.,0.));
+        let si = cone.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.p.x + 0.5).abs() < 1e-12);
+        assert!((si.uv.1 - 0.5).abs() < 1e-12);
+        assert!(si.n.dot(si.dpdv).abs() < 1e-12);
+        assert!(si.n.dot(si.dpdu).abs() < 1e-12);
+        assert!(si.n.x < 0. && si.n.y > 0.);
+        // the double cone above the apex is clipped away
+        let above = Ray::new(&Point::new(-5.,3.,0.),&Vector::new(1.,0.,0.));
+        assert!(cone.intersect(&above,f64::INFINITY).is_none());
+        // a ray up the axis passes through the open base to the apex
+        let up = Ray::new(&Point::new(0.,-1.,0.),&Vector::new(0.,1.,0.));
+        let si = cone.intersect(&up,f64::INFINITY).unwrap();
+        assert!((si.t - 3.).abs() < 1e-9);
+    }
+
+    #[test]
+    // samples should lie on the cone and spread by area
+    fn test_sample() {
+        let cone = Cone::new(1.,1.,2. * PI);
+        assert!((cone.area() - PI * f64::sqrt(2.)).abs() < 1e-12);
+        let mut lower = 0;
+        for i in 0..1000 {
+            let (p,n) = cone.sample(((i as f64 + 0.5) / 1000.,0.8)).unwrap();
+            assert!((f64::sqrt(p.x*p.x + p.z*p.z) - (1. - p.y)).abs() < 1e-12);
+            assert!((n.y - f64::sqrt(0.5)).abs() < 1e-12);
+            if p.y < 0.5 {
+                lower += 1;
+            }
+        }
+        // the lower half of the slant holds three quarters of the area
+        assert!((lower as f64 / 1000. - 0.75).abs() < 0.01);
+    }
+}
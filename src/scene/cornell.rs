@@ -0,0 +1,75 @@
+use super::{
+    world::World,
+    camera::Camera,
+    sphere::Sphere,
+    plane::Plane
+};
+use crate::{
+    image::spectrum::Spectrum,
+    math::{point::Point,vector::Vector,normal::Normal},
+    material::{lambertian::Lambertian,mirror::Mirror}
+};
+
+/// Cornell box reference scene
+///
+/// The box spans [-1,1] on every axis with an open front facing
+/// the camera, it is lit by a small spherical light under the
+/// ceiling and holds a diffuse and a mirrored sphere
+pub fn cornell_box(aspect: f64) -> (World,Camera) {
+    let mut world = World::new(8);
+
+    let white = world.add_material(Box::new(Lambertian::new(Spectrum::new(0.73,0.73,0.73))));
+    let red = world.add_material(Box::new(Lambertian::new(Spectrum::new(0.65,0.05,0.05))));
+    let green = world.add_material(Box::new(Lambertian::new(Spectrum::new(0.12,0.45,0.15))));
+    let mirror = world.add_material(Box::new(Mirror::new(Spectrum::new(0.9,0.9,0.9))));
+
+    // walls
+    world.add_primitive_with_material(Box::new(Plane::new(&Point::new(0.,-1.,0.),&Normal::new(0.,1.,0.))),white);
+    world.add_primitive_with_material(Box::new(Plane::new(&Point::new(0.,1.,0.),&Normal::new(0.,-1.,0.))),white);
+    world.add_primitive_with_material(Box::new(Plane::new(&Point::new(0.,0.,1.),&Normal::new(0.,0.,-1.))),white);
+    world.add_primitive_with_material(Box::new(Plane::new(&Point::new(-1.,0.,0.),&Normal::new(1.,0.,0.))),red);
+    world.add_primitive_with_material(Box::new(Plane::new(&Point::new(1.,0.,0.),&Normal::new(-1.,0.,0.))),green);
+
+    // contents
+    world.add_primitive_with_material(Box::new(Sphere::new(0.35,Point::new(-0.45,-0.65,0.3))),white);
+    world.add_primitive_with_material(Box::new(Sphere::new(0.35,Point::new(0.45,-0.65,-0.2))),mirror);
+
+    // light
+    world.add_area_light(
+        Box::new(Sphere::new(0.2,Point::new(0.,0.75,0.))),
+        white,
+        Spectrum::new(8.,8.,8.),
+        false
+    );
+
+    let camera = Camera::new(
+        Point::new(0.,0.,-3.4),
+        Point::new(0.,0.,0.),
+        Vector::new(0.,1.,0.),
+        40.,
+        aspect
+    ).unwrap();
+
+    (world,camera)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // should build the box with a single area light
+    fn test_cornell_box() {
+        let (world,camera) = cornell_box(1.);
+        assert_eq!(world.primitives.len(),8);
+        assert_eq!(world.lights.len(),1);
+        assert_eq!(world.primitive_lights[7],Some(0));
+        // center ray sees the back wall
+        let si = world.intersect(&camera.generate_ray(0.5,0.5)).unwrap();
+        assert!((si.p.z - 1.).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,261 @@
+use super::{
+    traits::Primitive,
+    interaction::SurfaceInteraction
+};
+use crate::math::ray::Ray;
+
+/// # Operation
+/// Boolean operation combining the solids of two primitives
+///
+/// # Parameters
+/// * Union (inside either solid)
+/// * Intersection (inside both solids)
+/// * Difference (inside the left solid but not the right one)
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum Operation {
+    Union,
+    Intersection,
+    Difference
+}
+
+/// # Csg
+/// Constructive solid geometry node, a boolean combination of the solids
+/// bounded by two primitives. Both operands must report their intervals
+/// along a ray, nodes nest to build up more complex solids. The result
+/// has no closed form area and cannot be sampled as a light
+///
+/// # Parameters
+/// * operation (how the solids are combined)
+/// * left (first operand)
+/// * right (second operand, subtracted for a difference)
+pub struct Csg {
+    pub operation: Operation,
+    pub left: Box<dyn Primitive>,
+    pub right: Box<dyn Primitive>
+}
+
+/// Primitive trait
+impl Primitive for Csg {
+    fn intersect(&self,ray: &Ray,tmax: f64) -> Option<SurfaceInteraction> {
+        self.intervals(ray)
+            .into_iter()
+            .flat_map(|(enter,exit)| [enter,exit])
+            .find(|si| si.t > 0.0 && si.t < tmax)
+    }
+
+    fn intervals(&self,ray: &Ray) -> Vec<(SurfaceInteraction,SurfaceInteraction)> {
+        combine(self.operation,&self.left.intervals(ray),&self.right.intervals(ray))
+    }
+}
+
+impl Csg {
+    /// Construct node combining left and right with operation
+    pub fn new(operation: Operation,left: Box<dyn Primitive>,right: Box<dyn Primitive>) -> Csg {
+        Csg {
+            operation,
+            left,
+            right
+        }
+    }
+
+    /// Construct union of left and right
+    pub fn union(left: Box<dyn Primitive>,right: Box<dyn Primitive>) -> Csg {
+        Csg::new(Operation::Union,left,right)
+    }
+
+    /// Construct intersection of left and right
+    pub fn intersection(left: Box<dyn Primitive>,right: Box<dyn Primitive>) -> Csg {
+        Csg::new(Operation::Intersection,left,right)
+    }
+
+    /// Construct left with right carved out of it
+    pub fn difference(left: Box<dyn Primitive>,right: Box<dyn Primitive>) -> Csg {
+        Csg::new(Operation::Difference,left,right)
+    }
+}
+
+/// Intervals inside the combined solid, sweeping the boundaries of both
+/// operands in order along the ray and keeping those where being inside
+/// the result changes. Surfaces of the right operand bounding a
+/// difference face into it, so their normals are flipped
+fn combine(
+    operation: Operation,
+    left: &[(SurfaceInteraction,SurfaceInteraction)],
+    right: &[(SurfaceInteraction,SurfaceInteraction)]
+) -> Vec<(SurfaceInteraction,SurfaceInteraction)> {
+    // (boundary,from left,entering)
+    let mut events: Vec<(SurfaceInteraction,bool,bool)> = Vec::with_capacity(2 * (left.len() + right.len()));
+    for (intervals,from_left) in [(left,true),(right,false)] {
+        for (enter,exit) in intervals {
+            events.push((*enter,from_left,true));
+            events.push((*exit,from_left,false));
+        }
+    }
+    events.sort_by(|a,b| a.0.t.total_cmp(&b.0.t));
+
+    let (mut in_left,mut in_right) = (false,false);
+    let mut entry: Option<SurfaceInteraction> = None;
+    let mut intervals = vec![];
+    for (mut si,from_left,entering) in events {
+        if from_left {
+            in_left = entering;
+        } else {
+            in_right = entering;
+        }
+        let inside = match operation {
+            Operation::Union => in_left || in_right,
+            Operation::Intersection => in_left && in_right,
+            Operation::Difference => in_left && !in_right
+        };
+        if operation == Operation::Difference && !from_left {
+            si.n = -si.n;
+            si.ns = -si.ns;
+        }
+        match entry {
+            None if inside => entry = Some(si),
+            Some(enter) if !inside => {
+                intervals.push((enter,si));
+                entry = None;
+            },
+            _ => {}
+        }
+    }
+    intervals
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::{
+        scene::{sphere::Sphere,cuboid::Cuboid,cylinder::Cylinder,torus::Torus,plane::Plane},
+        math::{point::Point,vector::Vector,normal::Normal}
+    };
+
+    // unit spheres centered at x = -0.5 and x = 0.5
+    fn pair() -> (Box<dyn Primitive>,Box<dyn Primitive>) {
+        (
+            Box::new(Sphere::new(1.,Point::new(-0.5,0.,0.))),
+            Box::new(Sphere::new(1.,Point::new(0.5,0.,0.)))
+        )
+    }
+
+    // ray along +x through the centers
+    fn axis() -> Ray {
+        Ray::new(&Point::new(-5.,0.,0.),&Vector::new(1.,0.,0.))
+    }
+
+    #[test]
+    // primitives bounding a solid should report their stretches along the whole line
+    fn test_primitive_intervals() {
+        let sphere = Sphere::new(1.,Point::new(0.,0.,0.));
+        let ray = Ray::new(&Point::new(0.5,0.,0.),&Vector::new(1.,0.,0.));
+        let intervals = sphere.intervals(&ray);
+        assert_eq!(intervals.len(),1);
+        assert!((intervals[0].0.t + 1.5).abs() < 1e-12);
+        assert!((intervals[0].1.t - 0.5).abs() < 1e-12);
+        // the torus is crossed twice through its hole
+        let torus = Torus::new(2.,1.);
+        let intervals = torus.intervals(&axis());
+        assert_eq!(intervals.len(),2);
+        assert!((intervals[0].0.t - 2.).abs() < 1e-9 && (intervals[0].1.t - 4.).abs() < 1e-9);
+        assert!((intervals[1].0.t - 6.).abs() < 1e-9 && (intervals[1].1.t - 8.).abs() < 1e-9);
+        // the full cylinder is closed by its caps
+        let cylinder = Cylinder::new(1.,0.,2.,2. * PI);
+        let up = Ray::new(&Point::new(0.5,-1.,0.),&Vector::new(0.,1.,0.));
+        let intervals = cylinder.intervals(&up);
+        assert_eq!(intervals.len(),1);
+        assert!((intervals[0].0.t - 1.).abs() < 1e-12 && (intervals[0].0.n.y + 1.).abs() < 1e-12);
+        assert!((intervals[0].1.t - 3.).abs() < 1e-12 && (intervals[0].1.n.y - 1.).abs() < 1e-12);
+        assert!(Cylinder::new(1.,0.,2.,PI).intervals(&up).is_empty());
+        // open surfaces enclose nothing
+        let plane = Plane { point: Point::new(0.,0.,0.),normal: Normal::new(1.,0.,0.) };
+        assert!(plane.intervals(&axis()).is_empty());
+    }
+
+    #[test]
+    // overlapping spheres should merge into a single stretch
+    fn test_union() {
+        let (a,b) = pair();
+        let csg = Csg::union(a,b);
+        let intervals = csg.intervals(&axis());
+        assert_eq!(intervals.len(),1);
+        assert!((intervals[0].0.t - 3.5).abs() < 1e-12);
+        assert!((intervals[0].1.t - 6.5).abs() < 1e-12);
+        let si = csg.intersect(&axis(),f64::INFINITY).unwrap();
+        assert!((si.n.x + 1.).abs() < 1e-12);
+        assert!(csg.intersect(&axis(),3.).is_none());
+    }
+
+    #[test]
+    // the lens shared by both spheres is bounded by the far sides of each
+    fn test_intersection() {
+        let (a,b) = pair();
+        let csg = Csg::intersection(a,b);
+        let si = csg.intersect(&axis(),f64::INFINITY).unwrap();
+        assert!((si.t - 4.5).abs() < 1e-12);
+        assert!((si.n.x + 1.).abs() < 1e-12);
+        // from inside the lens the ray leaves through the left sphere
+        let inside = Ray::new(&Point::new(0.,0.,0.),&Vector::new(1.,0.,0.));
+        let si = csg.intersect(&inside,f64::INFINITY).unwrap();
+        assert!((si.t - 0.5).abs() < 1e-12);
+        assert!((si.n.x - 1.).abs() < 1e-12);
+        // disjoint solids share nothing
+        let apart = Csg::intersection(
+            Box::new(Sphere::new(1.,Point::new(-2.,0.,0.))),
+            Box::new(Sphere::new(1.,Point::new(2.,0.,0.)))
+        );
+        assert!(apart.intersect(&axis(),f64::INFINITY).is_none());
+    }
+
+    #[test]
+    // carved surfaces should face out of the remaining solid
+    fn test_difference() {
+        let (a,b) = pair();
+        let csg = Csg::difference(a,b);
+        let intervals = csg.intervals(&axis());
+        assert_eq!(intervals.len(),1);
+        let (enter,exit) = intervals[0];
+        assert!((enter.t - 3.5).abs() < 1e-12 && (exit.t - 4.5).abs() < 1e-12);
+        // the exit lies on the right sphere, whose normal now points along +x
+        assert!((exit.n.x - 1.).abs() < 1e-12);
+        assert!((exit.ns.x - 1.).abs() < 1e-12);
+
+        // a block with a hole drilled through it along y
+        let drill = Cylinder::new(0.5,-2.,2.,2. * PI);
+        let block = Csg::difference(
+            Box::new(Cuboid::new(Point::new(-1.,-1.,-1.),Point::new(1.,1.,1.))),
+            Box::new(drill)
+        );
+        let down = Ray::new(&Point::new(0.,5.,0.),&Vector::new(0.,-1.,0.));
+        assert!(block.intersect(&down,f64::INFINITY).is_none());
+        let beside = Ray::new(&Point::new(0.75,5.,0.),&Vector::new(0.,-1.,0.));
+        assert!((block.intersect(&beside,f64::INFINITY).unwrap().t - 4.).abs() < 1e-12);
+        // across the hole the wall of the drill faces into it
+        let across = Ray::new(&Point::new(-5.,0.,0.),&Vector::new(1.,0.,0.));
+        let intervals = block.intervals(&across);
+        assert_eq!(intervals.len(),2);
+        assert!((intervals[0].1.t - 4.5).abs() < 1e-12);
+        assert!((intervals[0].1.n.x - 1.).abs() < 1e-12);
+        assert!((intervals[1].0.t - 5.5).abs() < 1e-12);
+        assert!((intervals[1].0.n.x + 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    // nodes should nest as operands of other nodes
+    fn test_nested() {
+        let (a,b) = pair();
+        let cut = Box::new(Cuboid::new(Point::new(-3.,-3.,-3.),Point::new(0.,3.,3.)));
+        let csg = Csg::difference(Box::new(Csg::union(a,b)),cut);
+        let si = csg.intersect(&axis(),f64::INFINITY).unwrap();
+        assert!((si.t - 5.).abs() < 1e-12);
+        assert!((si.n.x + 1.).abs() < 1e-12);
+        assert!(csg.area().is_infinite());
+        assert!(csg.sample((0.5,0.5)).is_none());
+    }
+}
@@ -0,0 +1,461 @@
+use std::f64::consts::PI;
+
+use super::{
+    traits::Primitive,
+    interaction::SurfaceInteraction,
+    transform::Transform
+};
+use crate::math::{
+    point::Point,
+    vector::Vector,
+    normal::Normal,
+    matrix::Matrix,
+    ray::Ray,
+    polynomial::solve_quadratic,
+    sampling::uniform_sample_sphere,
+    traits::Normalize
+};
+
+/// Axes spanning the face perpendicular to each axis, as (u,v)
+const FACE_AXES: [(usize,usize); 3] = [(2,1),(0,2),(0,1)];
+
+/// Ray parameter, point and outward normal where a ray crosses the box
+type Crossing = (f64,Point,Normal);
+
+/// # Cuboid
+/// Box between two corners in object space, optionally with its edges
+/// and corners rounded off with the given radius. Each face is mapped to
+/// the unit square of uv, x faces along (z,y), y faces along (x,z) and
+/// z faces along (x,y)
+///
+/// # Parameters
+/// * min (corner with the smallest coordinates)
+/// * max (corner with the largest coordinates)
+/// * radius (radius of the rounded edges, 0 for sharp ones)
+/// * transform (placement in the world, rotations give oriented boxes)
+pub struct Cuboid {
+    pub min: Point,
+    pub max: Point,
+    pub radius: f64,
+    pub transform: Transform
+}
+
+/// Primitive trait
+impl Primitive for Cuboid {
+    fn intersect(&self,ray: &Ray,tmax: f64) -> Option<SurfaceInteraction> {
+        let r = self.transform.ray_to_object(ray);
+        let (enter,exit) = self.span(&r)?;
+        let t = if enter.0 > 0.0 { enter } else { exit };
+        if !(t.0 > 0.0 && t.0 < tmax) {
+            return None
+        }
+        self.interaction(&r,ray,t)
+    }
+
+    fn area(&self) -> f64 {
+        let (faces,edges,corners) = self.areas();
+        faces.iter().sum::<f64>() + edges.iter().sum::<f64>() + corners
+    }
+
+    fn sample(&self,u: (f64,f64)) -> Option<(Point,Normal)> {
+        let (faces,edges,corners) = self.areas();
+        let (lo,hi) = self.inner();
+        let (lo,hi) = (coordinates(&lo),coordinates(&hi));
+        let mut target = u.0 * self.area();
+
+        // faces, two per axis
+        for (i,&area) in faces.iter().enumerate() {
+            if target < area {
+                let axis = i / 2;
+                let sign = if i % 2 == 0 { -1.0 } else { 1.0 };
+                let (ua,va) = FACE_AXES[axis];
+                let mut q = [0.0; 3];
+                q[axis] = if sign < 0.0 { lo[axis] - self.radius } else { hi[axis] + self.radius };
+                q[ua] = lo[ua] + (target / area) * (hi[ua] - lo[ua]);
+                q[va] = lo[va] + u.1 * (hi[va] - lo[va]);
+                let n = axis_vector(axis) * sign;
+                return Some(self.transform.sample_to_world((Point::new(q[0],q[1],q[2]),Normal::from(n))))
+            }
+            target -= area;
+        }
+
+        // quarter cylinders, four per axis
+        for (i,&area) in edges.iter().enumerate() {
+            if target < area {
+                let axis = i / 4;
+                let (b,c) = ((axis + 1) % 3,(axis + 2) % 3);
+                let sb = if i % 2 == 0 { -1.0 } else { 1.0 };
+                let sc = if (i / 2) % 2 == 0 { -1.0 } else { 1.0 };
+                let (sin_theta,cos_theta) = f64::sin_cos(0.5 * PI * u.1);
+                let mut n = [0.0; 3];
+                n[b] = sb * cos_theta;
+                n[c] = sc * sin_theta;
+                let mut q = [0.0; 3];
+                q[axis] = lo[axis] + (target / area) * (hi[axis] - lo[axis]);
+                q[b] = if sb < 0.0 { lo[b] } else { hi[b] } + self.radius * n[b];
+                q[c] = if sc < 0.0 { lo[c] } else { hi[c] } + self.radius * n[c];
+                return Some(self.transform.sample_to_world((Point::new(q[0],q[1],q[2]),Normal::new(n[0],n[1],n[2]))))
+            }
+            target -= area;
+        }
+
+        // the corners together make up a sphere, split by octant
+        if corners > 0.0 {
+            let d = uniform_sample_sphere((f64::min(target / corners,1.0),u.1));
+            let corner = Point::new(
+                if d.x < 0.0 { lo[0] } else { hi[0] },
+                if d.y < 0.0 { lo[1] } else { hi[1] },
+                if d.z < 0.0 { lo[2] } else { hi[2] }
+            );
+            return Some(self.transform.sample_to_world((corner + d * self.radius,Normal::from(d))))
+        }
+        None
+    }
+
+    fn intervals(&self,ray: &Ray) -> Vec<(SurfaceInteraction,SurfaceInteraction)> {
+        let r = self.transform.ray_to_object(ray);
+        let Some((enter,exit)) = self.span(&r) else {
+            return vec![]
+        };
+        match (self.interaction(&r,ray,enter),self.interaction(&r,ray,exit)) {
+            (Some(enter),Some(exit)) if enter.t < exit.t => vec![(enter,exit)],
+            _ => vec![]
+        }
+    }
+}
+
+impl Cuboid {
+    /// Construct axis aligned box with sharp edges
+    pub fn new(min: Point,max: Point) -> Cuboid {
+        Cuboid::rounded(min,max,0.0)
+    }
+
+    /// Construct axis aligned box with edges rounded off by radius,
+    /// at most half the smallest side
+    pub fn rounded(min: Point,max: Point,radius: f64) -> Cuboid {
+        let (min,max) = (
+            Point::new(f64::min(min.x,max.x),f64::min(min.y,max.y),f64::min(min.z,max.z)),
+            Point::new(f64::max(min.x,max.x),f64::max(min.y,max.y),f64::max(min.z,max.z))
+        );
+        let extent = max - min;
+        let largest = 0.5 * f64::min(extent.x,f64::min(extent.y,extent.z));
+        Cuboid {
+            min,
+            max,
+            radius: radius.clamp(0.0,largest),
+            transform: Transform::new()
+        }
+    }
+
+    /// Place the box in the world
+    pub fn set_object_to_world(&mut self,object_to_world: &Matrix) -> Result<(),String> {
+        self.transform = Transform::rigid_from_matrix(object_to_world)?;
+        Ok(())
+    }
+
+    /// Box whose dilation by radius gives the rounded box
+    fn inner(&self) -> (Point,Point) {
+        let r = Vector::new(self.radius,self.radius,self.radius);
+        (self.min + r,self.max - r)
+    }
+
+    /// Areas of the flat faces (-x,+x,-y,+y,-z,+z), of the rounded
+    /// edges (four per axis) and of all corners together
+    fn areas(&self) -> ([f64; 6],[f64; 12],f64) {
+        let (lo,hi) = self.inner();
+        let side = components(&(hi - lo));
+        let mut faces = [0.0; 6];
+        for axis in 0..3 {
+            let (ua,va) = FACE_AXES[axis];
+            faces[2*axis] = side[ua] * side[va];
+            faces[2*axis + 1] = side[ua] * side[va];
+        }
+        let mut edges = [0.0; 12];
+        for (i,edge) in edges.iter_mut().enumerate() {
+            *edge = 0.5 * PI * self.radius * side[i / 4];
+        }
+        (faces,edges,4.0 * PI * self.radius * self.radius)
+    }
+
+    /// Where the object space ray enters and leaves the box
+    fn span(&self,ray: &Ray) -> Option<(Crossing,Crossing)> {
+        if self.radius > 0.0 {
+            let (t0,t1) = self.span_rounded(ray)?;
+            Some((self.rounded_hit(ray,t0)?,self.rounded_hit(ray,t1)?))
+        } else {
+            let (t0,t1,near_axis,far_axis) = slabs(ray,&self.min,&self.max)?;
+            Some((self.face_hit(ray,t0,near_axis,-1.0),self.face_hit(ray,t1,far_axis,1.0)))
+        }
+    }
+
+    /// Object space hit moved to a world space interaction, with uv
+    /// taken from the face the normal points out of
+    fn interaction(&self,r: &Ray,ray: &Ray,(t,p,n): Crossing) -> Option<SurfaceInteraction> {
+        let axis = dominant_axis(&n);
+        let (ua,va) = FACE_AXES[axis];
+        let (min,extent) = (coordinates(&self.min),components(&(self.max - self.min)));
+        let q = coordinates(&p);
+        let si = SurfaceInteraction::new(
+            p,
+            n,
+            ((q[ua] - min[ua]) / extent[ua],(q[va] - min[va]) / extent[va]),
+            axis_vector(ua) * extent[ua],
+            axis_vector(va) * extent[va],
+            t,
+            -r.d
+        );
+        self.transform.interaction_to_world(&si,ray)
+    }
+
+    /// Crossing of a sharp box face at t, sign -1 on entry and 1 on exit
+    fn face_hit(&self,ray: &Ray,t: f64,axis: usize,sign: f64) -> Crossing {
+        let d = components(&ray.d);
+        let mut n = [0.0; 3];
+        // entering faces oppose the ray, exiting faces follow it
+        n[axis] = if d[axis] > 0.0 { sign } else { -sign };
+        let mut p = coordinates(&ray.at(t));
+        p[axis] = if n[axis] < 0.0 { coordinates(&self.min)[axis] } else { coordinates(&self.max)[axis] };
+        (t,Point::new(p[0],p[1],p[2]),Normal::new(n[0],n[1],n[2]))
+    }
+
+    /// The rounded box is the convex union of the inner box grown along
+    /// each axis, cylinders along its edges and spheres at its corners,
+    /// so the ray enters it at the first entry into any of them and
+    /// leaves it at the last exit, as ray parameters
+    fn span_rounded(&self,ray: &Ray) -> Option<(f64,f64)> {
+        let (lo,hi) = self.inner();
+        let (lo_c,hi_c) = (coordinates(&lo),coordinates(&hi));
+        let o = coordinates(&ray.o);
+        let d = components(&ray.d);
+        let r = self.radius;
+
+        let mut enter = f64::INFINITY;
+        let mut exit = f64::NEG_INFINITY;
+        let mut add = |interval: Option<(f64,f64)>| {
+            if let Some((t0,t1)) = interval {
+                enter = enter.min(t0);
+                exit = exit.max(t1);
+            }
+        };
+
+        for axis in 0..3 {
+            let grow = axis_vector(axis) * r;
+            add(slabs(ray,&(lo - grow),&(hi + grow)).map(|(t0,t1,_,_)| (t0,t1)));
+        }
+        for axis in 0..3 {
+            let (b,c) = ((axis + 1) % 3,(axis + 2) % 3);
+            let along = slab_1d(o[axis],d[axis],lo_c[axis],hi_c[axis]);
+            for (cb,cc) in [(lo_c[b],lo_c[c]),(hi_c[b],lo_c[c]),(lo_c[b],hi_c[c]),(hi_c[b],hi_c[c])] {
+                let (ob,oc) = (o[b] - cb,o[c] - cc);
+                let around = solve_quadratic(
+                    d[b]*d[b] + d[c]*d[c],
+                    2.0 * (ob*d[b] + oc*d[c]),
+                    ob*ob + oc*oc - r*r
+                ).filter(|(t0,t1)| t0 < t1);
+                add(match (along,around) {
+                    (Some((a0,a1)),Some((c0,c1))) if f64::max(a0,c0) <= f64::min(a1,c1) => Some((f64::max(a0,c0),f64::min(a1,c1))),
+                    _ => None
+                });
+            }
+        }
+        for x in [lo_c[0],hi_c[0]] {
+            for y in [lo_c[1],hi_c[1]] {
+                for z in [lo_c[2],hi_c[2]] {
+                    let m = ray.o - Point::new(x,y,z);
+                    let dd = ray.d.x*ray.d.x + ray.d.y*ray.d.y + ray.d.z*ray.d.z;
+                    let md = m.x*ray.d.x + m.y*ray.d.y + m.z*ray.d.z;
+                    let mm = m.x*m.x + m.y*m.y + m.z*m.z;
+                    add(solve_quadratic(dd,2.0 * md,mm - r*r).filter(|(t0,t1)| t0 < t1));
+                }
+            }
+        }
+
+        if enter > exit {
+            return None
+        }
+        Some((enter,exit))
+    }
+
+    /// Crossing of the rounded box at t, the normal points away from the
+    /// nearest point of the inner box
+    fn rounded_hit(&self,ray: &Ray,t: f64) -> Option<Crossing> {
+        let (lo,hi) = self.inner();
+        let p = ray.at(t);
+        let nearest = Point::new(p.x.clamp(lo.x,hi.x),p.y.clamp(lo.y,hi.y),p.z.clamp(lo.z,hi.z));
+        let n = Normal::from((p - nearest).normalize().ok()?);
+        Some((t,p,n))
+    }
+}
+
+/// Ray parameters where the ray enters and leaves the box spanned by
+/// min and max, with the axes of the entry and exit planes
+pub fn slabs(ray: &Ray,min: &Point,max: &Point) -> Option<(f64,f64,usize,usize)> {
+    let o = coordinates(&ray.o);
+    let d = components(&ray.d);
+    let (min,max) = (coordinates(min),coordinates(max));
+    let (mut t0,mut t1) = (f64::NEG_INFINITY,f64::INFINITY);
+    let (mut near_axis,mut far_axis) = (0,0);
+    for axis in 0..3 {
+        let (near,far) = slab_1d(o[axis],d[axis],min[axis],max[axis])?;
+        if near > t0 {
+            t0 = near;
+            near_axis = axis;
+        }
+        if far < t1 {
+            t1 = far;
+            far_axis = axis;
+        }
+    }
+    if t0 > t1 {
+        return None
+    }
+    Some((t0,t1,near_axis,far_axis))
+}
+
+/// Ray parameter range between the planes min and max of a single axis,
+/// unbounded for parallel rays between them
+fn slab_1d(o: f64,d: f64,min: f64,max: f64) -> Option<(f64,f64)> {
+    if d == 0.0 {
+        return if o >= min && o <= max { Some((f64::NEG_INFINITY,f64::INFINITY)) } else { None }
+    }
+    let (a,b) = ((min - o) / d,(max - o) / d);
+    Some((f64::min(a,b),f64::max(a,b)))
+}
+
+/// Axis along which n is largest
+fn dominant_axis(n: &Normal) -> usize {
+    let a = [n.x.abs(),n.y.abs(),n.z.abs()];
+    if a[0] >= a[1] && a[0] >= a[2] { 0 } else if a[1] >= a[2] { 1 } else { 2 }
+}
+
+/// Coordinates of p as an array indexed by axis
+fn coordinates(p: &Point) -> [f64; 3] {
+    [p.x,p.y,p.z]
+}
+
+/// Components of v as an array indexed by axis
+fn components(v: &Vector) -> [f64; 3] {
+    [v.x,v.y,v.z]
+}
+
+/// Unit vector along an axis
+fn axis_vector(axis: usize) -> Vector {
+    match axis {
+        0 => Vector::new(1.0,0.0,0.0),
+        1 => Vector::new(0.0,1.0,0.0),
+        _ => Vector::new(0.0,0.0,1.0)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::traits::{Dot,Len};
+
+    fn unit() -> Cuboid {
+        Cuboid::new(Point::new(-1.,-1.,-1.),Point::new(1.,1.,1.))
+    }
+
+    #[test]
+    // hits should report the face they cross with its own uvs
+    fn test_intersect() {
+        let cuboid = Cuboid::new(Point::new(0.,0.,0.),Point::new(2.,4.,1.));
+        let ray = Ray::new(&Point::new(-3.,1.,0.25),&Vector::new(1.,0.,0.));
+        let si = cuboid.intersect(&ray,f64::INFINITY).unwrap();
+        assert_eq!(si.t,3.);
+        assert_eq!(si.n.x,-1.);
+        assert_eq!(si.uv,(0.25,0.25));
+        let ray = Ray::new(&Point::new(0.5,10.,0.5),&Vector::new(0.,-2.,0.));
+        let si = cuboid.intersect(&ray,f64::INFINITY).unwrap();
+        assert_eq!(si.t,3.);
+        assert_eq!(si.n.y,1.);
+        assert_eq!(si.uv,(0.25,0.5));
+        assert!(cuboid.intersect(&ray,2.).is_none());
+        // parallel rays outside a slab miss
+        let miss = Ray::new(&Point::new(-3.,5.,0.5),&Vector::new(1.,0.,0.));
+        assert!(cuboid.intersect(&miss,f64::INFINITY).is_none());
+    }
+
+    #[test]
+    // rays from inside should leave through an outward facing face
+    fn test_inside() {
+        let si = unit().intersect(&Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,-1.)),f64::INFINITY).unwrap();
+        assert_eq!(si.t,1.);
+        assert_eq!(si.n.z,-1.);
+        assert!(si.n.dot(si.wo) < 0.);
+    }
+
+    #[test]
+    // a box turned about y should present its edge to the ray
+    fn test_oriented() {
+        let mut cuboid = unit();
+        cuboid.set_object_to_world(&(Matrix::translate(&Vector::new(0.,0.,5.)) * Matrix::rotate_y(0.25 * PI))).unwrap();
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
+        let si = cuboid.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.t - (5. - f64::sqrt(2.))).abs() < 1e-12);
+        assert!(cuboid.set_object_to_world(&Matrix::scale(1.,0.5,1.)).is_err());
+        let ray = Ray::new(&Point::new(0.1,0.,0.),&Vector::new(0.,0.,1.));
+        let si = cuboid.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.n.x.abs() - f64::sqrt(0.5)).abs() < 1e-12);
+        assert!((si.n.z + f64::sqrt(0.5)).abs() < 1e-12);
+    }
+
+    #[test]
+    // rounded boxes should match sharp ones on faces and cut their corners
+    fn test_rounded() {
+        let rounded = Cuboid::rounded(Point::new(-1.,-1.,-1.),Point::new(1.,1.,1.),0.5);
+        let ray = Ray::new(&Point::new(0.2,0.1,-5.),&Vector::new(0.,0.,1.));
+        let (a,b) = (rounded.intersect(&ray,f64::INFINITY).unwrap(),unit().intersect(&ray,f64::INFINITY).unwrap());
+        assert!((a.t - b.t).abs() < 1e-12);
+        assert!((a.n.z + 1.).abs() < 1e-12);
+        // along the diagonal the corner sphere is hit
+        let d = Vector::new(-1.,-1.,-1.).normalize().unwrap();
+        let ray = Ray::new(&(Point::new(0.,0.,0.) - d * 5.),&d);
+        let si = rounded.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.t - (5. - f64::sqrt(0.75) - 0.5)).abs() < 1e-12);
+        assert!((Vector::from(si.n) + d).len() < 1e-12);
+        // past an edge, where the sharp box would still be hit
+        let ray = Ray::new(&Point::new(0.95,0.95,-5.),&Vector::new(0.,0.,1.));
+        assert!(unit().intersect(&ray,f64::INFINITY).is_some());
+        assert!(rounded.intersect(&ray,f64::INFINITY).is_none());
+        // across the edge cylinder
+        let ray = Ray::new(&Point::new(0.8,0.,-5.),&Vector::new(0.,0.,1.));
+        let si = rounded.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.p.z + 0.5 + f64::sqrt(0.25 - 0.09)).abs() < 1e-12);
+        assert!((si.n.x - 0.6).abs() < 1e-12);
+        // from inside through the corner
+        let si = rounded.intersect(&Ray::new(&Point::new(0.,0.,0.),&(-d)),f64::INFINITY).unwrap();
+        assert!((si.t - (f64::sqrt(0.75) + 0.5)).abs() < 1e-12);
+        assert!(si.n.dot(-d) > 0.999);
+    }
+
+    #[test]
+    // samples should lie on the surface, spread by area
+    fn test_sample() {
+        let sharp = Cuboid::new(Point::new(0.,0.,0.),Point::new(1.,2.,3.));
+        assert!((sharp.area() - 22.).abs() < 1e-12);
+        let rounded = Cuboid::rounded(Point::new(-1.,-1.,-1.),Point::new(1.,1.,1.),0.5);
+        let expected = 6. + 3. * PI + PI;
+        assert!((rounded.area() - expected).abs() < 1e-12);
+        let mut flat = 0;
+        for i in 0..2000 {
+            let (p,n) = rounded.sample(((i as f64 + 0.5) / 2000.,((i % 37) as f64 + 0.5) / 37.)).unwrap();
+            let nearest = Point::new(p.x.clamp(-0.5,0.5),p.y.clamp(-0.5,0.5),p.z.clamp(-0.5,0.5));
+            assert!(((p - nearest).len() - 0.5).abs() < 1e-12);
+            assert!((Vector::from(n) - (p - nearest) * 2.).len() < 1e-9);
+            if [p.x,p.y,p.z].iter().filter(|c| c.abs() <= 0.5).count() == 2 {
+                flat += 1;
+            }
+        }
+        assert!((flat as f64 / 2000. - 6. / expected).abs() < 0.01);
+        for i in 0..100 {
+            let (p,_) = sharp.sample(((i as f64 + 0.5) / 100.,0.3)).unwrap();
+            let on_face = [p.x,p.y,p.z].iter().zip([1.,2.,3.]).any(|(c,m)| c.abs() < 1e-12 || (c - m).abs() < 1e-12);
+            assert!(on_face);
+        }
+    }
+}
@@ -0,0 +1,393 @@
+use super::{
+    traits::Primitive,
+    interaction::SurfaceInteraction,
+    transform::Transform
+};
+use crate::math::{
+    point::Point,
+    vector::Vector,
+    normal::Normal,
+    matrix::Matrix,
+    ray::Ray,
+    frame::Frame,
+    traits::{Dot,Cross,Len,Normalize}
+};
+
+/// Deepest subdivision of a curve segment
+const MAX_DEPTH: usize = 10;
+
+/// # CurveMode
+/// How the width of a curve is spread around its center line
+///
+/// # Parameters
+/// * Flat (ribbon always facing the ray, for thin hair seen from afar)
+/// * Cylinder (ribbon facing the ray, shaded as if it were a tube)
+/// * Oriented (ribbon facing the normals given at both ends, for grass and leaves)
+#[derive(Clone,Copy)]
+pub enum CurveMode {
+    Flat,
+    Cylinder,
+    Oriented(Normal,Normal)
+}
+
+/// # Curve
+/// Cubic Bezier curve with a width varying linearly along it. Rays are
+/// intersected in a space where they run along +z from the origin, the
+/// curve is split in halves until its pieces are nearly straight and
+/// pieces whose bounds miss the ray are dropped on the way. uv is the
+/// position along the curve and across its width
+///
+/// # Parameters
+/// * points (control points in object space)
+/// * width (width at the start and the end)
+/// * mode (how the width is spread)
+/// * u_range (range of u covered when the curve is part of a longer one)
+/// * transform (placement in the world)
+pub struct Curve {
+    pub points: [Point; 4],
+    pub width: (f64,f64),
+    pub mode: CurveMode,
+    pub u_range: (f64,f64),
+    pub transform: Transform,
+    depth: usize
+}
+
+/// Crossing of the ray with a piece of the curve
+///
+/// # Parameters
+/// * z (distance along the unit ray direction)
+/// * u (curve parameter)
+/// * offset (ray space offset in x and y from the center line to the ray)
+/// * width (width of the curve seen from the ray)
+struct CurveHit {
+    z: f64,
+    u: f64,
+    offset: (f64,f64),
+    width: f64
+}
+
+/// Primitive trait
+impl Primitive for Curve {
+    fn intersect(&self,ray: &Ray,tmax: f64) -> Option<SurfaceInteraction> {
+        let r = self.transform.ray_to_object(ray);
+        let length = r.d.len();
+        let d = r.d.normalize().ok()?;
+        let frame = Frame::from_normal(&Normal::from(d));
+        let to_ray = |p: &Point| {
+            let v = *p - r.o;
+            Point::new(v.dot(frame.s),v.dot(frame.t),v.dot(frame.n))
+        };
+        let cp = self.points.map(|p| to_ray(&p));
+        let mut zmax = tmax * length;
+        let hit = self.recurse(&cp,(0.0,1.0),self.depth,&mut zmax,&d)?;
+
+        let (_,dpdu) = evaluate(&self.points,hit.u);
+        let tangent = Vector::new(dpdu.dot(frame.s),dpdu.dot(frame.t),dpdu.dot(frame.n));
+        let (ox,oy) = hit.offset;
+        // ray space normal, facing back along the ray
+        let n_ray = match self.mode {
+            CurveMode::Flat => Vector::new(0.0,0.0,-1.0),
+            CurveMode::Cylinder => {
+                // point on a tube around the center line seen at the offset
+                let radius = 0.5 * hit.width;
+                let sin = (f64::sqrt(ox*ox + oy*oy) / radius).min(1.0);
+                Vector::new(ox / radius,oy / radius,-f64::sqrt(1.0 - sin*sin))
+            },
+            CurveMode::Oriented(n0,n1) => {
+                let n = Vector::from(slerp(&n0,&n1,hit.u));
+                let n = Vector::new(n.dot(frame.s),n.dot(frame.t),n.dot(frame.n));
+                if n.z > 0.0 { -n } else { n }
+            }
+        };
+        let t = tangent.normalize().ok()?;
+        let n_ray = (n_ray - t * n_ray.dot(t)).normalize().ok()?;
+        let across = n_ray.cross(tangent).normalize().ok()?;
+        let to_object = |v: &Vector| frame.s * v.x + frame.t * v.y + frame.n * v.z;
+
+        // v grows along dpdv, the center line at one half
+        let flat = Vector::new(across.x,across.y,0.0).normalize().unwrap_or(across);
+        let v = (0.5 + (ox * flat.x + oy * flat.y) / hit.width).clamp(0.0,1.0);
+        let (u0,u1) = self.u_range;
+        let si = SurfaceInteraction::new(
+            r.o + d * hit.z,
+            Normal::from(to_object(&n_ray)),
+            (u0 + (u1 - u0) * hit.u,v),
+            dpdu,
+            to_object(&across) * lerp(hit.u,self.width.0,self.width.1),
+            hit.z / length,
+            -r.d
+        );
+        self.transform.interaction_to_world(&si,ray)
+    }
+}
+
+impl Curve {
+    /// Construct curve from its control points and end widths
+    pub fn new(points: [Point; 4],width: (f64,f64),mode: CurveMode) -> Curve {
+        // enough halvings for the pieces to deviate from straight by a
+        // twentieth of the width (pbrt, "Curves")
+        let mut l0: f64 = 0.0;
+        for i in 0..2 {
+            let v = (points[i] - points[i + 1]) + (points[i + 2] - points[i + 1]);
+            l0 = l0.max(v.x.abs()).max(v.y.abs()).max(v.z.abs());
+        }
+        let epsilon = 0.05 * width.0.max(width.1);
+        let ratio = std::f64::consts::SQRT_2 * 6.0 * l0 / (8.0 * epsilon);
+        let depth = if ratio > 1.0 { (ratio.log2().floor() / 2.0) as usize } else { 0 };
+        Curve {
+            points,
+            width,
+            mode,
+            u_range: (0.0,1.0),
+            transform: Transform::new(),
+            depth: depth.min(MAX_DEPTH)
+        }
+    }
+
+    /// Split a piecewise cubic Bezier of 3 n + 1 control points into its
+    /// n segments, the widths and orientations are interpolated along
+    /// the whole curve
+    pub fn piecewise(points: &[Point],width: (f64,f64),mode: CurveMode) -> Result<Vec<Curve>,String> {
+        if points.len() < 4 || !(points.len() - 1).is_multiple_of(3) {
+            return Err(format!("piecewise cubic curve needs 3 n + 1 control points, found {}",points.len()))
+        }
+        let n = (points.len() - 1) / 3;
+        Ok((0..n).map(|i| {
+            let (u0,u1) = (i as f64 / n as f64,(i + 1) as f64 / n as f64);
+            let mode = match mode {
+                CurveMode::Oriented(n0,n1) => CurveMode::Oriented(slerp(&n0,&n1,u0),slerp(&n0,&n1,u1)),
+                other => other
+            };
+            let segment = [points[3*i],points[3*i + 1],points[3*i + 2],points[3*i + 3]];
+            let mut curve = Curve::new(segment,(lerp(u0,width.0,width.1),lerp(u1,width.0,width.1)),mode);
+            curve.u_range = (u0,u1);
+            curve
+        }).collect())
+    }
+
+    /// Place the curve in the world
+    pub fn set_object_to_world(&mut self,object_to_world: &Matrix) -> Result<(),String> {
+        self.transform = Transform::from_matrix(object_to_world)?;
+        Ok(())
+    }
+
+    /// World space box (min,max) enclosing the curve, the placed corners
+    /// of the control points' box grown by half the width
+    pub fn bounds(&self) -> (Point,Point) {
+        let half = 0.5 * self.width.0.max(self.width.1);
+        let (lo,hi) = extent(&self.points);
+        let mut min = Point::new(f64::INFINITY,f64::INFINITY,f64::INFINITY);
+        let mut max = Point::new(f64::NEG_INFINITY,f64::NEG_INFINITY,f64::NEG_INFINITY);
+        for x in [lo.x - half,hi.x + half] {
+            for y in [lo.y - half,hi.y + half] {
+                for z in [lo.z - half,hi.z + half] {
+                    let p = self.transform.point_to_world(&Point::new(x,y,z));
+                    min = Point::new(min.x.min(p.x),min.y.min(p.y),min.z.min(p.z));
+                    max = Point::new(max.x.max(p.x),max.y.max(p.y),max.z.max(p.z));
+                }
+            }
+        }
+        (min,max)
+    }
+
+    /// Nearest crossing before zmax of the ray along +z with the piece cp
+    /// covering u in range, zmax shrinks to every crossing found
+    fn recurse(&self,cp: &[Point; 4],range: (f64,f64),depth: usize,zmax: &mut f64,d: &Vector) -> Option<CurveHit> {
+        let (u0,u1) = range;
+        let half = 0.5 * lerp(u0,self.width.0,self.width.1).max(lerp(u1,self.width.0,self.width.1));
+        let (lo,hi) = extent(cp);
+        if lo.x - half > 0.0 || hi.x + half < 0.0 || lo.y - half > 0.0 || hi.y + half < 0.0
+            || hi.z + half < 0.0 || lo.z - half > *zmax {
+            return None
+        }
+
+        if depth > 0 {
+            let [a,b,c,m,e,f,g] = split(cp);
+            let mid = 0.5 * (u0 + u1);
+            let first = self.recurse(&[a,b,c,m],(u0,mid),depth - 1,zmax,d);
+            let second = self.recurse(&[m,e,f,g],(mid,u1),depth - 1,zmax,d);
+            // the second half only reports hits nearer than the first
+            return second.or(first)
+        }
+
+        // the ray must lie between the planes normal to the piece at its ends
+        let edge = (cp[1].y - cp[0].y) * -cp[0].y + cp[0].x * (cp[0].x - cp[1].x);
+        if edge < 0.0 {
+            return None
+        }
+        let edge = (cp[2].y - cp[3].y) * -cp[3].y + cp[3].x * (cp[3].x - cp[2].x);
+        if edge < 0.0 {
+            return None
+        }
+
+        // closest approach of the chord to the ray
+        let (sx,sy) = (cp[3].x - cp[0].x,cp[3].y - cp[0].y);
+        let denom = sx*sx + sy*sy;
+        if denom == 0.0 {
+            return None
+        }
+        let w = ((-cp[0].x * sx - cp[0].y * sy) / denom).clamp(0.0,1.0);
+        let u = lerp(w,u0,u1);
+        let mut width = lerp(u,self.width.0,self.width.1);
+        if let CurveMode::Oriented(n0,n1) = self.mode {
+            width *= slerp(&n0,&n1,u).dot(*d).abs();
+        }
+        let (pc,_) = evaluate(cp,w);
+        if pc.x*pc.x + pc.y*pc.y > 0.25 * width * width || pc.z < 0.0 || pc.z > *zmax {
+            return None
+        }
+        *zmax = pc.z;
+        Some(CurveHit {
+            z: pc.z,
+            u,
+            offset: (-pc.x,-pc.y),
+            width
+        })
+    }
+}
+
+/// Point and derivative of the cubic Bezier cp at u
+fn evaluate(cp: &[Point; 4],u: f64) -> (Point,Vector) {
+    let a = [lerp_point(u,&cp[0],&cp[1]),lerp_point(u,&cp[1],&cp[2]),lerp_point(u,&cp[2],&cp[3])];
+    let b = [lerp_point(u,&a[0],&a[1]),lerp_point(u,&a[1],&a[2])];
+    let derivative = if (b[1] - b[0]).len() > 0.0 {
+        (b[1] - b[0]) * 3.0
+    } else {
+        // degenerate end, fall back to the chord
+        cp[3] - cp[0]
+    };
+    (lerp_point(u,&b[0],&b[1]),derivative)
+}
+
+/// Control points of both halves of the cubic Bezier cp, sharing the middle one
+fn split(cp: &[Point; 4]) -> [Point; 7] {
+    let a = [lerp_point(0.5,&cp[0],&cp[1]),lerp_point(0.5,&cp[1],&cp[2]),lerp_point(0.5,&cp[2],&cp[3])];
+    let b = [lerp_point(0.5,&a[0],&a[1]),lerp_point(0.5,&a[1],&a[2])];
+    [cp[0],a[0],b[0],lerp_point(0.5,&b[0],&b[1]),b[1],a[2],cp[3]]
+}
+
+/// Box (min,max) of the control points
+fn extent(cp: &[Point; 4]) -> (Point,Point) {
+    cp.iter().fold(
+        (Point::new(f64::INFINITY,f64::INFINITY,f64::INFINITY),Point::new(f64::NEG_INFINITY,f64::NEG_INFINITY,f64::NEG_INFINITY)),
+        |(lo,hi),p| (
+            Point::new(lo.x.min(p.x),lo.y.min(p.y),lo.z.min(p.z)),
+            Point::new(hi.x.max(p.x),hi.y.max(p.y),hi.z.max(p.z))
+        )
+    )
+}
+
+fn lerp(t: f64,a: f64,b: f64) -> f64 {
+    a + (b - a) * t
+}
+
+fn lerp_point(t: f64,a: &Point,b: &Point) -> Point {
+    *a + (*b - *a) * t
+}
+
+/// Spherical interpolation between unit normals
+fn slerp(n0: &Normal,n1: &Normal,t: f64) -> Normal {
+    let cos = n0.dot(Vector::from(*n1)).clamp(-1.0,1.0);
+    let angle = cos.acos();
+    if angle.sin() < 1e-9 {
+        return (*n0 * (1.0 - t) + *n1 * t).normalize().unwrap_or(*n0)
+    }
+    let (a,b) = (((1.0 - t) * angle).sin(),(t * angle).sin());
+    (*n0 * a + *n1 * b) * (1.0 / angle.sin())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // straight curve along x of width 0.2 at the start and 0.1 at the end
+    fn straight(mode: CurveMode) -> Curve {
+        let points = [0.,1.,2.,3.].map(|x| Point::new(x,0.,0.));
+        Curve::new(points,(0.2,0.1),mode)
+    }
+
+    #[test]
+    // rays should hit within the varying width only
+    fn test_intersect() {
+        let curve = straight(CurveMode::Flat);
+        let down = |x: f64,z: f64| Ray::new(&Point::new(x,5.,z),&Vector::new(0.,-1.,0.));
+        let si = curve.intersect(&down(1.5,0.),f64::INFINITY).unwrap();
+        assert!((si.t - 5.).abs() < 1e-12);
+        assert!((si.uv.0 - 0.5).abs() < 1e-12 && (si.uv.1 - 0.5).abs() < 1e-12);
+        assert!((si.n.y - 1.).abs() < 1e-12);
+        // width is 0.15 at the middle
+        let si = curve.intersect(&down(1.5,0.07),f64::INFINITY).unwrap();
+        assert!((si.uv.1 - 0.5).abs() > 0.45);
+        assert!(curve.intersect(&down(1.5,0.08),f64::INFINITY).is_none());
+        assert!(curve.intersect(&down(0.5,0.09),f64::INFINITY).is_some());
+        assert!(curve.intersect(&down(3.1,0.),f64::INFINITY).is_none());
+        assert!(curve.intersect(&down(1.5,0.),4.).is_none());
+        // across both sides v should run along dpdv
+        let a = curve.intersect(&down(1.5,0.05),f64::INFINITY).unwrap();
+        let b = curve.intersect(&down(1.5,-0.05),f64::INFINITY).unwrap();
+        assert!((a.uv.1 - b.uv.1) * a.dpdv.z > 0.);
+    }
+
+    #[test]
+    // a bent curve should be hit along its center line
+    fn test_bent() {
+        let points = [Point::new(0.,0.,0.),Point::new(0.,0.,1.),Point::new(1.,0.,1.),Point::new(1.,0.,2.)];
+        let curve = Curve::new(points,(0.05,0.05),CurveMode::Flat);
+        assert!(curve.depth > 0);
+        for i in 1..10 {
+            let u = 0.1 * i as f64;
+            let (p,_) = evaluate(&points,u);
+            let ray = Ray::new(&Point::new(p.x,3.,p.z),&Vector::new(0.,-1.,0.));
+            let si = curve.intersect(&ray,f64::INFINITY).unwrap();
+            assert!((si.uv.0 - u).abs() < 0.02,"{} {}",u,si.uv.0);
+            assert!(si.n.dot(si.dpdu).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    // tubes should face outwards towards their edges
+    fn test_cylinder() {
+        let curve = straight(CurveMode::Cylinder);
+        let ray = Ray::new(&Point::new(1.5,5.,0.06),&Vector::new(0.,-1.,0.));
+        let si = curve.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.n.z - 0.8).abs() < 1e-9 && (si.n.y - 0.6).abs() < 1e-9);
+        assert!(si.n.dot(si.dpdv).abs() < 1e-12 && si.n.dot(si.dpdu).abs() < 1e-12);
+        let ray = Ray::new(&Point::new(1.5,5.,-0.06),&Vector::new(0.,-1.,0.));
+        assert!((curve.intersect(&ray,f64::INFINITY).unwrap().n.z + 0.8).abs() < 1e-9);
+    }
+
+    #[test]
+    // oriented ribbons should narrow when seen edge on
+    fn test_oriented() {
+        let n = Normal::new(0.,0.6,0.8);
+        let curve = straight(CurveMode::Oriented(n,n));
+        let down = |z: f64| Ray::new(&Point::new(1.5,5.,z),&Vector::new(0.,-1.,0.));
+        // seen width is 0.15 * 0.6
+        assert!(curve.intersect(&down(0.04),f64::INFINITY).is_some());
+        assert!(curve.intersect(&down(0.05),f64::INFINITY).is_none());
+        let si = curve.intersect(&down(0.),f64::INFINITY).unwrap();
+        assert!((si.n.y - 0.6).abs() < 1e-12 && (si.n.z - 0.8).abs() < 1e-12);
+        let sideways = Ray::new(&Point::new(1.5,0.,5.),&Vector::new(0.,0.,-1.));
+        assert!(curve.intersect(&sideways,f64::INFINITY).is_some());
+    }
+
+    #[test]
+    // piecewise curves should cover the whole parameter range
+    fn test_piecewise() {
+        let points: Vec<Point> = (0..7).map(|i| Point::new(i as f64,0.,0.)).collect();
+        let mut segments = Curve::piecewise(&points,(0.2,0.),CurveMode::Flat).unwrap();
+        assert_eq!(segments.len(),2);
+        assert_eq!((segments[1].u_range,segments[1].width),((0.5,1.),(0.1,0.)));
+        segments[1].set_object_to_world(&Matrix::translate(&Vector::new(0.,1.,0.))).unwrap();
+        let ray = Ray::new(&Point::new(3.75,5.,0.),&Vector::new(0.,-1.,0.));
+        let si = segments[1].intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.uv.0 - 0.625).abs() < 1e-12 && (si.p.y - 1.).abs() < 1e-12);
+        let (min,max) = segments[1].bounds();
+        assert!((min.y - 0.95).abs() < 1e-12 && (max.x - 6.05).abs() < 1e-12);
+        assert!(Curve::piecewise(&points[..5],(0.1,0.1),CurveMode::Flat).is_err());
+    }
+}
@@ -0,0 +1,228 @@
+use std::f64::consts::PI;
+
+use super::{
+    traits::Primitive,
+    interaction::SurfaceInteraction,
+    transform::Transform,
+    quadric::{azimuth,nearest_root}
+};
+use crate::math::{
+    point::Point,
+    vector::Vector,
+    normal::Normal,
+    matrix::Matrix,
+    ray::Ray,
+    polynomial::solve_quadratic
+};
+
+/// # Cylinder
+/// Open cylinder about the y axis of object space clipped to
+/// y_min <= y <= y_max and swept from phi = 0 to phi_max
+///
+/// # Parameters
+/// * radius (distance of the surface from the axis)
+/// * y_min (bottom of the cylinder)
+/// * y_max (top of the cylinder)
+/// * phi_max (sweep angle in (0,2 pi])
+/// * transform (placement in the world)
+pub struct Cylinder {
+    pub radius: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+    pub phi_max: f64,
+    pub transform: Transform
+}
+
+/// Primitive trait
+impl Primitive for Cylinder {
+    fn intersect(&self,ray: &Ray,tmax: f64) -> Option<SurfaceInteraction> {
+        let r = self.transform.ray_to_object(ray);
+        let (o,d) = (r.o,r.d);
+        let coefficients = (
+            d.x*d.x + d.z*d.z,
+            2.0 * (d.x*o.x + d.z*o.z),
+            o.x*o.x + o.z*o.z - self.radius*self.radius
+        );
+        let (t,p) = nearest_root(&r,tmax,coefficients,|p| {
+            p.y >= self.y_min && p.y <= self.y_max && azimuth(p.x,p.z) <= self.phi_max
+        })?;
+
+        let si = self.side_interaction(&p,t,-d);
+        self.transform.interaction_to_world(&si,ray)
+    }
+
+    fn area(&self) -> f64 {
+        (self.y_max - self.y_min) * self.radius * self.phi_max
+    }
+
+    fn sample(&self,u: (f64,f64)) -> Option<(Point,Normal)> {
+        let y = self.y_min + u.0 * (self.y_max - self.y_min);
+        let (sin_phi,cos_phi) = f64::sin_cos(u.1 * self.phi_max);
+        Some(self.transform.sample_to_world((
+            Point::new(self.radius * cos_phi,y,self.radius * sin_phi),
+            Normal::new(cos_phi,0.0,sin_phi)
+        )))
+    }
+
+    /// A full cylinder bounds the solid closed off by caps at y_min and
+    /// y_max, partial sweeps enclose no volume
+    fn intervals(&self,ray: &Ray) -> Vec<(SurfaceInteraction,SurfaceInteraction)> {
+        if self.phi_max < 2.0 * PI {
+            return vec![]
+        }
+        let r = self.transform.ray_to_object(ray);
+        let (o,d) = (r.o,r.d);
+
+        // inside the infinite tube, then between the cap planes
+        let a = d.x*d.x + d.z*d.z;
+        let c = o.x*o.x + o.z*o.z - self.radius*self.radius;
+        let (side0,side1) = if a == 0.0 {
+            if c > 0.0 {
+                return vec![]
+            }
+            (f64::NEG_INFINITY,f64::INFINITY)
+        } else {
+            match solve_quadratic(a,2.0 * (d.x*o.x + d.z*o.z),c) {
+                Some((t0,t1)) if t0 < t1 => (t0,t1),
+                _ => return vec![]
+            }
+        };
+        let (cap0,cap1) = if d.y == 0.0 {
+            if o.y < self.y_min || o.y > self.y_max {
+                return vec![]
+            }
+            (f64::NEG_INFINITY,f64::INFINITY)
+        } else {
+            let (a,b) = ((self.y_min - o.y) / d.y,(self.y_max - o.y) / d.y);
+            (f64::min(a,b),f64::max(a,b))
+        };
+        let (t0,t1) = (f64::max(side0,cap0),f64::min(side1,cap1));
+        if t0 >= t1 {
+            return vec![]
+        }
+
+        let boundary = |t: f64,on_side: bool| {
+            let p = r.at(t);
+            let si = if on_side {
+                self.side_interaction(&p,t,-d)
+            } else {
+                self.cap_interaction(&p,t,-d)
+            };
+            self.transform.interaction_to_world(&si,ray)
+        };
+        match (boundary(t0,side0 >= cap0),boundary(t1,side1 <= cap1)) {
+            (Some(enter),Some(exit)) => vec![(enter,exit)],
+            _ => vec![]
+        }
+    }
+}
+
+impl Cylinder {
+    /// Construct cylinder about the y axis
+    pub fn new(radius: f64,y_min: f64,y_max: f64,phi_max: f64) -> Cylinder {
+        Cylinder {
+            radius,
+            y_min: f64::min(y_min,y_max),
+            y_max: f64::max(y_min,y_max),
+            phi_max: phi_max.clamp(0.0,2.0 * PI),
+            transform: Transform::new()
+        }
+    }
+
+    /// Place the cylinder in the world
+    pub fn set_object_to_world(&mut self,object_to_world: &Matrix) -> Result<(),String> {
+        self.transform = Transform::rigid_from_matrix(object_to_world)?;
+        Ok(())
+    }
+
+    /// Object space interaction at point p on the curved side
+    fn side_interaction(&self,p: &Point,t: f64,wo: Vector) -> SurfaceInteraction {
+        let phi = azimuth(p.x,p.z);
+        SurfaceInteraction::new(
+            *p,
+            Normal::new(p.x / self.radius,0.0,p.z / self.radius),
+            (phi / self.phi_max,(p.y - self.y_min) / (self.y_max - self.y_min)),
+            Vector::new(-self.phi_max * p.z,0.0,self.phi_max * p.x),
+            Vector::new(0.0,self.y_max - self.y_min,0.0),
+            t,
+            wo
+        )
+    }
+
+    /// Object space interaction at point p on one of the caps closing a
+    /// full cylinder, parameterized like a disk with v running inwards
+    fn cap_interaction(&self,p: &Point,t: f64,wo: Vector) -> SurfaceInteraction {
+        let top = p.y >= 0.5 * (self.y_min + self.y_max);
+        let y = if top { self.y_max } else { self.y_min };
+        let dist = f64::sqrt(p.x*p.x + p.z*p.z);
+        let (cos_phi,sin_phi) = if dist > 0.0 { (p.x / dist,p.z / dist) } else { (1.0,0.0) };
+        SurfaceInteraction::new(
+            Point::new(p.x,y,p.z),
+            Normal::new(0.0,if top { 1.0 } else { -1.0 },0.0),
+            (azimuth(p.x,p.z) / self.phi_max,(self.radius - dist) / self.radius),
+            Vector::new(-self.phi_max * p.z,0.0,self.phi_max * p.x),
+            Vector::new(cos_phi,0.0,sin_phi) * -self.radius,
+            t,
+            wo
+        )
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::traits::Dot;
+
+    #[test]
+    // near side hits should face the ray, clipped ones fall through to the far side
+    fn test_intersect() {
+        let cylinder = Cylinder::new(1.,-1.,1.,2. * PI);
+        let ray = Ray::new(&Point::new(-5.,0.,0.),&Vector::new(1.,0.,0.));
+        let si = cylinder.intersect(&ray,f64::INFINITY).unwrap();
+        assert_eq!(si.t,4.);
+        assert_eq!(si.n.x,-1.);
+        assert_eq!(si.uv,(0.5,0.5));
+        // outside the height range
+        let above = Ray::new(&Point::new(-5.,1.5,0.),&Vector::new(1.,0.,0.));
+        assert!(cylinder.intersect(&above,f64::INFINITY).is_none());
+        // the open end lets the ray through to the inside of the far wall
+        let slanted = Ray::new(&Point::new(-3.,4.,0.),&Vector::new(1.,-1.,0.));
+        let si = cylinder.intersect(&slanted,f64::INFINITY).unwrap();
+        assert!((si.p.x - 1.).abs() < 1e-12);
+        assert!(si.n.dot(si.wo) < 0.);
+        // half cylinder keeps only z >= 0
+        let half = Cylinder::new(1.,-1.,1.,PI);
+        let ray = Ray::new(&Point::new(0.,0.,-5.),&Vector::new(0.,0.,1.));
+        let si = half.intersect(&ray,f64::INFINITY).unwrap();
+        assert_eq!(si.t,6.);
+        assert_eq!(si.n.z,1.);
+    }
+
+    #[test]
+    // a placed cylinder should move its surface and normals with it
+    fn test_placed() {
+        let mut cylinder = Cylinder::new(1.,-1.,1.,2. * PI);
+        cylinder.set_object_to_world(&(Matrix::translate(&Vector::new(0.,0.,3.)) * Matrix::rotate_y(0.5))).unwrap();
+        let ray = Ray::new(&Point::new(-5.,0.,3.),&Vector::new(1.,0.,0.));
+        let si = cylinder.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.t - 4.).abs() < 1e-12);
+        assert!((si.n.x + 1.).abs() < 1e-12);
+        assert!(si.dpdu.dot(Vector::from(si.n)).abs() < 1e-12);
+        assert!(cylinder.set_object_to_world(&Matrix::scale(2.,1.,1.)).is_err());
+    }
+
+    #[test]
+    // samples should lie on the clipped surface with outward normals
+    fn test_sample() {
+        let cylinder = Cylinder::new(2.,0.,3.,PI);
+        assert!((cylinder.area() - 6. * PI).abs() < 1e-12);
+        let (p,n) = cylinder.sample((0.25,0.5)).unwrap();
+        assert!((p.x*p.x + p.z*p.z - 4.).abs() < 1e-12);
+        assert!((p.y - 0.75).abs() < 1e-12);
+        assert!((n.z - 1.).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,167 @@
+use std::f64::consts::PI;
+
+use super::{
+    traits::Primitive,
+    interaction::SurfaceInteraction,
+    transform::Transform,
+    quadric::azimuth
+};
+use crate::math::{
+    point::Point,
+    vector::Vector,
+    normal::Normal,
+    matrix::Matrix,
+    ray::Ray
+};
+
+/// # Disk
+/// Flat annulus in the plane y = height of object space facing +y,
+/// swept about the y axis from phi = 0 to phi_max
+///
+/// # Parameters
+/// * height (y coordinate of the disk)
+/// * radius (outer radius)
+/// * inner_radius (radius of the hole, 0 for a full disk)
+/// * phi_max (sweep angle in (0,2 pi])
+/// * transform (placement in the world)
+pub struct Disk {
+    pub height: f64,
+    pub radius: f64,
+    pub inner_radius: f64,
+    pub phi_max: f64,
+    pub transform: Transform
+}
+
+/// Primitive trait
+impl Primitive for Disk {
+    fn intersect(&self,ray: &Ray,tmax: f64) -> Option<SurfaceInteraction> {
+        let r = self.transform.ray_to_object(ray);
+        if r.d.y == 0.0 {
+            return None
+        }
+        let t = (self.height - r.o.y) / r.d.y;
+        if t <= 0.0 || t >= tmax {
+            return None
+        }
+        let mut p = r.at(t);
+        p.y = self.height;
+        let dist2 = p.x*p.x + p.z*p.z;
+        if dist2 > self.radius*self.radius || dist2 < self.inner_radius*self.inner_radius {
+            return None
+        }
+        let phi = azimuth(p.x,p.z);
+        if phi > self.phi_max {
+            return None
+        }
+
+        // v runs inwards from the rim
+        let dist = f64::sqrt(dist2);
+        let (cos_phi,sin_phi) = if dist > 0.0 { (p.x / dist,p.z / dist) } else { (1.0,0.0) };
+        let dpdu = Vector::new(-self.phi_max * p.z,0.0,self.phi_max * p.x);
+        let dpdv = Vector::new(cos_phi,0.0,sin_phi) * (self.inner_radius - self.radius);
+        let si = SurfaceInteraction::new(
+            p,
+            Normal::new(0.0,1.0,0.0),
+            (phi / self.phi_max,(self.radius - dist) / (self.radius - self.inner_radius)),
+            dpdu,
+            dpdv,
+            t,
+            -r.d
+        );
+        self.transform.interaction_to_world(&si,ray)
+    }
+
+    fn area(&self) -> f64 {
+        0.5 * self.phi_max * (self.radius*self.radius - self.inner_radius*self.inner_radius)
+    }
+
+    fn sample(&self,u: (f64,f64)) -> Option<(Point,Normal)> {
+        let inner2 = self.inner_radius * self.inner_radius;
+        let r = f64::sqrt(inner2 + u.0 * (self.radius*self.radius - inner2));
+        let (sin_phi,cos_phi) = f64::sin_cos(u.1 * self.phi_max);
+        Some(self.transform.sample_to_world((
+            Point::new(r * cos_phi,self.height,r * sin_phi),
+            Normal::new(0.0,1.0,0.0)
+        )))
+    }
+}
+
+impl Disk {
+    /// Construct disk centered on the y axis
+    pub fn new(height: f64,radius: f64,inner_radius: f64,phi_max: f64) -> Disk {
+        Disk {
+            height,
+            radius,
+            inner_radius: inner_radius.clamp(0.0,radius),
+            phi_max: phi_max.clamp(0.0,2.0 * PI),
+            transform: Transform::new()
+        }
+    }
+
+    /// Place the disk in the world
+    pub fn set_object_to_world(&mut self,object_to_world: &Matrix) -> Result<(),String> {
+        self.transform = Transform::rigid_from_matrix(object_to_world)?;
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn down(x: f64,z: f64) -> Ray {
+        Ray::new(&Point::new(x,5.,z),&Vector::new(0.,-1.,0.))
+    }
+
+    #[test]
+    // hits should respect the hole and the sweep
+    fn test_intersect() {
+        let disk = Disk::new(1.,2.,0.5,1.5 * PI);
+        let si = disk.intersect(&down(1.,0.),f64::INFINITY).unwrap();
+        assert_eq!(si.t,4.);
+        assert_eq!(si.n.y,1.);
+        assert_eq!(si.uv,(0.,2. / 3.));
+        assert!(disk.intersect(&down(0.2,0.),f64::INFINITY).is_none());
+        assert!(disk.intersect(&down(2.5,0.),f64::INFINITY).is_none());
+        // the last quarter is swept away
+        assert!(disk.intersect(&down(1.,-1.),f64::INFINITY).is_none());
+        assert!(disk.intersect(&down(-1.,-1.),f64::INFINITY).is_some());
+        assert!(disk.intersect(&down(1.,0.),3.).is_none());
+        // parallel rays miss
+        assert!(disk.intersect(&Ray::new(&Point::new(0.,1.,0.),&Vector::new(1.,0.,0.)),f64::INFINITY).is_none());
+    }
+
+    #[test]
+    // a placed disk should face along its transformed axis
+    fn test_placed() {
+        let mut disk = Disk::new(0.,1.,0.,2. * PI);
+        disk.set_object_to_world(&Matrix::translate(&Vector::new(0.,-2.,0.))).unwrap();
+        let si = disk.intersect(&down(0.5,0.5),f64::INFINITY).unwrap();
+        assert!((si.t - 7.).abs() < 1e-12);
+        assert!((si.p.y + 2.).abs() < 1e-12);
+        assert!((si.wo.y - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    // samples should cover the annulus with the expected area
+    fn test_sample() {
+        let disk = Disk::new(1.,2.,1.,PI);
+        assert!((disk.area() - 1.5 * PI).abs() < 1e-12);
+        let mut inner = 0;
+        for i in 0..1000 {
+            let (p,n) = disk.sample(((i as f64 + 0.5) / 1000.,0.37)).unwrap();
+            let r = f64::sqrt(p.x*p.x + p.z*p.z);
+            assert!((1. - 1e-12..=2. + 1e-12).contains(&r));
+            assert!(p.z >= 0. && p.y == 1. && n.y == 1.);
+            if r < 1.5 {
+                inner += 1;
+            }
+        }
+        // area between radii 1 and 1.5 is 5/12 of the annulus
+        assert!((inner as f64 / 1000. - 5. / 12.).abs() < 0.01);
+    }
+}
@@ -0,0 +1,403 @@
+use super::{
+    traits::Primitive,
+    interaction::SurfaceInteraction,
+    transform::Transform,
+    cuboid::slabs,
+    bilinear::intersect_patch,
+    triangle::intersect_triangle
+};
+use crate::{
+    math::{
+        point::Point,
+        vector::Vector,
+        normal::Normal,
+        matrix::Matrix,
+        ray::Ray,
+        traits::Normalize
+    },
+    image::hdr::HdrImage
+};
+
+/// Hit in a cell as ((t,s,t) with s and t the position within the
+/// cell,(slope along x,slope along z))
+type CellHit = ((f64,f64,f64),(f64,f64));
+
+/// # CellSurface
+/// Surface spanning the four height samples at the corners of a cell
+///
+/// # Parameters
+/// * Triangles (two triangles split along the diagonal from the first to the last corner)
+/// * Bilinear (bilinear patch through the four corners)
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum CellSurface {
+    Triangles,
+    Bilinear
+}
+
+/// # Heightfield
+/// Terrain over the unit square of x and z in object space, with the
+/// height along y sampled on a regular grid whose corner samples sit on
+/// the edges of the square. Rays walk the cells they cross with a 2D DDA
+/// and are tested against the surface of each cell, cells entirely above
+/// or below the ray are skipped. Shading normals are interpolated from
+/// per sample normals of the height gradient, uv runs along x and z
+///
+/// # Parameters
+/// * resolution (sample count along x and z, at least 2 each)
+/// * heights (x varies fastest then z)
+/// * surface (how each cell is spanned)
+/// * transform (placement in the world, scale it to the terrain size)
+pub struct Heightfield {
+    pub resolution: (usize,usize),
+    pub heights: Vec<f64>,
+    pub surface: CellSurface,
+    pub transform: Transform,
+    normals: Vec<Normal>,
+    range: (f64,f64)
+}
+
+/// Primitive trait
+impl Primitive for Heightfield {
+    fn intersect(&self,ray: &Ray,tmax: f64) -> Option<SurfaceInteraction> {
+        let mut cells: usize = 0;
+        self.intersect_counted(ray,tmax,&mut cells)
+    }
+
+    /// Counts cells visited by the walk
+    fn intersect_counted(&self,ray: &Ray,tmax: f64,tests: &mut usize) -> Option<SurfaceInteraction> {
+        let r = self.transform.ray_to_object(ray);
+        // pad the box so flat terrain still has thickness to walk through
+        let (lo,hi) = self.range;
+        let pad = 1e-9 * (1.0 + lo.abs() + hi.abs());
+        let (t0,t1,_,_) = slabs(&r,&Point::new(0.0,lo - pad,0.0),&Point::new(1.0,hi + pad,1.0))?;
+        let (t0,t1) = (t0.max(0.0),t1.min(tmax));
+        if t0 >= t1 {
+            return None
+        }
+
+        for (i,j,start,end) in CellWalk::new(self,&r,t0,t1) {
+            *tests += 1;
+            let (ya,yb) = (r.o.y + r.d.y * start,r.o.y + r.d.y * end);
+            let corners = self.corners(i,j);
+            let low = corners.iter().fold(f64::INFINITY,|m,&h| m.min(h));
+            let high = corners.iter().fold(f64::NEG_INFINITY,|m,&h| m.max(h));
+            if ya.min(yb) > high + pad || ya.max(yb) < low - pad {
+                continue
+            }
+            if let Some(hit) = self.intersect_cell(&r,i,j,tmax) {
+                return self.interaction(&r,ray,i,j,hit)
+            }
+        }
+        None
+    }
+}
+
+impl Heightfield {
+    /// Construct heightfield from samples, x varying fastest then z
+    pub fn new(resolution: (usize,usize),heights: Vec<f64>,surface: CellSurface) -> Result<Heightfield,String> {
+        let (nx,nz) = resolution;
+        if nx < 2 || nz < 2 {
+            return Err("heightfield needs at least 2 samples along x and z".to_string())
+        }
+        if heights.len() != nx*nz {
+            return Err(format!("expected {} heights, found {}",nx*nz,heights.len()))
+        }
+        let range = heights.iter().fold((f64::INFINITY,f64::NEG_INFINITY),|(lo,hi),&h| (lo.min(h),hi.max(h)));
+        let mut heightfield = Heightfield {
+            resolution,
+            heights,
+            surface,
+            transform: Transform::new(),
+            normals: vec![],
+            range
+        };
+        heightfield.normals = (0..nz)
+            .flat_map(|j| (0..nx).map(move |i| (i,j)))
+            .map(|(i,j)| heightfield.sample_normal(i,j))
+            .collect();
+        Ok(heightfield)
+    }
+
+    /// Construct heightfield from the average of the channels of an
+    /// image, rows running from the top of the image along +z
+    pub fn from_image(image: &HdrImage,surface: CellSurface) -> Result<Heightfield,String> {
+        let heights = image.pixels.iter().map(|p| p.average()).collect();
+        Heightfield::new((image.width,image.height),heights,surface)
+    }
+
+    /// Load heightfield from a Radiance RGBE (.hdr) image
+    pub fn load(path: &str,surface: CellSurface) -> Result<Heightfield,String> {
+        Heightfield::from_image(&HdrImage::load(path)?,surface)
+    }
+
+    /// Load headerless little endian f32 heights of the given resolution
+    pub fn load_raw(path: &str,resolution: (usize,usize),surface: CellSurface) -> Result<Heightfield,String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("{}: {}",path,e))?;
+        if bytes.len() % 4 != 0 {
+            return Err(format!("{}: not a whole number of f32 heights",path))
+        }
+        let heights = bytes.chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0],b[1],b[2],b[3]]) as f64)
+            .collect();
+        Heightfield::new(resolution,heights,surface)
+    }
+
+    /// Place the heightfield in the world
+    pub fn set_object_to_world(&mut self,object_to_world: &Matrix) -> Result<(),String> {
+        self.transform = Transform::from_matrix(object_to_world)?;
+        Ok(())
+    }
+
+    /// Height sample, indices outside the grid are clamped to its border
+    pub fn at(&self,i: i64,j: i64) -> f64 {
+        let (nx,nz) = self.resolution;
+        let i = i.clamp(0,nx as i64 - 1) as usize;
+        let j = j.clamp(0,nz as i64 - 1) as usize;
+        self.heights[j*nx + i]
+    }
+
+    /// Number of cells along x and z
+    fn cells(&self) -> (usize,usize) {
+        (self.resolution.0 - 1,self.resolution.1 - 1)
+    }
+
+    /// Object space position of sample (i,j)
+    fn vertex(&self,i: usize,j: usize) -> Point {
+        let (cx,cz) = self.cells();
+        Point::new(i as f64 / cx as f64,self.at(i as i64,j as i64),j as f64 / cz as f64)
+    }
+
+    /// Heights at the corners (00,10,01,11) of cell (i,j)
+    fn corners(&self,i: usize,j: usize) -> [f64; 4] {
+        let (i,j) = (i as i64,j as i64);
+        [self.at(i,j),self.at(i + 1,j),self.at(i,j + 1),self.at(i + 1,j + 1)]
+    }
+
+    /// Normal at sample (i,j) from central differences of the heights,
+    /// one sided along the border
+    fn sample_normal(&self,i: usize,j: usize) -> Normal {
+        let (nx,nz) = self.resolution;
+        let (cx,cz) = self.cells();
+        let slope = |a: (usize,usize),b: (usize,usize),cells: usize| {
+            let h = self.at(b.0 as i64,b.1 as i64) - self.at(a.0 as i64,a.1 as i64);
+            let span = (b.0 - a.0 + b.1 - a.1) as f64 / cells as f64;
+            h / span
+        };
+        let hx = slope((i.saturating_sub(1),j),((i + 1).min(nx - 1),j),cx);
+        let hz = slope((i,j.saturating_sub(1)),(i,(j + 1).min(nz - 1)),cz);
+        Normal::new(-hx,1.0,-hz).normalize().unwrap_or(Normal::new(0.0,1.0,0.0))
+    }
+
+    /// Hit of the object space ray with the surface of cell (i,j)
+    fn intersect_cell(&self,ray: &Ray,i: usize,j: usize,tmax: f64) -> Option<CellHit> {
+        let (cx,cz) = self.cells();
+        let (cx,cz) = (cx as f64,cz as f64);
+        let [h00,h10,h01,h11] = self.corners(i,j);
+        let (p00,p10,p01,p11) = (self.vertex(i,j),self.vertex(i + 1,j),self.vertex(i,j + 1),self.vertex(i + 1,j + 1));
+        match self.surface {
+            CellSurface::Triangles => {
+                // p00 p10 p11 below the diagonal, p00 p11 p01 above it
+                let lower = intersect_triangle(&p00,&p10,&p11,ray,tmax)
+                    .map(|(t,b1,b2)| ((t,b1 + b2,b2),((h10 - h00) * cx,(h11 - h10) * cz)));
+                let upper = intersect_triangle(&p00,&p11,&p01,ray,tmax)
+                    .map(|(t,b1,b2)| ((t,b1,b1 + b2),((h11 - h01) * cx,(h01 - h00) * cz)));
+                match (lower,upper) {
+                    (Some(a),Some(b)) => Some(if a.0.0 <= b.0.0 { a } else { b }),
+                    (a,b) => a.or(b)
+                }
+            },
+            CellSurface::Bilinear => {
+                let (t,s,v) = intersect_patch(&p00,&p10,&p01,&p11,ray,tmax)?;
+                let hx = ((h10 - h00) * (1.0 - v) + (h11 - h01) * v) * cx;
+                let hz = ((h01 - h00) * (1.0 - s) + (h11 - h10) * s) * cz;
+                Some(((t,s,v),(hx,hz)))
+            }
+        }
+    }
+
+    /// World space interaction for a hit in cell (i,j), the shading
+    /// normal blends the sample normals at the corners of the cell
+    fn interaction(&self,r: &Ray,ray: &Ray,i: usize,j: usize,((t,s,v),(hx,hz)): CellHit) -> Option<SurfaceInteraction> {
+        let (cx,cz) = self.cells();
+        let nx = self.resolution.0;
+        let geometric = Normal::new(-hx,1.0,-hz).normalize().ok()?;
+        let dpdu = Vector::new(1.0,hx,0.0);
+        let si = SurfaceInteraction::new(
+            r.at(t),
+            geometric,
+            ((i as f64 + s) / cx as f64,(j as f64 + v) / cz as f64),
+            dpdu,
+            Vector::new(0.0,hz,1.0),
+            t,
+            -r.d
+        );
+        let mut si = self.transform.interaction_to_world(&si,ray)?;
+
+        let n = |i: usize,j: usize| self.normals[j*nx + i];
+        let blend = n(i,j) * ((1.0 - s) * (1.0 - v)) + n(i + 1,j) * (s * (1.0 - v))
+            + n(i,j + 1) * ((1.0 - s) * v) + n(i + 1,j + 1) * (s * v);
+        let ns = self.transform.normal_to_world(&blend.normalize().ok()?);
+        si.set_shading_geometry(&ns,&self.transform.vector_to_world(&dpdu));
+        Some(si)
+    }
+}
+
+/// # CellWalk
+/// 2D DDA over the cells of a heightfield in x and z, yields
+/// (i,j,start,end) for every cell the ray crosses between t0 and t1
+struct CellWalk {
+    cells: [i64; 2],
+    cell: [i64; 2],
+    step: [i64; 2],
+    next: [f64; 2],
+    delta: [f64; 2],
+    t: f64,
+    t1: f64
+}
+
+impl CellWalk {
+    fn new(heightfield: &Heightfield,ray: &Ray,t0: f64,t1: f64) -> CellWalk {
+        let (cx,cz) = heightfield.cells();
+        let resolution = [cx as f64,cz as f64];
+        let o = [ray.o.x,ray.o.z];
+        let d = [ray.d.x,ray.d.z];
+        let mut walk = CellWalk {
+            cells: [cx as i64,cz as i64],
+            cell: [0; 2],
+            step: [0; 2],
+            next: [f64::INFINITY; 2],
+            delta: [f64::INFINITY; 2],
+            t: t0,
+            t1
+        };
+        for axis in 0..2 {
+            // position in cell units where the ray enters
+            let g = (o[axis] + d[axis]*t0) * resolution[axis];
+            let cell = (g.floor() as i64).clamp(0,resolution[axis] as i64 - 1);
+            walk.cell[axis] = cell;
+            if d[axis] > 0.0 {
+                walk.step[axis] = 1;
+                walk.delta[axis] = 1.0 / (d[axis] * resolution[axis]);
+                walk.next[axis] = t0 + ((cell + 1) as f64 - g) / (d[axis] * resolution[axis]);
+            } else if d[axis] < 0.0 {
+                walk.step[axis] = -1;
+                walk.delta[axis] = -1.0 / (d[axis] * resolution[axis]);
+                walk.next[axis] = t0 + (cell as f64 - g) / (d[axis] * resolution[axis]);
+            }
+        }
+        walk
+    }
+}
+
+impl Iterator for CellWalk {
+    type Item = (usize,usize,f64,f64);
+
+    fn next(&mut self) -> Option<(usize,usize,f64,f64)> {
+        if self.t >= self.t1 || (0..2).any(|a| self.cell[a] < 0 || self.cell[a] >= self.cells[a]) {
+            return None
+        }
+
+        let axis = if self.next[0] <= self.next[1] { 0 } else { 1 };
+        let (i,j) = (self.cell[0] as usize,self.cell[1] as usize);
+        let start = self.t;
+        let end = self.next[axis].min(self.t1);
+        self.t = end;
+        self.cell[axis] += self.step[axis];
+        self.next[axis] += self.delta[axis];
+        Some((i,j,start,end))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        image::spectrum::Spectrum,
+        math::traits::{Dot,Len}
+    };
+
+    // h = x^2 + z sampled on a 5 x 3 grid
+    fn terrain(surface: CellSurface) -> Heightfield {
+        let heights = (0..3)
+            .flat_map(|j| (0..5).map(move |i| (i as f64 / 4.).powi(2) + j as f64 / 2.))
+            .collect();
+        Heightfield::new((5,3),heights,surface).unwrap()
+    }
+
+    #[test]
+    // vertical rays should land on the interpolated height of their cell
+    fn test_intersect() {
+        for surface in [CellSurface::Triangles,CellSurface::Bilinear] {
+            let heightfield = terrain(surface);
+            let ray = Ray::new(&Point::new(0.375,5.,0.25),&Vector::new(0.,-1.,0.));
+            let si = heightfield.intersect(&ray,f64::INFINITY).unwrap();
+            // between the samples at x = 0.25 and 0.5, z = 0 and 0.5
+            assert!((si.p.y - (0.5 * (0.0625 + 0.25) + 0.25)).abs() < 1e-12,"{:?}",surface);
+            assert!((si.uv.0 - 0.375).abs() < 1e-12 && (si.uv.1 - 0.25).abs() < 1e-12);
+            assert!(si.n.dot(si.dpdu).abs() < 1e-12 && si.n.dot(si.dpdv).abs() < 1e-12);
+            assert!(si.n.y > 0.);
+            assert!(heightfield.intersect(&ray,4.).is_none());
+            let outside = Ray::new(&Point::new(1.5,5.,0.25),&Vector::new(0.,-1.,0.));
+            assert!(heightfield.intersect(&outside,f64::INFINITY).is_none());
+        }
+    }
+
+    #[test]
+    // a grazing ray should walk the cells until it meets the rising terrain
+    fn test_walk() {
+        let heightfield = terrain(CellSurface::Bilinear);
+        // along x at z = 0.5 the terrain is 0.5 + x^2, met at x = 0.6 by y = 0.86
+        let ray = Ray::new(&Point::new(-1.,0.86,0.5),&Vector::new(1.,0.,0.));
+        let si = heightfield.intersect(&ray,f64::INFINITY).unwrap();
+        let x = si.p.x;
+        let (a,b) = (0.5,0.75);
+        let expected = 0.5 + a*a + (b*b - a*a) * (x - a) / (b - a);
+        assert!((si.p.y - expected).abs() < 1e-12);
+        assert!(x > a && x < b);
+        // the walk crosses the cells before the hit, a vertical ray only one
+        let mut cells: usize = 0;
+        heightfield.intersect_counted(&ray,f64::INFINITY,&mut cells).unwrap();
+        assert_eq!(cells,3);
+        let mut cells: usize = 0;
+        heightfield.intersect_counted(&Ray::new(&Point::new(0.375,5.,0.25),&Vector::new(0.,-1.,0.)),f64::INFINITY,&mut cells).unwrap();
+        assert_eq!(cells,1);
+        // from above the field looking down towards -x, meeting 2x - 0.3 = 0.5 + x^2
+        let back = Ray::new(&Point::new(0.9,1.5,0.5),&Vector::new(-1.,-2.,0.));
+        let si = heightfield.intersect(&back,f64::INFINITY).unwrap();
+        assert!(si.p.x > a && si.p.x < b);
+        assert!((si.p.y - (0.5 + a*a + (b*b - a*a) * (si.p.x - a) / (b - a))).abs() < 1e-12);
+        assert!((CellWalk::new(&heightfield,&back,0.,0.9).count()) == 4);
+    }
+
+    #[test]
+    // shading normals should follow the smooth gradient of the samples
+    fn test_normals() {
+        let heightfield = terrain(CellSurface::Triangles);
+        // at sample (2,1), x = 0.5: dh/dx = 1, dh/dz = 1
+        let n = heightfield.sample_normal(2,1);
+        assert!((Vector::from(n) - Vector::new(-1.,1.,-1.).normalize().unwrap()).len() < 1e-12);
+        let ray = Ray::new(&Point::new(0.5,5.,0.5),&Vector::new(0.,-1.,0.));
+        let si = heightfield.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.ns.dot(Vector::from(n)) - 1.).abs() < 1e-9);
+        // flat terrain is still hit
+        let flat = Heightfield::new((2,2),vec![0.;4],CellSurface::Triangles).unwrap();
+        let si = flat.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.t - 5.).abs() < 1e-12 && (si.n.y - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    // images and invalid grids should be handled
+    fn test_from_image() {
+        let mut image = HdrImage::new(3,2,Spectrum::new(0.,0.,0.));
+        image.pixels[4] = Spectrum::new(0.3,0.6,0.9);
+        let heightfield = Heightfield::from_image(&image,CellSurface::Bilinear).unwrap();
+        assert_eq!(heightfield.at(1,1),0.6);
+        assert_eq!(heightfield.at(5,-1),0.);
+        assert!(Heightfield::new((1,4),vec![0.;4],CellSurface::Bilinear).is_err());
+        assert!(Heightfield::new((2,2),vec![0.;3],CellSurface::Bilinear).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use std::f64::consts::PI;
+
+use super::{
+    traits::Primitive,
+    interaction::SurfaceInteraction,
+    transform::Transform,
+    quadric::{azimuth,nearest_root}
+};
+use crate::math::{
+    point::Point,
+    vector::Vector,
+    normal::Normal,
+    matrix::Matrix,
+    ray::Ray,
+    traits::Normalize
+};
+
+/// Number of intervals tabulating the area along the generating segment
+const AREA_INTERVALS: usize = 64;
+
+/// # Hyperboloid
+/// Surface swept by revolving the segment from p1 to p2 about the y axis
+/// of object space, a hyperboloid of one sheet unless the segment lies in
+/// a plane through the axis (then a cone or cylinder), swept from phi = 0
+/// to phi_max. The squared distance from the axis along the segment
+/// r^2(s) = a + 2 b s + c s^2 is kept for intersection and sampling
+///
+/// # Parameters
+/// * p1 (start of the segment, v = 0)
+/// * p2 (end of the segment, v = 1)
+/// * phi_max (sweep angle in (0,2 pi])
+/// * transform (placement in the world)
+pub struct Hyperboloid {
+    pub p1: Point,
+    pub p2: Point,
+    pub phi_max: f64,
+    pub transform: Transform,
+    a: f64,
+    b: f64,
+    c: f64,
+    cdf: Vec<f64>
+}
+
+/// Primitive trait
+impl Primitive for Hyperboloid {
+    fn intersect(&self,ray: &Ray,tmax: f64) -> Option<SurfaceInteraction> {
+        let r = self.transform.ray_to_object(ray);
+        let (o,d) = (r.o,r.d);
+        let dy = self.p2.y - self.p1.y;
+        // segment parameter along the ray, s = s0 + s1 t
+        let s0 = (o.y - self.p1.y) / dy;
+        let s1 = d.y / dy;
+        let coefficients = (
+            d.x*d.x + d.z*d.z - self.c*s1*s1,
+            2.0 * (o.x*d.x + o.z*d.z - self.b*s1 - self.c*s0*s1),
+            o.x*o.x + o.z*o.z - self.a - 2.0*self.b*s0 - self.c*s0*s0
+        );
+        let (t,p) = nearest_root(&r,tmax,coefficients,|p| {
+            let s = (p.y - self.p1.y) / dy;
+            (0.0..=1.0).contains(&s) && azimuth(p.x,p.z) <= self.phi_max
+        })?;
+
+        let s = (p.y - self.p1.y) / dy;
+        let phi = azimuth(p.x,p.z);
+        let radius = f64::sqrt(f64::max(0.0,self.a + 2.0*self.b*s + self.c*s*s));
+        // d(r)/ds, the radius changes by (b + c s) / r along the segment
+        let dpdv = if radius > 0.0 {
+            let (sin_phi,cos_phi) = f64::sin_cos(phi);
+            let drds = (self.b + self.c*s) / radius;
+            Vector::new(drds * cos_phi,dy,drds * sin_phi)
+        } else {
+            Vector::new(0.0,dy,0.0)
+        };
+        let si = SurfaceInteraction::new(
+            p,
+            self.normal(&p,s),
+            (phi / self.phi_max,s),
+            Vector::new(-self.phi_max * p.z,0.0,self.phi_max * p.x),
+            dpdv,
+            t,
+            -d
+        );
+        self.transform.interaction_to_world(&si,ray)
+    }
+
+    fn area(&self) -> f64 {
+        self.phi_max * self.cdf[AREA_INTERVALS]
+    }
+
+    fn sample(&self,u: (f64,f64)) -> Option<(Point,Normal)> {
+        // find the interval holding the target area, then bisect within it
+        let target = u.0 * self.cdf[AREA_INTERVALS];
+        let i = (self.cdf.partition_point(|&c| c <= target).max(1) - 1).min(AREA_INTERVALS - 1);
+        let ds = 1.0 / AREA_INTERVALS as f64;
+        let (mut lo,mut hi) = (i as f64 * ds,(i + 1) as f64 * ds);
+        let start = lo;
+        for _ in 0..40 {
+            let mid = 0.5 * (lo + hi);
+            if self.cdf[i] + self.integrate(start,mid) < target {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        let s = 0.5 * (lo + hi);
+
+        let radius = f64::sqrt(f64::max(0.0,self.a + 2.0*self.b*s + self.c*s*s));
+        let (sin_phi,cos_phi) = f64::sin_cos(u.1 * self.phi_max);
+        let p = Point::new(radius * cos_phi,self.p1.y + s * (self.p2.y - self.p1.y),radius * sin_phi);
+        Some(self.transform.sample_to_world((p,self.normal(&p,s))))
+    }
+}
+
+impl Hyperboloid {
+    /// Construct hyperboloid from the segment p1 p2, which must not be
+    /// perpendicular to the y axis
+    pub fn new(p1: Point,p2: Point,phi_max: f64) -> Result<Hyperboloid,String> {
+        if p1.y == p2.y {
+            return Err("hyperboloid segment must change in y".to_string())
+        }
+        let d = p2 - p1;
+        let mut hyperboloid = Hyperboloid {
+            p1,
+            p2,
+            phi_max: phi_max.clamp(0.0,2.0 * PI),
+            transform: Transform::new(),
+            a: p1.x*p1.x + p1.z*p1.z,
+            b: p1.x*d.x + p1.z*d.z,
+            c: d.x*d.x + d.z*d.z,
+            cdf: vec![]
+        };
+        let ds = 1.0 / AREA_INTERVALS as f64;
+        let mut cdf = vec![0.0; AREA_INTERVALS + 1];
+        for i in 0..AREA_INTERVALS {
+            cdf[i + 1] = cdf[i] + hyperboloid.integrate(i as f64 * ds,(i + 1) as f64 * ds);
+        }
+        hyperboloid.cdf = cdf;
+        Ok(hyperboloid)
+    }
+
+    /// Place the hyperboloid in the world
+    pub fn set_object_to_world(&mut self,object_to_world: &Matrix) -> Result<(),String> {
+        self.transform = Transform::rigid_from_matrix(object_to_world)?;
+        Ok(())
+    }
+
+    /// Area per unit of s and phi, r(s) |(r'(s),dy)|
+    fn area_density(&self,s: f64) -> f64 {
+        let dy = self.p2.y - self.p1.y;
+        let rdr = self.b + self.c*s;
+        let r2 = self.a + 2.0*self.b*s + self.c*s*s;
+        f64::sqrt(f64::max(0.0,rdr*rdr + dy*dy*r2))
+    }
+
+    /// Integral of the area density from s0 to s1 by Simpson's rule
+    fn integrate(&self,s0: f64,s1: f64) -> f64 {
+        let h = 0.25 * (s1 - s0);
+        let f = |k: f64| self.area_density(s0 + k*h);
+        (h / 3.0) * (f(0.0) + 4.0*f(1.0) + 2.0*f(2.0) + 4.0*f(3.0) + f(4.0))
+    }
+
+    /// Outward normal at object space point p at segment parameter s,
+    /// (x,-r r'(y),z) with r r'(y) = (b + c s) / dy
+    fn normal(&self,p: &Point,s: f64) -> Normal {
+        let dy = self.p2.y - self.p1.y;
+        let n = Normal::new(p.x,-(self.b + self.c*s) / dy,p.z);
+        n.normalize().unwrap_or(Normal::new(0.0,-dy.signum(),0.0))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        scene::{cylinder::Cylinder,cone::Cone},
+        math::traits::Dot
+    };
+
+    // twisted segment giving a waisted hyperboloid of radius 1 at y = 0
+    fn waisted() -> Hyperboloid {
+        Hyperboloid::new(Point::new(1.,-1.,-1.),Point::new(1.,1.,1.),2. * PI).unwrap()
+    }
+
+    #[test]
+    // hits should lie on the surface with the normal perpendicular to it
+    fn test_intersect() {
+        let hyperboloid = waisted();
+        let ray = Ray::new(&Point::new(-5.,0.,0.),&Vector::new(1.,0.,0.));
+        let si = hyperboloid.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.t - 4.).abs() < 1e-12);
+        assert!((si.n.x + 1.).abs() < 1e-12);
+        assert!((si.uv.1 - 0.5).abs() < 1e-12);
+        let ray = Ray::new(&Point::new(-5.,0.5,0.3),&Vector::new(1.,0.,0.));
+        let si = hyperboloid.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.p.x*si.p.x + si.p.z*si.p.z - (1. + si.p.y*si.p.y)).abs() < 1e-9);
+        assert!(si.n.dot(si.dpdu).abs() < 1e-9);
+        assert!(si.n.dot(si.dpdv).abs() < 1e-9);
+        // above the waist the surface flares out and faces down
+        assert!(si.n.x < 0. && si.n.y < 0.);
+        // beyond the end of the segment
+        let above = Ray::new(&Point::new(-5.,1.5,0.),&Vector::new(1.,0.,0.));
+        assert!(hyperboloid.intersect(&above,f64::INFINITY).is_none());
+        assert!(Hyperboloid::new(Point::new(1.,0.,0.),Point::new(2.,0.,0.),PI).is_err());
+    }
+
+    #[test]
+    // straight segments should reproduce the cylinder and cone
+    fn test_degenerate() {
+        let tube = Hyperboloid::new(Point::new(2.,0.,0.),Point::new(2.,3.,0.),PI).unwrap();
+        let cylinder = Cylinder::new(2.,0.,3.,PI);
+        assert!((tube.area() - cylinder.area()).abs() < 1e-9);
+        let ray = Ray::new(&Point::new(0.5,1.,-5.),&Vector::new(0.,0.,1.));
+        let (a,b) = (tube.intersect(&ray,f64::INFINITY).unwrap(),cylinder.intersect(&ray,f64::INFINITY).unwrap());
+        assert!((a.t - b.t).abs() < 1e-9);
+        assert!((a.n.z - b.n.z).abs() < 1e-9);
+        let funnel = Hyperboloid::new(Point::new(1.,0.,0.),Point::new(0.,2.,0.),2. * PI).unwrap();
+        assert!((funnel.area() - Cone::new(1.,2.,2. * PI).area()).abs() < 1e-9);
+    }
+
+    #[test]
+    // samples should lie on the surface and spread by area
+    fn test_sample() {
+        let hyperboloid = waisted();
+        let mut lower = 0;
+        for i in 0..1000 {
+            let (p,n) = hyperboloid.sample(((i as f64 + 0.5) / 1000.,0.6)).unwrap();
+            assert!((p.x*p.x + p.z*p.z - (1. + p.y*p.y)).abs() < 1e-9);
+            assert!(n.dot(Vector::new(p.x,0.,p.z)) > 0.);
+            if p.y < -0.5 {
+                lower += 1;
+            }
+        }
+        // the same surface cut at y = -0.5
+        let part = Hyperboloid::new(Point::new(1.,-1.,-1.),Point::new(1.,-0.5,-0.5),2. * PI).unwrap();
+        let expected = part.area() / hyperboloid.area();
+        assert!((lower as f64 / 1000. - expected).abs() < 0.01);
+    }
+}
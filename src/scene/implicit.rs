@@ -0,0 +1,292 @@
+use std::f64::consts::PI;
+
+use super::{
+    traits::Primitive,
+    interaction::SurfaceInteraction,
+    transform::Transform,
+    cuboid::slabs,
+    quadric::azimuth
+};
+use crate::{
+    math::{
+        point::Point,
+        vector::Vector,
+        normal::Normal,
+        matrix::Matrix,
+        ray::Ray,
+        frame::Frame,
+        traits::{Len,Normalize}
+    },
+    sdf::traits::DistanceField
+};
+
+/// Default distance below which sphere tracing looks for the surface
+pub const DEFAULT_EPSILON: f64 = 1e-5;
+
+/// Steps allowed before a ray is taken to miss
+const MAX_STEPS: usize = 1024;
+
+/// Offset for the central differences estimating the normal
+const NORMAL_DELTA: f64 = 1e-6;
+
+/// # Implicit
+/// Surface where a signed distance field vanishes, intersected by sphere
+/// tracing within the bounds of the field. Each step advances by the
+/// distance divided by the lipschitz factor so the surface is never
+/// passed, once close the crossing is bracketed and bisected. uv is the
+/// orbit trap of fractal fields and otherwise follows the direction from
+/// the center of the bounds, the normal is the gradient of the field
+///
+/// # Parameters
+/// * field (distance field in object space)
+/// * epsilon (distance below which the surface is looked for)
+/// * transform (placement in the world)
+pub struct Implicit {
+    pub field: Box<dyn DistanceField>,
+    pub epsilon: f64,
+    pub transform: Transform
+}
+
+/// Primitive trait
+impl Primitive for Implicit {
+    fn intersect(&self,ray: &Ray,tmax: f64) -> Option<SurfaceInteraction> {
+        let mut steps: usize = 0;
+        self.intersect_counted(ray,tmax,&mut steps)
+    }
+
+    /// Counts sphere tracing steps
+    fn intersect_counted(&self,ray: &Ray,tmax: f64,tests: &mut usize) -> Option<SurfaceInteraction> {
+        let r = self.transform.ray_to_object(ray);
+        let length = r.d.len();
+        let d = r.d.normalize().ok()?;
+        let (lo,hi) = self.field.bounds();
+        let (t0,t1,_,_) = slabs(&Ray::new(&r.o,&d),&lo,&hi)?;
+        let end = f64::min(t1,tmax * length);
+        let distance = self.trace(&r.o,&d,f64::max(t0,0.0),end,tests)?;
+        if distance <= 0.0 || distance >= end {
+            return None
+        }
+
+        let p = r.o + d * distance;
+        let n = self.normal(&p)?;
+        let frame = Frame::from_normal(&n);
+        let uv = self.field.orbit_trap(&p).unwrap_or_else(|| {
+            let center = lo + (hi - lo) * 0.5;
+            let direction = (p - center).normalize().unwrap_or(Vector::new(0.0,1.0,0.0));
+            (azimuth(direction.x,direction.z) / (2.0 * PI),f64::acos(direction.y.clamp(-1.0,1.0)) / PI)
+        });
+        let si = SurfaceInteraction::new(
+            p,
+            n,
+            uv,
+            frame.s,
+            frame.t,
+            distance / length,
+            -r.d
+        );
+        self.transform.interaction_to_world(&si,ray)
+    }
+}
+
+impl Implicit {
+    /// Construct implicit surface of a distance field
+    pub fn new(field: Box<dyn DistanceField>) -> Implicit {
+        Implicit {
+            field,
+            epsilon: DEFAULT_EPSILON,
+            transform: Transform::new()
+        }
+    }
+
+    /// Place the surface in the world
+    pub fn set_object_to_world(&mut self,object_to_world: &Matrix) -> Result<(),String> {
+        self.transform = Transform::rigid_from_matrix(object_to_world)?;
+        Ok(())
+    }
+
+    /// World space box (min,max) enclosing the surface, the placed
+    /// corners of the bounds of the field
+    pub fn bounds(&self) -> (Point,Point) {
+        let (lo,hi) = self.field.bounds();
+        let mut min = Point::new(f64::INFINITY,f64::INFINITY,f64::INFINITY);
+        let mut max = Point::new(f64::NEG_INFINITY,f64::NEG_INFINITY,f64::NEG_INFINITY);
+        for x in [lo.x,hi.x] {
+            for y in [lo.y,hi.y] {
+                for z in [lo.z,hi.z] {
+                    let p = self.transform.point_to_world(&Point::new(x,y,z));
+                    min = Point::new(min.x.min(p.x),min.y.min(p.y),min.z.min(p.z));
+                    max = Point::new(max.x.max(p.x),max.y.max(p.y),max.z.max(p.z));
+                }
+            }
+        }
+        (min,max)
+    }
+
+    /// Distance along the unit direction d from o to the first crossing
+    /// of the surface in [start,end]. Rays starting inside march to where
+    /// they leave, and rays starting on the surface only stop where they
+    /// approach it again. Every step taken is added to steps
+    fn trace(&self,o: &Point,d: &Vector,start: f64,end: f64,steps: &mut usize) -> Option<f64> {
+        let f = |s: f64| self.field.distance(&(*o + *d * s));
+        let sign = if f(start) < 0.0 { -1.0 } else { 1.0 };
+        let lipschitz = self.field.lipschitz();
+        let mut s = start;
+        let mut dist = sign * f(s);
+        for _ in 0..MAX_STEPS {
+            *steps += 1;
+            if s > end {
+                return None
+            }
+            let next = s + dist / lipschitz;
+            let next_dist = sign * f(next);
+            if next_dist <= 0.0 {
+                return Some(bisect(&f,sign,s,next))
+            }
+            if next_dist < self.epsilon && next_dist < dist {
+                // close and approaching, look just ahead for the crossing
+                let probe = next + 2.0 * self.epsilon;
+                if sign * f(probe) <= 0.0 {
+                    return Some(bisect(&f,sign,next,probe))
+                }
+                s = probe;
+                dist = sign * f(probe);
+                continue
+            }
+            s = next;
+            dist = next_dist;
+        }
+        None
+    }
+
+    /// Unit gradient of the field at p by central differences
+    fn normal(&self,p: &Point) -> Option<Normal> {
+        let h = NORMAL_DELTA;
+        let f = |dx: f64,dy: f64,dz: f64| self.field.distance(&Point::new(p.x + dx,p.y + dy,p.z + dz));
+        let gradient = Normal::new(
+            f(h,0.0,0.0) - f(-h,0.0,0.0),
+            f(0.0,h,0.0) - f(0.0,-h,0.0),
+            f(0.0,0.0,h) - f(0.0,0.0,-h)
+        );
+        gradient.normalize().ok()
+    }
+}
+
+/// Crossing between a on the starting side and b past the surface,
+/// returned on the starting side
+fn bisect<F: Fn(f64) -> f64>(f: &F,sign: f64,mut a: f64,mut b: f64) -> f64 {
+    for _ in 0..64 {
+        let mid = 0.5 * (a + b);
+        if mid <= a || mid >= b {
+            break
+        }
+        if sign * f(mid) > 0.0 {
+            a = mid;
+        } else {
+            b = mid;
+        }
+    }
+    a
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::traits::Dot,
+        sdf::{
+            shapes::{Sphere,Cuboid},
+            operators::{SmoothUnion,Translate,Twist}
+        }
+    };
+
+    // unit sphere field
+    fn ball() -> Implicit {
+        Implicit::new(Box::new(Sphere::new(1.)))
+    }
+
+    #[test]
+    // sphere tracing should agree with the analytic sphere
+    fn test_intersect() {
+        let implicit = ball();
+        let ray = Ray::new(&Point::new(0.3,0.2,-5.),&Vector::new(0.,0.,2.));
+        let si = implicit.intersect(&ray,f64::INFINITY).unwrap();
+        let expected = 0.5 * (5. - f64::sqrt(1. - 0.13));
+        assert!((si.t - expected).abs() < 1e-10,"{}",si.t);
+        assert!((Vector::from(si.n) - (si.p - Point::new(0.,0.,0.))).len() < 1e-6);
+        assert!(si.n.dot(si.dpdu).abs() < 1e-12 && si.n.dot(si.dpdv).abs() < 1e-12);
+        assert!(implicit.intersect(&ray,2.).is_none());
+        let miss = Ray::new(&Point::new(0.,1.5,-5.),&Vector::new(0.,0.,1.));
+        assert!(implicit.intersect(&miss,f64::INFINITY).is_none());
+    }
+
+    #[test]
+    // rays grazing the surface should take more sphere tracing steps
+    fn test_intersect_counted() {
+        let implicit = ball();
+        let mut head_on: usize = 0;
+        let ray = Ray::new(&Point::new(0.,0.,-5.),&Vector::new(0.,0.,1.));
+        assert!(implicit.intersect_counted(&ray,f64::INFINITY,&mut head_on).is_some());
+        let mut grazing: usize = 0;
+        let ray = Ray::new(&Point::new(0.72,0.72,-5.),&Vector::new(0.,0.,1.));
+        assert!(implicit.intersect_counted(&ray,f64::INFINITY,&mut grazing).is_none());
+        assert!(head_on >= 1);
+        assert!(grazing > 4 * head_on,"grazing {} head on {}",grazing,head_on);
+    }
+
+    #[test]
+    // rays spawned off the surface should not find it again unless they cross it
+    fn test_spawned() {
+        let implicit = ball();
+        let ray = Ray::new(&Point::new(0.,0.,-5.),&Vector::new(0.,0.,1.));
+        let si = implicit.intersect(&ray,f64::INFINITY).unwrap();
+        let away = si.spawn_ray(&Vector::new(0.3,0.,-1.));
+        assert!(implicit.intersect(&away,f64::INFINITY).is_none());
+        // into the sphere the ray leaves through the far side
+        let through = si.spawn_ray(&Vector::new(0.,0.,1.));
+        let exit = implicit.intersect(&through,f64::INFINITY).unwrap();
+        assert!((exit.p.z - 1.).abs() < 1e-9);
+        assert!((exit.n.z - 1.).abs() < 1e-6);
+    }
+
+    #[test]
+    // blended and twisted fields should still be hit on their surface
+    fn test_operators() {
+        let blend = Implicit::new(Box::new(SmoothUnion::new(
+            Box::new(Translate::new(Box::new(Sphere::new(1.)),Vector::new(-1.,0.,0.))),
+            Box::new(Translate::new(Box::new(Sphere::new(1.)),Vector::new(1.,0.,0.))),
+            0.5
+        )));
+        // the neck where both spheres are 1 / 8 away
+        let down = Ray::new(&Point::new(0.,5.,0.),&Vector::new(0.,-1.,0.));
+        let si = blend.intersect(&down,f64::INFINITY).unwrap();
+        assert!((si.p.y - f64::sqrt(1.125 * 1.125 - 1.)).abs() < 1e-9);
+        assert!((si.n.y - 1.).abs() < 1e-6);
+
+        let twisted = Implicit::new(Box::new(Twist::new(Box::new(Cuboid::new(Vector::new(1.,2.,0.25))),0.8)));
+        for i in 0..20 {
+            let y = -1.9 + 0.2 * i as f64;
+            let ray = Ray::new(&Point::new(-5.,y,0.1),&Vector::new(1.,0.,0.));
+            let si = twisted.intersect(&ray,f64::INFINITY).unwrap();
+            assert!(twisted.field.distance(&si.p).abs() < 1e-9);
+            assert!(si.p.x < 0.);
+        }
+    }
+
+    #[test]
+    // placed bounds should follow the transform
+    fn test_bounds() {
+        let mut implicit = ball();
+        assert!(implicit.set_object_to_world(&Matrix::scale(2.,2.,2.)).is_err());
+        implicit.set_object_to_world(&(Matrix::translate(&Vector::new(0.,3.,0.)) * Matrix::rotate_y(0.25 * PI))).unwrap();
+        let (min,max) = implicit.bounds();
+        assert!((min.y - 2.).abs() < 1e-12 && (max.y - 4.).abs() < 1e-12);
+        assert!((max.x - f64::sqrt(2.)).abs() < 1e-12);
+        let ray = Ray::new(&Point::new(0.,3.,-5.),&Vector::new(0.,0.,1.));
+        let si = implicit.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.t - 4.).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,332 @@
+use crate::{
+    math::{
+        point::Point,
+        vector::Vector,
+        normal::Normal,
+        frame::Frame,
+        ray::{Ray,RayDifferential},
+        traits::{Dot,Normalize}
+    },
+    medium::traits::MediumInterface
+};
+
+/// Offset applied to spawned ray origins to avoid self intersection
+pub const RAY_EPSILON: f64 = 1e-6;
+
+/// # SurfaceInteraction
+/// Local geometric information at a ray-primitive intersection
+///
+/// # Parameters
+/// * p (hit point)
+/// * n (unit geometric normal, pointing out of the primitive)
+/// * ns (unit shading normal, equal to n unless perturbed)
+/// * uv (surface parameterization)
+/// * dpdu (partial derivative of p with respect to u)
+/// * dpdv (partial derivative of p with respect to v)
+/// * t (ray parameter at the hit)
+/// * wo (unit direction back towards the ray origin)
+/// * barycentric (barycentric coordinates for primitives built from triangles)
+/// * primitive (index of the primitive in the world)
+/// * material (index of the primitive's material in the world)
+/// * light (index of the area light attached to the primitive, if any)
+/// * medium_interface (media on both sides of the primitive, None if it does not change medium)
+/// * dpdx, dpdy (change of p from one pixel to the next in x and y, zero without ray differentials)
+/// * duvdx, duvdy (change of uv from one pixel to the next in x and y)
+/// * dpdus (shading tangent, equal to dpdu unless perturbed)
+/// * tangent (per-vertex tangent and bitangent sign interpolated by meshes, None to derive it from dpdu)
+#[derive(Clone,Copy)]
+pub struct SurfaceInteraction {
+    pub p: Point,
+    pub n: Normal,
+    pub ns: Normal,
+    pub uv: (f64,f64),
+    pub dpdu: Vector,
+    pub dpdv: Vector,
+    pub t: f64,
+    pub wo: Vector,
+    pub barycentric: Option<(f64,f64)>,
+    pub primitive: usize,
+    pub material: usize,
+    pub light: Option<usize>,
+    pub medium_interface: Option<MediumInterface>,
+    pub dpdx: Vector,
+    pub dpdy: Vector,
+    pub duvdx: (f64,f64),
+    pub duvdy: (f64,f64),
+    pub dpdus: Vector,
+    pub tangent: Option<(Vector,f64)>
+}
+
+impl SurfaceInteraction {
+    /// Construct interaction, world indices are filled in by the world
+    pub fn new(p: Point,n: Normal,uv: (f64,f64),dpdu: Vector,dpdv: Vector,t: f64,wo: Vector) -> SurfaceInteraction {
+        SurfaceInteraction {
+            p,n,uv,dpdu,dpdv,t,wo,
+            ns: n,
+            barycentric: None,
+            primitive: 0,
+            material: 0,
+            light: None,
+            medium_interface: None,
+            dpdx: Vector::new(0.0,0.0,0.0),
+            dpdy: Vector::new(0.0,0.0,0.0),
+            duvdx: (0.0,0.0),
+            duvdy: (0.0,0.0),
+            dpdus: dpdu,
+            tangent: None
+        }
+    }
+
+    /// Replace the shading normal and tangent, the normal is flipped
+    /// onto the side of the geometric normal so that perturbations
+    /// never turn the surface inside out
+    pub fn set_shading_geometry(&mut self,ns: &Normal,dpdus: &Vector) {
+        self.ns = if ns.dot(Vector::from(self.n)) < 0.0 { -*ns } else { *ns };
+        self.dpdus = *dpdus;
+    }
+
+    /// Orthonormal shading frame around ns, with s along the shading tangent
+    pub fn shading_frame(&self) -> Frame {
+        Frame::from_normal_tangent(&self.ns,&self.dpdus)
+    }
+
+    /// Medium entered by a ray leaving the surface in direction w,
+    /// current if the primitive does not separate media
+    pub fn medium(&self,w: &Vector,current: Option<usize>) -> Option<usize> {
+        match &self.medium_interface {
+            Some(interface) => interface.medium(&self.n,w),
+            None => current
+        }
+    }
+
+    /// Origin offset along the normal to the side that d leaves from
+    pub fn offset_origin(&self,d: &Vector) -> Point {
+        offset_ray_origin(&self.p,&self.n,d)
+    }
+
+    /// Spawn ray leaving the surface in direction d
+    pub fn spawn_ray(&self,d: &Vector) -> Ray {
+        Ray::new(&self.offset_origin(d),d)
+    }
+
+    /// Spawn ray towards p, returns the ray and the parametric
+    /// distance (just short of p) to test for occluders
+    pub fn spawn_ray_to(&self,p: &Point) -> (Ray,f64) {
+        let d = *p - self.p;
+        let origin = self.offset_origin(&d);
+        (Ray::new(&origin,&(*p - origin)),1.0 - 1e-4)
+    }
+
+    /// Estimate the footprint of a pixel on the surface from the
+    /// differentials of the ray that hit it, the offset rays are
+    /// intersected with the tangent plane at p and the uv derivatives
+    /// follow from dpdu and dpdv by least squares
+    pub fn compute_differentials(&mut self,ray: &Ray) {
+        let rd = match &ray.differentials {
+            Some(rd) => rd,
+            None => return
+        };
+        let n = Vector::from(self.n);
+        let plane_hit = |o: &Point,d: &Vector| {
+            let denom = n.dot(*d);
+            if denom == 0.0 {
+                return None
+            }
+            let t = n.dot(self.p - *o) / denom;
+            Some(*o + *d * t)
+        };
+        let (px,py) = match (plane_hit(&rd.rx_o,&rd.rx_d),plane_hit(&rd.ry_o,&rd.ry_d)) {
+            (Some(px),Some(py)) => (px,py),
+            _ => return
+        };
+        self.dpdx = px - self.p;
+        self.dpdy = py - self.p;
+
+        let ata00 = self.dpdu.dot(self.dpdu);
+        let ata01 = self.dpdu.dot(self.dpdv);
+        let ata11 = self.dpdv.dot(self.dpdv);
+        let det = ata00*ata11 - ata01*ata01;
+        if det == 0.0 {
+            return
+        }
+        let solve = |dp: &Vector| {
+            let b0 = self.dpdu.dot(*dp);
+            let b1 = self.dpdv.dot(*dp);
+            let du = (ata11*b0 - ata01*b1) / det;
+            let dv = (ata00*b1 - ata01*b0) / det;
+            (if du.is_finite() { du } else { 0.0 },if dv.is_finite() { dv } else { 0.0 })
+        };
+        self.duvdx = solve(&self.dpdx);
+        self.duvdy = solve(&self.dpdy);
+    }
+
+    /// Spawn ray leaving the surface in direction wi after specular
+    /// reflection or refraction (relative index eta) of ray, the
+    /// differentials follow the mirror or refraction law to first order,
+    /// treating the surface as locally flat
+    pub fn spawn_specular_ray(&self,ray: &Ray,wi: &Vector,eta: f64) -> Ray {
+        let spawned = self.spawn_ray(wi);
+        let rd = match &ray.differentials {
+            Some(rd) => rd,
+            None => return spawned
+        };
+        let (wox,woy) = match ((-rd.rx_d).normalize(),(-rd.ry_d).normalize()) {
+            (Ok(wox),Ok(woy)) => (wox,woy),
+            _ => return spawned
+        };
+        let dwodx = wox - self.wo;
+        let dwody = woy - self.wo;
+        let mut n = Vector::from(self.ns);
+
+        let (rx_d,ry_d) = if wi.dot(n) * self.wo.dot(n) > 0.0 {
+            // mirror law, wi = -wo + 2 (wo.n) n
+            (*wi - dwodx + n * (2.0 * dwodx.dot(n)),*wi - dwody + n * (2.0 * dwody.dot(n)))
+        } else {
+            // refraction, wi = -eta wo + mu n with n on the side of wo
+            if self.wo.dot(n) < 0.0 {
+                n = -n;
+            }
+            let eta = 1.0 / eta;
+            let cos_i = self.wo.dot(n);
+            let cos_t = wi.dot(n).abs();
+            if cos_t == 0.0 {
+                return spawned
+            }
+            let dmu = eta - eta*eta*cos_i / cos_t;
+            (
+                *wi - dwodx * eta + n * (dmu * dwodx.dot(n)),
+                *wi - dwody * eta + n * (dmu * dwody.dot(n))
+            )
+        };
+
+        spawned.with_differentials(RayDifferential {
+            rx_o: self.p + self.dpdx,
+            rx_d,
+            ry_o: self.p + self.dpdy,
+            ry_d
+        })
+    }
+}
+
+/// Offset p along the surface normal n to the side that d leaves from
+pub fn offset_ray_origin(p: &Point,n: &Normal,d: &Vector) -> Point {
+    let scale = RAY_EPSILON * (1.0 + f64::max(p.x.abs(),f64::max(p.y.abs(),p.z.abs())));
+    let offset = Vector::from(*n) * scale;
+    if n.dot(*d) < 0.0 {
+        *p - offset
+    } else {
+        *p + offset
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_interaction() -> SurfaceInteraction {
+        SurfaceInteraction::new(
+            Point::new(0.,0.,0.),
+            Normal::new(0.,1.,0.),
+            (0.,0.),
+            Vector::new(1.,0.,0.),
+            Vector::new(0.,0.,1.),
+            1.,
+            Vector::new(0.,1.,0.)
+        )
+    }
+
+    #[test]
+    // spawned rays should start on the side they leave towards
+    fn test_spawn_ray() {
+        let si = test_interaction();
+        let up = si.spawn_ray(&Vector::new(0.,1.,0.));
+        assert!(up.o.y > 0.);
+        let down = si.spawn_ray(&Vector::new(1.,-1.,0.));
+        assert!(down.o.y < 0.);
+    }
+
+    #[test]
+    // ray towards a point should stop just short of it
+    fn test_spawn_ray_to() {
+        let si = test_interaction();
+        let (ray,tmax) = si.spawn_ray_to(&Point::new(0.,2.,0.));
+        let end = ray.at(tmax);
+        assert!(end.y < 2.);
+        assert!(end.y > 1.99);
+    }
+
+    #[test]
+    // medium should follow the side a direction leaves towards
+    fn test_medium() {
+        let mut si = test_interaction();
+        let up = Vector::new(0.,1.,0.);
+        let down = Vector::new(0.,-1.,0.);
+        assert_eq!(si.medium(&up,Some(3)),Some(3));
+        si.medium_interface = Some(MediumInterface::new(Some(1),None));
+        assert_eq!(si.medium(&up,Some(3)),None);
+        assert_eq!(si.medium(&down,None),Some(1));
+    }
+
+    #[test]
+    // a pixel footprint on a plane seen head on should scale with distance
+    fn test_compute_differentials() {
+        let mut si = test_interaction();
+        let ray = Ray::new(&Point::new(0.,2.,0.),&Vector::new(0.,-1.,0.)).with_differentials(RayDifferential {
+            rx_o: Point::new(0.,2.,0.),
+            rx_d: Vector::new(0.01,-1.,0.),
+            ry_o: Point::new(0.,2.,0.),
+            ry_d: Vector::new(0.,-1.,0.02)
+        });
+        si.compute_differentials(&ray);
+        assert!((si.dpdx.x - 0.02).abs() < 1e-12);
+        assert!((si.duvdx.0 - 0.02).abs() < 1e-12);
+        assert_eq!(si.duvdx.1,0.);
+        assert!((si.duvdy.1 - 0.04).abs() < 1e-12);
+        // no differentials leaves the footprint empty
+        let mut si = test_interaction();
+        si.compute_differentials(&Ray::new(&Point::new(0.,2.,0.),&Vector::new(0.,-1.,0.)));
+        assert_eq!(si.duvdx,(0.,0.));
+    }
+
+    // first order prediction of the offset direction against the exact one
+    fn check_specular(eta: f64,exact: &dyn Fn(&Vector) -> Vector) {
+        let mut si = test_interaction();
+        let d = Vector::new(0.6,-0.8,0.);
+        si.wo = -d;
+        let ray = Ray::new(&Point::new(-1.2,1.6,0.),&d).with_differentials(RayDifferential {
+            rx_o: Point::new(-1.2,1.6,0.),
+            rx_d: Vector::new(0.6,-0.8,1e-3).normalize().unwrap(),
+            ry_o: Point::new(-1.2,1.6,0.),
+            ry_d: Vector::new(0.6 + 1e-3,-0.8,0.).normalize().unwrap()
+        });
+        si.compute_differentials(&ray);
+        let spawned = si.spawn_specular_ray(&ray,&exact(&d),eta);
+        let rd = spawned.differentials.unwrap();
+        for (predicted,offset) in [(rd.rx_d,ray.differentials.unwrap().rx_d),(rd.ry_d,ray.differentials.unwrap().ry_d)] {
+            let expected = exact(&offset);
+            let error = predicted - expected;
+            assert!(error.dot(error).sqrt() < 1e-5,"{} {}",predicted,expected);
+        }
+    }
+
+    #[test]
+    // reflected and refracted differentials should follow the exact directions
+    fn test_spawn_specular_ray() {
+        check_specular(1.,&|d: &Vector| Vector::new(d.x,-d.y,d.z));
+        let eta = 1.5;
+        check_specular(eta,&|d: &Vector| {
+            let d = d.normalize().unwrap();
+            let cos_i = -d.y;
+            let cos_t = f64::sqrt(1. - (1. - cos_i*cos_i) / (eta*eta));
+            Vector::new(d.x / eta,-cos_t,d.z / eta)
+        });
+        // rays without differentials stay without
+        let si = test_interaction();
+        let ray = Ray::new(&Point::new(0.,1.,0.),&Vector::new(0.,-1.,0.));
+        assert!(si.spawn_specular_ray(&ray,&Vector::new(0.,1.,0.),1.).differentials.is_none());
+    }
+}
@@ -0,0 +1,420 @@
+use super::{
+    traits::Primitive,
+    interaction::SurfaceInteraction,
+    transform::Transform,
+    cuboid::slabs,
+    triangle::intersect_triangle
+};
+use crate::{
+    math::{
+        point::Point,
+        vector::Vector,
+        normal::Normal,
+        matrix::Matrix,
+        ray::Ray,
+        frame::Frame,
+        sampling::Distribution1D,
+        traits::{Cross,Dot,Len,Normalize}
+    },
+    material::normalmap::generate_tangents
+};
+
+/// Largest number of triangles in a leaf of the hierarchy
+const LEAF_SIZE: usize = 4;
+
+/// # TriangleMesh
+/// Indexed triangles sharing their vertices, intersected through a
+/// bounding volume hierarchy built once over the triangles. Vertex
+/// normals are interpolated into the shading normal, vertex uvs default
+/// to (0,0) (1,0) (1,1) at the corners of every triangle, and vertex
+/// tangents are generated once both normals and uvs are known
+///
+/// # Parameters
+/// * positions (vertex positions in object space)
+/// * indices (vertices of each triangle, counter clockwise seen from outside)
+/// * normals (vertex normals, None for faceted shading)
+/// * uvs (vertex uvs)
+/// * tangents (vertex tangents and bitangent signs, derived from normals and uvs)
+/// * transform (placement in the world)
+pub struct TriangleMesh {
+    pub positions: Vec<Point>,
+    pub indices: Vec<[usize; 3]>,
+    pub normals: Option<Vec<Normal>>,
+    pub uvs: Option<Vec<(f64,f64)>>,
+    pub tangents: Option<Vec<(Vector,f64)>>,
+    pub transform: Transform,
+    nodes: Vec<Node>,
+    order: Vec<usize>,
+    distribution: Distribution1D
+}
+
+/// # Node
+/// Box of the hierarchy, leaves list the triangles order[start..start + count]
+/// and inner nodes are followed by their first child
+///
+/// # Parameters
+/// * min, max (bounds of the triangles below)
+/// * start (first triangle of a leaf)
+/// * count (triangles in a leaf, 0 for inner nodes)
+/// * second (index of the second child of an inner node)
+struct Node {
+    min: Point,
+    max: Point,
+    start: usize,
+    count: usize,
+    second: usize
+}
+
+/// Primitive trait
+impl Primitive for TriangleMesh {
+    fn intersect(&self,ray: &Ray,tmax: f64) -> Option<SurfaceInteraction> {
+        let mut tests: usize = 0;
+        self.intersect_counted(ray,tmax,&mut tests)
+    }
+
+    /// Counts hierarchy nodes visited and triangles tested
+    fn intersect_counted(&self,ray: &Ray,tmax: f64,tests: &mut usize) -> Option<SurfaceInteraction> {
+        let r = self.transform.ray_to_object(ray);
+        let mut tmax = tmax;
+        let mut nearest = None;
+        let mut stack = vec![0];
+        while let Some(i) = stack.pop() {
+            let node = &self.nodes[i];
+            *tests += 1;
+            match slabs(&r,&node.min,&node.max) {
+                Some((t0,t1,_,_)) if t1 > 0.0 && t0 < tmax => (),
+                _ => continue
+            }
+            if node.count == 0 {
+                stack.push(node.second);
+                stack.push(i + 1);
+                continue
+            }
+            for &k in &self.order[node.start..node.start + node.count] {
+                let [p0,p1,p2] = self.corners(k);
+                *tests += 1;
+                if let Some((t,b1,b2)) = intersect_triangle(&p0,&p1,&p2,&r,tmax) {
+                    tmax = t;
+                    nearest = Some((k,t,b1,b2));
+                }
+            }
+        }
+        let (k,t,b1,b2) = nearest?;
+        self.interaction(&r,ray,k,t,(b1,b2))
+    }
+
+    fn area(&self) -> f64 {
+        (0..self.indices.len()).map(|k| self.triangle_area(k)).sum()
+    }
+
+    fn sample(&self,u: (f64,f64)) -> Option<(Point,Normal)> {
+        let (x,_,k) = self.distribution.sample_continuous(u.0);
+        // reuse the position within the chosen piece
+        let u0 = (x * self.indices.len() as f64 - k as f64).clamp(0.0,1.0);
+        let root = u0.sqrt();
+        let (b1,b2) = (u.1 * root,1.0 - root);
+        let [p0,p1,p2] = self.corners(k);
+        let n = (p1 - p0).cross(p2 - p0).normalize().ok()?;
+        Some(self.transform.sample_to_world((p0 + (p1 - p0) * b1 + (p2 - p0) * b2,Normal::from(n))))
+    }
+}
+
+impl TriangleMesh {
+    /// Construct mesh from vertex positions and triangles
+    pub fn new(positions: Vec<Point>,indices: Vec<[usize; 3]>) -> Result<TriangleMesh,String> {
+        if indices.is_empty() {
+            return Err("mesh has no triangles".to_string())
+        }
+        if let Some(i) = indices.iter().flatten().find(|&&i| i >= positions.len()) {
+            return Err(format!("vertex index {} out of range for {} positions",i,positions.len()))
+        }
+        let mut mesh = TriangleMesh {
+            positions,
+            indices,
+            normals: None,
+            uvs: None,
+            tangents: None,
+            transform: Transform::new(),
+            nodes: vec![],
+            order: vec![],
+            distribution: Distribution1D::new(&[1.0])
+        };
+        let areas: Vec<f64> = (0..mesh.indices.len()).map(|k| mesh.triangle_area(k)).collect();
+        mesh.distribution = Distribution1D::new(&areas);
+        let mut order: Vec<usize> = (0..mesh.indices.len()).collect();
+        let centroids: Vec<Point> = (0..mesh.indices.len()).map(|k| {
+            let [p0,p1,p2] = mesh.corners(k);
+            p0 + ((p1 - p0) + (p2 - p0)) * (1.0 / 3.0)
+        }).collect();
+        let count = order.len();
+        mesh.build(&mut order,&centroids,0,count);
+        mesh.order = order;
+        Ok(mesh)
+    }
+
+    /// Attach vertex normals
+    pub fn with_normals(mut self,normals: Vec<Normal>) -> Result<TriangleMesh,String> {
+        if normals.len() != self.positions.len() {
+            return Err(format!("expected {} normals, found {}",self.positions.len(),normals.len()))
+        }
+        self.normals = Some(normals.iter().map(|n| n.normalize().unwrap_or(*n)).collect());
+        self.update_tangents();
+        Ok(self)
+    }
+
+    /// Attach vertex uvs
+    pub fn with_uvs(mut self,uvs: Vec<(f64,f64)>) -> Result<TriangleMesh,String> {
+        if uvs.len() != self.positions.len() {
+            return Err(format!("expected {} uvs, found {}",self.positions.len(),uvs.len()))
+        }
+        self.uvs = Some(uvs);
+        self.update_tangents();
+        Ok(self)
+    }
+
+    /// Place the mesh in the world
+    pub fn set_object_to_world(&mut self,object_to_world: &Matrix) -> Result<(),String> {
+        self.transform = Transform::from_matrix(object_to_world)?;
+        Ok(())
+    }
+
+    /// Generate tangents once normals and uvs are both present
+    fn update_tangents(&mut self) {
+        if let (Some(normals),Some(uvs)) = (&self.normals,&self.uvs) {
+            self.tangents = Some(generate_tangents(&self.positions,normals,uvs,&self.indices));
+        }
+    }
+
+    /// Object space corners of triangle k
+    fn corners(&self,k: usize) -> [Point; 3] {
+        self.indices[k].map(|i| self.positions[i])
+    }
+
+    fn triangle_area(&self,k: usize) -> f64 {
+        let [p0,p1,p2] = self.corners(k);
+        0.5 * (p1 - p0).cross(p2 - p0).len()
+    }
+
+    /// Build the node over order[start..end] and those below it, split at
+    /// the median centroid along the longest axis of the centroids
+    fn build(&mut self,order: &mut [usize],centroids: &[Point],start: usize,end: usize) {
+        let mut min = Point::new(f64::INFINITY,f64::INFINITY,f64::INFINITY);
+        let mut max = Point::new(f64::NEG_INFINITY,f64::NEG_INFINITY,f64::NEG_INFINITY);
+        for &k in &order[start..end] {
+            for p in self.corners(k) {
+                min = Point::new(min.x.min(p.x),min.y.min(p.y),min.z.min(p.z));
+                max = Point::new(max.x.max(p.x),max.y.max(p.y),max.z.max(p.z));
+            }
+        }
+        let index = self.nodes.len();
+        self.nodes.push(Node {min,max,start,count: end - start,second: 0});
+        if end - start <= LEAF_SIZE {
+            return
+        }
+
+        let axis_of = |p: &Point,axis: usize| [p.x,p.y,p.z][axis];
+        let (lo,hi) = order[start..end].iter().fold(
+            ([f64::INFINITY; 3],[f64::NEG_INFINITY; 3]),
+            |(mut lo,mut hi),&k| {
+                for axis in 0..3 {
+                    lo[axis] = lo[axis].min(axis_of(&centroids[k],axis));
+                    hi[axis] = hi[axis].max(axis_of(&centroids[k],axis));
+                }
+                (lo,hi)
+            }
+        );
+        let axis = (0..3).fold(0,|best,axis| if hi[axis] - lo[axis] > hi[best] - lo[best] { axis } else { best });
+        let mid = (start + end) / 2;
+        order[start..end].select_nth_unstable_by(mid - start,|&a,&b| {
+            axis_of(&centroids[a],axis).total_cmp(&axis_of(&centroids[b],axis))
+        });
+
+        self.nodes[index].count = 0;
+        self.build(order,centroids,start,mid);
+        self.nodes[index].second = self.nodes.len();
+        self.build(order,centroids,mid,end);
+    }
+
+    /// World space interaction at barycentrics (b1,b2) of triangle k
+    fn interaction(&self,r: &Ray,ray: &Ray,k: usize,t: f64,(b1,b2): (f64,f64)) -> Option<SurfaceInteraction> {
+        let [i0,i1,i2] = self.indices[k];
+        let b0 = 1.0 - b1 - b2;
+        let [p0,p1,p2] = self.corners(k);
+        let (e1,e2) = (p1 - p0,p2 - p0);
+        let [uv0,uv1,uv2] = match &self.uvs {
+            Some(uvs) => [uvs[i0],uvs[i1],uvs[i2]],
+            None => [(0.0,0.0),(1.0,0.0),(1.0,1.0)]
+        };
+        let uv = (
+            b0 * uv0.0 + b1 * uv1.0 + b2 * uv2.0,
+            b0 * uv0.1 + b1 * uv1.1 + b2 * uv2.1
+        );
+
+        let mut n = Normal::from(e1.cross(e2).normalize().ok()?);
+        let shading = self.normals.as_ref().and_then(|normals| {
+            (normals[i0] * b0 + normals[i1] * b1 + normals[i2] * b2).normalize().ok()
+        });
+        if let Some(ns) = shading {
+            // the geometric normal follows the side the vertex normals face
+            if n.dot(Vector::from(ns)) < 0.0 {
+                n = -n;
+            }
+        }
+
+        let (du1,dv1) = (uv1.0 - uv0.0,uv1.1 - uv0.1);
+        let (du2,dv2) = (uv2.0 - uv0.0,uv2.1 - uv0.1);
+        let det = du1*dv2 - dv1*du2;
+        let (dpdu,dpdv) = if det.abs() > 1e-12 {
+            ((e1 * dv2 - e2 * dv1) * (1.0 / det),(e2 * du1 - e1 * du2) * (1.0 / det))
+        } else {
+            let frame = Frame::from_normal(&n);
+            (frame.s,frame.t)
+        };
+
+        let si = SurfaceInteraction::new(
+            p0 + e1 * b1 + e2 * b2,
+            n,
+            uv,
+            dpdu,
+            dpdv,
+            t,
+            -r.d
+        );
+        let mut si = self.transform.interaction_to_world(&si,ray)?;
+        si.barycentric = Some((b1,b2));
+        if let Some(ns) = shading {
+            let dpdus = si.dpdu;
+            si.set_shading_geometry(&self.transform.normal_to_world(&ns),&dpdus);
+        }
+        if let Some(tangents) = &self.tangents {
+            let (t0,t1,t2) = (tangents[i0],tangents[i1],tangents[i2]);
+            let tangent = t0.0 * b0 + t1.0 * b1 + t2.0 * b2;
+            // the sign cannot be blended, take the nearest vertex's
+            let sign = if b0 >= b1 && b0 >= b2 { t0.1 } else if b1 >= b2 { t1.1 } else { t2.1 };
+            if let Ok(tangent) = self.transform.vector_to_world(&tangent).normalize() {
+                si.tangent = Some((tangent,sign));
+            }
+        }
+        Some(si)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // unit square in the xz plane facing +y
+    fn square() -> TriangleMesh {
+        let positions = vec![
+            Point::new(0.,0.,0.),Point::new(1.,0.,0.),Point::new(1.,0.,1.),Point::new(0.,0.,1.)
+        ];
+        TriangleMesh::new(positions,vec![[0,2,1],[0,3,2]]).unwrap()
+    }
+
+    // bumpy grid of n x n cells over [0,1]^2 in x and z
+    fn grid(n: usize) -> TriangleMesh {
+        let height = |x: f64,z: f64| 0.1 * f64::sin(7. * x) * f64::cos(5. * z);
+        let positions = (0..=n).flat_map(|j| (0..=n).map(move |i| {
+            let (x,z) = (i as f64 / n as f64,j as f64 / n as f64);
+            Point::new(x,height(x,z),z)
+        })).collect();
+        let indices = (0..n).flat_map(|j| (0..n).flat_map(move |i| {
+            let a = j*(n + 1) + i;
+            [[a,a + n + 2,a + 1],[a,a + n + 1,a + n + 2]]
+        })).collect();
+        TriangleMesh::new(positions,indices).unwrap()
+    }
+
+    #[test]
+    // hits should report barycentrics, uvs and a normal facing out
+    fn test_intersect() {
+        let mesh = square();
+        let ray = Ray::new(&Point::new(0.75,2.,0.25),&Vector::new(0.,-1.,0.));
+        let si = mesh.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.t - 2.).abs() < 1e-12);
+        assert!((si.n.y - 1.).abs() < 1e-12);
+        let (b1,b2) = si.barycentric.unwrap();
+        assert!((b1 - 0.25).abs() < 1e-12 && (b2 - 0.5).abs() < 1e-12);
+        assert!((si.uv.0 - 0.75).abs() < 1e-12 && (si.uv.1 - 0.5).abs() < 1e-12);
+        assert!(si.n.dot(si.dpdu).abs() < 1e-12 && si.n.dot(si.dpdv).abs() < 1e-12);
+        assert!(mesh.intersect(&ray,1.).is_none());
+        let outside = Ray::new(&Point::new(1.5,2.,0.25),&Vector::new(0.,-1.,0.));
+        assert!(mesh.intersect(&outside,f64::INFINITY).is_none());
+        assert!(TriangleMesh::new(vec![Point::new(0.,0.,0.)],vec![[0,0,1]]).is_err());
+    }
+
+    #[test]
+    // the hierarchy should find the same hits as testing every triangle
+    fn test_hierarchy() {
+        let mesh = grid(12);
+        assert!(mesh.nodes.len() > 1);
+        for i in 0..50 {
+            let o = Point::new(-0.5 + 0.04 * i as f64,1.,(0.37 * i as f64).fract());
+            let ray = Ray::new(&o,&Vector::new(0.6,-1.,0.1 * (i % 5) as f64 - 0.2));
+            let brute = (0..mesh.indices.len()).filter_map(|k| {
+                let [p0,p1,p2] = mesh.corners(k);
+                intersect_triangle(&p0,&p1,&p2,&ray,f64::INFINITY)
+            }).map(|(t,_,_)| t).fold(f64::INFINITY,f64::min);
+            match mesh.intersect(&ray,f64::INFINITY) {
+                Some(si) => assert_eq!(si.t,brute),
+                None => assert!(brute.is_infinite())
+            }
+        }
+
+        // a hit tests a few nodes and triangles, a miss of the bounds only the root
+        let mut tests: usize = 0;
+        let ray = Ray::new(&Point::new(0.5,1.,0.5),&Vector::new(0.,-1.,0.));
+        mesh.intersect_counted(&ray,f64::INFINITY,&mut tests).unwrap();
+        assert!(tests > 2 && tests < mesh.indices.len() / 4,"{}",tests);
+        let mut tests: usize = 0;
+        let away = Ray::new(&Point::new(0.5,1.,0.5),&Vector::new(0.,1.,0.));
+        assert!(mesh.intersect_counted(&away,f64::INFINITY,&mut tests).is_none());
+        assert_eq!(tests,1);
+    }
+
+    #[test]
+    // vertex normals and uvs should be interpolated with tangents along u
+    fn test_shading() {
+        let n = Normal::new(0.,1.,0.);
+        let tilted = Normal::new(0.6,0.8,0.);
+        let mesh = square()
+            .with_normals(vec![n,tilted,tilted,n]).unwrap()
+            .with_uvs(vec![(0.,0.),(2.,0.),(2.,2.),(0.,2.)]).unwrap();
+        let ray = Ray::new(&Point::new(0.5,2.,0.25),&Vector::new(0.,-1.,0.));
+        let si = mesh.intersect(&ray,f64::INFINITY).unwrap();
+        let expected = (Vector::new(0.,1.,0.) + Vector::new(0.6,0.8,0.)).normalize().unwrap();
+        assert!((Vector::from(si.ns) - expected).len() < 1e-12);
+        assert!((si.uv.0 - 1.).abs() < 1e-12 && (si.uv.1 - 0.5).abs() < 1e-12);
+        // v runs along +z, mirrored against n x tangent
+        let (tangent,sign) = si.tangent.unwrap();
+        assert!(tangent.x > 0.9 && tangent.z.abs() < 1e-12 && sign == -1.);
+        assert!(square().with_normals(vec![n]).is_err());
+    }
+
+    #[test]
+    // samples should spread by area over the placed mesh
+    fn test_sample() {
+        let positions = vec![
+            Point::new(0.,0.,0.),Point::new(1.,0.,0.),Point::new(0.,1.,0.),Point::new(3.,0.,0.),Point::new(0.,3.,0.)
+        ];
+        let mut mesh = TriangleMesh::new(positions,vec![[0,1,2],[0,3,4]]).unwrap();
+        assert_eq!(mesh.area(),5.);
+        mesh.set_object_to_world(&Matrix::translate(&Vector::new(0.,0.,2.))).unwrap();
+        let mut small: i32 = 0;
+        for i in 0..100 {
+            let (p,n) = mesh.sample(((i as f64 + 0.5) / 100.,(0.61 * i as f64).fract())).unwrap();
+            assert!(p.z == 2. && n.z == 1.);
+            assert!(p.x >= 0. && p.y >= 0. && p.x + p.y <= 3. + 1e-12);
+            if p.x + p.y <= 1. {
+                small += 1;
+            }
+        }
+        // a tenth of the samples land on the small triangle and a ninth of
+        // the rest on the part of the large one it covers
+        assert!((small - 20).abs() <= 3,"{}",small);
+    }
+}
@@ -0,0 +1,167 @@
+use std::f64::consts::PI;
+
+use super::{
+    traits::Primitive,
+    interaction::SurfaceInteraction,
+    transform::Transform,
+    quadric::{azimuth,nearest_root}
+};
+use crate::math::{
+    point::Point,
+    vector::Vector,
+    normal::Normal,
+    matrix::Matrix,
+    ray::Ray,
+    traits::Normalize
+};
+
+/// # Paraboloid
+/// Paraboloid x^2 + z^2 = radius^2 y / y_max opening up the y axis of
+/// object space, clipped to y_min <= y <= y_max and swept from
+/// phi = 0 to phi_max
+///
+/// # Parameters
+/// * radius (radius at y_max)
+/// * y_min (bottom of the paraboloid, 0 keeps the vertex)
+/// * y_max (top of the paraboloid)
+/// * phi_max (sweep angle in (0,2 pi])
+/// * transform (placement in the world)
+pub struct Paraboloid {
+    pub radius: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+    pub phi_max: f64,
+    pub transform: Transform
+}
+
+/// Primitive trait
+impl Primitive for Paraboloid {
+    fn intersect(&self,ray: &Ray,tmax: f64) -> Option<SurfaceInteraction> {
+        let r = self.transform.ray_to_object(ray);
+        let (o,d) = (r.o,r.d);
+        let k = self.y_max / (self.radius * self.radius);
+        let coefficients = (
+            k * (d.x*d.x + d.z*d.z),
+            2.0 * k * (d.x*o.x + d.z*o.z) - d.y,
+            k * (o.x*o.x + o.z*o.z) - o.y
+        );
+        let (t,p) = nearest_root(&r,tmax,coefficients,|p| {
+            p.y >= self.y_min && p.y <= self.y_max && azimuth(p.x,p.z) <= self.phi_max
+        })?;
+
+        let phi = azimuth(p.x,p.z);
+        let height = self.y_max - self.y_min;
+        // the slope away from the axis is infinite at the vertex
+        let dpdv = if p.y > 0.0 {
+            Vector::new(p.x / (2.0 * p.y),1.0,p.z / (2.0 * p.y)) * height
+        } else {
+            Vector::new(0.0,height,0.0)
+        };
+        let si = SurfaceInteraction::new(
+            p,
+            self.normal(&p)?,
+            (phi / self.phi_max,(p.y - self.y_min) / height),
+            Vector::new(-self.phi_max * p.z,0.0,self.phi_max * p.x),
+            dpdv,
+            t,
+            -d
+        );
+        self.transform.interaction_to_world(&si,ray)
+    }
+
+    fn area(&self) -> f64 {
+        let radius2 = self.radius * self.radius;
+        let k = 4.0 * self.y_max / radius2;
+        (radius2 * radius2 * self.phi_max / (12.0 * self.y_max * self.y_max))
+            * (f64::powf(k * self.y_max + 1.0,1.5) - f64::powf(k * self.y_min + 1.0,1.5))
+    }
+
+    fn sample(&self,u: (f64,f64)) -> Option<(Point,Normal)> {
+        // area up to y grows as (k y + 1)^1.5, invert it
+        let k = 4.0 * self.y_max / (self.radius * self.radius);
+        let w0 = f64::powf(k * self.y_min + 1.0,1.5);
+        let w1 = f64::powf(k * self.y_max + 1.0,1.5);
+        let y = (f64::powf(w0 + u.0 * (w1 - w0),2.0 / 3.0) - 1.0) / k;
+        let r = self.radius * f64::sqrt(f64::max(0.0,y / self.y_max));
+        let (sin_phi,cos_phi) = f64::sin_cos(u.1 * self.phi_max);
+        let p = Point::new(r * cos_phi,y,r * sin_phi);
+        Some(self.transform.sample_to_world((p,self.normal(&p)?)))
+    }
+}
+
+impl Paraboloid {
+    /// Construct paraboloid about the y axis, the range is clamped to y >= 0
+    pub fn new(radius: f64,y_min: f64,y_max: f64,phi_max: f64) -> Paraboloid {
+        Paraboloid {
+            radius,
+            y_min: f64::max(0.0,f64::min(y_min,y_max)),
+            y_max: f64::max(y_min,y_max),
+            phi_max: phi_max.clamp(0.0,2.0 * PI),
+            transform: Transform::new()
+        }
+    }
+
+    /// Place the paraboloid in the world
+    pub fn set_object_to_world(&mut self,object_to_world: &Matrix) -> Result<(),String> {
+        self.transform = Transform::rigid_from_matrix(object_to_world)?;
+        Ok(())
+    }
+
+    /// Outward normal at object space point p, pointing away from the axis
+    fn normal(&self,p: &Point) -> Option<Normal> {
+        let k = self.y_max / (self.radius * self.radius);
+        Normal::new(2.0 * k * p.x,-1.0,2.0 * k * p.z).normalize().ok()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::traits::Dot;
+
+    #[test]
+    // hits should lie on the surface with the normal perpendicular to it
+    fn test_intersect() {
+        let paraboloid = Paraboloid::new(1.,0.,1.,2. * PI);
+        let ray = Ray::new(&Point::new(-5.,0.25,0.),&Vector::new(1.,0.,0.));
+        let si = paraboloid.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.p.x + 0.5).abs() < 1e-12);
+        assert!((si.uv.1 - 0.25).abs() < 1e-12);
+        assert!(si.n.dot(si.dpdu).abs() < 1e-12);
+        assert!(si.n.dot(si.dpdv).abs() < 1e-12);
+        assert!(si.n.x < 0. && si.n.y < 0.);
+        // the vertex faces straight down
+        let up = Ray::new(&Point::new(0.,-1.,0.),&Vector::new(0.,1.,0.));
+        let si = paraboloid.intersect(&up,f64::INFINITY).unwrap();
+        assert!((si.t - 1.).abs() < 1e-12);
+        assert_eq!(si.n.y,-1.);
+        // clipping the bottom opens a hole at the vertex
+        let clipped = Paraboloid::new(1.,0.5,1.,2. * PI);
+        assert!(clipped.intersect(&up,f64::INFINITY).is_none());
+        assert!(clipped.intersect(&ray,f64::INFINITY).is_none());
+    }
+
+    #[test]
+    // samples should lie on the surface and spread by area
+    fn test_sample() {
+        let paraboloid = Paraboloid::new(1.,0.,1.,2. * PI);
+        // area of revolution of r = sqrt(y), pi / 6 (5^1.5 - 1)
+        assert!((paraboloid.area() - PI / 6. * (f64::powf(5.,1.5) - 1.)).abs() < 1e-12);
+        let mut lower = 0;
+        for i in 0..1000 {
+            let (p,n) = paraboloid.sample(((i as f64 + 0.5) / 1000.,0.3)).unwrap();
+            assert!((p.x*p.x + p.z*p.z - p.y).abs() < 1e-12);
+            assert!(n.y < 0.);
+            if p.y < 0.5 {
+                lower += 1;
+            }
+        }
+        // the same surface cut at y = 0.5
+        let expected = Paraboloid::new(f64::sqrt(0.5),0.,0.5,2. * PI).area() / paraboloid.area();
+        assert!((lower as f64 / 1000. - expected).abs() < 0.01);
+    }
+}
@@ -1,10 +1,14 @@
-use super::traits::Primitive;
+use super::{
+    traits::Primitive,
+    interaction::SurfaceInteraction
+};
 use crate::math::{
     point::Point,
     vector::Vector,
     normal::Normal,
     ray::Ray,
-    traits::Dot
+    frame::Frame,
+    traits::{Dot,Normalize}
 };
 
 pub struct Plane {
@@ -24,6 +28,33 @@ impl Primitive for Plane {
 
         None
     }
+
+    fn intersect(&self,ray: &Ray,tmax: f64) -> Option<SurfaceInteraction> {
+        let n: Normal = self.normal.normalize().ok()?;
+        let denom: f64 = ray.d.dot(n);
+        if denom == 0.0 {
+            return None
+        }
+        let t: f64 = (self.point - ray.o).dot(n) / denom;
+        if t <= 0.0 || t >= tmax {
+            return None
+        }
+
+        // uv measured along an arbitrary tangent frame through the plane point
+        let p = ray.at(t);
+        let frame = Frame::from_normal(&n);
+        let offset = p - self.point;
+
+        Some(SurfaceInteraction::new(
+            p,
+            n,
+            (offset.dot(frame.s),offset.dot(frame.t)),
+            frame.s,
+            frame.t,
+            t,
+            (-ray.d).normalize().ok()?
+        ))
+    }
 }
 
 impl Plane {
@@ -86,4 +117,49 @@ mod tests {
         assert_eq!(hit.y,5.);
         assert_eq!(hit.z,0.);
     }
+
+    #[test]
+    // intersect should use the unit normal and respect tmax
+    fn test_intersect() {
+        let plane: Plane = Plane::new(
+            &Point::new(0.,-1.,0.),
+            &Normal::new(0.,2.,0.)
+        );
+        let ray: Ray = Ray::new(
+            &Point::new(3.,1.,0.),
+            &Vector::new(0.,-1.,0.)
+        );
+        let si = plane.intersect(&ray,f64::INFINITY).unwrap();
+        assert_eq!(si.t,2.);
+        assert_eq!(si.p.y,-1.);
+        assert_eq!(si.n.y,1.);
+        assert!(plane.intersect(&ray,1.).is_none());
+        // parallel and receding rays miss
+        let parallel: Ray = Ray::new(&Point::new(0.,1.,0.),&Vector::new(1.,0.,0.));
+        assert!(plane.intersect(&parallel,f64::INFINITY).is_none());
+        let away: Ray = Ray::new(&Point::new(0.,1.,0.),&Vector::new(0.,1.,0.));
+        assert!(plane.intersect(&away,f64::INFINITY).is_none());
+        // infinite planes cannot be sampled
+        assert!(plane.sample((0.5,0.5)).is_none());
+    }
+
+    #[test]
+    // pixel footprints on a receding plane should grow with distance
+    fn test_receding_footprint() {
+        use crate::scene::camera::Camera;
+
+        let plane: Plane = Plane::new(&Point::new(0.,0.,0.),&Normal::new(0.,1.,0.));
+        let camera = Camera::new(Point::new(0.,1.,0.),Point::new(0.,0.,10.),Vector::new(0.,1.,0.),60.,1.).unwrap();
+        let footprint = |v: f64| {
+            let ray = camera.generate_ray_differential(0.5,v,1. / 64.,1. / 64.);
+            let mut si = plane.intersect(&ray,f64::INFINITY).unwrap();
+            si.compute_differentials(&ray);
+            let (du,dv) = si.duvdy;
+            f64::sqrt(du*du + dv*dv)
+        };
+        let near = footprint(0.9);
+        let far = footprint(0.6);
+        assert!(near > 0.);
+        assert!(far > 4. * near,"{} {}",near,far);
+    }
 }
@@ -0,0 +1,50 @@
+use std::f64::consts::PI;
+
+use crate::math::{point::Point,ray::Ray,polynomial::solve_quadratic};
+
+/// Angle of (x,z) about the y axis in [0,2 pi), measured from +x towards +z
+pub fn azimuth(x: f64,z: f64) -> f64 {
+    let phi = f64::atan2(z,x);
+    if phi < 0.0 { phi + 2.0 * PI } else { phi }
+}
+
+/// Nearest root of a t^2 + b t + c in (0,tmax) whose point along ray
+/// passes the clipping test, the far root is tried when the near one
+/// is behind the origin or clipped away
+pub fn nearest_root<F: Fn(&Point) -> bool>(ray: &Ray,tmax: f64,coefficients: (f64,f64,f64),inside: F) -> Option<(f64,Point)> {
+    let (t0,t1) = solve_quadratic(coefficients.0,coefficients.1,coefficients.2)?;
+    [t0,t1].into_iter()
+        .filter(|&t| t > 0.0 && t < tmax)
+        .map(|t| (t,ray.at(t)))
+        .find(|(_,p)| inside(p))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::vector::Vector;
+
+    #[test]
+    // azimuth should wrap into [0,2 pi)
+    fn test_azimuth() {
+        assert_eq!(azimuth(1.,0.),0.);
+        assert!((azimuth(0.,1.) - PI / 2.).abs() < 1e-12);
+        assert!((azimuth(0.,-1.) - 1.5 * PI).abs() < 1e-12);
+    }
+
+    #[test]
+    // clipped near roots should fall through to the far one
+    fn test_nearest_root() {
+        let ray = Ray::new(&Point::new(-5.,0.,0.),&Vector::new(1.,0.,0.));
+        // unit circle in x, roots at t = 4 and 6
+        let circle = (1.,-10.,24.);
+        assert_eq!(nearest_root(&ray,f64::INFINITY,circle,|_| true).unwrap().0,4.);
+        assert_eq!(nearest_root(&ray,f64::INFINITY,circle,|p| p.x > 0.).unwrap().0,6.);
+        assert!(nearest_root(&ray,5.,circle,|p| p.x > 0.).is_none());
+        assert!(nearest_root(&ray,f64::INFINITY,circle,|_| false).is_none());
+    }
+}
@@ -0,0 +1,135 @@
+use super::{
+    traits::Primitive,
+    interaction::SurfaceInteraction
+};
+use crate::math::{
+    point::Point,
+    vector::Vector,
+    normal::Normal,
+    ray::Ray,
+    traits::{Cross,Dot,Len,LenSq,Normalize}
+};
+
+/// # Rectangle
+/// Bounded parallelogram spanned by two edges from a corner, a rectangle
+/// when the edges are perpendicular. u runs along edge_u and v along
+/// edge_v, the normal is edge_u x edge_v
+///
+/// # Parameters
+/// * corner (point at uv (0,0))
+/// * edge_u (edge from the corner to uv (1,0))
+/// * edge_v (edge from the corner to uv (0,1))
+pub struct Rectangle {
+    pub corner: Point,
+    pub edge_u: Vector,
+    pub edge_v: Vector
+}
+
+/// Primitive trait
+impl Primitive for Rectangle {
+    fn intersect(&self,ray: &Ray,tmax: f64) -> Option<SurfaceInteraction> {
+        let n = self.edge_u.cross(self.edge_v);
+        let denom = ray.d.dot(n);
+        if denom == 0.0 {
+            return None
+        }
+        let t = (self.corner - ray.o).dot(n) / denom;
+        if t <= 0.0 || t >= tmax {
+            return None
+        }
+
+        // w = u edge_u + v edge_v, crossing with one edge isolates the other
+        let p = ray.at(t);
+        let w = p - self.corner;
+        let n_sq = n.len_sq();
+        let u = w.cross(self.edge_v).dot(n) / n_sq;
+        let v = self.edge_u.cross(w).dot(n) / n_sq;
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return None
+        }
+
+        Some(SurfaceInteraction::new(
+            p,
+            Normal::from(n.normalize().ok()?),
+            (u,v),
+            self.edge_u,
+            self.edge_v,
+            t,
+            (-ray.d).normalize().ok()?
+        ))
+    }
+
+    fn area(&self) -> f64 {
+        self.edge_u.cross(self.edge_v).len()
+    }
+
+    fn sample(&self,u: (f64,f64)) -> Option<(Point,Normal)> {
+        let n = self.edge_u.cross(self.edge_v).normalize().ok()?;
+        Some((self.corner + self.edge_u * u.0 + self.edge_v * u.1,Normal::from(n)))
+    }
+}
+
+impl Rectangle {
+    /// Construct parallelogram from a corner and its two edges
+    pub fn new(corner: Point,edge_u: Vector,edge_v: Vector) -> Rectangle {
+        Rectangle {
+            corner,
+            edge_u,
+            edge_v
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // unit square floor facing up
+    fn floor() -> Rectangle {
+        Rectangle::new(Point::new(0.,0.,0.),Vector::new(0.,0.,2.),Vector::new(3.,0.,0.))
+    }
+
+    #[test]
+    // hits inside the edges should report uv, misses outside them
+    fn test_intersect() {
+        let rectangle = floor();
+        let ray = Ray::new(&Point::new(1.5,4.,0.5),&Vector::new(0.,-2.,0.));
+        let si = rectangle.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.t - 2.).abs() < 1e-12);
+        assert!((si.n.y - 1.).abs() < 1e-12);
+        assert!((si.uv.0 - 0.25).abs() < 1e-12);
+        assert!((si.uv.1 - 0.5).abs() < 1e-12);
+        assert!(rectangle.intersect(&ray,1.5).is_none());
+        let outside = Ray::new(&Point::new(3.5,4.,0.5),&Vector::new(0.,-1.,0.));
+        assert!(rectangle.intersect(&outside,f64::INFINITY).is_none());
+        let parallel = Ray::new(&Point::new(-1.,0.,0.5),&Vector::new(1.,0.,0.));
+        assert!(rectangle.intersect(&parallel,f64::INFINITY).is_none());
+    }
+
+    #[test]
+    // sheared edges should still bound the hits to the parallelogram
+    fn test_parallelogram() {
+        let rectangle = Rectangle::new(Point::new(0.,0.,0.),Vector::new(1.,0.,0.),Vector::new(1.,1.,0.));
+        let ray = Ray::new(&Point::new(1.5,0.9,-1.),&Vector::new(0.,0.,1.));
+        let si = rectangle.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.uv.0 - 0.6).abs() < 1e-12);
+        assert!((si.uv.1 - 0.9).abs() < 1e-12);
+        let ray = Ray::new(&Point::new(0.5,0.9,-1.),&Vector::new(0.,0.,1.));
+        assert!(rectangle.intersect(&ray,f64::INFINITY).is_none());
+        assert!((rectangle.area() - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    // samples should cover the rectangle with its normal
+    fn test_sample() {
+        let rectangle = floor();
+        assert!((rectangle.area() - 6.).abs() < 1e-12);
+        let (p,n) = rectangle.sample((0.5,1.)).unwrap();
+        assert!((p - Point::new(3.,0.,1.)).len() < 1e-12);
+        assert!((n.y - 1.).abs() < 1e-12);
+    }
+}
@@ -1,9 +1,16 @@
-use super::traits::Primitive;
+use std::f64::consts::PI;
+
+use super::{
+    traits::Primitive,
+    interaction::SurfaceInteraction
+};
 use crate::math::{
     point::Point,
     vector::Vector,
+    normal::Normal,
     ray::Ray,
-    traits::Dot
+    sampling::uniform_sample_sphere,
+    traits::{Dot,Normalize}
 };
 
 pub struct Sphere {
@@ -33,6 +40,53 @@ impl Primitive for Sphere {
         // return hit point
         Some(ray.o + ray.d*(*tmin))
     }
+
+    fn intersect(&self,ray: &Ray,tmax: f64) -> Option<SurfaceInteraction> {
+        let m: Vector = ray.o - self.center;
+        let a: f64 = ray.d.dot(ray.d);
+        let b: f64 = m.dot(ray.d);
+        let c: f64 = m.dot(m) - self.radius*self.radius;
+        let discrim: f64 = b*b - a*c;
+        if discrim < 0.0 {
+            return None
+        }
+
+        // nearest root in front of the origin
+        let root = f64::sqrt(discrim);
+        let mut t = (-b - root) / a;
+        if t <= 0.0 {
+            t = (-b + root) / a;
+        }
+        if t <= 0.0 || t >= tmax {
+            return None
+        }
+        self.interaction(ray,t)
+    }
+
+    fn area(&self) -> f64 {
+        4.0 * PI * self.radius * self.radius
+    }
+
+    fn sample(&self,u: (f64,f64)) -> Option<(Point,Normal)> {
+        let d = uniform_sample_sphere(u);
+        Some((self.center + d*self.radius,Normal::from(d)))
+    }
+
+    fn intervals(&self,ray: &Ray) -> Vec<(SurfaceInteraction,SurfaceInteraction)> {
+        let m: Vector = ray.o - self.center;
+        let a: f64 = ray.d.dot(ray.d);
+        let b: f64 = m.dot(ray.d);
+        let c: f64 = m.dot(m) - self.radius*self.radius;
+        let discrim: f64 = b*b - a*c;
+        if discrim <= 0.0 {
+            return vec![]
+        }
+        let root = f64::sqrt(discrim);
+        match (self.interaction(ray,(-b - root) / a),self.interaction(ray,(-b + root) / a)) {
+            (Some(enter),Some(exit)) => vec![(enter,exit)],
+            _ => vec![]
+        }
+    }
 }
 
 impl Sphere {
@@ -43,6 +97,33 @@ impl Sphere {
             center
         }
     }
+
+    /// Interaction at ray parameter t, which must lie on the sphere
+    fn interaction(&self,ray: &Ray,t: f64) -> Option<SurfaceInteraction> {
+        let p = ray.at(t);
+        let local = (p - self.center) * (1.0 / self.radius);
+
+        // spherical parameterization with y as the polar axis
+        let mut phi = f64::atan2(local.z,local.x);
+        if phi < 0.0 {
+            phi += 2.0 * PI;
+        }
+        let theta = f64::acos(local.y.clamp(-1.0,1.0));
+        let (sin_theta,cos_theta) = f64::sin_cos(theta);
+        let (sin_phi,cos_phi) = f64::sin_cos(phi);
+        let dpdu = Vector::new(-local.z,0.0,local.x) * (2.0 * PI * self.radius);
+        let dpdv = Vector::new(cos_theta*cos_phi,-sin_theta,cos_theta*sin_phi) * (PI * self.radius);
+
+        Some(SurfaceInteraction::new(
+            p,
+            Normal::from(local),
+            (phi / (2.0 * PI),theta / PI),
+            dpdu,
+            dpdv,
+            t,
+            (-ray.d).normalize().ok()?
+        ))
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -86,4 +167,45 @@ mod tests {
         assert_eq!(hit.y,0.0);
         assert_eq!(hit.z,4.0);
     }
+
+    #[test]
+    // intersect should return nearest hit with outward normal
+    fn test_intersect() {
+        let sphere: Sphere = Sphere::new(2.,Point::new(0.,0.,5.));
+        let ray: Ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,2.));
+        let si = sphere.intersect(&ray,f64::INFINITY).unwrap();
+        assert_eq!(si.t,1.5);
+        assert_eq!(si.p.z,3.);
+        assert_eq!(si.n.z,-1.);
+        assert_eq!(si.wo.z,-1.);
+        // limited by tmax
+        assert!(sphere.intersect(&ray,1.).is_none());
+        // missing ray
+        let miss: Ray = Ray::new(&Point::new(0.,3.,0.),&Vector::new(0.,0.,1.));
+        assert!(sphere.intersect(&miss,f64::INFINITY).is_none());
+    }
+
+    #[test]
+    // ray starting inside should hit the far side
+    fn test_intersect_inside() {
+        let sphere: Sphere = Sphere::new(1.,Point::new(0.,0.,0.));
+        let ray: Ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,1.,0.));
+        let si = sphere.intersect(&ray,f64::INFINITY).unwrap();
+        assert_eq!(si.t,1.);
+        assert_eq!(si.n.y,1.);
+        assert_eq!(si.uv.1,0.);
+    }
+
+    #[test]
+    // area samples should lie on the surface
+    fn test_sample() {
+        let sphere: Sphere = Sphere::new(2.,Point::new(1.,0.,0.));
+        assert_eq!(sphere.area(),16. * PI);
+        let (p,n) = sphere.sample((0.3,0.6)).unwrap();
+        assert!((p.distance(sphere.center) - 2.).abs() < 1e-12);
+        let expected = (p - sphere.center) * 0.5;
+        assert!((n.x - expected.x).abs() < 1e-12);
+        assert!((n.y - expected.y).abs() < 1e-12);
+        assert!((n.z - expected.z).abs() < 1e-12);
+    }
 }
@@ -0,0 +1,514 @@
+use std::{collections::HashMap,f64::consts::PI};
+
+use super::mesh::TriangleMesh;
+use crate::math::{
+    point::Point,
+    vector::Vector,
+    normal::Normal,
+    traits::{Cross,Dot,Normalize}
+};
+
+/// # Scheme
+/// Refinement rule applied at every level
+///
+/// # Parameters
+/// * Loop (triangles split in four, for triangle cages)
+/// * CatmullClark (polygons split into quads at their centroid, for quad and polygon cages)
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum Scheme {
+    Loop,
+    CatmullClark
+}
+
+/// # SubdivisionSurface
+/// Control cage refined a number of times and tessellated into a
+/// triangle mesh before rendering. Creased edges follow the semi-sharp
+/// rules of DeRose et al. "Subdivision Surfaces in Character Animation":
+/// an edge of sharpness s is refined by the sharp rules for the first
+/// floor(s) levels, blended with the smooth rules for the fraction left,
+/// and smooth after that. Vertices on two creased edges follow the
+/// crease, vertices on more stay put. Boundary edges are infinitely
+/// sharp, and normals are split across edges still creased at the end
+///
+/// # Parameters
+/// * scheme (refinement rule)
+/// * positions (cage vertices)
+/// * faces (cage polygons, counter clockwise seen from outside)
+/// * creases (sharpness of creased edges keyed by their vertices in increasing order)
+/// * levels (refinements before tessellation)
+pub struct SubdivisionSurface {
+    pub scheme: Scheme,
+    pub positions: Vec<Point>,
+    pub faces: Vec<Vec<usize>>,
+    pub creases: HashMap<(usize,usize),f64>,
+    pub levels: usize
+}
+
+/// # Topology
+/// Adjacency of a polygon mesh
+///
+/// # Parameters
+/// * edges (vertices of each edge in increasing order)
+/// * edge_index (edge number of a vertex pair in increasing order)
+/// * edge_faces (faces on each edge, one for boundary edges)
+/// * vertex_edges (edges around each vertex)
+/// * vertex_faces (faces around each vertex)
+struct Topology {
+    edges: Vec<(usize,usize)>,
+    edge_index: HashMap<(usize,usize),usize>,
+    edge_faces: Vec<Vec<usize>>,
+    vertex_edges: Vec<Vec<usize>>,
+    vertex_faces: Vec<Vec<usize>>
+}
+
+/// Refined mesh as (positions,faces,creases)
+type Level = (Vec<Point>,Vec<Vec<usize>>,HashMap<(usize,usize),f64>);
+
+impl SubdivisionSurface {
+    /// Construct surface from a control cage, Loop needs triangles
+    pub fn new(scheme: Scheme,positions: Vec<Point>,faces: Vec<Vec<usize>>,levels: usize) -> Result<SubdivisionSurface,String> {
+        for face in &faces {
+            if face.len() < 3 {
+                return Err(format!("face with {} vertices",face.len()))
+            }
+            if scheme == Scheme::Loop && face.len() != 3 {
+                return Err(format!("Loop subdivision needs triangles, found a face with {} vertices",face.len()))
+            }
+            if let Some(i) = face.iter().find(|&&i| i >= positions.len()) {
+                return Err(format!("vertex index {} out of range for {} positions",i,positions.len()))
+            }
+        }
+        Topology::new(positions.len(),&faces)?;
+        Ok(SubdivisionSurface {
+            scheme,
+            positions,
+            faces,
+            creases: HashMap::new(),
+            levels
+        })
+    }
+
+    /// Crease the edge between vertices a and b, an infinite sharpness
+    /// keeps it sharp at every level
+    pub fn set_crease(&mut self,a: usize,b: usize,sharpness: f64) -> Result<(),String> {
+        let found = self.faces.iter().any(|face| {
+            (0..face.len()).any(|i| edge(face[i],face[(i + 1) % face.len()]) == edge(a,b))
+        });
+        if !found {
+            return Err(format!("no edge between vertices {} and {}",a,b))
+        }
+        self.creases.insert(edge(a,b),sharpness.max(0.0));
+        Ok(())
+    }
+
+    /// Refine the cage and tessellate it into triangles with vertex normals
+    pub fn tessellate(&self) -> Result<TriangleMesh,String> {
+        let mut level = (self.positions.clone(),self.faces.clone(),self.creases.clone());
+        for _ in 0..self.levels {
+            let (positions,faces,creases) = &level;
+            level = match self.scheme {
+                Scheme::Loop => loop_step(positions,faces,creases)?,
+                Scheme::CatmullClark => catmull_clark_step(positions,faces,creases)?
+            };
+        }
+        let (positions,faces,creases) = level;
+        let triangles: Vec<[usize; 3]> = faces.iter()
+            .flat_map(|face| (1..face.len() - 1).map(move |i| [face[0],face[i],face[i + 1]]))
+            .collect();
+        let (positions,triangles,normals) = split_normals(&positions,&triangles,&creases);
+        TriangleMesh::new(positions,triangles)?.with_normals(normals)
+    }
+}
+
+impl Topology {
+    /// Gather adjacency of faces over n vertices, edges on more than two
+    /// faces cannot be refined
+    fn new(n: usize,faces: &[Vec<usize>]) -> Result<Topology,String> {
+        let mut topology = Topology {
+            edges: vec![],
+            edge_index: HashMap::new(),
+            edge_faces: vec![],
+            vertex_edges: vec![vec![]; n],
+            vertex_faces: vec![vec![]; n]
+        };
+        for (f,face) in faces.iter().enumerate() {
+            for i in 0..face.len() {
+                let key = edge(face[i],face[(i + 1) % face.len()]);
+                let e = match topology.edge_index.get(&key) {
+                    Some(&e) => e,
+                    None => {
+                        let e = topology.edges.len();
+                        topology.edges.push(key);
+                        topology.edge_index.insert(key,e);
+                        topology.edge_faces.push(vec![]);
+                        topology.vertex_edges[key.0].push(e);
+                        topology.vertex_edges[key.1].push(e);
+                        e
+                    }
+                };
+                topology.edge_faces[e].push(f);
+                if topology.edge_faces[e].len() > 2 {
+                    return Err(format!("edge between vertices {} and {} is shared by more than two faces",key.0,key.1))
+                }
+                topology.vertex_faces[face[i]].push(f);
+            }
+        }
+        Ok(topology)
+    }
+
+    /// Sharpness of edge e, infinite on the boundary
+    fn sharpness(&self,e: usize,creases: &HashMap<(usize,usize),f64>) -> f64 {
+        if self.edge_faces[e].len() < 2 {
+            return f64::INFINITY
+        }
+        creases.get(&self.edges[e]).copied().unwrap_or(0.0)
+    }
+
+    /// Vertex across edge e from v
+    fn other(&self,e: usize,v: usize) -> usize {
+        let (a,b) = self.edges[e];
+        if a == v { b } else { a }
+    }
+
+    /// Position of vertex v by the smooth rule, the crease rule or left in
+    /// place depending on its creased edges, blended by their sharpness
+    fn vertex_rule(&self,positions: &[Point],creases: &HashMap<(usize,usize),f64>,v: usize,smooth: Point) -> Point {
+        let creased: Vec<(usize,f64)> = self.vertex_edges[v].iter()
+            .map(|&e| (self.other(e,v),self.sharpness(e,creases)))
+            .filter(|&(_,s)| s > 0.0)
+            .collect();
+        if creased.len() < 2 {
+            return smooth
+        }
+        let sharpness = creased.iter().map(|&(_,s)| s).sum::<f64>() / creased.len() as f64;
+        let sharp = if creased.len() == 2 {
+            combine(&[(positions[v],0.75),(positions[creased[0].0],0.125),(positions[creased[1].0],0.125)])
+        } else {
+            positions[v]
+        };
+        blend(&smooth,&sharp,sharpness)
+    }
+}
+
+/// One level of Loop subdivision, every triangle split in four
+fn loop_step(positions: &[Point],faces: &[Vec<usize>],creases: &HashMap<(usize,usize),f64>) -> Result<Level,String> {
+    let topology = Topology::new(positions.len(),faces)?;
+    let n = positions.len();
+
+    let mut refined: Vec<Point> = (0..n).map(|v| {
+        let ring: Vec<usize> = topology.vertex_edges[v].iter().map(|&e| topology.other(e,v)).collect();
+        if ring.is_empty() {
+            return positions[v]
+        }
+        // Loop's weights for a vertex of valence k
+        let k = ring.len() as f64;
+        let beta = (0.625 - (0.375 + 0.25 * f64::cos(2.0 * PI / k)).powi(2)) / k;
+        let mut terms = vec![(positions[v],1.0 - k * beta)];
+        terms.extend(ring.iter().map(|&i| (positions[i],beta)));
+        topology.vertex_rule(positions,creases,v,combine(&terms))
+    }).collect();
+
+    refined.extend(topology.edges.iter().enumerate().map(|(e,&(a,b))| {
+        let mid = combine(&[(positions[a],0.5),(positions[b],0.5)]);
+        let across = &topology.edge_faces[e];
+        if across.len() < 2 {
+            return mid
+        }
+        let opposite = |f: usize| *faces[f].iter().find(|&&i| i != a && i != b).unwrap_or(&a);
+        let smooth = combine(&[
+            (positions[a],0.375),(positions[b],0.375),
+            (positions[opposite(across[0])],0.125),(positions[opposite(across[1])],0.125)
+        ]);
+        blend(&smooth,&mid,topology.sharpness(e,creases))
+    }));
+
+    let middle = |a: usize,b: usize| n + topology.edge_index[&edge(a,b)];
+    let faces = faces.iter().flat_map(|face| {
+        let [a,b,c] = [face[0],face[1],face[2]];
+        let (ab,bc,ca) = (middle(a,b),middle(b,c),middle(c,a));
+        [vec![a,ab,ca],vec![ab,b,bc],vec![ca,bc,c],vec![ab,bc,ca]]
+    }).collect();
+    Ok((refined,faces,refine_creases(creases,middle)))
+}
+
+/// One level of Catmull-Clark subdivision, every polygon split into quads
+fn catmull_clark_step(positions: &[Point],faces: &[Vec<usize>],creases: &HashMap<(usize,usize),f64>) -> Result<Level,String> {
+    let topology = Topology::new(positions.len(),faces)?;
+    let n = positions.len();
+    let m = topology.edges.len();
+    let face_points: Vec<Point> = faces.iter()
+        .map(|face| combine(&face.iter().map(|&i| (positions[i],1.0 / face.len() as f64)).collect::<Vec<_>>()))
+        .collect();
+
+    let mut refined: Vec<Point> = (0..n).map(|v| {
+        let edges = &topology.vertex_edges[v];
+        let around = &topology.vertex_faces[v];
+        if edges.is_empty() || around.is_empty() {
+            return positions[v]
+        }
+        // (Q + 2 R + (k - 3) v) / k with Q the average of the face points
+        // and R of the edge midpoints around v
+        let k = edges.len() as f64;
+        let mut terms = vec![(positions[v],(k - 3.0) / k)];
+        terms.extend(around.iter().map(|&f| (face_points[f],1.0 / (k * around.len() as f64))));
+        terms.extend(edges.iter().flat_map(|&e| {
+            let (a,b) = topology.edges[e];
+            [(positions[a],1.0 / (k * k)),(positions[b],1.0 / (k * k))]
+        }));
+        topology.vertex_rule(positions,creases,v,combine(&terms))
+    }).collect();
+
+    refined.extend(topology.edges.iter().enumerate().map(|(e,&(a,b))| {
+        let mid = combine(&[(positions[a],0.5),(positions[b],0.5)]);
+        let across = &topology.edge_faces[e];
+        if across.len() < 2 {
+            return mid
+        }
+        let smooth = combine(&[
+            (positions[a],0.25),(positions[b],0.25),
+            (face_points[across[0]],0.25),(face_points[across[1]],0.25)
+        ]);
+        blend(&smooth,&mid,topology.sharpness(e,creases))
+    }));
+    refined.extend(face_points);
+
+    let middle = |a: usize,b: usize| n + topology.edge_index[&edge(a,b)];
+    let faces = faces.iter().enumerate().flat_map(|(f,face)| {
+        let k = face.len();
+        (0..k).map(move |i| {
+            let (prev,v,next) = (face[(i + k - 1) % k],face[i],face[(i + 1) % k]);
+            vec![v,middle(v,next),n + m + f,middle(prev,v)]
+        }).collect::<Vec<_>>()
+    }).collect();
+    Ok((refined,faces,refine_creases(creases,middle)))
+}
+
+/// Creases of the next level, both halves of an edge keep its sharpness
+/// less one and smooth out once it runs out
+fn refine_creases<F: Fn(usize,usize) -> usize>(creases: &HashMap<(usize,usize),f64>,middle: F) -> HashMap<(usize,usize),f64> {
+    let mut refined = HashMap::new();
+    for (&(a,b),&sharpness) in creases {
+        let sharpness = sharpness - 1.0;
+        if sharpness > 0.0 {
+            let mid = middle(a,b);
+            refined.insert(edge(a,mid),sharpness);
+            refined.insert(edge(mid,b),sharpness);
+        }
+    }
+    refined
+}
+
+/// Vertex normals averaged over the triangles around each vertex,
+/// weighted by their angle there. Triangles only share a normal when
+/// they are joined through uncreased edges, vertices on creases are
+/// duplicated once per side. Returns (positions,triangles,normals)
+fn split_normals(positions: &[Point],triangles: &[[usize; 3]],creases: &HashMap<(usize,usize),f64>) -> (Vec<Point>,Vec<[usize; 3]>,Vec<Normal>) {
+    // corners 3 k + c, joined across smooth edges
+    let mut parent: Vec<usize> = (0..3 * triangles.len()).collect();
+    fn root(parent: &mut [usize],mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    let mut sides: HashMap<(usize,usize),Vec<(usize,usize)>> = HashMap::new();
+    for (k,triangle) in triangles.iter().enumerate() {
+        for c in 0..3 {
+            let (a,b) = (triangle[c],triangle[(c + 1) % 3]);
+            // corners of the lower and higher numbered vertex
+            let (ca,cb) = (3*k + c,3*k + (c + 1) % 3);
+            sides.entry(edge(a,b)).or_default().push(if a < b { (ca,cb) } else { (cb,ca) });
+        }
+    }
+    for (key,corners) in &sides {
+        if corners.len() != 2 || creases.get(key).is_some_and(|&s| s > 0.0) {
+            continue
+        }
+        for (x,y) in [(corners[0].0,corners[1].0),(corners[0].1,corners[1].1)] {
+            let (rx,ry) = (root(&mut parent,x),root(&mut parent,y));
+            parent[rx] = ry;
+        }
+    }
+
+    let mut index: HashMap<usize,usize> = HashMap::new();
+    let mut split_positions = vec![];
+    let mut sums = vec![];
+    let mut split_triangles = vec![[0; 3]; triangles.len()];
+    for (k,triangle) in triangles.iter().enumerate() {
+        let [p0,p1,p2] = triangle.map(|i| positions[i]);
+        let normal = (p1 - p0).cross(p2 - p0).normalize().unwrap_or(Vector::new(0.0,0.0,0.0));
+        for c in 0..3 {
+            let r = root(&mut parent,3*k + c);
+            let i = *index.entry(r).or_insert_with(|| {
+                split_positions.push(positions[triangle[c]]);
+                sums.push(Vector::new(0.0,0.0,0.0));
+                split_positions.len() - 1
+            });
+            split_triangles[k][c] = i;
+            let p = positions[triangle[c]];
+            let angle = match ((positions[triangle[(c + 1) % 3]] - p).normalize(),(positions[triangle[(c + 2) % 3]] - p).normalize()) {
+                (Ok(a),Ok(b)) => a.dot(b).clamp(-1.0,1.0).acos(),
+                _ => 0.0
+            };
+            sums[i] = sums[i] + normal * angle;
+        }
+    }
+    let normals = sums.iter().map(|n| Normal::from(n.normalize().unwrap_or(Vector::new(0.0,1.0,0.0)))).collect();
+    (split_positions,split_triangles,normals)
+}
+
+/// Key of the edge between a and b
+fn edge(a: usize,b: usize) -> (usize,usize) {
+    (a.min(b),a.max(b))
+}
+
+/// Weighted sum of points whose weights add up to one
+fn combine(terms: &[(Point,f64)]) -> Point {
+    let origin = Point::new(0.0,0.0,0.0);
+    terms.iter().fold(origin,|sum,&(p,w)| sum + (p - origin) * w)
+}
+
+/// Smooth rule result moved towards the sharp one by the sharpness
+fn blend(smooth: &Point,sharp: &Point,sharpness: f64) -> Point {
+    *smooth + (*sharp - *smooth) * sharpness.min(1.0)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        scene::traits::Primitive,
+        math::{ray::Ray,traits::Len}
+    };
+
+    // cube [-1,1]^3 of six quads facing out
+    fn cube(levels: usize) -> SubdivisionSurface {
+        let positions = (0..8).map(|i| Point::new(
+            if i & 1 == 0 { -1. } else { 1. },
+            if i & 2 == 0 { -1. } else { 1. },
+            if i & 4 == 0 { -1. } else { 1. }
+        )).collect();
+        let faces = vec![
+            vec![0,2,3,1],vec![4,5,7,6],vec![0,1,5,4],vec![2,6,7,3],vec![0,4,6,2],vec![1,3,7,5]
+        ];
+        SubdivisionSurface::new(Scheme::CatmullClark,positions,faces,levels).unwrap()
+    }
+
+    // octahedron with unit vertices on the axes
+    fn octahedron(levels: usize) -> SubdivisionSurface {
+        let positions = vec![
+            Point::new(1.,0.,0.),Point::new(-1.,0.,0.),Point::new(0.,1.,0.),
+            Point::new(0.,-1.,0.),Point::new(0.,0.,1.),Point::new(0.,0.,-1.)
+        ];
+        let faces = vec![
+            vec![0,2,4],vec![2,1,4],vec![1,3,4],vec![3,0,4],
+            vec![2,0,5],vec![1,2,5],vec![3,1,5],vec![0,3,5]
+        ];
+        SubdivisionSurface::new(Scheme::Loop,positions,faces,levels).unwrap()
+    }
+
+    #[test]
+    // one level of Catmull-Clark on a cube should follow the smooth rules
+    fn test_catmull_clark() {
+        let surface = cube(1);
+        let (positions,faces,_) = catmull_clark_step(&surface.positions,&surface.faces,&surface.creases).unwrap();
+        assert_eq!((positions.len(),faces.len()),(8 + 12 + 6,24));
+        // corners move to 5 / 9, edges to 3 / 4 and faces stay
+        let corner = positions[7];
+        assert!((corner - Point::new(5. / 9.,5. / 9.,5. / 9.)).len() < 1e-12);
+        assert!(positions[8..20].iter().all(|p| (p.x.abs() + p.y.abs() + p.z.abs() - 1.5).abs() < 1e-12));
+        assert!(positions[20..].iter().all(|p| (p.x.abs() + p.y.abs() + p.z.abs() - 1.).abs() < 1e-12));
+
+        // the refined cube should stay closed, symmetric and smooth
+        let mesh = cube(3).tessellate().unwrap();
+        assert_eq!(mesh.indices.len(),6 * 64 * 2);
+        let ray = Ray::new(&Point::new(5.,0.,0.),&Vector::new(-1.,0.,0.));
+        let si = mesh.intersect(&ray,f64::INFINITY).unwrap();
+        assert!(si.p.x > 0.7 && si.p.x < 1.);
+        assert!((si.ns.x - 1.).abs() < 1e-9 && si.n.x > 0.99);
+        let back = Ray::new(&Point::new(-5.,0.,0.),&Vector::new(1.,0.,0.));
+        assert!((mesh.intersect(&back,f64::INFINITY).unwrap().p.x + si.p.x).abs() < 1e-12);
+    }
+
+    #[test]
+    // sharp creases should keep the cube a cube with split normals
+    fn test_creases() {
+        let mut surface = cube(2);
+        for face in surface.faces.clone() {
+            for i in 0..4 {
+                surface.set_crease(face[i],face[(i + 1) % 4],f64::INFINITY).unwrap();
+            }
+        }
+        let mesh = surface.tessellate().unwrap();
+        for p in &mesh.positions {
+            assert!((p.x.abs().max(p.y.abs()).max(p.z.abs()) - 1.).abs() < 1e-12);
+        }
+        // every corner is split three ways
+        let corners = mesh.positions.iter().filter(|p| p.x.abs() == 1. && p.y.abs() == 1. && p.z.abs() == 1.).count();
+        assert_eq!(corners,24);
+        let ray = Ray::new(&Point::new(0.9,0.9,5.),&Vector::new(0.,0.,-1.));
+        let si = mesh.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.ns.z - 1.).abs() < 1e-12 && (si.p.z - 1.).abs() < 1e-12);
+
+        // a semi-sharp edge lies between the smooth and sharp results
+        let edge_point = |sharpness: f64| {
+            let mut surface = cube(1);
+            surface.set_crease(3,7,sharpness).unwrap();
+            let (positions,_,creases) = catmull_clark_step(&surface.positions,&surface.faces,&surface.creases).unwrap();
+            let p = *positions[8..20].iter().find(|p| p.x > 0. && p.y > 0. && p.z.abs() < 1e-12).unwrap();
+            (p,creases.len())
+        };
+        let (smooth,_) = edge_point(0.);
+        let (half,refined) = edge_point(0.5);
+        let (sharp,kept) = edge_point(1.5);
+        assert!((smooth.x - 0.75).abs() < 1e-12 && (sharp.x - 1.).abs() < 1e-12);
+        assert!((half.x - 0.875).abs() < 1e-12);
+        assert_eq!((refined,kept),(0,2));
+        assert!(surface.set_crease(0,7,1.).is_err());
+    }
+
+    #[test]
+    // Loop should follow its weights and round the octahedron
+    fn test_loop() {
+        let surface = octahedron(1);
+        let (positions,faces,_) = loop_step(&surface.positions,&surface.faces,&surface.creases).unwrap();
+        assert_eq!((positions.len(),faces.len()),(6 + 12,32));
+        assert!((positions[0].x - 0.515625).abs() < 1e-12);
+        let e = 6 + edge_number(&surface,0,2);
+        assert!((positions[e] - Point::new(0.375,0.375,0.)).len() < 1e-12);
+
+        let mesh = octahedron(4).tessellate().unwrap();
+        assert_eq!(mesh.indices.len(),8 * 256);
+        let radii: Vec<f64> = mesh.positions.iter().map(|p| (*p - Point::new(0.,0.,0.)).len()).collect();
+        let (lo,hi) = radii.iter().fold((f64::INFINITY,0f64),|(lo,hi),&r| (lo.min(r),hi.max(r)));
+        assert!(hi / lo < 1.15,"{} {}",lo,hi);
+        assert_eq!(mesh.positions.len(),6 + 12 * 15 + 8 * 105);
+    }
+
+    #[test]
+    // open cages should keep their boundary and invalid cages be refused
+    fn test_boundary() {
+        let positions = vec![Point::new(0.,0.,0.),Point::new(2.,0.,0.),Point::new(0.,0.,2.)];
+        let surface = SubdivisionSurface::new(Scheme::Loop,positions,vec![vec![0,2,1]],3).unwrap();
+        let mesh = surface.tessellate().unwrap();
+        assert_eq!(mesh.indices.len(),64);
+        assert!(mesh.positions.iter().all(|p| p.y == 0. && p.x >= 0. && p.z >= 0. && p.x + p.z <= 2. + 1e-12));
+        assert!(mesh.normals.unwrap().iter().all(|n| (n.y - 1.).abs() < 1e-12));
+
+        let square = vec![Point::new(0.,0.,0.),Point::new(1.,0.,0.),Point::new(1.,0.,1.),Point::new(0.,0.,1.)];
+        assert!(SubdivisionSurface::new(Scheme::Loop,square.clone(),vec![vec![0,1,2,3]],1).is_err());
+        assert!(SubdivisionSurface::new(Scheme::CatmullClark,square.clone(),vec![vec![0,1,4]],1).is_err());
+        let fan = vec![vec![0,1,2],vec![0,1,3],vec![1,0,2]];
+        assert!(SubdivisionSurface::new(Scheme::CatmullClark,square,fan,1).is_err());
+    }
+
+    // number of the edge between a and b in the cage
+    fn edge_number(surface: &SubdivisionSurface,a: usize,b: usize) -> usize {
+        Topology::new(surface.positions.len(),&surface.faces).unwrap().edge_index[&edge(a,b)]
+    }
+}
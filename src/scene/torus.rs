@@ -0,0 +1,284 @@
+use std::f64::consts::PI;
+
+use super::{
+    traits::Primitive,
+    interaction::SurfaceInteraction,
+    transform::Transform,
+    quadric::azimuth
+};
+use crate::math::{
+    point::Point,
+    vector::Vector,
+    normal::Normal,
+    matrix::Matrix,
+    ray::Ray,
+    polynomial::roots_in,
+    traits::{Dot,Len,Normalize}
+};
+
+/// # Torus
+/// Ring torus about the y axis of object space, the tube of radius
+/// minor_radius circles the axis at distance major_radius in the y = 0
+/// plane. u runs around the axis from +x towards +z, v around the tube
+/// starting on its outer equator and moving up
+///
+/// # Parameters
+/// * major_radius (distance from the axis to the center of the tube)
+/// * minor_radius (radius of the tube, less than major_radius)
+/// * transform (placement in the world)
+pub struct Torus {
+    pub major_radius: f64,
+    pub minor_radius: f64,
+    pub transform: Transform
+}
+
+/// Primitive trait
+impl Primitive for Torus {
+    fn intersect(&self,ray: &Ray,tmax: f64) -> Option<SurfaceInteraction> {
+        let r = self.transform.ray_to_object(ray);
+        let length = r.d.len();
+        let d = r.d.normalize().ok()?;
+        let distance = self.crossings(&r.o,&d,0.0,tmax * length)
+            .into_iter()
+            .find(|&s| s > 0.0)?;
+        let si = self.interaction(&(r.o + d * distance),distance / length,-r.d);
+        self.transform.interaction_to_world(&si,ray)
+    }
+
+    fn area(&self) -> f64 {
+        4.0 * PI * PI * self.major_radius * self.minor_radius
+    }
+
+    fn sample(&self,u: (f64,f64)) -> Option<(Point,Normal)> {
+        // area around the tube grows as R theta + r sin(theta), invert it
+        let k = self.minor_radius / self.major_radius;
+        let target = 2.0 * PI * u.0;
+        let (mut lo,mut hi) = (0.0,2.0 * PI);
+        let mut theta = target;
+        for _ in 0..50 {
+            let g = theta + k * f64::sin(theta) - target;
+            if g < 0.0 { lo = theta } else { hi = theta }
+            let next = theta - g / (1.0 + k * f64::cos(theta));
+            theta = if next > lo && next < hi { next } else { 0.5 * (lo + hi) };
+        }
+
+        let (sin_phi,cos_phi) = f64::sin_cos(2.0 * PI * u.1);
+        let (sin_theta,cos_theta) = f64::sin_cos(theta);
+        let ring = self.major_radius + self.minor_radius * cos_theta;
+        Some(self.transform.sample_to_world((
+            Point::new(ring * cos_phi,self.minor_radius * sin_theta,ring * sin_phi),
+            Normal::new(cos_theta * cos_phi,sin_theta,cos_theta * sin_phi)
+        )))
+    }
+
+    fn intervals(&self,ray: &Ray) -> Vec<(SurfaceInteraction,SurfaceInteraction)> {
+        let r = self.transform.ray_to_object(ray);
+        let length = r.d.len();
+        let Ok(d) = r.d.normalize() else {
+            return vec![]
+        };
+        // entries face against the line and exits along it, a line only
+        // touching the tube does neither and is skipped
+        let mut intervals = vec![];
+        let mut entry: Option<SurfaceInteraction> = None;
+        for distance in self.crossings(&r.o,&d,f64::NEG_INFINITY,f64::INFINITY) {
+            let p = r.o + d * distance;
+            let si = self.interaction(&p,distance / length,-r.d);
+            let facing = si.n.dot(d);
+            match entry {
+                None if facing < -1e-9 => entry = Some(si),
+                Some(enter) if facing > 1e-9 => {
+                    if let (Some(enter),Some(exit)) = (
+                        self.transform.interaction_to_world(&enter,ray),
+                        self.transform.interaction_to_world(&si,ray)
+                    ) {
+                        intervals.push((enter,exit));
+                    }
+                    entry = None;
+                },
+                _ => {}
+            }
+        }
+        intervals
+    }
+}
+
+impl Torus {
+    /// Construct torus about the y axis
+    pub fn new(major_radius: f64,minor_radius: f64) -> Torus {
+        Torus {
+            major_radius,
+            minor_radius,
+            transform: Transform::new()
+        }
+    }
+
+    /// Place the torus in the world
+    pub fn set_object_to_world(&mut self,object_to_world: &Matrix) -> Result<(),String> {
+        self.transform = Transform::rigid_from_matrix(object_to_world)?;
+        Ok(())
+    }
+
+    /// Sorted distances in [lo,hi] along the unit direction d from o
+    /// where the line crosses the torus
+    fn crossings(&self,o: &Point,d: &Vector,lo: f64,hi: f64) -> Vec<f64> {
+        // search only inside the bounding sphere, starting from where the ray
+        // enters it so that far away origins do not blow up the coefficients
+        let bound = self.major_radius + self.minor_radius;
+        let oc = *o - Point::new(0.0,0.0,0.0);
+        let b = oc.dot(*d);
+        let discrim = b*b - (oc.dot(oc) - bound*bound);
+        if discrim < 0.0 {
+            return vec![]
+        }
+        let root = f64::sqrt(discrim);
+        let start = f64::max(-b - root,lo);
+        let end = f64::min(-b + root,hi);
+        if start >= end {
+            return vec![]
+        }
+
+        // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + z^2) along o + s d
+        let o = *o + *d * start;
+        let r2 = self.major_radius * self.major_radius;
+        let o_v = o - Point::new(0.0,0.0,0.0);
+        let n = o_v.dot(*d);
+        let q = o_v.dot(o_v) + r2 - self.minor_radius * self.minor_radius;
+        let coefficients = [
+            q*q - 4.0*r2*(o.x*o.x + o.z*o.z),
+            4.0*n*q - 8.0*r2*(o.x*d.x + o.z*d.z),
+            4.0*n*n + 2.0*q - 4.0*r2*(d.x*d.x + d.z*d.z),
+            4.0*n,
+            1.0
+        ];
+        roots_in(&coefficients,0.0,end - start)
+            .into_iter()
+            .map(|s| start + s)
+            .collect()
+    }
+
+    /// Object space interaction at point p on the torus
+    fn interaction(&self,p: &Point,t: f64,wo: Vector) -> SurfaceInteraction {
+        let (phi,theta) = self.angles(p);
+        let (sin_phi,cos_phi) = f64::sin_cos(phi);
+        let (sin_theta,cos_theta) = f64::sin_cos(theta);
+        SurfaceInteraction::new(
+            *p,
+            Normal::new(cos_theta * cos_phi,sin_theta,cos_theta * sin_phi),
+            (phi / (2.0 * PI),theta / (2.0 * PI)),
+            Vector::new(-p.z,0.0,p.x) * (2.0 * PI),
+            Vector::new(-sin_theta * cos_phi,cos_theta,-sin_theta * sin_phi) * (2.0 * PI * self.minor_radius),
+            t,
+            wo
+        )
+    }
+
+    /// Angle about the axis and angle around the tube of object space point p
+    fn angles(&self,p: &Point) -> (f64,f64) {
+        let phi = azimuth(p.x,p.z);
+        let mut theta = f64::atan2(p.y,f64::sqrt(p.x*p.x + p.z*p.z) - self.major_radius);
+        if theta < 0.0 {
+            theta += 2.0 * PI;
+        }
+        (phi,theta)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // implicit function, zero on the surface of a torus with radii 2 and 1
+    fn implicit(p: &Point) -> f64 {
+        let s = p.x*p.x + p.y*p.y + p.z*p.z + 3.;
+        s*s - 16. * (p.x*p.x + p.z*p.z)
+    }
+
+    #[test]
+    // hits should land on the near side of the tube with outward normals
+    fn test_intersect() {
+        let torus = Torus::new(2.,1.);
+        let ray = Ray::new(&Point::new(-5.,0.,0.),&Vector::new(2.,0.,0.));
+        let si = torus.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.t - 1.).abs() < 1e-12);
+        assert!((si.n.x + 1.).abs() < 1e-12);
+        assert!((si.uv.0 - 0.5).abs() < 1e-12);
+        assert!(si.uv.1.abs() < 1e-12);
+        assert!(torus.intersect(&ray,0.9).is_none());
+        // from above the tube
+        let ray = Ray::new(&Point::new(0.,5.,2.),&Vector::new(0.,-1.,0.));
+        let si = torus.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.t - 4.).abs() < 1e-12);
+        assert!((si.n.y - 1.).abs() < 1e-12);
+        assert!((si.uv.1 - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    // rays through the hole should miss or hit the inner wall
+    fn test_hole() {
+        let torus = Torus::new(2.,1.);
+        let axis = Ray::new(&Point::new(0.,5.,0.),&Vector::new(0.,-1.,0.));
+        assert!(torus.intersect(&axis,f64::INFINITY).is_none());
+        let tilted = Ray::new(&Point::new(0.2,5.,-0.3),&Vector::new(0.01,-1.,0.02));
+        assert!(torus.intersect(&tilted,f64::INFINITY).is_none());
+        // starting in the hole, the inner equator faces the axis
+        let out = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
+        let si = torus.intersect(&out,f64::INFINITY).unwrap();
+        assert!((si.t - 1.).abs() < 1e-12);
+        assert!((si.n.z + 1.).abs() < 1e-12);
+        assert!((si.uv.1 - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    // rays skimming the top of the tube should hit just inside and miss just outside
+    fn test_grazing() {
+        let torus = Torus::new(2.,1.);
+        for offset in [1e-3,1e-6] {
+            let inside = Ray::new(&Point::new(-10.,1. - offset,0.),&Vector::new(1.,0.,0.));
+            let si = torus.intersect(&inside,f64::INFINITY).unwrap();
+            assert!(implicit(&si.p).abs() < 1e-9,"{}",implicit(&si.p));
+            assert!((si.p.x + 2. + f64::sqrt(2. * offset - offset*offset)).abs() < 1e-9);
+            assert!(si.n.y > 0.9);
+            let outside = Ray::new(&Point::new(-10.,1. + offset,0.),&Vector::new(1.,0.,0.));
+            assert!(torus.intersect(&outside,f64::INFINITY).is_none());
+        }
+    }
+
+    #[test]
+    // distant and placed tori should keep accurate hits
+    fn test_far() {
+        let torus = Torus::new(2.,1.);
+        let ray = Ray::new(&Point::new(-1e6,0.,0.),&Vector::new(1.,0.,0.));
+        let si = torus.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.t - (1e6 - 3.)).abs() < 1e-6);
+        let mut placed = Torus::new(2.,1.);
+        placed.set_object_to_world(&Matrix::translate(&Vector::new(0.,10.,0.))).unwrap();
+        let ray = Ray::new(&Point::new(2.,20.,0.),&Vector::new(0.,-1.,0.));
+        let si = placed.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.p.y - 11.).abs() < 1e-12);
+        assert!(placed.set_object_to_world(&Matrix::scale(1.,1.,3.)).is_err());
+    }
+
+    #[test]
+    // samples should lie on the surface and favour the outer half by area
+    fn test_sample() {
+        let torus = Torus::new(2.,1.);
+        assert!((torus.area() - 8. * PI * PI).abs() < 1e-12);
+        let mut outer = 0;
+        for i in 0..1000 {
+            let (p,n) = torus.sample(((i as f64 + 0.5) / 1000.,0.4)).unwrap();
+            assert!(implicit(&p).abs() < 1e-9);
+            let ring = Point::new(p.x,0.,p.z) - Point::new(0.,0.,0.);
+            let center = Point::new(0.,0.,0.) + ring * (2. / ring.len());
+            assert!((Vector::from(n) - (p - center)).len() < 1e-9);
+            if n.dot(ring) > 0. {
+                outer += 1;
+            }
+        }
+        assert!((outer as f64 / 1000. - (2. * PI + 2.) / (4. * PI)).abs() < 0.01);
+    }
+}
@@ -1,8 +1,45 @@
 use crate::math::{
     point::Point,
+    normal::Normal,
     ray::Ray
 };
+use super::interaction::SurfaceInteraction;
 
-pub trait Primitive {
-    fn hit(&self,ray: &Ray,tmin: &mut f64) -> Option<Point>;
+pub trait Primitive: Send + Sync {
+    /// Nearest hit point, its ray parameter is written to tmin
+    fn hit(&self,ray: &Ray,tmin: &mut f64) -> Option<Point> {
+        let si = self.intersect(ray,f64::INFINITY)?;
+        *tmin = si.t;
+        Some(si.p)
+    }
+
+    /// Nearest intersection with ray parameter in (0,tmax)
+    fn intersect(&self,ray: &Ray,tmax: f64) -> Option<SurfaceInteraction>;
+
+    /// Same as intersect, also adding the work it took to tests. Primitives
+    /// marching or traversing an internal structure count their steps,
+    /// anything else counts as a single test
+    fn intersect_counted(&self,ray: &Ray,tmax: f64,tests: &mut usize) -> Option<SurfaceInteraction> {
+        *tests += 1;
+        self.intersect(ray,tmax)
+    }
+
+    /// Surface area
+    fn area(&self) -> f64 {
+        f64::INFINITY
+    }
+
+    /// Sample a point uniformly by area, returns (point,unit normal)
+    /// unbounded primitives cannot be sampled
+    fn sample(&self,_u: (f64,f64)) -> Option<(Point,Normal)> {
+        None
+    }
+
+    /// Stretches of the whole line through ray lying inside the solid
+    /// bounded by the primitive, as (entry,exit) interactions sorted along
+    /// the ray and including those behind its origin. Open surfaces bound
+    /// no solid and report none
+    fn intervals(&self,_ray: &Ray) -> Vec<(SurfaceInteraction,SurfaceInteraction)> {
+        vec![]
+    }
 }
@@ -0,0 +1,133 @@
+use super::interaction::SurfaceInteraction;
+use crate::math::{
+    point::Point,
+    vector::Vector,
+    normal::Normal,
+    matrix::Matrix,
+    ray::Ray,
+    traits::{Normalize,Dot}
+};
+
+/// # Transform
+/// Placement of a primitive defined in its own object space, rays are
+/// brought into object space and interactions back out to world space
+///
+/// # Parameters
+/// * object_to_world (placement of the object)
+/// * world_to_object (inverse placement)
+#[derive(Clone,Copy)]
+pub struct Transform {
+    pub object_to_world: Matrix,
+    pub world_to_object: Matrix
+}
+
+impl Default for Transform {
+    fn default() -> Transform {
+        Transform::new()
+    }
+}
+
+impl Transform {
+    /// Construct identity placement
+    pub fn new() -> Transform {
+        Transform {
+            object_to_world: Matrix::new(),
+            world_to_object: Matrix::new()
+        }
+    }
+
+    /// Construct placement from an object to world matrix
+    pub fn from_matrix(object_to_world: &Matrix) -> Result<Transform,String> {
+        Ok(Transform {
+            object_to_world: *object_to_world,
+            world_to_object: object_to_world.inverse()?
+        })
+    }
+
+    /// Construct placement from an object to world matrix that preserves
+    /// lengths, primitives sampling their area in object space need this
+    /// so that the sampling density carries over to world space
+    pub fn rigid_from_matrix(object_to_world: &Matrix) -> Result<Transform,String> {
+        let axes = [Vector::new(1.,0.,0.),Vector::new(0.,1.,0.),Vector::new(0.,0.,1.)].map(|a| *object_to_world * a);
+        for (i,a) in axes.iter().enumerate() {
+            for (j,b) in axes.iter().enumerate() {
+                let expected = if i == j {1.} else {0.};
+                if (a.dot(*b) - expected).abs() > 1e-9 {
+                    return Err(format!("placement does not preserve lengths: axes {} and {} have dot product {}",i,j,a.dot(*b)))
+                }
+            }
+        }
+        Transform::from_matrix(object_to_world)
+    }
+
+    /// World space ray in object space, the ray parameter is unchanged
+    pub fn ray_to_object(&self,ray: &Ray) -> Ray {
+        self.world_to_object * *ray
+    }
+
+    /// Object space point in world space
+    pub fn point_to_world(&self,p: &Point) -> Point {
+        self.object_to_world * *p
+    }
+
+    /// Object space normal in world space, transformed by the inverse
+    /// transpose so that it stays perpendicular to the surface
+    pub fn normal_to_world(&self,n: &Normal) -> Normal {
+        let n = self.world_to_object.transpose() * *n;
+        n.normalize().unwrap_or(n)
+    }
+
+    /// Object space interaction with a world space ray moved to world space,
+    /// the outgoing direction is taken from the world space ray
+    pub fn interaction_to_world(&self,si: &SurfaceInteraction,ray: &Ray) -> Option<SurfaceInteraction> {
+        Some(SurfaceInteraction::new(
+            self.point_to_world(&si.p),
+            self.normal_to_world(&si.n),
+            si.uv,
+            self.object_to_world * si.dpdu,
+            self.object_to_world * si.dpdv,
+            si.t,
+            (-ray.d).normalize().ok()?
+        ))
+    }
+
+    /// Object space area sample moved to world space
+    pub fn sample_to_world(&self,sample: (Point,Normal)) -> (Point,Normal) {
+        (self.point_to_world(&sample.0),self.normal_to_world(&sample.1))
+    }
+
+    /// Object space vector in world space
+    pub fn vector_to_world(&self,v: &Vector) -> Vector {
+        self.object_to_world * *v
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // normals should stay perpendicular to transformed tangents
+    fn test_normal_to_world() {
+        let transform = Transform::from_matrix(&(Matrix::rotate_y(0.7) * Matrix::scale(1.,4.,1.))).unwrap();
+        let n = Normal::new(1.,1.,0.).normalize().unwrap();
+        let tangent = Vector::new(1.,-1.,0.);
+        let nw = transform.normal_to_world(&n);
+        assert!(nw.dot(transform.vector_to_world(&tangent)).abs() < 1e-12);
+        assert!((nw.dot(Vector::from(nw)) - 1.).abs() < 1e-12);
+        assert!(Transform::from_matrix(&Matrix::scale(0.,1.,1.)).is_err());
+    }
+
+    #[test]
+    // rotations and translations are accepted, scales and shears are not
+    fn test_rigid_from_matrix() {
+        assert!(Transform::rigid_from_matrix(&(Matrix::translate(&Vector::new(1.,2.,3.)) * Matrix::rotate_y(0.7))).is_ok());
+        assert!(Transform::rigid_from_matrix(&Matrix::scale(1.,2.,1.)).is_err());
+        assert!(Transform::rigid_from_matrix(&Matrix::scale(-1.,1.,1.)).is_ok());
+        assert!(Transform::rigid_from_matrix(&(Matrix::rotate_y(0.3) * Matrix::scale(1.,1.,0.5))).is_err());
+    }
+}
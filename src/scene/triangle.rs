@@ -0,0 +1,60 @@
+use crate::math::{
+    point::Point,
+    ray::Ray,
+    traits::{Cross,Dot}
+};
+
+/// Hit (t,b1,b2) of ray with the triangle p0 p1 p2 with t in (0,tmax),
+/// the hit point being (1 - b1 - b2) p0 + b1 p1 + b2 p2 (Moller and
+/// Trumbore, "Fast, Minimum Storage Ray/Triangle Intersection")
+pub fn intersect_triangle(p0: &Point,p1: &Point,p2: &Point,ray: &Ray,tmax: f64) -> Option<(f64,f64,f64)> {
+    let e1 = *p1 - *p0;
+    let e2 = *p2 - *p0;
+    let pvec = ray.d.cross(e2);
+    let det = e1.dot(pvec);
+    if det == 0.0 {
+        return None
+    }
+    let inv_det = 1.0 / det;
+    let tvec = ray.o - *p0;
+    let b1 = tvec.dot(pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None
+    }
+    let qvec = tvec.cross(e1);
+    let b2 = ray.d.dot(qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None
+    }
+    let t = e2.dot(qvec) * inv_det;
+    if t <= 0.0 || t >= tmax {
+        return None
+    }
+    Some((t,b1,b2))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::vector::Vector;
+
+    #[test]
+    // hits should report barycentrics of the hit point, edges included
+    fn test_intersect_triangle() {
+        let (p0,p1,p2) = (Point::new(0.,0.,0.),Point::new(2.,0.,0.),Point::new(0.,2.,0.));
+        let ray = Ray::new(&Point::new(0.5,0.25,3.),&Vector::new(0.,0.,-1.));
+        let (t,b1,b2) = intersect_triangle(&p0,&p1,&p2,&ray,f64::INFINITY).unwrap();
+        assert_eq!((t,b1,b2),(3.,0.25,0.125));
+        assert!(intersect_triangle(&p0,&p1,&p2,&ray,3.).is_none());
+        let edge = Ray::new(&Point::new(1.,1.,3.),&Vector::new(0.,0.,-1.));
+        assert!(intersect_triangle(&p0,&p1,&p2,&edge,f64::INFINITY).is_some());
+        let outside = Ray::new(&Point::new(1.5,1.,3.),&Vector::new(0.,0.,-1.));
+        assert!(intersect_triangle(&p0,&p1,&p2,&outside,f64::INFINITY).is_none());
+        let parallel = Ray::new(&Point::new(0.5,0.25,3.),&Vector::new(1.,0.,0.));
+        assert!(intersect_triangle(&p0,&p1,&p2,&parallel,f64::INFINITY).is_none());
+    }
+}
@@ -1,12 +1,59 @@
-use super::traits::Primitive;
-use crate::math::{
-    point::Point,
-    vector::Vector,
-    ray::Ray
+use std::sync::Arc;
+
+use super::{
+    traits::Primitive,
+    interaction::SurfaceInteraction
+};
+use crate::{
+    math::{
+        point::Point,
+        vector::Vector,
+        ray::Ray
+    },
+    image::spectrum::{self,Spectrum},
+    light::{traits::Light,area::DiffuseAreaLight},
+    material::{traits::Material,lambertian::Lambertian,interface::Interface},
+    medium::traits::{Medium,MediumInterface},
+    sampler::traits::Sampler
 };
 
+/// # Hit
+/// Outcome of tracing a ray through the world
+pub enum Hit {
+    /// ray hit a primitive at the given point
+    Surface(Point),
+    /// ray escaped the scene carrying the environment radiance
+    Escaped(Spectrum)
+}
+
+/// # World
+/// Primitives with their materials and the lights illuminating them
+///
+/// Materials, lights and media are referenced by index, material 0 is a
+/// default grey diffuse used by primitives added without a material
+///
+/// # Parameters
+/// * primitives
+/// * primitive_materials (material index per primitive)
+/// * primitive_lights (area light index per primitive)
+/// * primitive_media (media on both sides of each primitive, None if it does not change medium)
+/// * materials
+/// * lights (every light, including area lights and the environment)
+/// * environment (light seen by rays escaping the scene)
+/// * media
+/// * camera_medium (medium the camera sits in, None is vacuum)
+/// * interface_material (material shared by invisible medium boundaries, added on first use)
 pub struct World {
-    pub primitives: Vec<Box<dyn Primitive>>
+    pub primitives: Vec<Arc<dyn Primitive>>,
+    pub primitive_materials: Vec<usize>,
+    pub primitive_lights: Vec<Option<usize>>,
+    pub primitive_media: Vec<Option<MediumInterface>>,
+    pub materials: Vec<Box<dyn Material>>,
+    pub lights: Vec<Arc<dyn Light>>,
+    pub environment: Option<Arc<dyn Light>>,
+    pub media: Vec<Box<dyn Medium>>,
+    pub camera_medium: Option<usize>,
+    interface_material: Option<usize>
 }
 
 impl World {
@@ -14,18 +61,199 @@ impl World {
     pub fn new(num_primitives: usize) -> World {
         World {
             // primitives: vec![]
-            primitives: Vec::with_capacity(num_primitives)
+            primitives: Vec::with_capacity(num_primitives),
+            primitive_materials: Vec::with_capacity(num_primitives),
+            primitive_lights: Vec::with_capacity(num_primitives),
+            primitive_media: Vec::with_capacity(num_primitives),
+            materials: vec![Box::new(Lambertian::new(Spectrum::new(0.5,0.5,0.5)))],
+            lights: vec![],
+            environment: None,
+            media: vec![],
+            camera_medium: None,
+            interface_material: None
         }
     }
 
     /// Add primitive
     pub fn add_primitive(&mut self,primitive: Box<dyn Primitive>) {
-        self.primitives.push(primitive);
+        self.add_primitive_with_material(primitive,0);
+    }
+
+    /// Add primitive using the given material index, returns primitive index
+    pub fn add_primitive_with_material(&mut self,primitive: Box<dyn Primitive>,material: usize) -> usize {
+        self.primitives.push(Arc::from(primitive));
+        self.primitive_materials.push(material);
+        self.primitive_lights.push(None);
+        self.primitive_media.push(None);
+        self.primitives.len() - 1
+    }
+
+    /// Add emitting primitive, returns primitive index
+    pub fn add_area_light(&mut self,primitive: Box<dyn Primitive>,material: usize,le: Spectrum,two_sided: bool) -> usize {
+        let shape: Arc<dyn Primitive> = Arc::from(primitive);
+        self.lights.push(Arc::new(DiffuseAreaLight::new(shape.clone(),le,two_sided)));
+        self.primitives.push(shape);
+        self.primitive_materials.push(material);
+        self.primitive_lights.push(Some(self.lights.len() - 1));
+        self.primitive_media.push(None);
+        self.primitives.len() - 1
+    }
+
+    /// Add invisible primitive bounding a medium, returns primitive index
+    pub fn add_medium_boundary(&mut self,primitive: Box<dyn Primitive>,interface: MediumInterface) -> usize {
+        let material = match self.interface_material {
+            Some(material) => material,
+            None => {
+                let material = self.add_material(Box::new(Interface));
+                self.interface_material = Some(material);
+                material
+            }
+        };
+        let index = self.add_primitive_with_material(primitive,material);
+        self.set_medium_interface(index,interface);
+        index
+    }
+
+    /// Set the media on both sides of a primitive, a visible
+    /// primitive such as glass can hold a medium this way
+    pub fn set_medium_interface(&mut self,primitive: usize,interface: MediumInterface) {
+        self.primitive_media[primitive] = Some(interface);
+    }
+
+    /// True if the intersected primitive is an invisible medium boundary
+    pub fn is_boundary(&self,si: &SurfaceInteraction) -> bool {
+        self.interface_material == Some(si.material)
+    }
+
+    /// Add material, returns material index
+    pub fn add_material(&mut self,material: Box<dyn Material>) -> usize {
+        self.materials.push(material);
+        self.materials.len() - 1
+    }
+
+    /// Add medium, returns medium index
+    pub fn add_medium(&mut self,medium: Box<dyn Medium>) -> usize {
+        self.media.push(medium);
+        self.media.len() - 1
+    }
+
+    /// Add light that has no geometry, returns light index
+    pub fn add_light(&mut self,light: Box<dyn Light>) -> usize {
+        self.lights.push(Arc::from(light));
+        self.lights.len() - 1
+    }
+
+    /// Set infinite environment light surrounding the scene
+    pub fn set_environment(&mut self,environment: Box<dyn Light>) {
+        let environment: Arc<dyn Light> = Arc::from(environment);
+        // replace any previous environment in the light list
+        if let Some(previous) = self.environment.take() {
+            self.lights.retain(|light| !Arc::ptr_eq(light,&previous));
+        }
+        self.lights.push(environment.clone());
+        self.environment = Some(environment);
+    }
+
+    /// Nearest intersection along the ray
+    pub fn intersect(&self,ray: &Ray) -> Option<SurfaceInteraction> {
+        let mut tests: usize = 0;
+        self.intersect_counted(ray,&mut tests)
+    }
+
+    /// Nearest intersection along the ray, adding the intersection work
+    /// of every primitive to tests
+    pub fn intersect_counted(&self,ray: &Ray,tests: &mut usize) -> Option<SurfaceInteraction> {
+        let mut tmax: f64 = f64::INFINITY;
+        let mut nearest: Option<SurfaceInteraction> = None;
+
+        for (i,primitive) in self.primitives.iter().enumerate() {
+            if let Some(mut si) = primitive.intersect_counted(ray,tmax,tests) {
+                tmax = si.t;
+                si.primitive = i;
+                si.material = self.primitive_materials[i];
+                si.light = self.primitive_lights[i];
+                si.medium_interface = self.primitive_media[i];
+                nearest = Some(si);
+            }
+        }
+
+        if let Some(si) = &mut nearest {
+            si.compute_differentials(ray);
+            if let Some(material) = self.materials.get(si.material) {
+                material.apply_shading(si);
+            }
+        }
+        nearest
+    }
+
+    /// Any-hit query, true if anything blocks the ray before tmax,
+    /// invisible medium boundaries do not block
+    pub fn occluded(&self,ray: &Ray,tmax: f64) -> bool {
+        self.primitives.iter()
+            .zip(self.primitive_materials.iter())
+            .filter(|(_,material)| self.interface_material != Some(**material))
+            .any(|(primitive,_)| primitive.intersect(ray,tmax).is_some())
+    }
+
+    /// Transmittance along ray up to tmax for a ray starting in medium,
+    /// medium boundaries are crossed and any other surface blocks the ray
+    pub fn transmittance(&self,ray: &Ray,tmax: f64,medium: Option<usize>,sampler: &mut dyn Sampler) -> Spectrum {
+        if self.media.is_empty() {
+            return if self.occluded(ray,tmax) { spectrum::BLACK } else { spectrum::WHITE }
+        }
+
+        let mut tr = spectrum::WHITE;
+        let mut ray = *ray;
+        let mut tmax = tmax;
+        let mut medium = medium;
+        loop {
+            let si = self.intersect(&ray).filter(|si| si.t < tmax);
+            if let Some(si) = &si {
+                if !self.is_boundary(si) {
+                    return spectrum::BLACK
+                }
+            }
+            if let Some(m) = medium {
+                tr = tr * self.media[m].tr(&ray,si.as_ref().map_or(tmax,|si| si.t),sampler);
+            }
+            let si = match si {
+                Some(si) if !tr.is_black() => si,
+                _ => return tr
+            };
+
+            // continue on the far side towards the same end point
+            medium = si.medium(&ray.d,medium);
+            if tmax.is_infinite() {
+                ray = si.spawn_ray(&ray.d);
+            } else {
+                let end = ray.at(tmax);
+                let origin = si.offset_origin(&ray.d);
+                ray = Ray::new(&origin,&(end - origin));
+                tmax = 1.0;
+            }
+        }
+    }
+
+    /// Radiance emitted from an intersected surface back along wo
+    pub fn emitted(&self,si: &SurfaceInteraction) -> Spectrum {
+        match si.light {
+            Some(light) => self.lights[light].l(&si.n,&si.wo),
+            None => spectrum::BLACK
+        }
+    }
+
+    /// Radiance arriving along a ray that escapes the scene
+    pub fn le(&self,ray: &Ray) -> Spectrum {
+        match &self.environment {
+            Some(environment) => environment.le(ray),
+            None => spectrum::BLACK
+        }
     }
 
     /// test hit - not final just for testing purposes
-    /// returns the very first hit point, none otherwise
-    pub fn hit(&self,ray: &Ray) -> Option<Point> {
+    /// returns the very first hit point, otherwise
+    /// the environment radiance seen along the ray
+    pub fn hit(&self,ray: &Ray) -> Hit {
         let mut thit: f64 = f64::INFINITY;
 
         let mut result: Option<Point>;
@@ -34,13 +262,13 @@ impl World {
             result = primitive.hit(&ray,&mut thit);
             match result {
                 Some(hit) => {
-                    return Some(hit);
+                    return Hit::Surface(hit);
                 },
                 None => {} // check next primitive
             }
         }
 
-        None // no hit
+        Hit::Escaped(self.le(ray)) // no hit
     }
 }
 
@@ -79,6 +307,86 @@ mod tests {
         primitive = Box::new(Plane::new(&Point::new(0.,0.,0.),&Normal::new(0.,1.,0.)));
         world.add_primitive(primitive);
         assert_eq!(world.primitives.len(),2);
+        assert_eq!(world.primitive_materials.len(),2);
+        assert_eq!(world.primitive_materials[1],0);
+    }
+
+    #[test]
+    // intersect should find the nearest primitive and its indices
+    fn test_intersect() {
+        use crate::material::mirror::Mirror;
+
+        let mut world = World::new(3);
+        let mirror = world.add_material(Box::new(Mirror::new(Spectrum::new(1.,1.,1.))));
+        assert_eq!(mirror,1);
+        world.add_primitive(Box::new(Sphere::new(1.,Point::new(0.,0.,10.))));
+        world.add_primitive_with_material(Box::new(Sphere::new(1.,Point::new(0.,0.,5.))),mirror);
+        world.add_area_light(Box::new(Sphere::new(1.,Point::new(0.,5.,0.))),0,Spectrum::new(3.,3.,3.),false);
+        assert_eq!(world.lights.len(),1);
+
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
+        let si = world.intersect(&ray).unwrap();
+        assert_eq!(si.t,4.);
+        assert_eq!(si.primitive,1);
+        assert_eq!(si.material,mirror);
+        assert!(si.light.is_none());
+        assert!(world.emitted(&si).is_black());
+
+        // emitter
+        let up = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,1.,0.));
+        let mut tests: usize = 0;
+        let si = world.intersect_counted(&up,&mut tests).unwrap();
+        assert_eq!(tests,3);
+        assert_eq!(si.primitive,2);
+        assert_eq!(si.light,Some(0));
+        assert_eq!(world.emitted(&si).r,3.);
+    }
+
+    #[test]
+    // occlusion should respect the distance limit
+    fn test_occluded() {
+        let mut world = World::new(1);
+        world.add_primitive(Box::new(Sphere::new(1.,Point::new(0.,0.,5.))));
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
+        assert!(world.occluded(&ray,10.));
+        assert!(!world.occluded(&ray,3.));
+        let away = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,-1.));
+        assert!(!world.occluded(&away,f64::INFINITY));
+    }
+
+    #[test]
+    // boundaries should be crossed and attenuate by their medium
+    fn test_transmittance() {
+        use crate::{
+            medium::homogeneous::HomogeneousMedium,
+            sampler::random::RandomSampler
+        };
+
+        let mut world = World::new(2);
+        let fog = world.add_medium(Box::new(HomogeneousMedium::new(Spectrum::new(0.5,0.5,0.5),spectrum::BLACK,0.)));
+        let boundary = world.add_medium_boundary(Box::new(Sphere::new(1.,Point::new(0.,0.,5.))),MediumInterface::new(Some(fog),None));
+        assert_eq!(world.primitive_media[boundary],Some(MediumInterface::new(Some(fog),None)));
+        let mut sampler = RandomSampler::new(1);
+
+        // through the whole sphere, unit and unnormalized directions
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
+        let tr = world.transmittance(&ray,f64::INFINITY,None,&mut sampler);
+        assert!((tr.r - (-1f64).exp()).abs() < 1e-6);
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,10.));
+        let tr = world.transmittance(&ray,0.5,None,&mut sampler);
+        assert!((tr.g - (-0.5f64).exp()).abs() < 1e-5);
+
+        // starting inside the medium
+        let ray = Ray::new(&Point::new(0.,0.,5.),&Vector::new(0.,1.,0.));
+        let tr = world.transmittance(&ray,f64::INFINITY,Some(fog),&mut sampler);
+        assert!((tr.b - (-0.5f64).exp()).abs() < 1e-6);
+        assert!(!world.occluded(&ray,f64::INFINITY));
+
+        // an opaque primitive blocks the ray
+        world.add_primitive(Box::new(Sphere::new(1.,Point::new(0.,0.,10.))));
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
+        assert!(world.transmittance(&ray,f64::INFINITY,None,&mut sampler).is_black());
+        assert!(world.is_boundary(&world.intersect(&ray).unwrap()));
     }
 
     #[test]
@@ -93,15 +401,17 @@ mod tests {
         let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
         
         // we have a hit
-        let result = world.hit(&ray);
-        let p = result.unwrap();
-        assert_eq!(p.x,0.);
-        assert_eq!(p.y,0.);
-        assert_eq!(p.z,4.);
+        match world.hit(&ray) {
+            Hit::Surface(p) => {
+                assert_eq!(p.x,0.);
+                assert_eq!(p.y,0.);
+                assert_eq!(p.z,4.);
+            },
+            Hit::Escaped(_) => panic!("expected a hit")
+        }
     }
 
     #[test]
-    #[should_panic]
     /// test hit
     fn test_hit_false() {
         let mut world = World::new(1);
@@ -112,8 +422,33 @@ mod tests {
 
         let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,-1.));
         
-        // no hit
-        let result = world.hit(&ray);
-        result.unwrap(); // should panic
+        // no hit, no environment so radiance is black
+        match world.hit(&ray) {
+            Hit::Surface(_) => panic!("expected no hit"),
+            Hit::Escaped(le) => assert!(le.is_black())
+        }
+    }
+
+    #[test]
+    /// escaping rays should see the environment
+    fn test_hit_environment() {
+        use crate::image::hdr::HdrImage;
+        use crate::light::environment::EnvironmentLight;
+
+        let mut world = World::new(1);
+        world.add_primitive(Box::new(Sphere::new(1.,Point::new(0.,0.,5.))));
+        let image = HdrImage::new(2,1,Spectrum::new(0.5,1.5,2.5));
+        world.set_environment(Box::new(EnvironmentLight::new(image,0.,2.)));
+        assert_eq!(world.lights.len(),1);
+
+        let ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,-1.));
+        match world.hit(&ray) {
+            Hit::Surface(_) => panic!("expected no hit"),
+            Hit::Escaped(le) => {
+                assert_eq!(le.r,1.);
+                assert_eq!(le.g,3.);
+                assert_eq!(le.b,5.);
+            }
+        }
     }
 }
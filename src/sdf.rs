@@ -0,0 +1,6 @@
+// sdf
+pub mod shapes;
+pub mod operators;
+pub mod fractal;
+
+pub mod traits;
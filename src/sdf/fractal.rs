@@ -0,0 +1,251 @@
+use super::traits::DistanceField;
+use crate::math::point::Point;
+
+/// Orbit radius beyond which a point is taken to escape
+const BAILOUT: f64 = 4.0;
+
+/// Orbit radius below which a point is taken to stay inside, before the
+/// radius and derivative underflow
+const TRAPPED: f64 = 1e-100;
+
+/// # Mandelbulb
+/// Three dimensional analogue of the Mandelbrot set, z -> z^power + p in
+/// spherical coordinates with y as the polar axis. The distance is
+/// estimated as r ln(r) / 2 |dz| from the running derivative of the
+/// orbit, and is negative for points whose orbit stays within the unit
+/// sphere. The orbit trap reports the closest approach of the orbit to
+/// the origin and the fraction of iterations before escape
+///
+/// # Parameters
+/// * power (exponent of the iteration, 8 for the classic bulb)
+/// * iterations (largest number of iterations, more gives finer detail)
+pub struct Mandelbulb {
+    pub power: f64,
+    pub iterations: usize
+}
+
+/// DistanceField trait
+impl DistanceField for Mandelbulb {
+    fn distance(&self,p: &Point) -> f64 {
+        let orbit = self.orbit(p);
+        estimate(orbit.radius,orbit.derivative)
+    }
+
+    fn bounds(&self) -> (Point,Point) {
+        // beyond this radius every orbit grows without bound
+        let r = f64::max(1.0,f64::powf(2.0,1.0 / (self.power - 1.0)));
+        (Point::new(-r,-r,-r),Point::new(r,r,r))
+    }
+
+    fn orbit_trap(&self,p: &Point) -> Option<(f64,f64)> {
+        let orbit = self.orbit(p);
+        Some((orbit.closest.min(1.0),orbit.steps as f64 / self.iterations as f64))
+    }
+}
+
+impl Mandelbulb {
+    /// Construct Mandelbulb, the power is at least 2
+    pub fn new(power: f64,iterations: usize) -> Mandelbulb {
+        Mandelbulb {
+            power: power.max(2.0),
+            iterations: iterations.max(1)
+        }
+    }
+
+    /// Follow the orbit of p until it escapes or runs out of iterations
+    fn orbit(&self,p: &Point) -> Orbit {
+        let n = self.power;
+        let (mut x,mut y,mut z) = (p.x,p.y,p.z);
+        let mut derivative = 1.0;
+        let mut radius = f64::sqrt(x*x + y*y + z*z);
+        let mut closest = radius;
+        let mut steps = self.iterations;
+        for i in 0..self.iterations {
+            if radius > BAILOUT {
+                steps = i;
+                break
+            }
+            if radius < TRAPPED {
+                break
+            }
+            // z^n scales the radius to r^n and multiplies both angles by n
+            derivative = n * radius.powf(n - 1.0) * derivative + 1.0;
+            let theta = f64::acos((y / radius).clamp(-1.0,1.0)) * n;
+            let phi = f64::atan2(z,x) * n;
+            let scaled = radius.powf(n);
+            let (sin_theta,cos_theta) = f64::sin_cos(theta);
+            let (sin_phi,cos_phi) = f64::sin_cos(phi);
+            x = scaled * sin_theta * cos_phi + p.x;
+            y = scaled * cos_theta + p.y;
+            z = scaled * sin_theta * sin_phi + p.z;
+            radius = f64::sqrt(x*x + y*y + z*z);
+            closest = closest.min(radius);
+        }
+        Orbit {radius,derivative,closest,steps}
+    }
+}
+
+/// # QuaternionJulia
+/// Three dimensional slice of the quaternion Julia set of q -> q^2 + c,
+/// points p map to the quaternion (p.x,p.y,p.z,slice). The norm of
+/// quaternions is multiplicative, so the derivative of the orbit is
+/// tracked as a scalar and the distance estimated as for the Mandelbulb
+///
+/// # Parameters
+/// * c (constant of the iteration as real,i,j,k)
+/// * slice (fourth coordinate of the slice)
+/// * iterations (largest number of iterations)
+pub struct QuaternionJulia {
+    pub c: [f64; 4],
+    pub slice: f64,
+    pub iterations: usize
+}
+
+/// DistanceField trait
+impl DistanceField for QuaternionJulia {
+    fn distance(&self,p: &Point) -> f64 {
+        let orbit = self.orbit(p);
+        estimate(orbit.radius,orbit.derivative)
+    }
+
+    fn bounds(&self) -> (Point,Point) {
+        // beyond this radius |q^2 + c| > |q| and the orbit escapes
+        let c = self.c;
+        let length = f64::sqrt(c[0]*c[0] + c[1]*c[1] + c[2]*c[2] + c[3]*c[3]);
+        let r = 0.5 + f64::sqrt(0.25 + length);
+        (Point::new(-r,-r,-r),Point::new(r,r,r))
+    }
+
+    fn orbit_trap(&self,p: &Point) -> Option<(f64,f64)> {
+        let orbit = self.orbit(p);
+        Some((orbit.closest.min(1.0),orbit.steps as f64 / self.iterations as f64))
+    }
+}
+
+impl QuaternionJulia {
+    /// Construct slice of the Julia set of c
+    pub fn new(c: [f64; 4],slice: f64,iterations: usize) -> QuaternionJulia {
+        QuaternionJulia {
+            c,
+            slice,
+            iterations: iterations.max(1)
+        }
+    }
+
+    /// Follow the orbit of p until it escapes or runs out of iterations
+    fn orbit(&self,p: &Point) -> Orbit {
+        let mut q = [p.x,p.y,p.z,self.slice];
+        let norm = |q: &[f64; 4]| f64::sqrt(q[0]*q[0] + q[1]*q[1] + q[2]*q[2] + q[3]*q[3]);
+        let mut derivative = 1.0;
+        let mut radius = norm(&q);
+        let mut closest = radius;
+        let mut steps = self.iterations;
+        for i in 0..self.iterations {
+            if radius > BAILOUT {
+                steps = i;
+                break
+            }
+            if radius < TRAPPED {
+                break
+            }
+            derivative *= 2.0 * radius;
+            // q^2 = (a^2 - |v|^2, 2 a v) for q = (a,v)
+            q = [
+                q[0]*q[0] - q[1]*q[1] - q[2]*q[2] - q[3]*q[3] + self.c[0],
+                2.0 * q[0] * q[1] + self.c[1],
+                2.0 * q[0] * q[2] + self.c[2],
+                2.0 * q[0] * q[3] + self.c[3]
+            ];
+            radius = norm(&q);
+            closest = closest.min(radius);
+        }
+        Orbit {radius,derivative,closest,steps}
+    }
+}
+
+/// Final state of an orbit
+///
+/// # Parameters
+/// * radius (distance of the last point from the origin)
+/// * derivative (length of the derivative of the last point with respect to the start)
+/// * closest (closest approach to the origin)
+/// * steps (iterations taken before escaping)
+struct Orbit {
+    radius: f64,
+    derivative: f64,
+    closest: f64,
+    steps: usize
+}
+
+/// Distance estimate r ln(r) / 2 |dz|, negative for orbits ending
+/// inside the unit sphere
+fn estimate(radius: f64,derivative: f64) -> f64 {
+    let r = radius.max(f64::MIN_POSITIVE);
+    0.5 * r * r.ln() / derivative
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        scene::{implicit::Implicit,traits::Primitive},
+        math::{ray::Ray,vector::Vector,traits::Dot}
+    };
+
+    #[test]
+    // with c = 0 the Julia set is the unit ball and the estimate is |p| ln|p| / 2
+    fn test_julia_ball() {
+        let julia = QuaternionJulia::new([0.,0.,0.,0.],0.,12);
+        let p = Point::new(0.6,0.,0.8);
+        assert!(julia.distance(&Point::new(0.5,0.,0.)) < 0.);
+        let far = Point::new(1.5,0.,0.);
+        assert!((julia.distance(&far) - 0.75 * f64::ln(1.5)).abs() < 1e-12);
+        assert!(julia.distance(&p).abs() < 1e-12);
+        let implicit = Implicit::new(Box::new(julia));
+        let ray = Ray::new(&Point::new(0.,0.,-5.),&Vector::new(0.,0.,1.));
+        let si = implicit.intersect(&ray,f64::INFINITY).unwrap();
+        assert!((si.t - 4.).abs() < 1e-9);
+        assert!((si.n.z + 1.).abs() < 1e-6);
+    }
+
+    #[test]
+    // the bulb should be hit within its bounds on its estimated surface
+    fn test_mandelbulb() {
+        let bulb = Mandelbulb::new(8.,10);
+        let (min,max) = bulb.bounds();
+        assert!((max.x - f64::powf(2.,1. / 7.)).abs() < 1e-12 && min.y == -max.y);
+        assert!(bulb.distance(&Point::new(0.,0.,0.)) < 0.);
+        assert!(bulb.distance(&Point::new(0.,2.,0.)) > 0.);
+        let implicit = Implicit::new(Box::new(Mandelbulb::new(8.,10)));
+        let mut hits = 0;
+        for i in 0..10 {
+            let d = Vector::new(0.1 * i as f64 - 0.45,-0.3,1.);
+            let ray = Ray::new(&Point::new(0.,0.2,-3.),&d);
+            if let Some(si) = implicit.intersect(&ray,f64::INFINITY) {
+                hits += 1;
+                assert!(implicit.field.distance(&si.p).abs() < 1e-9);
+                assert!(si.n.dot(ray.d) < 0.);
+                assert!((0.0..=1.0).contains(&si.uv.0) && (0.0..=1.0).contains(&si.uv.1));
+            }
+        }
+        assert!(hits > 5);
+    }
+
+    #[test]
+    // the orbit trap should follow the orbit and reach operators
+    fn test_orbit_trap() {
+        let julia = QuaternionJulia::new([0.,0.,0.,0.],0.,8);
+        let (closest,steps) = julia.orbit_trap(&Point::new(0.5,0.,0.)).unwrap();
+        assert!((closest - 0.5f64.powi(256)).abs() < 1e-12);
+        assert_eq!(steps,1.);
+        let (closest,steps) = julia.orbit_trap(&Point::new(1.5,0.,0.)).unwrap();
+        assert_eq!((closest,steps),(1.,0.25));
+        let moved = crate::sdf::operators::Translate::new(Box::new(julia),Vector::new(1.,0.,0.));
+        assert_eq!(moved.orbit_trap(&Point::new(2.5,0.,0.)).unwrap().1,0.25);
+        assert!(crate::sdf::shapes::Sphere::new(1.).orbit_trap(&Point::new(0.,0.,0.)).is_none());
+    }
+}
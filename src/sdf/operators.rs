@@ -0,0 +1,391 @@
+use super::traits::DistanceField;
+use crate::math::{
+    point::Point,
+    vector::Vector
+};
+
+/// # SmoothUnion
+/// Union of two fields blended over a distance k, the polynomial
+/// smooth minimum lies at most k / 4 below the plain minimum
+///
+/// # Parameters
+/// * a
+/// * b
+/// * k (width of the blend, 0 for a sharp union)
+pub struct SmoothUnion {
+    pub a: Box<dyn DistanceField>,
+    pub b: Box<dyn DistanceField>,
+    pub k: f64
+}
+
+/// DistanceField trait
+impl DistanceField for SmoothUnion {
+    fn distance(&self,p: &Point) -> f64 {
+        smooth_min(self.a.distance(p),self.b.distance(p),self.k)
+    }
+
+    fn bounds(&self) -> (Point,Point) {
+        let (a_min,a_max) = self.a.bounds();
+        let (b_min,b_max) = self.b.bounds();
+        grow(&(min(&a_min,&b_min),max(&a_max,&b_max)),0.25 * self.k)
+    }
+
+    fn lipschitz(&self) -> f64 {
+        f64::max(self.a.lipschitz(),self.b.lipschitz())
+    }
+
+    fn orbit_trap(&self,p: &Point) -> Option<(f64,f64)> {
+        // coordinates of whichever field is nearer
+        if self.a.distance(p) <= self.b.distance(p) { self.a.orbit_trap(p) } else { self.b.orbit_trap(p) }
+    }
+}
+
+impl SmoothUnion {
+    /// Construct union of a and b blended over k
+    pub fn new(a: Box<dyn DistanceField>,b: Box<dyn DistanceField>,k: f64) -> SmoothUnion {
+        SmoothUnion {a,b,k: k.max(0.0)}
+    }
+}
+
+/// # SmoothSubtraction
+/// Field a with b carved out of it, the cut blended over a distance k.
+/// The blend only removes material, so a bounds the result
+///
+/// # Parameters
+/// * a (field cut into)
+/// * b (field removed)
+/// * k (width of the blend, 0 for a sharp cut)
+pub struct SmoothSubtraction {
+    pub a: Box<dyn DistanceField>,
+    pub b: Box<dyn DistanceField>,
+    pub k: f64
+}
+
+/// DistanceField trait
+impl DistanceField for SmoothSubtraction {
+    fn distance(&self,p: &Point) -> f64 {
+        -smooth_min(-self.a.distance(p),self.b.distance(p),self.k)
+    }
+
+    fn bounds(&self) -> (Point,Point) {
+        self.a.bounds()
+    }
+
+    fn lipschitz(&self) -> f64 {
+        f64::max(self.a.lipschitz(),self.b.lipschitz())
+    }
+
+    fn orbit_trap(&self,p: &Point) -> Option<(f64,f64)> {
+        self.a.orbit_trap(p)
+    }
+}
+
+impl SmoothSubtraction {
+    /// Construct a minus b blended over k
+    pub fn new(a: Box<dyn DistanceField>,b: Box<dyn DistanceField>,k: f64) -> SmoothSubtraction {
+        SmoothSubtraction {a,b,k: k.max(0.0)}
+    }
+}
+
+/// # Translate
+/// Field moved by an offset, to arrange shapes before combining them
+///
+/// # Parameters
+/// * field
+/// * offset (where the origin of the field is moved to)
+pub struct Translate {
+    pub field: Box<dyn DistanceField>,
+    pub offset: Vector
+}
+
+/// DistanceField trait
+impl DistanceField for Translate {
+    fn distance(&self,p: &Point) -> f64 {
+        self.field.distance(&(*p - self.offset))
+    }
+
+    fn bounds(&self) -> (Point,Point) {
+        let (lo,hi) = self.field.bounds();
+        (lo + self.offset,hi + self.offset)
+    }
+
+    fn lipschitz(&self) -> f64 {
+        self.field.lipschitz()
+    }
+
+    fn orbit_trap(&self,p: &Point) -> Option<(f64,f64)> {
+        self.field.orbit_trap(&(*p - self.offset))
+    }
+}
+
+impl Translate {
+    /// Construct field moved by offset
+    pub fn new(field: Box<dyn DistanceField>,offset: Vector) -> Translate {
+        Translate {field,offset}
+    }
+}
+
+/// # Twist
+/// Field twisted about the y axis, each slice turned by rate times its
+/// height. The twist stretches space by up to rate times the distance
+/// from the axis, which raises the lipschitz factor
+///
+/// # Parameters
+/// * field
+/// * rate (turn in radians per unit of height)
+pub struct Twist {
+    pub field: Box<dyn DistanceField>,
+    pub rate: f64
+}
+
+/// DistanceField trait
+impl DistanceField for Twist {
+    fn distance(&self,p: &Point) -> f64 {
+        self.field.distance(&self.untwist(p))
+    }
+
+    fn bounds(&self) -> (Point,Point) {
+        // any slice may turn to any angle, so bound the swept cylinder
+        let r = self.radius();
+        let (lo,hi) = self.field.bounds();
+        (Point::new(-r,lo.y,-r),Point::new(r,hi.y,r))
+    }
+
+    fn lipschitz(&self) -> f64 {
+        let stretch = self.rate * self.radius();
+        self.field.lipschitz() * f64::sqrt(1.0 + stretch * stretch)
+    }
+
+    fn orbit_trap(&self,p: &Point) -> Option<(f64,f64)> {
+        self.field.orbit_trap(&self.untwist(p))
+    }
+}
+
+impl Twist {
+    /// Construct field twisted by rate radians per unit of height
+    pub fn new(field: Box<dyn DistanceField>,rate: f64) -> Twist {
+        Twist {field,rate}
+    }
+
+    /// Point of the untwisted field corresponding to p
+    fn untwist(&self,p: &Point) -> Point {
+        let (sin,cos) = f64::sin_cos(self.rate * p.y);
+        Point::new(cos * p.x - sin * p.z,p.y,sin * p.x + cos * p.z)
+    }
+
+    /// Largest distance of the untwisted bounds from the y axis
+    fn radius(&self) -> f64 {
+        let (lo,hi) = self.field.bounds();
+        let x = f64::max(lo.x.abs(),hi.x.abs());
+        let z = f64::max(lo.z.abs(),hi.z.abs());
+        f64::sqrt(x*x + z*z)
+    }
+}
+
+/// # Repeat
+/// Copies of a field laid out on a grid with the given spacing, limited
+/// to copies cells on either side of the origin along each axis. The
+/// field should fit within one cell for the distances to stay valid
+///
+/// # Parameters
+/// * field
+/// * spacing (distance between copies along each axis)
+/// * copies (number of extra copies on each side along x, y and z)
+pub struct Repeat {
+    pub field: Box<dyn DistanceField>,
+    pub spacing: Vector,
+    pub copies: [usize; 3]
+}
+
+/// DistanceField trait
+impl DistanceField for Repeat {
+    fn distance(&self,p: &Point) -> f64 {
+        self.field.distance(&self.cell(p))
+    }
+
+    fn bounds(&self) -> (Point,Point) {
+        let (lo,hi) = self.field.bounds();
+        let reach = Vector::new(
+            self.spacing.x.max(0.0) * self.copies[0] as f64,
+            self.spacing.y.max(0.0) * self.copies[1] as f64,
+            self.spacing.z.max(0.0) * self.copies[2] as f64
+        );
+        (lo - reach,hi + reach)
+    }
+
+    fn lipschitz(&self) -> f64 {
+        self.field.lipschitz()
+    }
+
+    fn orbit_trap(&self,p: &Point) -> Option<(f64,f64)> {
+        self.field.orbit_trap(&self.cell(p))
+    }
+}
+
+impl Repeat {
+    /// Construct grid of copies of field
+    pub fn new(field: Box<dyn DistanceField>,spacing: Vector,copies: [usize; 3]) -> Repeat {
+        Repeat {field,spacing,copies}
+    }
+
+    /// Point of the copy nearest to p, moved back to the original
+    fn cell(&self,p: &Point) -> Point {
+        let wrap = |x: f64,spacing: f64,copies: usize| {
+            if spacing <= 0.0 {
+                return x
+            }
+            let n = copies as f64;
+            x - spacing * (x / spacing).round().clamp(-n,n)
+        };
+        Point::new(
+            wrap(p.x,self.spacing.x,self.copies[0]),
+            wrap(p.y,self.spacing.y,self.copies[1]),
+            wrap(p.z,self.spacing.z,self.copies[2])
+        )
+    }
+}
+
+/// # Displace
+/// Field rippled by amplitude sin(f x) sin(f y) sin(f z), moving the
+/// surface by at most amplitude
+///
+/// # Parameters
+/// * field
+/// * amplitude (largest displacement)
+/// * frequency (angular frequency f of the ripples)
+pub struct Displace {
+    pub field: Box<dyn DistanceField>,
+    pub amplitude: f64,
+    pub frequency: f64
+}
+
+/// DistanceField trait
+impl DistanceField for Displace {
+    fn distance(&self,p: &Point) -> f64 {
+        let f = self.frequency;
+        self.field.distance(p) + self.amplitude * f64::sin(f * p.x) * f64::sin(f * p.y) * f64::sin(f * p.z)
+    }
+
+    fn bounds(&self) -> (Point,Point) {
+        grow(&self.field.bounds(),self.amplitude.abs())
+    }
+
+    fn lipschitz(&self) -> f64 {
+        // the gradient of the ripples is at most amplitude f along each axis
+        self.field.lipschitz() + self.amplitude.abs() * self.frequency.abs() * f64::sqrt(3.0)
+    }
+
+    fn orbit_trap(&self,p: &Point) -> Option<(f64,f64)> {
+        self.field.orbit_trap(p)
+    }
+}
+
+impl Displace {
+    /// Construct field rippled with given amplitude and frequency
+    pub fn new(field: Box<dyn DistanceField>,amplitude: f64,frequency: f64) -> Displace {
+        Displace {field,amplitude,frequency}
+    }
+}
+
+/// Polynomial smooth minimum of a and b over a blend width k
+fn smooth_min(a: f64,b: f64,k: f64) -> f64 {
+    if k <= 0.0 {
+        return f64::min(a,b)
+    }
+    let h = (0.5 + 0.5 * (b - a) / k).clamp(0.0,1.0);
+    b + (a - b) * h - k * h * (1.0 - h)
+}
+
+/// Component wise minimum of two points
+fn min(a: &Point,b: &Point) -> Point {
+    Point::new(f64::min(a.x,b.x),f64::min(a.y,b.y),f64::min(a.z,b.z))
+}
+
+/// Component wise maximum of two points
+fn max(a: &Point,b: &Point) -> Point {
+    Point::new(f64::max(a.x,b.x),f64::max(a.y,b.y),f64::max(a.z,b.z))
+}
+
+/// Box grown by margin on every side
+fn grow(bounds: &(Point,Point),margin: f64) -> (Point,Point) {
+    let m = Vector::new(margin,margin,margin);
+    (bounds.0 - m,bounds.1 + m)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::sdf::shapes::{Sphere,Cuboid};
+
+    // unit sphere moved along x
+    fn ball(x: f64) -> Box<dyn DistanceField> {
+        Box::new(Translate::new(Box::new(Sphere::new(1.)),Vector::new(x,0.,0.)))
+    }
+
+    #[test]
+    // the blend should only differ from the sharp union near both surfaces
+    fn test_smooth_union() {
+        let sharp = SmoothUnion::new(ball(-1.),ball(1.),0.);
+        let smooth = SmoothUnion::new(ball(-1.),ball(1.),0.5);
+        let far = Point::new(-3.,0.,0.);
+        assert_eq!(sharp.distance(&far),smooth.distance(&far));
+        // midway both spheres are equally close, the blend digs k / 4 deeper
+        let mid = Point::new(0.,1.2,0.);
+        assert!((sharp.distance(&mid) - smooth.distance(&mid) - 0.125).abs() < 1e-12);
+        let (lo,hi) = smooth.bounds();
+        assert_eq!((lo.x,hi.x,hi.y),(-2.125,2.125,1.125));
+    }
+
+    #[test]
+    // subtraction should hollow the first field where the second is inside
+    fn test_smooth_subtraction() {
+        let cut = SmoothSubtraction::new(ball(0.),ball(1.),0.);
+        assert_eq!(cut.distance(&Point::new(0.5,0.,0.)),0.5);
+        assert_eq!(cut.distance(&Point::new(-0.5,0.,0.)),-0.5);
+        assert_eq!(cut.bounds().1.x,1.);
+        let smooth = SmoothSubtraction::new(ball(0.),ball(1.),0.5);
+        assert!(smooth.distance(&Point::new(-0.1,0.,0.)) >= cut.distance(&Point::new(-0.1,0.,0.)));
+    }
+
+    #[test]
+    // twisting should turn slices about y and bound the swept volume
+    fn test_twist() {
+        let bar = Twist::new(Box::new(Cuboid::new(Vector::new(2.,4.,0.5))),PI / 4.);
+        // at y = 2 the slice has turned a quarter, the long side now lies along z
+        assert!((bar.distance(&Point::new(0.,2.,1.9)) + 0.1).abs() < 1e-12);
+        assert!((bar.distance(&Point::new(0.,0.,1.9)) - 1.4).abs() < 1e-12);
+        let (lo,hi) = bar.bounds();
+        let r = f64::sqrt(4.25);
+        assert!((hi.x - r).abs() < 1e-12 && (lo.z + r).abs() < 1e-12 && hi.y == 4.);
+        assert!((bar.lipschitz() - f64::sqrt(1. + (PI / 4. * r).powi(2))).abs() < 1e-12);
+    }
+
+    #[test]
+    // copies should stop after the requested count
+    fn test_repeat() {
+        let row = Repeat::new(Box::new(Sphere::new(1.)),Vector::new(4.,0.,0.),[2,0,0]);
+        assert_eq!(row.distance(&Point::new(8.,0.,0.)),-1.);
+        assert_eq!(row.distance(&Point::new(-6.,0.,0.)),1.);
+        assert_eq!(row.distance(&Point::new(14.,0.,0.)),5.);
+        assert_eq!(row.distance(&Point::new(0.,3.,0.)),2.);
+        let (lo,hi) = row.bounds();
+        assert_eq!((lo.x,hi.x,hi.y),(-9.,9.,1.));
+    }
+
+    #[test]
+    // ripples should stay within the amplitude
+    fn test_displace() {
+        let bumpy = Displace::new(Box::new(Sphere::new(1.)),0.1,10.);
+        let p = Point::new(0.3,0.4,0.5);
+        let plain = Sphere::new(1.).distance(&p);
+        assert!((bumpy.distance(&p) - plain).abs() <= 0.1);
+        assert_eq!(bumpy.bounds().1.z,1.1);
+        assert!((bumpy.lipschitz() - (1. + f64::sqrt(3.))).abs() < 1e-12);
+    }
+}
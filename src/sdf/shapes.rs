@@ -0,0 +1,222 @@
+use super::traits::DistanceField;
+use crate::math::{
+    point::Point,
+    vector::Vector,
+    traits::{Dot,Len}
+};
+
+/// # Sphere
+/// Sphere centered at the origin
+///
+/// # Parameters
+/// * radius
+pub struct Sphere {
+    pub radius: f64
+}
+
+/// DistanceField trait
+impl DistanceField for Sphere {
+    fn distance(&self,p: &Point) -> f64 {
+        (*p - origin()).len() - self.radius
+    }
+
+    fn bounds(&self) -> (Point,Point) {
+        cube(self.radius)
+    }
+}
+
+impl Sphere {
+    /// Construct sphere of given radius
+    pub fn new(radius: f64) -> Sphere {
+        Sphere {radius}
+    }
+}
+
+/// # Cuboid
+/// Box centered at the origin
+///
+/// # Parameters
+/// * half_extent (half the side lengths along each axis)
+pub struct Cuboid {
+    pub half_extent: Vector
+}
+
+/// DistanceField trait
+impl DistanceField for Cuboid {
+    fn distance(&self,p: &Point) -> f64 {
+        box_distance(p,&self.half_extent)
+    }
+
+    fn bounds(&self) -> (Point,Point) {
+        (origin() - self.half_extent,origin() + self.half_extent)
+    }
+}
+
+impl Cuboid {
+    /// Construct box with given half side lengths
+    pub fn new(half_extent: Vector) -> Cuboid {
+        Cuboid {half_extent}
+    }
+}
+
+/// # RoundedCuboid
+/// Box centered at the origin with its edges and corners rounded off
+///
+/// # Parameters
+/// * half_extent (half the side lengths along each axis, including the rounding)
+/// * radius (radius of the rounded edges)
+pub struct RoundedCuboid {
+    pub half_extent: Vector,
+    pub radius: f64
+}
+
+/// DistanceField trait
+impl DistanceField for RoundedCuboid {
+    fn distance(&self,p: &Point) -> f64 {
+        let r = self.radius;
+        box_distance(p,&(self.half_extent - Vector::new(r,r,r))) - r
+    }
+
+    fn bounds(&self) -> (Point,Point) {
+        (origin() - self.half_extent,origin() + self.half_extent)
+    }
+}
+
+impl RoundedCuboid {
+    /// Construct rounded box, the radius is at most half the smallest side
+    pub fn new(half_extent: Vector,radius: f64) -> RoundedCuboid {
+        let smallest = f64::min(half_extent.x,f64::min(half_extent.y,half_extent.z));
+        RoundedCuboid {
+            half_extent,
+            radius: radius.clamp(0.0,smallest)
+        }
+    }
+}
+
+/// # Torus
+/// Torus about the y axis, the tube circles the axis in the y = 0 plane
+///
+/// # Parameters
+/// * major_radius (distance from the axis to the center of the tube)
+/// * minor_radius (radius of the tube)
+pub struct Torus {
+    pub major_radius: f64,
+    pub minor_radius: f64
+}
+
+/// DistanceField trait
+impl DistanceField for Torus {
+    fn distance(&self,p: &Point) -> f64 {
+        let ring = f64::sqrt(p.x*p.x + p.z*p.z) - self.major_radius;
+        f64::sqrt(ring*ring + p.y*p.y) - self.minor_radius
+    }
+
+    fn bounds(&self) -> (Point,Point) {
+        let outer = self.major_radius + self.minor_radius;
+        (Point::new(-outer,-self.minor_radius,-outer),Point::new(outer,self.minor_radius,outer))
+    }
+}
+
+impl Torus {
+    /// Construct torus about the y axis
+    pub fn new(major_radius: f64,minor_radius: f64) -> Torus {
+        Torus {major_radius,minor_radius}
+    }
+}
+
+/// # Capsule
+/// Points within radius of the segment from a to b
+///
+/// # Parameters
+/// * a (start of the segment)
+/// * b (end of the segment)
+/// * radius
+pub struct Capsule {
+    pub a: Point,
+    pub b: Point,
+    pub radius: f64
+}
+
+/// DistanceField trait
+impl DistanceField for Capsule {
+    fn distance(&self,p: &Point) -> f64 {
+        let pa = *p - self.a;
+        let ba = self.b - self.a;
+        let length_sq = ba.dot(ba);
+        let h = if length_sq > 0.0 { (pa.dot(ba) / length_sq).clamp(0.0,1.0) } else { 0.0 };
+        (pa - ba * h).len() - self.radius
+    }
+
+    fn bounds(&self) -> (Point,Point) {
+        let r = self.radius;
+        (
+            Point::new(f64::min(self.a.x,self.b.x) - r,f64::min(self.a.y,self.b.y) - r,f64::min(self.a.z,self.b.z) - r),
+            Point::new(f64::max(self.a.x,self.b.x) + r,f64::max(self.a.y,self.b.y) + r,f64::max(self.a.z,self.b.z) + r)
+        )
+    }
+}
+
+impl Capsule {
+    /// Construct capsule around the segment from a to b
+    pub fn new(a: Point,b: Point,radius: f64) -> Capsule {
+        Capsule {a,b,radius}
+    }
+}
+
+/// Exact distance to the box with the given half extents at the origin
+fn box_distance(p: &Point,half_extent: &Vector) -> f64 {
+    let q = Vector::new(p.x.abs() - half_extent.x,p.y.abs() - half_extent.y,p.z.abs() - half_extent.z);
+    let outside = Vector::new(q.x.max(0.0),q.y.max(0.0),q.z.max(0.0)).len();
+    outside + f64::min(q.x.max(q.y.max(q.z)),0.0)
+}
+
+/// Origin of object space
+fn origin() -> Point {
+    Point::new(0.0,0.0,0.0)
+}
+
+/// Cube of half side r about the origin
+fn cube(r: f64) -> (Point,Point) {
+    (Point::new(-r,-r,-r),Point::new(r,r,r))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // distances should be exact inside and outside each shape
+    fn test_distance() {
+        let sphere = Sphere::new(2.);
+        assert_eq!(sphere.distance(&Point::new(0.,3.,0.)),1.);
+        assert_eq!(sphere.distance(&Point::new(0.,0.,0.)),-2.);
+        let cuboid = Cuboid::new(Vector::new(1.,2.,3.));
+        assert_eq!(cuboid.distance(&Point::new(4.,0.,0.)),3.);
+        assert!((cuboid.distance(&Point::new(4.,6.,0.)) - 5.).abs() < 1e-12);
+        assert_eq!(cuboid.distance(&Point::new(0.5,0.,0.)),-0.5);
+        let rounded = RoundedCuboid::new(Vector::new(1.,1.,1.),0.5);
+        assert!((rounded.distance(&Point::new(2.,2.,0.)) - (f64::sqrt(4.5) - 0.5)).abs() < 1e-12);
+        assert_eq!(rounded.distance(&Point::new(2.,0.,0.)),1.);
+        let torus = Torus::new(2.,0.5);
+        assert_eq!(torus.distance(&Point::new(2.,1.,0.)),0.5);
+        assert_eq!(torus.distance(&Point::new(0.,0.,0.)),1.5);
+        let capsule = Capsule::new(Point::new(0.,0.,0.),Point::new(0.,2.,0.),0.5);
+        assert_eq!(capsule.distance(&Point::new(1.,1.,0.)),0.5);
+        assert_eq!(capsule.distance(&Point::new(0.,4.,0.)),1.5);
+    }
+
+    #[test]
+    // bounds should enclose the surface of each shape
+    fn test_bounds() {
+        let (min,max) = Torus::new(2.,0.5).bounds();
+        assert_eq!((min.x,min.y,max.z),(-2.5,-0.5,2.5));
+        let (min,max) = Capsule::new(Point::new(0.,0.,0.),Point::new(1.,-2.,0.),0.5).bounds();
+        assert_eq!((min.x,min.y,min.z),(-0.5,-2.5,-0.5));
+        assert_eq!((max.x,max.y,max.z),(1.5,0.5,0.5));
+        assert_eq!(RoundedCuboid::new(Vector::new(1.,2.,3.),5.).radius,1.);
+    }
+}
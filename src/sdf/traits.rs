@@ -0,0 +1,25 @@
+use crate::math::point::Point;
+
+/// Signed distance to an implicit surface, negative inside it. Fields
+/// are composed from shapes and operators and traced by the implicit
+/// primitive
+pub trait DistanceField: Send + Sync {
+    /// Signed distance from p to the surface, it may overestimate the
+    /// true distance by at most the lipschitz factor
+    fn distance(&self,p: &Point) -> f64;
+
+    /// Box (min,max) enclosing the whole surface, possibly loosely
+    fn bounds(&self) -> (Point,Point);
+
+    /// Bound on how fast the distance changes with p, 1 for exact
+    /// distances, sphere tracing divides its steps by it
+    fn lipschitz(&self) -> f64 {
+        1.0
+    }
+
+    /// Orbit trap of fractal fields at p as (u,v) in [0,1], used as
+    /// surface coordinates for coloring, None for other fields
+    fn orbit_trap(&self,_p: &Point) -> Option<(f64,f64)> {
+        None
+    }
+}
@@ -0,0 +1,10 @@
+// texture
+pub mod constant;
+pub mod imagemap;
+pub mod mipmap;
+pub mod checkerboard;
+pub mod mix;
+pub mod mapping;
+pub mod noise;
+
+pub mod traits;
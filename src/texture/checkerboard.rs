@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use super::{traits::Texture,mapping::TextureMapping};
+use crate::{
+    image::spectrum::Spectrum,
+    scene::interaction::SurfaceInteraction
+};
+
+/// # CheckerboardTexture
+/// Alternates between two textures on unit squares of the texture
+/// coordinates, the mapping scale sets the number of checks
+///
+/// # Parameters
+/// * even (texture where floor(s) + floor(t) is even)
+/// * odd (texture on the other squares)
+/// * mapping (texture coordinates at surface points)
+pub struct CheckerboardTexture {
+    pub even: Arc<dyn Texture>,
+    pub odd: Arc<dyn Texture>,
+    pub mapping: TextureMapping
+}
+
+/// Texture trait
+impl Texture for CheckerboardTexture {
+    fn evaluate(&self,si: &SurfaceInteraction) -> Spectrum {
+        self.mapping.apply(si,|tc| {
+            if (tc.st.0.floor() + tc.st.1.floor()).rem_euclid(2.0) == 0.0 {
+                self.even.evaluate(si)
+            } else {
+                self.odd.evaluate(si)
+            }
+        })
+    }
+}
+
+impl CheckerboardTexture {
+    /// Construct checkerboard of two textures
+    pub fn new(even: Arc<dyn Texture>,odd: Arc<dyn Texture>,mapping: TextureMapping) -> CheckerboardTexture {
+        CheckerboardTexture {even,odd,mapping}
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        image::spectrum,
+        math::{point::Point,vector::Vector,normal::Normal},
+        texture::constant::ConstantTexture
+    };
+
+    #[test]
+    // neighbouring squares should alternate, diagonal ones match
+    fn test_checkerboard() {
+        let texture = CheckerboardTexture::new(
+            Arc::new(ConstantTexture::new(spectrum::WHITE)),
+            Arc::new(ConstantTexture::new(spectrum::BLACK)),
+            TextureMapping::Uv {scale: (4.,4.),offset: (0.,0.)}
+        );
+        let at = |u: f64,v: f64| {
+            let si = SurfaceInteraction::new(
+                Point::new(0.,0.,0.),Normal::new(0.,0.,1.),(u,v),
+                Vector::new(1.,0.,0.),Vector::new(0.,1.,0.),1.,Vector::new(0.,0.,1.)
+            );
+            texture.evaluate(&si).r
+        };
+        assert_eq!(at(0.1,0.1),1.);
+        assert_eq!(at(0.3,0.1),0.);
+        assert_eq!(at(0.1,0.3),0.);
+        assert_eq!(at(0.3,0.3),1.);
+        assert_eq!(at(-0.1,0.1),0.);
+    }
+}
@@ -0,0 +1,28 @@
+use super::traits::Texture;
+use crate::{
+    image::spectrum::Spectrum,
+    scene::interaction::SurfaceInteraction
+};
+
+/// # ConstantTexture
+/// Same value everywhere
+///
+/// # Parameters
+/// * value
+pub struct ConstantTexture {
+    pub value: Spectrum
+}
+
+/// Texture trait
+impl Texture for ConstantTexture {
+    fn evaluate(&self,_si: &SurfaceInteraction) -> Spectrum {
+        self.value
+    }
+}
+
+impl ConstantTexture {
+    /// Construct constant texture
+    pub fn new(value: Spectrum) -> ConstantTexture {
+        ConstantTexture {value}
+    }
+}
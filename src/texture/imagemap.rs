@@ -0,0 +1,144 @@
+use std::sync::Arc;
+
+use super::{
+    traits::Texture,
+    mapping::TextureMapping,
+    mipmap::{MipMap,FilterMode}
+};
+use crate::{
+    image::{hdr::HdrImage,spectrum::Spectrum},
+    scene::interaction::SurfaceInteraction
+};
+
+/// # WrapMode
+/// Handling of texel coordinates outside the image
+///
+/// # Variants
+/// * Repeat (tile the image)
+/// * Clamp (extend the border texels)
+/// * Mirror (tile the image, flipping every other copy)
+/// * Black (zero outside the image)
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum WrapMode {
+    Repeat,
+    Clamp,
+    Mirror,
+    Black
+}
+
+impl WrapMode {
+    /// Texel index for i in an axis of n texels, None if it is black
+    pub fn wrap(&self,i: i64,n: usize) -> Option<usize> {
+        let n = n as i64;
+        match self {
+            WrapMode::Repeat => Some(i.rem_euclid(n) as usize),
+            WrapMode::Clamp => Some(i.clamp(0,n - 1) as usize),
+            WrapMode::Mirror => {
+                let i = i.rem_euclid(2 * n);
+                Some(if i < n { i } else { 2 * n - 1 - i } as usize)
+            },
+            WrapMode::Black => if (0..n).contains(&i) { Some(i as usize) } else { None }
+        }
+    }
+}
+
+/// # ImageTexture
+/// Texture looked up in a MIP-mapped image, filtered over the pixel
+/// footprint. (0,0) is the top left corner of the image and (1,1)
+/// the bottom right
+///
+/// # Parameters
+/// * mipmap (image pyramid, shared between textures using the same file)
+/// * mapping (texture coordinates at surface points)
+/// * filter (filtering of lookups)
+pub struct ImageTexture {
+    pub mipmap: Arc<MipMap>,
+    pub mapping: TextureMapping,
+    pub filter: FilterMode
+}
+
+/// Texture trait
+impl Texture for ImageTexture {
+    fn evaluate(&self,si: &SurfaceInteraction) -> Spectrum {
+        self.mapping.apply(si,|tc| self.mipmap.filter(self.filter,tc))
+    }
+}
+
+impl ImageTexture {
+    /// Construct texture from an image pyramid
+    pub fn new(mipmap: Arc<MipMap>,mapping: TextureMapping,filter: FilterMode) -> ImageTexture {
+        ImageTexture {mipmap,mapping,filter}
+    }
+
+    /// Load Radiance RGBE (.hdr) image texture from disk
+    pub fn load(path: &str,mapping: TextureMapping,wrap: WrapMode,filter: FilterMode) -> Result<ImageTexture,String> {
+        let mipmap = MipMap::new(HdrImage::load(path)?,wrap);
+        Ok(ImageTexture::new(Arc::new(mipmap),mapping,filter))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        image::spectrum,
+        math::{point::Point,vector::Vector,normal::Normal}
+    };
+
+    // 2x1 image, black on the left and white on the right
+    fn test_mipmap(wrap: WrapMode) -> MipMap {
+        let mut image = HdrImage::new(2,1,spectrum::BLACK);
+        image.pixels[1] = spectrum::WHITE;
+        MipMap::new(image,wrap)
+    }
+
+    #[test]
+    // each mode should map out of range indices differently
+    fn test_wrap() {
+        assert_eq!(WrapMode::Repeat.wrap(-1,4),Some(3));
+        assert_eq!(WrapMode::Repeat.wrap(9,4),Some(1));
+        assert_eq!(WrapMode::Clamp.wrap(-3,4),Some(0));
+        assert_eq!(WrapMode::Clamp.wrap(7,4),Some(3));
+        assert_eq!(WrapMode::Mirror.wrap(-1,4),Some(0));
+        assert_eq!(WrapMode::Mirror.wrap(5,4),Some(2));
+        assert_eq!(WrapMode::Black.wrap(4,4),None);
+        assert_eq!(WrapMode::Black.wrap(2,4),Some(2));
+    }
+
+    #[test]
+    // texel centers should be exact, values in between interpolated
+    fn test_bilinear() {
+        let mipmap = test_mipmap(WrapMode::Clamp);
+        assert_eq!(mipmap.bilinear(0,(0.25,0.5)).r,0.);
+        assert_eq!(mipmap.bilinear(0,(0.75,0.5)).r,1.);
+        assert!((mipmap.bilinear(0,(0.5,0.5)).r - 0.5).abs() < 1e-12);
+        assert_eq!(mipmap.bilinear(0,(1.5,0.5)).r,1.);
+        // repeating wraps the white texel around to the left edge
+        let mipmap = test_mipmap(WrapMode::Repeat);
+        assert!((mipmap.bilinear(0,(0.,0.5)).r - 0.5).abs() < 1e-12);
+        let mipmap = test_mipmap(WrapMode::Black);
+        assert!((mipmap.bilinear(0,(1.,0.5)).r - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    // the texture should be looked up through its mapping
+    fn test_evaluate() {
+        let texture = ImageTexture::new(
+            Arc::new(test_mipmap(WrapMode::Repeat)),
+            TextureMapping::Uv {scale: (1.,1.),offset: (0.5,0.)},
+            FilterMode::Trilinear
+        );
+        let mut si = SurfaceInteraction::new(
+            Point::new(0.,0.,0.),Normal::new(0.,0.,1.),(0.25,0.5),
+            Vector::new(1.,0.,0.),Vector::new(0.,1.,0.),1.,Vector::new(0.,0.,1.)
+        );
+        assert_eq!(texture.evaluate(&si).r,1.);
+        // a footprint covering the image sees its average
+        si.duvdx = (1.,0.);
+        assert!((texture.evaluate(&si).r - 0.5).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,225 @@
+use std::f64::consts::PI;
+
+use crate::{
+    image::spectrum::{self,Spectrum},
+    math::{point::Point,vector::Vector,traits::{Dot,Normalize}},
+    scene::interaction::SurfaceInteraction
+};
+
+/// # TextureMapping
+/// Computes (s,t) texture coordinates at a surface point,
+/// y is the polar axis like for spheres and environment maps
+///
+/// # Variants
+/// * Uv (surface parameterization scaled then offset)
+/// * Planar (projection of p onto two vectors, then offset)
+/// * Spherical (angles of p seen from center, s around the y axis and t from the pole)
+/// * Cylindrical (angle around a y axis through center, and height along it)
+/// * Triplanar (planar projections along x, y and z scaled by scale,
+///   blended by the normal raised to the sharpness power)
+#[derive(Clone,Copy)]
+pub enum TextureMapping {
+    Uv {scale: (f64,f64),offset: (f64,f64)},
+    Planar {s: Vector,t: Vector,offset: (f64,f64)},
+    Spherical {center: Point},
+    Cylindrical {center: Point},
+    Triplanar {scale: f64,sharpness: f64}
+}
+
+impl Default for TextureMapping {
+    fn default() -> Self {
+        TextureMapping::Uv {scale: (1.0,1.0),offset: (0.0,0.0)}
+    }
+}
+
+/// # TexCoord
+/// Texture coordinates at a surface point and their change
+/// from one pixel to the next, used to filter lookups
+///
+/// # Parameters
+/// * st (texture coordinates)
+/// * dstdx (change of st one pixel over in x)
+/// * dstdy (change of st one pixel over in y)
+#[derive(Clone,Copy,Debug)]
+pub struct TexCoord {
+    pub st: (f64,f64),
+    pub dstdx: (f64,f64),
+    pub dstdy: (f64,f64)
+}
+
+impl TextureMapping {
+    /// Evaluate a 2D lookup at the coordinates of si, triplanar
+    /// mapping blends three lookups, the others need a single one
+    pub fn apply<F: Fn(&TexCoord) -> Spectrum>(&self,si: &SurfaceInteraction,lookup: F) -> Spectrum {
+        match *self {
+            TextureMapping::Uv {scale,offset} => lookup(&TexCoord {
+                st: (si.uv.0 * scale.0 + offset.0,si.uv.1 * scale.1 + offset.1),
+                dstdx: (si.duvdx.0 * scale.0,si.duvdx.1 * scale.1),
+                dstdy: (si.duvdy.0 * scale.0,si.duvdy.1 * scale.1)
+            }),
+            TextureMapping::Planar {s,t,offset} => {
+                let p = si.p - Point::new(0.0,0.0,0.0);
+                lookup(&TexCoord {
+                    st: (p.dot(s) + offset.0,p.dot(t) + offset.1),
+                    dstdx: (si.dpdx.dot(s),si.dpdx.dot(t)),
+                    dstdy: (si.dpdy.dot(s),si.dpdy.dot(t))
+                })
+            },
+            TextureMapping::Spherical {center} => lookup(&finite_differences(si,|p| spherical(&(*p - center)))),
+            TextureMapping::Cylindrical {center} => lookup(&finite_differences(si,|p| {
+                let d = *p - center;
+                (phi(d.z,d.x) / (2.0 * PI),d.y)
+            })),
+            TextureMapping::Triplanar {scale,sharpness} => {
+                let weights = [si.n.x.abs(),si.n.y.abs(),si.n.z.abs()].map(|w| w.powf(sharpness));
+                let total: f64 = weights.iter().sum();
+                if total == 0.0 {
+                    return spectrum::BLACK
+                }
+                let project = |v: &Vector| [(v.z,v.y),(v.x,v.z),(v.x,v.y)];
+                let p = si.p - Point::new(0.0,0.0,0.0);
+                let planes = project(&p);
+                let (dx,dy) = (project(&si.dpdx),project(&si.dpdy));
+                let mut value = spectrum::BLACK;
+                for i in 0..3 {
+                    if weights[i] > 0.0 {
+                        let tc = TexCoord {
+                            st: (planes[i].0 * scale,planes[i].1 * scale),
+                            dstdx: (dx[i].0 * scale,dx[i].1 * scale),
+                            dstdy: (dy[i].0 * scale,dy[i].1 * scale)
+                        };
+                        value += lookup(&tc) * (weights[i] / total);
+                    }
+                }
+                value
+            }
+        }
+    }
+}
+
+/// Coordinates of a mapping given as a function of p, derivatives
+/// are finite differences over the pixel footprint. Angles wrap
+/// around, differences are taken the short way
+fn finite_differences<F: Fn(&Point) -> (f64,f64)>(si: &SurfaceInteraction,map: F) -> TexCoord {
+    let st = map(&si.p);
+    let difference = |dp: &Vector| {
+        let moved = map(&(si.p + *dp));
+        let mut ds = moved.0 - st.0;
+        if ds > 0.5 {
+            ds -= 1.0;
+        } else if ds < -0.5 {
+            ds += 1.0;
+        }
+        (ds,moved.1 - st.1)
+    };
+    TexCoord {
+        st,
+        dstdx: difference(&si.dpdx),
+        dstdy: difference(&si.dpdy)
+    }
+}
+
+/// Spherical coordinates of direction d, s around the y axis and t from the pole
+fn spherical(d: &Vector) -> (f64,f64) {
+    match d.normalize() {
+        Ok(d) => (phi(d.z,d.x) / (2.0 * PI),f64::acos(d.y.clamp(-1.0,1.0)) / PI),
+        Err(_) => (0.0,0.0)
+    }
+}
+
+/// Angle of (x,z) around the y axis in [0,2pi)
+fn phi(z: f64,x: f64) -> f64 {
+    let phi = f64::atan2(z,x);
+    if phi < 0.0 { phi + 2.0 * PI } else { phi }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::normal::Normal;
+
+    fn interaction(p: Point,n: Normal,uv: (f64,f64)) -> SurfaceInteraction {
+        SurfaceInteraction::new(p,n,uv,Vector::new(1.,0.,0.),Vector::new(0.,1.,0.),1.,Vector::new(0.,0.,1.))
+    }
+
+    // lookup returning the coordinates as a spectrum
+    fn coordinates(mapping: &TextureMapping,si: &SurfaceInteraction) -> (f64,f64) {
+        let value = mapping.apply(si,|tc| Spectrum::new(tc.st.0,tc.st.1,0.));
+        (value.r,value.g)
+    }
+
+    #[test]
+    // uv mapping should scale then offset the parameterization
+    fn test_uv() {
+        let si = interaction(Point::new(0.,0.,0.),Normal::new(0.,0.,1.),(0.25,0.5));
+        assert_eq!(coordinates(&TextureMapping::default(),&si),(0.25,0.5));
+        let mapping = TextureMapping::Uv {scale: (2.,4.),offset: (0.1,0.)};
+        assert_eq!(coordinates(&mapping,&si),(0.6,2.));
+    }
+
+    #[test]
+    // planar mapping should project onto its vectors
+    fn test_planar() {
+        let si = interaction(Point::new(1.,2.,3.),Normal::new(0.,0.,1.),(0.,0.));
+        let mapping = TextureMapping::Planar {s: Vector::new(1.,0.,0.),t: Vector::new(0.,0.,0.5),offset: (0.,1.)};
+        assert_eq!(coordinates(&mapping,&si),(1.,2.5));
+    }
+
+    #[test]
+    // spherical and cylindrical mappings should follow angles around y
+    fn test_spherical_cylindrical() {
+        let center = Point::new(1.,0.,0.);
+        let si = interaction(Point::new(1.,0.,2.),Normal::new(0.,0.,1.),(0.,0.));
+        let (s,t) = coordinates(&TextureMapping::Spherical {center},&si);
+        assert!((s - 0.25).abs() < 1e-12 && (t - 0.5).abs() < 1e-12);
+        let top = interaction(Point::new(1.,3.,0.),Normal::new(0.,1.,0.),(0.,0.));
+        assert!(coordinates(&TextureMapping::Spherical {center},&top).1.abs() < 1e-12);
+
+        let si = interaction(Point::new(0.,1.5,0.),Normal::new(-1.,0.,0.),(0.,0.));
+        let (s,t) = coordinates(&TextureMapping::Cylindrical {center},&si);
+        assert!((s - 0.5).abs() < 1e-12 && (t - 1.5).abs() < 1e-12);
+    }
+
+    #[test]
+    // triplanar weights should sum to one and follow the normal
+    fn test_triplanar() {
+        let mapping = TextureMapping::Triplanar {scale: 1.,sharpness: 4.};
+        let si = interaction(Point::new(1.,2.,3.),Normal::new(0.,1.,0.),(0.,0.));
+        assert_eq!(coordinates(&mapping,&si),(1.,3.));
+        let n = f64::sqrt(0.5);
+        let si = interaction(Point::new(1.,2.,3.),Normal::new(n,0.,n),(0.,0.));
+        let blend = mapping.apply(&si,|_| spectrum::WHITE);
+        assert!((blend.r - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    // derivatives should follow the pixel footprint through each mapping
+    fn test_derivatives() {
+        let mut si = interaction(Point::new(0.,1.,2.),Normal::new(0.,0.,1.),(0.5,0.5));
+        si.duvdx = (0.01,0.);
+        si.duvdy = (0.,0.02);
+        si.dpdx = Vector::new(0.1,0.,0.);
+        si.dpdy = Vector::new(0.,0.2,0.);
+        let dx = |mapping: TextureMapping| mapping.apply(&si,|tc| Spectrum::new(tc.dstdx.0,tc.dstdx.1,tc.dstdy.1));
+
+        let uv = dx(TextureMapping::Uv {scale: (2.,2.),offset: (0.,0.)});
+        assert_eq!((uv.r,uv.g,uv.b),(0.02,0.,0.04));
+        let planar = dx(TextureMapping::Planar {s: Vector::new(1.,0.,0.),t: Vector::new(0.,1.,0.),offset: (0.,0.)});
+        assert_eq!((planar.r,planar.b),(0.1,0.2));
+        let triplanar = dx(TextureMapping::Triplanar {scale: 2.,sharpness: 1.});
+        assert_eq!((triplanar.r,triplanar.b),(0.2,0.4));
+        // moving along x on the circle around the y axis decreases s
+        let cylindrical = dx(TextureMapping::Cylindrical {center: Point::new(0.,0.,0.)});
+        let expected = f64::atan2(2.,0.1) / (2. * PI) - 0.25;
+        assert!((cylindrical.r - expected).abs() < 1e-12);
+        assert!((cylindrical.b - 0.2).abs() < 1e-12);
+        let mut seam = interaction(Point::new(1.,0.,-1e-3),Normal::new(0.,0.,1.),(0.,0.));
+        seam.dpdx = Vector::new(0.,0.,2e-3);
+        let across = TextureMapping::Spherical {center: Point::new(0.,0.,0.)}.apply(&seam,|tc| Spectrum::new(tc.dstdx.0,0.,0.));
+        assert!(across.r > 0. && across.r < 1e-3);
+    }
+}
@@ -0,0 +1,281 @@
+use super::{imagemap::WrapMode,mapping::TexCoord};
+use crate::image::{hdr::HdrImage,spectrum::{self,Spectrum}};
+
+/// Largest ratio between the axes of an EWA footprint, longer
+/// ellipses are widened so the lookup cost stays bounded
+const MAX_ANISOTROPY: f64 = 8.0;
+
+/// Falloff of the gaussian weighting EWA samples
+const EWA_ALPHA: f64 = 2.0;
+
+/// # FilterMode
+/// Filtering of image texture lookups over the pixel footprint
+///
+/// # Variants
+/// * Bilinear (full resolution only, aliases when minified)
+/// * Trilinear (isotropic, blends the two levels matching the footprint width)
+/// * Ewa (elliptically weighted average, follows anisotropic footprints)
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum FilterMode {
+    Bilinear,
+    Trilinear,
+    Ewa
+}
+
+/// # MipMap
+/// Image pyramid, each level halves the resolution of the one
+/// before by box filtering down to a single texel
+///
+/// # Parameters
+/// * levels (full resolution image first)
+/// * wrap (handling of texel coordinates outside the image)
+pub struct MipMap {
+    pub levels: Vec<HdrImage>,
+    pub wrap: WrapMode
+}
+
+impl MipMap {
+    /// Build pyramid from the full resolution image
+    pub fn new(image: HdrImage,wrap: WrapMode) -> MipMap {
+        let mut levels = vec![image];
+        loop {
+            let last = &levels[levels.len() - 1];
+            if last.width <= 1 && last.height <= 1 {
+                break;
+            }
+            let next = downsample(last);
+            levels.push(next);
+        }
+        MipMap {levels,wrap}
+    }
+
+    /// Texel (x,y) of a level after wrapping
+    pub fn texel(&self,level: usize,x: i64,y: i64) -> Spectrum {
+        let image = &self.levels[level];
+        match (self.wrap.wrap(x,image.width),self.wrap.wrap(y,image.height)) {
+            (Some(x),Some(y)) => image.at(x,y),
+            _ => spectrum::BLACK
+        }
+    }
+
+    /// Bilinear interpolation of the texels of a level around st,
+    /// texel centers sit at half integer coordinates
+    pub fn bilinear(&self,level: usize,st: (f64,f64)) -> Spectrum {
+        let image = &self.levels[level];
+        if image.width == 0 || image.height == 0 {
+            return spectrum::BLACK
+        }
+        let x = st.0 * image.width as f64 - 0.5;
+        let y = st.1 * image.height as f64 - 0.5;
+        let (x0,y0) = (x.floor(),y.floor());
+        let (dx,dy) = (x - x0,y - y0);
+        let (x0,y0) = (x0 as i64,y0 as i64);
+        self.texel(level,x0,y0) * ((1.0 - dx) * (1.0 - dy))
+            + self.texel(level,x0 + 1,y0) * (dx * (1.0 - dy))
+            + self.texel(level,x0,y0 + 1) * ((1.0 - dx) * dy)
+            + self.texel(level,x0 + 1,y0 + 1) * (dx * dy)
+    }
+
+    /// Filtered lookup over the footprint of tc
+    pub fn filter(&self,mode: FilterMode,tc: &TexCoord) -> Spectrum {
+        match mode {
+            FilterMode::Bilinear => self.bilinear(0,tc.st),
+            FilterMode::Trilinear => {
+                let width = [tc.dstdx.0,tc.dstdx.1,tc.dstdy.0,tc.dstdy.1]
+                    .iter()
+                    .fold(0.0,|m: f64,d| m.max(d.abs()));
+                self.trilinear(tc.st,2.0 * width)
+            },
+            FilterMode::Ewa => self.ewa(tc.st,tc.dstdx,tc.dstdy)
+        }
+    }
+
+    /// Isotropic lookup of a square footprint of the given width in st,
+    /// blending the two levels whose texels are closest to that width
+    pub fn trilinear(&self,st: (f64,f64),width: f64) -> Spectrum {
+        let last = self.levels.len() - 1;
+        let level = last as f64 + f64::log2(f64::max(width,1e-8));
+        if level <= 0.0 {
+            return self.bilinear(0,st)
+        }
+        if level >= last as f64 {
+            return self.bilinear(last,st)
+        }
+        let lower = level.floor() as usize;
+        let delta = level - lower as f64;
+        self.bilinear(lower,st) * (1.0 - delta) + self.bilinear(lower + 1,st) * delta
+    }
+
+    /// Elliptically weighted average over the ellipse spanned by the
+    /// axes dst0 and dst1, computed on the level matching its minor axis
+    pub fn ewa(&self,st: (f64,f64),dst0: (f64,f64),dst1: (f64,f64)) -> Spectrum {
+        let length = |d: (f64,f64)| f64::sqrt(d.0*d.0 + d.1*d.1);
+        let (major,mut minor) = if length(dst0) >= length(dst1) { (dst0,dst1) } else { (dst1,dst0) };
+        let major_length = length(major);
+        let mut minor_length = length(minor);
+
+        // widen overly eccentric ellipses
+        if minor_length * MAX_ANISOTROPY < major_length && minor_length > 0.0 {
+            let scale = major_length / (minor_length * MAX_ANISOTROPY);
+            minor = (minor.0 * scale,minor.1 * scale);
+            minor_length *= scale;
+        }
+        if minor_length == 0.0 {
+            return self.bilinear(0,st)
+        }
+
+        let last = self.levels.len() - 1;
+        let level = f64::max(0.0,last as f64 + f64::log2(minor_length));
+        let lower = level.floor() as usize;
+        if lower >= last {
+            return self.bilinear(last,st)
+        }
+        let delta = level - lower as f64;
+        self.ewa_level(lower,st,major,minor) * (1.0 - delta)
+            + self.ewa_level(lower + 1,st,major,minor) * delta
+    }
+
+    /// Gaussian weighted sum of the texels of a level inside the ellipse
+    fn ewa_level(&self,level: usize,st: (f64,f64),dst0: (f64,f64),dst1: (f64,f64)) -> Spectrum {
+        let image = &self.levels[level];
+        let (w,h) = (image.width as f64,image.height as f64);
+        let s = st.0 * w - 0.5;
+        let t = st.1 * h - 0.5;
+        let dst0 = (dst0.0 * w,dst0.1 * h);
+        let dst1 = (dst1.0 * w,dst1.1 * h);
+
+        // implicit ellipse a s^2 + b s t + c t^2 = 1, grown by a texel
+        // so that footprints smaller than a texel still catch some
+        let mut a = dst0.1*dst0.1 + dst1.1*dst1.1 + 1.0;
+        let mut b = -2.0 * (dst0.0*dst0.1 + dst1.0*dst1.1);
+        let mut c = dst0.0*dst0.0 + dst1.0*dst1.0 + 1.0;
+        let inv_f = 1.0 / (a*c - b*b*0.25);
+        a *= inv_f;
+        b *= inv_f;
+        c *= inv_f;
+
+        // bounding box of the ellipse
+        let det = 4.0*a*c - b*b;
+        let s_extent = 2.0 * f64::sqrt(det * c) / det;
+        let t_extent = 2.0 * f64::sqrt(a * det) / det;
+        let (s0,s1) = ((s - s_extent).ceil() as i64,(s + s_extent).floor() as i64);
+        let (t0,t1) = ((t - t_extent).ceil() as i64,(t + t_extent).floor() as i64);
+
+        let mut sum = spectrum::BLACK;
+        let mut weights = 0.0;
+        for it in t0..=t1 {
+            let tt = it as f64 - t;
+            for is in s0..=s1 {
+                let ss = is as f64 - s;
+                let r2 = a*ss*ss + b*ss*tt + c*tt*tt;
+                if r2 < 1.0 {
+                    let weight = f64::exp(-EWA_ALPHA * r2) - f64::exp(-EWA_ALPHA);
+                    sum += self.texel(level,is,it) * weight;
+                    weights += weight;
+                }
+            }
+        }
+        if weights > 0.0 {
+            sum / weights
+        } else {
+            self.bilinear(level,st)
+        }
+    }
+}
+
+/// Half resolution image, each texel averages the two by two block
+/// above it, clipped at the border of odd sized images
+fn downsample(image: &HdrImage) -> HdrImage {
+    let width = usize::max(1,image.width.div_ceil(2));
+    let height = usize::max(1,image.height.div_ceil(2));
+    let mut next = HdrImage::new(width,height,spectrum::BLACK);
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = spectrum::BLACK;
+            let mut count = 0;
+            for sy in 2*y..usize::min(2*y + 2,image.height) {
+                for sx in 2*x..usize::min(2*x + 2,image.width) {
+                    sum += image.at(sx,sy);
+                    count += 1;
+                }
+            }
+            next.pixels[y*width + x] = sum / count as f64;
+        }
+    }
+    next
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // n by n checkerboard of single black and white texels
+    fn checks(n: usize) -> MipMap {
+        let mut image = HdrImage::new(n,n,spectrum::BLACK);
+        for y in 0..n {
+            for x in 0..n {
+                if (x + y) % 2 == 0 {
+                    image.pixels[y*n + x] = spectrum::WHITE;
+                }
+            }
+        }
+        MipMap::new(image,WrapMode::Repeat)
+    }
+
+    #[test]
+    // pyramid should halve down to one texel holding the average
+    fn test_new() {
+        let mipmap = checks(16);
+        assert_eq!(mipmap.levels.len(),5);
+        assert_eq!(mipmap.levels[1].width,8);
+        assert!((mipmap.levels[4].at(0,0).r - 0.5).abs() < 1e-12);
+        // odd sizes round up and keep the average of what they cover
+        let mut image = HdrImage::new(3,1,spectrum::BLACK);
+        image.pixels[2] = spectrum::WHITE;
+        let mipmap = MipMap::new(image,WrapMode::Clamp);
+        assert_eq!(mipmap.levels.len(),3);
+        assert_eq!(mipmap.levels[1].width,2);
+        assert_eq!(mipmap.levels[1].at(1,0).r,1.);
+        assert_eq!(mipmap.levels[1].at(0,0).r,0.);
+    }
+
+    #[test]
+    // small footprints should see texels, large ones the average
+    fn test_trilinear() {
+        let mipmap = checks(64);
+        let st = (0.5 / 64.,0.5 / 64.);
+        assert_eq!(mipmap.trilinear(st,0.).r,1.);
+        assert!((mipmap.trilinear(st,0.25).r - 0.5).abs() < 1e-12);
+        assert!((mipmap.trilinear(st,2.).r - 0.5).abs() < 1e-12);
+        // in between levels blend
+        let halfway = mipmap.trilinear(st,f64::sqrt(2.) / 64.).r;
+        assert!(halfway > 0.5 && halfway < 1.);
+    }
+
+    #[test]
+    // a thin footprint along the checks should still average them out
+    fn test_ewa() {
+        let mipmap = checks(64);
+        let st = (0.5 / 64.,0.5 / 64.);
+        assert!((mipmap.ewa(st,(0.,0.),(0.,0.)).r - 1.).abs() < 1e-12);
+        let l = mipmap.ewa(st,(8. / 64.,0.),(0.,1. / 64.)).r;
+        assert!((l - 0.5).abs() < 0.1,"{}",l);
+        // very eccentric footprints are clamped but still filtered
+        let l = mipmap.ewa((0.3,0.6),(0.5,0.),(0.,1e-4)).r;
+        assert!((l - 0.5).abs() < 0.1,"{}",l);
+    }
+
+    #[test]
+    // filter modes should pick the matching lookup
+    fn test_filter() {
+        let mipmap = checks(64);
+        let tc = TexCoord {st: (0.5 / 64.,0.5 / 64.),dstdx: (0.25,0.),dstdy: (0.,0.25)};
+        assert_eq!(mipmap.filter(FilterMode::Bilinear,&tc).r,1.);
+        assert!((mipmap.filter(FilterMode::Trilinear,&tc).r - 0.5).abs() < 1e-12);
+        assert!((mipmap.filter(FilterMode::Ewa,&tc).r - 0.5).abs() < 1e-12);
+    }
+}
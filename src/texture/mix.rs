@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use super::traits::Texture;
+use crate::{
+    image::spectrum::Spectrum,
+    scene::interaction::SurfaceInteraction
+};
+
+/// # ScaleTexture
+/// Product of two textures, typically a texture and a tint
+///
+/// # Parameters
+/// * texture
+/// * scale
+pub struct ScaleTexture {
+    pub texture: Arc<dyn Texture>,
+    pub scale: Arc<dyn Texture>
+}
+
+/// Texture trait
+impl Texture for ScaleTexture {
+    fn evaluate(&self,si: &SurfaceInteraction) -> Spectrum {
+        self.texture.evaluate(si) * self.scale.evaluate(si)
+    }
+}
+
+impl ScaleTexture {
+    /// Construct product of two textures
+    pub fn new(texture: Arc<dyn Texture>,scale: Arc<dyn Texture>) -> ScaleTexture {
+        ScaleTexture {texture,scale}
+    }
+}
+
+/// # MixTexture
+/// Linear blend of two textures
+///
+/// # Parameters
+/// * a (texture where amount is 0)
+/// * b (texture where amount is 1)
+/// * amount (blend factor, the average of its channels is used)
+pub struct MixTexture {
+    pub a: Arc<dyn Texture>,
+    pub b: Arc<dyn Texture>,
+    pub amount: Arc<dyn Texture>
+}
+
+/// Texture trait
+impl Texture for MixTexture {
+    fn evaluate(&self,si: &SurfaceInteraction) -> Spectrum {
+        let t = self.amount.evaluate(si).average();
+        // skip lookups that do not contribute
+        if t <= 0.0 {
+            return self.a.evaluate(si)
+        }
+        if t >= 1.0 {
+            return self.b.evaluate(si)
+        }
+        self.a.evaluate(si) * (1.0 - t) + self.b.evaluate(si) * t
+    }
+}
+
+impl MixTexture {
+    /// Construct blend of a and b
+    pub fn new(a: Arc<dyn Texture>,b: Arc<dyn Texture>,amount: Arc<dyn Texture>) -> MixTexture {
+        MixTexture {a,b,amount}
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        image::spectrum,
+        math::{point::Point,vector::Vector,normal::Normal},
+        texture::constant::ConstantTexture
+    };
+
+    fn test_interaction() -> SurfaceInteraction {
+        SurfaceInteraction::new(
+            Point::new(0.,0.,0.),Normal::new(0.,0.,1.),(0.,0.),
+            Vector::new(1.,0.,0.),Vector::new(0.,1.,0.),1.,Vector::new(0.,0.,1.)
+        )
+    }
+
+    #[test]
+    // scaling should multiply channel by channel
+    fn test_scale() {
+        let texture = ScaleTexture::new(
+            Arc::new(ConstantTexture::new(Spectrum::new(0.5,1.,1.))),
+            Arc::new(ConstantTexture::new(Spectrum::new(1.,0.5,0.)))
+        );
+        let value = texture.evaluate(&test_interaction());
+        assert_eq!((value.r,value.g,value.b),(0.5,0.5,0.));
+    }
+
+    #[test]
+    // textures should be shareable between several nodes
+    fn test_mix() {
+        let white: Arc<dyn Texture> = Arc::new(ConstantTexture::new(spectrum::WHITE));
+        let black: Arc<dyn Texture> = Arc::new(ConstantTexture::new(spectrum::BLACK));
+        let quarter: Arc<dyn Texture> = Arc::new(ConstantTexture::new(spectrum::WHITE * 0.25));
+        let mix = MixTexture::new(black.clone(),white.clone(),quarter.clone());
+        assert!((mix.evaluate(&test_interaction()).g - 0.25).abs() < 1e-12);
+        let mix = MixTexture::new(white.clone(),black,white);
+        assert_eq!(mix.evaluate(&test_interaction()).g,0.);
+        assert_eq!(Arc::strong_count(&quarter),2);
+    }
+}
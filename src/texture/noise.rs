@@ -0,0 +1,154 @@
+use super::traits::Texture;
+use crate::{
+    image::spectrum::{self,Spectrum},
+    math::{point::Point,matrix::Matrix,noise::{Noise,Fractal}},
+    scene::interaction::SurfaceInteraction
+};
+
+/// # NoiseBasis
+/// Noise function summed by the pattern
+///
+/// # Variants
+/// * Perlin (gradient noise)
+/// * Simplex (gradient noise on a simplex lattice)
+/// * Worley (distance to the nearest feature point, cells and pebbles)
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum NoiseBasis {
+    Perlin,
+    Simplex,
+    Worley
+}
+
+/// # NoisePattern
+/// How octaves of the basis are combined
+///
+/// # Variants
+/// * Single (one octave)
+/// * Fbm (fractional brownian motion, clouds and terrain)
+/// * Turbulence (absolute octaves, smoke and fire)
+/// * Ridged (ridged multifractal, mountain crests and veins)
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum NoisePattern {
+    Single,
+    Fbm,
+    Turbulence,
+    Ridged
+}
+
+/// # NoiseTexture
+/// Grey level in [0,1] from procedural noise at the surface point,
+/// mix two textures by it to color marble, wood or clouds
+///
+/// # Parameters
+/// * noise (seeded noise generator)
+/// * basis (noise function)
+/// * pattern (combination of octaves)
+/// * fractal (octaves, lacunarity and gain)
+/// * frequency (scale from texture space to noise space)
+/// * warp (strength of domain warping, 0 disables it)
+/// * world_to_texture (identity for world space, inverse placement for object space)
+pub struct NoiseTexture {
+    pub noise: Noise,
+    pub basis: NoiseBasis,
+    pub pattern: NoisePattern,
+    pub fractal: Fractal,
+    pub frequency: f64,
+    pub warp: f64,
+    pub world_to_texture: Matrix
+}
+
+/// Texture trait
+impl Texture for NoiseTexture {
+    fn evaluate(&self,si: &SurfaceInteraction) -> Spectrum {
+        spectrum::WHITE * self.value(&si.p)
+    }
+}
+
+impl NoiseTexture {
+    /// Construct world space noise texture with default octaves
+    pub fn new(seed: u64,basis: NoiseBasis,pattern: NoisePattern) -> NoiseTexture {
+        NoiseTexture {
+            noise: Noise::new(seed),
+            basis,
+            pattern,
+            fractal: Fractal::default(),
+            frequency: 1.0,
+            warp: 0.0,
+            world_to_texture: Matrix::new()
+        }
+    }
+
+    /// Attach the texture to an object placed by object_to_world
+    pub fn set_object_to_world(&mut self,object_to_world: &Matrix) -> Result<(),String> {
+        self.world_to_texture = object_to_world.inverse()?;
+        Ok(())
+    }
+
+    /// Value in [0,1] at world space point p
+    pub fn value(&self,p: &Point) -> f64 {
+        let p = self.world_to_texture * *p;
+        let mut q = [p.x * self.frequency,p.y * self.frequency,p.z * self.frequency];
+        if self.warp != 0.0 {
+            q = self.fractal.warp(q,self.warp,|q| self.basis_value(q));
+        }
+        let basis = |q| self.basis_value(q);
+        let value = match self.pattern {
+            NoisePattern::Single => 0.5 * (basis(q) + 1.0),
+            NoisePattern::Fbm => 0.5 * (self.fractal.fbm(q,basis) + 1.0),
+            NoisePattern::Turbulence => self.fractal.turbulence(q,basis),
+            NoisePattern::Ridged => self.fractal.ridged(q,basis)
+        };
+        value.clamp(0.0,1.0)
+    }
+
+    /// Basis in [-1,1]
+    fn basis_value(&self,q: [f64; 3]) -> f64 {
+        match self.basis {
+            NoiseBasis::Perlin => self.noise.perlin(q),
+            NoiseBasis::Simplex => self.noise.simplex(q),
+            // nearest feature distance rarely exceeds one cell
+            NoiseBasis::Worley => (2.0 * self.noise.worley(q).0 - 1.0).clamp(-1.0,1.0)
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////// UNIT TESTS //////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::vector::Vector;
+
+    #[test]
+    // every combination should stay in [0,1] and depend on the seed
+    fn test_value() {
+        for basis in [NoiseBasis::Perlin,NoiseBasis::Simplex,NoiseBasis::Worley] {
+            for pattern in [NoisePattern::Single,NoisePattern::Fbm,NoisePattern::Turbulence,NoisePattern::Ridged] {
+                let mut texture = NoiseTexture::new(1,basis,pattern);
+                texture.frequency = 3.;
+                texture.warp = 0.3;
+                let other = NoiseTexture {noise: Noise::new(2),..NoiseTexture::new(1,basis,pattern)};
+                let mut differs = false;
+                for i in 0..50 {
+                    let p = Point::new(i as f64 * 0.137,0.5 - i as f64 * 0.051,i as f64 * 0.093);
+                    let v = texture.value(&p);
+                    assert!((0. ..=1.).contains(&v));
+                    differs |= other.value(&p) != NoiseTexture::new(1,basis,pattern).value(&p);
+                }
+                assert!(differs,"{:?} {:?}",basis,pattern);
+            }
+        }
+    }
+
+    #[test]
+    // object space noise should move with its object
+    fn test_object_space() {
+        let mut texture = NoiseTexture::new(3,NoiseBasis::Perlin,NoisePattern::Fbm);
+        let world = NoiseTexture::new(3,NoiseBasis::Perlin,NoisePattern::Fbm);
+        texture.set_object_to_world(&Matrix::translate(&Vector::new(10.,0.,0.))).unwrap();
+        let p = Point::new(0.3,0.2,0.1);
+        assert!((texture.value(&Point::new(10.3,0.2,0.1)) - world.value(&p)).abs() < 1e-9);
+    }
+}
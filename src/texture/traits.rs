@@ -0,0 +1,11 @@
+use crate::{
+    image::spectrum::Spectrum,
+    scene::interaction::SurfaceInteraction
+};
+
+/// Spatially varying quantity evaluated at surface points, textures
+/// are shared between materials and nodes behind an Arc
+pub trait Texture: Send + Sync {
+    /// Value of the texture at a surface interaction
+    fn evaluate(&self,si: &SurfaceInteraction) -> Spectrum;
+}
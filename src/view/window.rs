@@ -11,10 +11,19 @@ use sdl2::{
 
 use std::time::Duration;
 
-use crate::{
+use rust_raytracer::{
     image::{film::Film,color::Color},
     scene::{world::*,sphere::*,plane::*},
-    math::{point::*,ray::*,vector::*,normal::*}
+    math::{point::*,ray::*,vector::*,normal::*},
+    image::spectrum::Spectrum,
+    light::{traits::Light,point::PointLight},
+    material::{glass::Glass,lambertian::Lambertian},
+    integrator::{
+        traits::Integrator,
+        whitted::WhittedIntegrator,
+        debug::{self,DebugIntegrator,DebugMode}
+    },
+    sampler::random::RandomSampler
 };
 
 use rand::Rng;
@@ -27,6 +36,7 @@ pub struct Window<'a> {
     video: VideoSubsystem,
     canvas: Canvas<video::Window>,
     film: &'a mut Film,
+    environment: Option<Box<dyn Light>>,
     // texture_creator: TextureCreator<video::WindowContext>,
     // texture: Texture<'a>
 }
@@ -62,29 +72,51 @@ impl<'a> Window<'a> {
             width,height,
             context,video,canvas,
             film,
+            environment: None,
             // texture_creator,texture
         }
     }
 
+    /// Set environment light seen by rays escaping the scene
+    pub fn set_environment(&mut self,environment: Box<dyn Light>) {
+        self.environment = Some(environment);
+    }
+
     /// Main loop
     pub fn run(mut self) -> Result<(),String> {
 
         // set up the world
-        let mut world = World::new(1);
+        let mut world = World::new(2);
+        let glass = world.add_material(Box::new(Glass::new(1.5)));
+        let floor = world.add_material(Box::new(Lambertian::new(Spectrum::new(0.8,0.8,0.8))));
+
         let mut s_center = Point::new(0.,0.,1.);
-        let mut s_radius = 0.5;
-        let mut sphere = Sphere::new(s_radius,s_center);
-        world.add_primitive(Box::new(sphere));
+        let s_radius = 0.5;
+        let sphere = Sphere::new(s_radius,s_center);
+        world.add_primitive_with_material(Box::new(sphere),glass);
 
         let plane = Plane::new(
             &Point::new(0.,-1.,5.),&
             Normal::new(0.,1.,0.25)
         );
-        world.add_primitive(Box::new(plane));
+        world.add_primitive_with_material(Box::new(plane),floor);
+
+        world.add_light(Box::new(PointLight::new(
+            Point::new(-2.,3.,-1.),
+            Spectrum::new(20.,20.,20.)
+        )));
+
+        if let Some(environment) = self.environment.take() {
+            world.set_environment(environment);
+        }
 
         // ray initialized at origin, pointing in positive Z
         let mut ray: Ray = Ray::new(&Point::new(0.,0.,0.),&Vector::new(0.,0.,1.));
-        let mut hit_color = Color::new(1.,0.,0.,1.).unwrap();
+        let whitted = WhittedIntegrator::new(5);
+        // number keys 1-9 pick a debug mode, 0 returns to shaded rendering
+        let mut debug_mode: Option<DebugMode> = None;
+        let base_title: String = self.canvas.window().title().to_string();
+        let mut sampler = RandomSampler::new(0);
         let mut SPEED = 0.1;
 
         // set up texture
@@ -110,10 +142,29 @@ impl<'a> Window<'a> {
                     } => {
                         break 'running;
                     },
+                    Event::KeyDown {keycode: Some(keycode),..} => {
+                        if let Some(mode) = Window::debug_mode_for_key(keycode) {
+                            debug_mode = mode;
+                            let title = match debug_mode {
+                                Some(mode) => format!("{} ({:?})",base_title,mode),
+                                None => base_title.clone()
+                            };
+                            self.canvas.window_mut().set_title(&title).map_err(|e| e.to_string())?;
+                        }
+                    },
                     _ => {}
                 }
             }
 
+            let debug_integrator: DebugIntegrator;
+            let integrator: &dyn Integrator = match debug_mode {
+                Some(mode) => {
+                    debug_integrator = DebugIntegrator::new(mode);
+                    &debug_integrator
+                },
+                None => &whitted
+            };
+
             texture.update(
                 sdl2::rect::Rect::new(0,0,self.width as u32,self.height as u32),
                 unsafe {
@@ -124,7 +175,7 @@ impl<'a> Window<'a> {
             .expect("Coudln't copy framebuffer to texture");
 
             let color: Color = Color::new(0.,0.,0.,1.).unwrap();
-            self.film.clear(crate::image::color::BLACK);
+            self.film.clear(rust_raytracer::image::color::BLACK);
 
             // raytrace!
             for i in 0..self.width {
@@ -133,20 +184,8 @@ impl<'a> Window<'a> {
                     // we only need to adjust ray origin's x and y coord
                     ray.o.x = x;
                     ray.o.y = y;
-                    let result = world.hit(&ray);
-                    match result {
-                        Some(x) => {
-                            // got a hit!
-                            let mut v = x - s_center;
-                            hit_color.r = (v.x * 0.5) + 0.5;
-                            hit_color.g = (v.y * 0.5) + 0.5;
-                            hit_color.b = (v.z * 0.5) + 0.5;
-                            self.film.write_pixel(i as usize,j as usize,hit_color);
-                        },
-                        None => {
-                            // do nothing
-                        }
-                    }
+                    let l = integrator.li(&ray,&world,&mut sampler);
+                    self.film.write_pixel(i as usize,j as usize,l.to_color());
                 }
             }
 
@@ -158,7 +197,7 @@ impl<'a> Window<'a> {
                 SPEED *= -1.;
             }
             s_center.x += SPEED;
-            world.primitives[0] = Box::new(Sphere::new(s_radius,s_center));
+            world.primitives[0] = std::sync::Arc::new(Sphere::new(s_radius,s_center));
 
             // self.update();
             self.render();
@@ -175,6 +214,24 @@ impl<'a> Window<'a> {
         Ok(())
     }
 
+    /// Map number keys to render modes, Some(None) selects shaded rendering
+    fn debug_mode_for_key(keycode: Keycode) -> Option<Option<DebugMode>> {
+        let index = match keycode {
+            Keycode::Num0 => return Some(None),
+            Keycode::Num1 => 0,
+            Keycode::Num2 => 1,
+            Keycode::Num3 => 2,
+            Keycode::Num4 => 3,
+            Keycode::Num5 => 4,
+            Keycode::Num6 => 5,
+            Keycode::Num7 => 6,
+            Keycode::Num8 => 7,
+            Keycode::Num9 => 8,
+            _ => return None
+        };
+        Some(Some(debug::MODES[index]))
+    }
+
     /// Update
     fn update(&mut self) {
         let r = rand::thread_rng().gen();